@@ -122,6 +122,19 @@ pub fn handle(
     } else {
         terminal::render(model, &annotations, options)?;
         terminal::render_summaries(model, &annotations, options)?;
+        if sub_matches.is_present("memory-plan") {
+            let typed = model
+                .downcast_ref::<TypedModel>()
+                .context("Memory planning requires a typed model")?;
+            let plan = MemoryPlan::for_model(typed)?;
+            println!(
+                "Memory plan: {} tensors, peak {} bytes (naive {} bytes, lower bound {} bytes)",
+                plan.lifetimes.len(),
+                plan.peak_memory(),
+                plan.naive_memory(),
+                plan.live_memory_bound()
+            );
+        }
     }
 
     Ok(())
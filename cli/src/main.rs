@@ -119,6 +119,9 @@ fn main() -> tract_core::anyhow::Result<()> {
     (@arg input_node: --("input-node") +takes_value +multiple number_of_values(1)
      "Override input nodes names (auto-detects otherwise).")
 
+    (@arg tf_signature: --("tf-signature") +takes_value
+     "Signature to load from a TensorFlow SavedModel directory (default: serving_default)")
+
     (@arg tf_initializer_output_node: --("tf-initializer-output-node") +takes_value +multiple number_of_values(1)
      "Set an initializer node")
 
@@ -211,6 +214,11 @@ fn main() -> tract_core::anyhow::Result<()> {
         .long_about("Dumps the Tensorflow graph in human readable form.")
         .arg(Arg::with_name("cost").long("cost").help("Include const information"))
         .arg(Arg::with_name("profile").long("profile").help("Include results for profile run"))
+        .arg(
+            Arg::with_name("memory-plan")
+                .long("memory-plan")
+                .help("Plan intermediate tensors memory and report peak usage (typed models only)"),
+        )
         .arg(
             Arg::with_name("assert-cost")
             .takes_value(true)
@@ -461,7 +469,19 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
         #[cfg(feature = "onnx")]
         {
             let onnx = tract_onnx::onnx();
-            let names = onnx.op_register.0.keys().sorted().into_iter().join(", ");
+            let names = onnx
+                .op_register
+                .0
+                .keys()
+                .map(|(domain, op)| {
+                    if domain.is_empty() {
+                        op.to_string()
+                    } else {
+                        format!("{}.{}", domain, op)
+                    }
+                })
+                .sorted()
+                .join(", ");
             println!("Onnx:\n");
             println!("{}", names);
             println!("\n");
@@ -62,6 +62,11 @@ pub trait Model:
             vec![("loop".into(), lir.plan.model())]
         } else if let Some(mir) = self.node_op(id).downcast_ref::<tract_core::ops::scan::Scan>() {
             vec![("loop".into(), &mir.body)]
+        } else if let Some(lir) = self.node_op(id).downcast_ref::<tract_core::ops::scan::LirLoop>()
+        {
+            vec![("body".into(), lir.plan.model())]
+        } else if let Some(mir) = self.node_op(id).downcast_ref::<tract_core::ops::scan::Loop>() {
+            vec![("body".into(), &mir.body)]
         } else if let Some(hir) =
             self.node_op(id).downcast_ref::<tract_hir::ops::scan::InferenceScan>()
         {
@@ -77,6 +82,11 @@ pub trait Model:
             vec![lir.iteration_count(input)]
         } else if let Some(mir) = self.node_op(id).downcast_ref::<tract_core::ops::scan::Scan>() {
             vec![mir.iteration_count(input)]
+        } else if let Some(lir) = self.node_op(id).downcast_ref::<tract_core::ops::scan::LirLoop>()
+        {
+            vec![lir.iteration_count(input)]
+        } else if let Some(mir) = self.node_op(id).downcast_ref::<tract_core::ops::scan::Loop>() {
+            vec![mir.iteration_count(input)]
         } else if let Some(_) =
             self.node_op(id).downcast_ref::<tract_hir::ops::scan::InferenceScan>()
         {
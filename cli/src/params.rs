@@ -12,7 +12,7 @@ use tract_hir::internal::*;
 #[cfg(feature = "pulse")]
 use tract_pulse::internal::*;
 #[cfg(feature = "tf")]
-use tract_tensorflow::tfpb::tensorflow::GraphDef;
+use tract_tensorflow::tfpb::tensorflow::{GraphDef, MetaGraphDef};
 
 use crate::display_params::DisplayParams;
 use crate::CliResult;
@@ -147,6 +147,8 @@ impl Parameters {
             } else if location.path().extension().map(|s| s == "raw" || s == "txt").unwrap_or(false)
             {
                 "kaldi"
+            } else if location.is_dir() && location.path().join("saved_model.pb").exists() {
+                "tf"
             } else if location.is_dir()
                 || location.path().to_string_lossy().ends_with(".tar")
                 || location.path().to_string_lossy().ends_with(".tar.gz")
@@ -217,7 +219,11 @@ impl Parameters {
                 info_usage("loaded framework (onnx)", probe);
                 let graph = onnx.proto_model_for_read(&mut *location.read()?)?;
                 info_usage("proto model loaded", probe);
-                let parsed = onnx.parse(&graph)?;
+                let model_dir = match location {
+                    ModelLocation::Fs(path) => path.parent(),
+                    ModelLocation::Http(_) => None,
+                };
+                let parsed = onnx.parse_with_dir(&graph, model_dir)?;
                 if need_graph {
                     (
                         SomeGraphDef::Onnx(graph, parsed.clone()),
@@ -232,12 +238,27 @@ impl Parameters {
             "tf" => {
                 let tf = tract_tensorflow::tensorflow();
                 info_usage("loaded framework (tf)", probe);
-                let mut graph = tf.proto_model_for_read(&mut *location.read()?)?;
+                let (mut graph, variables) = if location.is_dir() {
+                    let signature = matches.value_of("tf_signature").unwrap_or("serving_default");
+                    let saved = tf.open_saved_model_dir(location.path())?;
+                    let meta_graph =
+                        tract_tensorflow::Tensorflow::saved_model_meta_graph(&saved, signature)?;
+                    let variables = tf.read_saved_model_variables(location.path())?;
+                    (meta_graph.clone(), Some((signature, variables)))
+                } else {
+                    let graph = tf.proto_model_for_read(&mut *location.read()?)?;
+                    (MetaGraphDef { graph_def: Some(graph), ..MetaGraphDef::default() }, None)
+                };
                 info_usage("proto model loaded", probe);
                 if matches.is_present("determinize") {
-                    tract_tensorflow::Tensorflow::determinize(&mut graph)?;
+                    tract_tensorflow::Tensorflow::determinize(graph.graph_def.as_mut().unwrap())?;
                 }
-                let mut model_and_ext = tf.parse_graph(&graph)?;
+                let mut model_and_ext = if let Some((signature, variables)) = &variables {
+                    tf.parse_meta_graph(&graph, signature, variables)?
+                } else {
+                    tf.parse_graph(graph.graph_def.as_ref().unwrap())?
+                };
+                let graph = graph.graph_def.unwrap();
                 model_and_ext.1.initializing_nodes = matches
                     .values_of("tf_initializer_output_node")
                     .map(|values| {
@@ -0,0 +1,60 @@
+extern crate criterion;
+extern crate tract_core;
+use criterion::*;
+
+use tract_core::internal::*;
+use tract_core::ops::math;
+use tract_core::ops::matmul::MatMul;
+use tract_core::ops::nn::{Attention, Reduce, Reducer};
+
+/// Scaled dot-product attention over q, k, v of shape [heads, len, 64], in
+/// its expanded form. Exposing the exponentials prevents the fusion.
+fn expanded(heads: usize, len: usize, expose_exp: bool) -> TypedModel {
+    let mut model = TypedModel::default();
+    let fact = TypedFact::dt_shape(f32::datum_type(), &[heads, len, 64]);
+    let q = model.add_source("q", fact.clone()).unwrap();
+    let k = model.add_source("k", fact.clone()).unwrap();
+    let v = model.add_source("v", fact).unwrap();
+    let kt = model.wire_node("kt", AxisOp::Move(2, 1), &[k]).unwrap();
+    let qk = model.wire_node("qk", MatMul::default(), &[q, kt[0]]).unwrap();
+    let x = model.wire_node("scaled", math::mul::unary(rctensor3(&[[[0.125f32]]])), &qk).unwrap();
+    let max = model.wire_node("max", Reduce::new(tvec!(2), Reducer::Max), &x).unwrap();
+    let sub = model.wire_node("sub", math::sub::bin_typed(), &[x[0], max[0]]).unwrap();
+    let exp = model.wire_node("exp", math::exp(), &sub).unwrap();
+    let sum = model.wire_node("sum", Reduce::new(tvec!(2), Reducer::Sum), &exp).unwrap();
+    let probs = model.wire_node("probs", math::div::bin_typed(), &[exp[0], sum[0]]).unwrap();
+    let y = model.wire_node("y", MatMul::default(), &[probs[0], v]).unwrap();
+    if expose_exp {
+        model.set_output_outlets(&[y[0], exp[0]]).unwrap();
+    } else {
+        model.set_output_outlets(&y).unwrap();
+    }
+    model
+}
+
+fn attention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("attention");
+    for &(heads, len) in &[(12, 64), (12, 128), (12, 384)] {
+        let inputs: TVec<Tensor> = (0..3)
+            .map(|i| {
+                let data = (0..heads * len * 64).map(|x| ((x + i) % 17) as f32 / 17.).collect();
+                tract_ndarray::Array3::from_shape_vec((heads, len, 64), data).unwrap().into()
+            })
+            .collect();
+        let unfused = expanded(heads, len, true).into_optimized().unwrap();
+        assert!(!unfused.nodes().iter().any(|n| n.op_is::<Attention>()));
+        let unfused = SimplePlan::new(unfused).unwrap();
+        let fused = expanded(heads, len, false).into_optimized().unwrap();
+        assert!(fused.nodes().iter().any(|n| n.op_is::<Attention>()));
+        let fused = SimplePlan::new(fused).unwrap();
+        group.bench_function(&format!("unfused_{}x{}", heads, len), |b| {
+            b.iter(|| unfused.run(inputs.clone()).unwrap())
+        });
+        group.bench_function(&format!("fused_{}x{}", heads, len), |b| {
+            b.iter(|| fused.run(inputs.clone()).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, attention);
+criterion_main!(benches);
@@ -74,6 +74,7 @@ mod hash;
 mod late_bind;
 pub mod model;
 pub mod optim;
+pub mod parallel_plan;
 pub mod plan;
 
 pub use dyn_clone;
@@ -82,6 +83,7 @@ pub use dyn_clone;
 pub mod prelude {
     pub use crate::framework::Framework;
     pub use crate::model::*;
+    pub use crate::parallel_plan::{ParallelPlan, ParallelState};
     pub use crate::plan::{SimplePlan, SimpleState};
     pub use std::sync::Arc;
     pub use tract_data::prelude::*;
@@ -153,6 +153,75 @@ impl AsRef<[TDim]> for ShapeFact {
     }
 }
 
+/// Element information of a sequence-valued outlet.
+///
+/// A sequence travels in the graph as a scalar tensor of DatumType::Sequence.
+/// An ONNX optional is a sequence of zero or one element.
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct SequenceFact {
+    /// element type
+    pub datum_type: DatumType,
+    /// element shape, if all elements share the same rank
+    pub shape: Option<ShapeFact>,
+    /// number of elements, if known statically
+    pub len: Option<usize>,
+    /// whether the sequence stands for an optional
+    pub optional: bool,
+}
+
+impl SequenceFact {
+    pub fn new(datum_type: DatumType, shape: Option<ShapeFact>, len: Option<usize>) -> SequenceFact {
+        SequenceFact { datum_type, shape, len, optional: false }
+    }
+
+    /// Fact of a sequence made of elements with the given shapes.
+    ///
+    /// Dimensions varying from one element to another are replaced by a
+    /// fresh symbol.
+    pub fn from_element_shapes<'a>(
+        datum_type: DatumType,
+        shapes: impl IntoIterator<Item = &'a ShapeFact>,
+        len: Option<usize>,
+    ) -> SequenceFact {
+        let mut shapes = shapes.into_iter();
+        let mut shape = shapes.next().cloned();
+        for other in shapes {
+            shape = shape.and_then(|s| Self::merge_shapes(&s, other));
+        }
+        SequenceFact::new(datum_type, shape, len)
+    }
+
+    /// Common shape of the elements of two sequences, if any.
+    pub fn merge_shapes(a: &ShapeFact, b: &ShapeFact) -> Option<ShapeFact> {
+        if a.rank() != b.rank() {
+            return None;
+        }
+        Some(
+            a.iter()
+                .zip(b.iter())
+                .map(|(a, b)| if a == b { a } else { Symbol::new('s').into() })
+                .collect(),
+        )
+    }
+
+    fn from_value(sequence: &Sequence) -> SequenceFact {
+        let shapes = sequence
+            .iter()
+            .map(|t| ShapeFact::from_dims(t.shape().iter().map(TDim::from)))
+            .collect::<TVec<_>>();
+        SequenceFact {
+            optional: sequence.optional,
+            ..Self::from_element_shapes(sequence.datum_type, &shapes, Some(sequence.len()))
+        }
+    }
+
+    /// Fact of an element of the sequence.
+    pub fn element_fact(&self) -> TractResult<TypedFact> {
+        let shape = self.shape.clone().context("Sequence elements have no common rank")?;
+        Ok(TypedFact::dt_shape(self.datum_type, shape))
+    }
+}
+
 /// Fully determined tensor information for TypedModel.
 #[derive(Clone, PartialEq, Hash)]
 pub struct TypedFact {
@@ -164,6 +233,8 @@ pub struct TypedFact {
     pub konst: Option<Arc<Tensor>>,
     /// optional uniform value
     pub uniform: Option<Arc<Tensor>>,
+    /// element information, for sequences
+    pub sequence: Option<Arc<SequenceFact>>,
 }
 
 impl_dyn_hash!(TypedFact);
@@ -187,14 +258,35 @@ impl TypedFact {
 
     pub fn dt_scalar(datum_type: DatumType) -> TypedFact {
         let foo: &[usize] = &[];
-        TypedFact { datum_type, shape: ShapeFact::from(foo), konst: None, uniform: None }
+        TypedFact {
+            datum_type,
+            shape: ShapeFact::from(foo),
+            konst: None,
+            uniform: None,
+            sequence: None,
+        }
     }
 
     pub fn dt_shape<S>(datum_type: DatumType, shape: S) -> TypedFact
     where
         S: Into<ShapeFact>,
     {
-        TypedFact { datum_type, shape: shape.into(), konst: None, uniform: None }
+        TypedFact { datum_type, shape: shape.into(), konst: None, uniform: None, sequence: None }
+    }
+
+    pub fn sequence(fact: SequenceFact) -> TypedFact {
+        TypedFact { sequence: Some(Arc::new(fact)), ..TypedFact::scalar::<Sequence>() }
+    }
+
+    /// Element information of a sequence fact.
+    pub fn sequence_fact(&self) -> TractResult<&SequenceFact> {
+        if let Some(seq) = self.sequence.as_deref() {
+            Ok(seq)
+        } else if self.datum_type == DatumType::Sequence {
+            bail!("No element type or shape known for sequence {:?}", self)
+        } else {
+            bail!("Expected a sequence, got {:?}", self)
+        }
     }
 
     pub fn rank(&self) -> usize {
@@ -230,6 +322,9 @@ impl TypedFact {
                 bail!("fact as uniform value {:?}, but is of type {:?}", u, self.datum_type);
             }
         }
+        if self.sequence.is_some() && self.datum_type != DatumType::Sequence {
+            bail!("fact has type {:?}, but sequence information {:?}", self.datum_type, self.sequence);
+        }
         if let (Some(u), Some(k)) = (self.uniform.as_deref(), self.konst.as_deref()) {
             if let Some(k) = k.as_uniform() {
                 if &k != u {
@@ -243,7 +338,10 @@ impl TypedFact {
     }
 
     pub fn without_value(&self) -> Self {
-        Self::dt_shape(self.datum_type, self.shape.clone())
+        TypedFact {
+            sequence: self.sequence.clone(),
+            ..Self::dt_shape(self.datum_type, self.shape.clone())
+        }
     }
 }
 
@@ -303,11 +401,17 @@ impl<'t> From<&'t Tensor> for TypedFact {
 
 impl From<Arc<Tensor>> for TypedFact {
     fn from(t: Arc<Tensor>) -> TypedFact {
+        let sequence = if t.datum_type() == DatumType::Sequence && t.rank() == 0 {
+            t.to_scalar::<Sequence>().ok().map(|s| Arc::new(SequenceFact::from_value(s)))
+        } else {
+            None
+        };
         TypedFact {
             datum_type: t.datum_type(),
             shape: ShapeFact::from_dims(t.shape().iter().map(TDim::from)),
             uniform: t.as_uniform().map(Arc::new),
             konst: Some(t),
+            sequence,
         }
     }
 }
@@ -322,6 +426,14 @@ impl fmt::Debug for TypedFact {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self.konst {
             Some(ref k) => write!(fmt, "{:?}", k),
+            None if self.sequence.is_some() => {
+                let seq = self.sequence.as_ref().unwrap();
+                write!(fmt, "Sequence<{:?},{:?}>", seq.shape, seq.datum_type)?;
+                if let Some(len) = seq.len {
+                    write!(fmt, "x{}", len)?;
+                }
+                Ok(())
+            }
             None if self.rank() > 0 => write!(fmt, "{:?},{:?}", self.shape, self.datum_type),
             None => write!(fmt, "{:?}", self.datum_type),
         }
@@ -0,0 +1,436 @@
+//! Static memory planning for intermediate tensors.
+//!
+//! On a fully typed model with concrete shapes, every intermediate tensor has a
+//! known size and a known lifetime (from the step computing it to the last step
+//! reading it). The planner packs these lifetimes in a single arena, letting
+//! tensors with disjoint lifetimes share the same bytes, and `ArenaState` runs
+//! the model in this arena.
+use std::borrow::Borrow;
+use std::mem::ManuallyDrop;
+
+use crate::internal::*;
+
+/// Offsets in the arena are aligned on this boundary.
+pub const ARENA_ALIGNMENT: usize = 64;
+
+/// Size and lifetime of an intermediate tensor.
+///
+/// `first` is the step (index in the evaluation order) computing the value,
+/// `last` the last step using it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lifetime {
+    pub outlet: OutletId,
+    pub size: usize,
+    pub first: usize,
+    pub last: usize,
+}
+
+impl Lifetime {
+    pub fn overlaps(&self, other: &Lifetime) -> bool {
+        self.first <= other.last && other.first <= self.last
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryPlan {
+    pub order: Vec<usize>,
+    pub lifetimes: Vec<Lifetime>,
+    pub offsets: HashMap<OutletId, usize>,
+    pub arena_size: usize,
+}
+
+impl MemoryPlan {
+    /// Plan the memory for the model default evaluation order.
+    pub fn for_model(model: &TypedModel) -> TractResult<MemoryPlan> {
+        let order = model.eval_order()?;
+        Self::for_order(model, order)
+    }
+
+    /// Plan the memory for a given evaluation order.
+    ///
+    /// Model inputs and constants are not part of the arena.
+    pub fn for_order(model: &TypedModel, order: Vec<usize>) -> TractResult<MemoryPlan> {
+        let inputs = model.input_outlets()?;
+        let outputs = model.output_outlets()?;
+        let mut step_of = vec![None; model.nodes().len()];
+        for (step, &node) in order.iter().enumerate() {
+            step_of[node] = Some(step);
+        }
+        let mut lifetimes = vec![];
+        for (step, &node) in order.iter().enumerate() {
+            let node = model.node(node);
+            for (slot, output) in node.outputs.iter().enumerate() {
+                let outlet = OutletId::new(node.id, slot);
+                if inputs.contains(&outlet) || output.fact.konst.is_some() {
+                    continue;
+                }
+                let shape = output.fact.shape.as_concrete().with_context(|| {
+                    format!(
+                        "Memory planning requires concrete shapes, {} has {:?}",
+                        node, output.fact
+                    )
+                })?;
+                let size = shape.iter().product::<usize>() * output.fact.datum_type.size_of();
+                let last = if outputs.contains(&outlet) {
+                    order.len()
+                } else {
+                    output
+                        .successors
+                        .iter()
+                        .filter_map(|inlet| step_of[inlet.node])
+                        .max()
+                        .unwrap_or(step)
+                };
+                lifetimes.push(Lifetime { outlet, size, first: step, last });
+            }
+        }
+        let (offsets, arena_size) = Self::pack(&lifetimes);
+        Ok(MemoryPlan { order, lifetimes, offsets, arena_size })
+    }
+
+    /// Greedy by size packing: biggest tensors are placed first, each at the
+    /// lowest offset not clashing with an already placed tensor alive at the
+    /// same time.
+    fn pack(lifetimes: &[Lifetime]) -> (HashMap<OutletId, usize>, usize) {
+        let mut by_size: Vec<&Lifetime> = lifetimes.iter().collect();
+        by_size.sort_by_key(|lt| (std::cmp::Reverse(lt.size), lt.first));
+        let mut placed: Vec<(&Lifetime, usize)> = vec![];
+        let mut offsets = HashMap::new();
+        let mut arena_size = 0;
+        for lt in by_size {
+            let mut clashing: Vec<(usize, usize)> = placed
+                .iter()
+                .filter(|(other, _)| other.overlaps(lt))
+                .map(|(other, offset)| (*offset, offset + other.size))
+                .collect();
+            clashing.sort();
+            let mut offset = 0;
+            for (start, end) in clashing {
+                if offset + lt.size <= start {
+                    break;
+                }
+                offset = offset.max(Self::align(end));
+            }
+            arena_size = arena_size.max(offset + lt.size);
+            offsets.insert(lt.outlet, offset);
+            placed.push((lt, offset));
+        }
+        (offsets, arena_size)
+    }
+
+    fn align(offset: usize) -> usize {
+        (offset + ARENA_ALIGNMENT - 1) / ARENA_ALIGNMENT * ARENA_ALIGNMENT
+    }
+
+    /// Offset of an outlet value in the arena.
+    pub fn offset(&self, outlet: OutletId) -> Option<usize> {
+        self.offsets.get(&outlet).cloned()
+    }
+
+    /// Size of the arena, that is the peak memory used by intermediate tensors.
+    pub fn peak_memory(&self) -> usize {
+        self.arena_size
+    }
+
+    /// Memory needed if every intermediate tensor was kept alive.
+    pub fn naive_memory(&self) -> usize {
+        self.lifetimes.iter().map(|lt| lt.size).sum()
+    }
+
+    /// Lower bound for the peak memory: the biggest total size of the tensors
+    /// alive at the same step.
+    pub fn live_memory_bound(&self) -> usize {
+        (0..=self.order.len())
+            .map(|step| {
+                self.lifetimes
+                    .iter()
+                    .filter(|lt| lt.first <= step && step <= lt.last)
+                    .map(|lt| lt.size)
+                    .sum()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Value of an outlet in an `ArenaState`.
+#[derive(Debug)]
+enum Value {
+    /// Intermediate tensor, living in the arena.
+    Arena(ManuallyDrop<Tensor>),
+    /// Model input or constant.
+    Owned(Arc<Tensor>),
+}
+
+impl Value {
+    fn tensor(&self) -> &Tensor {
+        match self {
+            Value::Arena(t) => t,
+            Value::Owned(t) => t,
+        }
+    }
+}
+
+/// Runs a model with all its intermediate tensors in a single arena, laid out
+/// by a `MemoryPlan` and allocated once, when building the state.
+///
+/// Ops supporting `EvalOp::eval_into` write their outputs in the arena
+/// directly: running a model made of such ops does not allocate. Other ops
+/// are evaluated with `eval`, and their outputs copied in the arena
+/// (`allocating_nodes` lists them after a run).
+#[derive(Debug)]
+pub struct ArenaState<M: Borrow<TypedModel>> {
+    model: M,
+    plan: MemoryPlan,
+    /// Memory of the `Value::Arena` tensors.
+    _arena: Tensor,
+    values: Vec<TVec<Value>>,
+    states: Vec<Option<Box<dyn OpState>>>,
+    session: SessionState,
+    allocating: Vec<usize>,
+}
+
+impl<M: Borrow<TypedModel>> ArenaState<M> {
+    pub fn new(model: M) -> TractResult<ArenaState<M>> {
+        let plan = MemoryPlan::for_model(model.borrow())?;
+        Self::new_with_plan(model, plan)
+    }
+
+    pub fn new_with_plan(model: M, plan: MemoryPlan) -> TractResult<ArenaState<M>> {
+        let mut arena = unsafe {
+            Tensor::uninitialized_aligned_dt(u8::datum_type(), &[plan.arena_size], ARENA_ALIGNMENT)?
+        };
+        let mut session = SessionState::default();
+        let graph = model.borrow();
+        let mut values: Vec<TVec<Value>> = (0..graph.nodes().len()).map(|_| tvec!()).collect();
+        let mut states: Vec<Option<Box<dyn OpState>>> = vec![];
+        for node in graph.nodes() {
+            states.push(node.op.state(&mut session, node.id)?);
+            for (slot, output) in node.outputs.iter().enumerate() {
+                let outlet = OutletId::new(node.id, slot);
+                let fact = &output.fact;
+                let value = if let Some(k) = &fact.konst {
+                    Value::Owned(k.clone())
+                } else if let Some(offset) = plan.offset(outlet) {
+                    let shape = fact.shape.as_concrete().unwrap();
+                    let data = unsafe { arena.as_ptr_mut_unchecked::<u8>().add(offset) };
+                    Value::Arena(
+                        unsafe { Tensor::from_raw_parts_unowned(fact.datum_type, shape, data) }
+                            .with_context(|| format!("Placing {} output in the arena", node))?,
+                    )
+                } else if graph.input_outlets()?.contains(&outlet) {
+                    let shape = fact.shape.as_concrete().with_context(|| {
+                        format!(
+                            "Arena evaluation requires concrete shapes, {} has {:?}",
+                            node, fact
+                        )
+                    })?;
+                    Value::Owned(Tensor::zero_dt(fact.datum_type, shape)?.into_arc_tensor())
+                } else {
+                    // not evaluated with the plan order
+                    continue;
+                };
+                values[node.id].push(value);
+            }
+        }
+        Ok(ArenaState { model, plan, _arena: arena, values, states, session, allocating: vec![] })
+    }
+
+    fn input_outlet(&self, input: usize) -> TractResult<OutletId> {
+        self.model
+            .borrow()
+            .input_outlets()?
+            .get(input)
+            .cloned()
+            .ok_or_else(|| format_err!("Invalid input id for model ({}).", input))
+    }
+
+    /// The input tensor, to be filled in place before a run.
+    pub fn input_mut(&mut self, input: usize) -> TractResult<&mut Tensor> {
+        let outlet = self.input_outlet(input)?;
+        match &mut self.values[outlet.node][outlet.slot] {
+            Value::Owned(t) => Arc::get_mut(t).context("Input tensor is shared"),
+            Value::Arena(_) => unreachable!(),
+        }
+    }
+
+    pub fn set_input(&mut self, input: usize, t: Tensor) -> TractResult<()> {
+        let outlet = self.input_outlet(input)?;
+        let fact = self.model.borrow().outlet_fact(outlet)?;
+        anyhow::ensure!(
+            t.datum_type() == fact.datum_type && fact.shape.as_concrete() == Some(t.shape()),
+            "Input {} expected {:?}, got {:?}",
+            input,
+            fact,
+            t
+        );
+        self.values[outlet.node][outlet.slot] = Value::Owned(t.into_arc_tensor());
+        Ok(())
+    }
+
+    pub fn output(&self, output: usize) -> TractResult<&Tensor> {
+        let outlet = *self
+            .model
+            .borrow()
+            .output_outlets()?
+            .get(output)
+            .ok_or_else(|| format_err!("Invalid output id for model ({}).", output))?;
+        Ok(self.values[outlet.node][outlet.slot].tensor())
+    }
+
+    pub fn run(&mut self) -> TractResult<()> {
+        let ArenaState { ref model, ref plan, values, states, session, allocating, .. } = self;
+        let model: &TypedModel = model.borrow();
+        for &id in &plan.order {
+            let node = model.node(id);
+            if model.input_outlets()?.iter().any(|i| i.node == id)
+                || values[id].iter().all(|v| matches!(v, Value::Owned(_)))
+            {
+                continue;
+            }
+            let mut outputs = std::mem::take(&mut values[id]);
+            let done =
+                if states[id].is_none() && outputs.iter().all(|v| matches!(v, Value::Arena(_))) {
+                    let inputs: TVec<&Tensor> =
+                        node.inputs.iter().map(|i| values[i.node][i.slot].tensor()).collect();
+                    let mut outputs: TVec<&mut Tensor> = outputs
+                        .iter_mut()
+                        .map(|v| match v {
+                            Value::Arena(t) => &mut **t,
+                            Value::Owned(_) => unreachable!(),
+                        })
+                        .collect();
+                    node.op
+                        .eval_into(&inputs, &mut outputs)
+                        .with_context(|| format!("Evaluating {} in the arena", node))?
+                } else {
+                    false
+                };
+            if !done {
+                let inputs: TVec<Arc<Tensor>> = node
+                    .inputs
+                    .iter()
+                    .map(|i| match &values[i.node][i.slot] {
+                        Value::Arena(t) => Tensor::clone(&**t).into_arc_tensor(),
+                        Value::Owned(t) => t.clone(),
+                    })
+                    .collect();
+                let state = states[id].as_mut().map(|s| &mut **s);
+                let results = crate::plan::eval(session, state, node, inputs)?;
+                for (value, result) in outputs.iter_mut().zip(results.iter()) {
+                    if let Value::Arena(t) = value {
+                        anyhow::ensure!(
+                            t.datum_type() == result.datum_type() && t.shape() == result.shape(),
+                            "{} computed {:?}, planned for {:?}",
+                            node,
+                            result,
+                            t
+                        );
+                        unsafe { t.as_bytes_mut().copy_from_slice(result.as_bytes()) };
+                    }
+                }
+                if !allocating.contains(&id) {
+                    allocating.push(id);
+                }
+            }
+            values[id] = outputs;
+        }
+        Ok(())
+    }
+
+    /// Nodes evaluated out of the arena (and allocating) during the runs.
+    pub fn allocating_nodes(&self) -> &[usize] {
+        &self.allocating
+    }
+
+    pub fn plan(&self) -> &MemoryPlan {
+        &self.plan
+    }
+
+    pub fn model(&self) -> &TypedModel {
+        self.model.borrow()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::change_axes::AxisOp;
+    use crate::ops::math;
+
+    fn chain(len: usize) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let mut wire = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[64]))?;
+        for i in 0..len {
+            wire = model.wire_node(
+                format!("add.{}", i),
+                math::add::unary(rctensor1(&[1f32])),
+                &[wire],
+            )?[0];
+        }
+        model.set_output_outlets(&[wire])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn chain_reuses_two_slots() -> TractResult<()> {
+        let plan = MemoryPlan::for_model(&chain(6)?)?;
+        assert_eq!(plan.lifetimes.len(), 6);
+        assert_eq!(plan.naive_memory(), 6 * 256);
+        assert_eq!(plan.peak_memory(), 2 * 256);
+        for a in &plan.lifetimes {
+            for b in &plan.lifetimes {
+                if a != b && a.overlaps(b) {
+                    assert_ne!(plan.offset(a.outlet), plan.offset(b.outlet));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn diamond_keeps_branches_apart() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let s = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[16]))?;
+        let a = model.wire_node("a", math::add::unary(rctensor1(&[1f32])), &[s])?[0];
+        let b = model.wire_node("b", math::mul::unary(rctensor1(&[2f32])), &[s])?[0];
+        let c = model.wire_node("c", math::add::bin_typed(), &[a, b])?[0];
+        model.set_output_outlets(&[c])?;
+        let plan = MemoryPlan::for_model(&model)?;
+        assert_eq!(plan.peak_memory(), plan.live_memory_bound());
+        assert_ne!(plan.offset(a), plan.offset(b));
+        assert_eq!(plan.offset(s), None);
+        Ok(())
+    }
+
+    #[test]
+    fn arena_state_falls_back_on_eval() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let s = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[2, 3]))?;
+        let a = model.wire_node("a", math::add::unary(rctensor2(&[[1f32]])), &[s])?[0];
+        let t = model.wire_node("t", AxisOp::Move(0, 1), &[a])?[0];
+        let b = model.wire_node("b", math::mul::unary(rctensor2(&[[2f32]])), &[t])?[0];
+        model.set_output_outlets(&[b])?;
+        let input = tensor2(&[[0f32, 1., 2.], [3., 4., 5.]]);
+        let expected = SimplePlan::new(&model)?.run(tvec!(input.clone()))?;
+        let mut state = ArenaState::new(&model)?;
+        state.set_input(0, input)?;
+        state.run()?;
+        assert_eq!(state.output(0)?, &*expected[0]);
+        assert_eq!(state.allocating_nodes(), &[t.node]);
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic_shapes_are_rejected() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let s = model.add_source(
+            "s",
+            TypedFact::dt_shape(f32::datum_type(), &[TDim::from(Symbol::new('N'))]),
+        )?;
+        let a = model.wire_node("a", math::add::unary(rctensor1(&[1f32])), &[s])?[0];
+        model.set_output_outlets(&[a])?;
+        assert!(MemoryPlan::for_model(&model).is_err());
+        Ok(())
+    }
+}
@@ -38,6 +38,7 @@ use std::str;
 
 mod fact;
 mod graph;
+pub mod memory;
 mod node;
 pub mod order;
 mod patch;
@@ -46,6 +47,7 @@ pub mod typed;
 
 pub use self::fact::*;
 pub use self::graph::*;
+pub use self::memory::{ArenaState, MemoryPlan};
 pub use self::node::*;
 pub use self::order::eval_order;
 pub use self::patch::ModelPatch;
@@ -73,7 +73,7 @@ impl EvalOp for DynSlice {
 
 impl TypedOp for DynSlice {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        let mut fact = inputs[0].clone();
+        let mut fact = inputs[0].without_value();
         fact.shape.set(self.axis, Symbol::new('l').into());
         Ok(tvec!(fact))
     }
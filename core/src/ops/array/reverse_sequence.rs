@@ -0,0 +1,128 @@
+use crate::internal::*;
+use ndarray::*;
+
+/// Reverse variable length prefixes of the sequences of a batch.
+///
+/// Inputs are the data and the i64 lengths of each sequence, one per element
+/// of `batch_axis`. For each of them, the first `len` items along `time_axis`
+/// are reversed, the others are copied unchanged.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ReverseSequence {
+    pub batch_axis: usize,
+    pub time_axis: usize,
+}
+
+impl_dyn_hash!(ReverseSequence);
+
+impl Op for ReverseSequence {
+    fn name(&self) -> Cow<str> {
+        "ReverseSequence".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("batch_axis: {} time_axis: {}", self.batch_axis, self.time_axis)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl ReverseSequence {
+    fn eval_t<T: Datum>(&self, input: &Tensor, lens: &[i64]) -> TractResult<Tensor> {
+        let input = input.to_array_view::<T>()?;
+        let mut output = input.to_owned();
+        let time_len = input.shape()[self.time_axis];
+        // once the batch axis is removed, the time axis may have moved
+        let time_axis = self.time_axis - (self.batch_axis < self.time_axis) as usize;
+        for (b, &len) in lens.iter().enumerate() {
+            if len < 0 || len as usize > time_len {
+                bail!("Invalid sequence length {} for batch {} (max is {})", len, b, time_len)
+            }
+            let len = len as usize;
+            let source = input.index_axis(Axis(self.batch_axis), b);
+            let mut target = output.index_axis_mut(Axis(self.batch_axis), b);
+            for t in 0..len {
+                target
+                    .index_axis_mut(Axis(time_axis), t)
+                    .assign(&source.index_axis(Axis(time_axis), len - 1 - t));
+            }
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+impl EvalOp for ReverseSequence {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, lens) = args_2!(inputs);
+        let lens = lens.cast_to::<i64>()?;
+        let lens = lens.as_slice::<i64>()?;
+        if lens.len() != input.shape()[self.batch_axis] {
+            bail!(
+                "Expected {} sequence lengths, got {}",
+                input.shape()[self.batch_axis],
+                lens.len()
+            )
+        }
+        let output = dispatch_datum!(Self::eval_t(input.datum_type())(self, &input, lens))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for ReverseSequence {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let rank = inputs[0].rank();
+        if self.batch_axis == self.time_axis || self.batch_axis >= rank || self.time_axis >= rank {
+            bail!(
+                "Invalid batch and time axes {} and {} for {:?}",
+                self.batch_axis,
+                self.time_axis,
+                inputs[0]
+            )
+        }
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*inputs[0].shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        let axes = (0..inputs[0].rank())
+            .filter(|&ax| ax != self.batch_axis && ax != self.time_axis)
+            .map(|ax| AxisInfo {
+                inputs: tvec!(Some(ax), None),
+                outputs: tvec!(Some(ax)),
+                period: 1,
+                disposable: true,
+            })
+            .collect::<Vec<_>>();
+        Ok(axes.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn time_major() {
+        let op = ReverseSequence::new(1, 0);
+        let input = tensor2(&[[0i32, 4, 8], [1, 5, 9], [2, 6, 10], [3, 7, 11]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), rctensor1(&[4i64, 3, 1]))).unwrap();
+        assert_eq!(*output[0], tensor2(&[[3i32, 6, 8], [2, 5, 9], [1, 4, 10], [0, 7, 11]]));
+    }
+
+    #[test]
+    fn batch_major() {
+        let op = ReverseSequence::new(0, 1);
+        let input = tensor2(&[[0f32, 1., 2., 3.], [4., 5., 6., 7.]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), rctensor1(&[2i64, 4]))).unwrap();
+        assert_eq!(*output[0], tensor2(&[[1f32, 0., 2., 3.], [7., 6., 5., 4.]]));
+    }
+}
@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+
+use crate::internal::*;
+use ndarray::*;
+
+/// Extract the k largest (or smallest) elements along an axis.
+///
+/// Inputs are the data and k (a scalar or one-element tensor). Outputs are the
+/// values and their i64 indices, sorted, with ties resolved by lowest index.
+/// When k is not known at analysis time, the output axis is `fallback_k`.
+#[derive(Debug, Clone, new, Hash)]
+pub struct TopK {
+    pub axis: usize,
+    pub largest: bool,
+    pub fallback_k: TDim,
+}
+
+impl_dyn_hash!(TopK);
+
+impl Op for TopK {
+    fn name(&self) -> Cow<str> {
+        "TopK".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} largest: {}", self.axis, self.largest)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl TopK {
+    unsafe fn eval_t<T: Datum + PartialOrd>(
+        &self,
+        input: &Tensor,
+        k: usize,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[self.axis] = k;
+        let mut values = Tensor::uninitialized_dt(input.datum_type(), &shape)?;
+        let mut indices = Tensor::uninitialized::<i64>(&shape)?;
+        let input = input.to_array_view_unchecked::<T>();
+        let mut values_view = values.to_array_view_mut_unchecked::<T>();
+        let mut indices_view = indices.to_array_view_mut_unchecked::<i64>();
+        let axis = Axis(self.axis);
+        let mut sorted: Vec<(usize, T)> = Vec::with_capacity(input.shape()[self.axis]);
+        for ((lane, mut v), mut i) in input
+            .lanes(axis)
+            .into_iter()
+            .zip(values_view.lanes_mut(axis).into_iter())
+            .zip(indices_view.lanes_mut(axis).into_iter())
+        {
+            sorted.clear();
+            sorted.extend(lane.iter().cloned().enumerate());
+            sorted.sort_by(|a, b| {
+                let order = a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal);
+                let order = if self.largest { order.reverse() } else { order };
+                order.then(a.0.cmp(&b.0))
+            });
+            for (ix, (pos, value)) in sorted.drain(..).take(k).enumerate() {
+                v[ix] = value;
+                i[ix] = pos as i64;
+            }
+        }
+        Ok(tvec!(values.into_arc_tensor(), indices.into_arc_tensor()))
+    }
+}
+
+impl EvalOp for TopK {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, k) = args_2!(inputs);
+        let k = k.cast_to_scalar::<i64>()?;
+        let len = input.shape()[self.axis];
+        if k < 0 || k as usize > len {
+            bail!("TopK: k is {}, axis {} has length {}", k, self.axis, len)
+        }
+        unsafe { dispatch_numbers!(Self::eval_t(input.datum_type())(self, &input, k as usize)) }
+    }
+}
+
+impl TypedOp for TopK {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let mut shape = inputs[0].shape.to_tvec();
+        shape[self.axis] = if let Some(k) = &inputs[1].konst {
+            k.cast_to_scalar::<i64>()?.to_dim()
+        } else {
+            self.fallback_k.clone()
+        };
+        Ok(tvec!(
+            TypedFact::dt_shape(inputs[0].datum_type, &*shape),
+            TypedFact::dt_shape(i64::datum_type(), &*shape)
+        ))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        let axes = (0..inputs[0].rank())
+            .filter(|&ax| ax != self.axis)
+            .map(|ax| AxisInfo {
+                inputs: tvec!(Some(ax), None),
+                outputs: tvec!(Some(ax), Some(ax)),
+                period: 1,
+                disposable: true,
+            })
+            .collect::<Vec<_>>();
+        Ok(axes.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn largest() {
+        let op = TopK::new(1, true, 0.to_dim());
+        let input = tensor2(&[[1f32, 4., 2., 4.], [3., 0., 5., 1.]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), rctensor1(&[3i64]))).unwrap();
+        assert_eq!(*output[0], tensor2(&[[4f32, 4., 2.], [5., 3., 1.]]));
+        assert_eq!(*output[1], tensor2(&[[1i64, 3, 2], [2, 0, 3]]));
+    }
+
+    #[test]
+    fn smallest_axis_0() {
+        let op = TopK::new(0, false, 0.to_dim());
+        let input = tensor2(&[[1i32, 4], [3, 0], [2, 0]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), rctensor0(2i64))).unwrap();
+        assert_eq!(*output[0], tensor2(&[[1i32, 0], [2, 0]]));
+        assert_eq!(*output[1], tensor2(&[[0i64, 1], [2, 2]]));
+    }
+}
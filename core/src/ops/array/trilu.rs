@@ -0,0 +1,103 @@
+use crate::internal::*;
+
+/// Keep the upper (or lower) triangular part of the matrices formed by the
+/// two innermost axes, zeroing the rest.
+///
+/// Inputs are the data and k, the i64 scalar offset of the diagonal. With
+/// `upper`, elements at (i, j) are kept if j - i >= k, otherwise if j - i <= k.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Trilu {
+    pub upper: bool,
+}
+
+impl_dyn_hash!(Trilu);
+
+impl Op for Trilu {
+    fn name(&self) -> Cow<str> {
+        "Trilu".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("upper: {}", self.upper)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl Trilu {
+    fn eval_t<T: Datum>(&self, input: &mut Tensor, k: i64) -> TractResult<()> {
+        let mut view = input.to_array_view_mut::<T>()?;
+        let rank = view.ndim();
+        for (coords, value) in view.indexed_iter_mut() {
+            let (i, j) = (coords[rank - 2] as i64, coords[rank - 1] as i64);
+            let keep = if self.upper { j - i >= k } else { j - i <= k };
+            if !keep {
+                *value = T::default();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EvalOp for Trilu {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, k) = args_2!(inputs);
+        let k = k.cast_to_scalar::<i64>()?;
+        let mut output = input.into_tensor();
+        dispatch_datum!(Self::eval_t(output.datum_type())(self, &mut output, k))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Trilu {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].rank() < 2 {
+            bail!("Trilu expects an input of rank 2 or more, got {:?}", inputs[0])
+        }
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*inputs[0].shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        let axes = (0..inputs[0].rank() - 2)
+            .map(|ax| AxisInfo {
+                inputs: tvec!(Some(ax), None),
+                outputs: tvec!(Some(ax)),
+                period: 1,
+                disposable: true,
+            })
+            .collect::<Vec<_>>();
+        Ok(axes.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn upper() {
+        let op = Trilu::new(true);
+        let input = tensor2(&[[1i32, 2, 3], [4, 5, 6], [7, 8, 9]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), rctensor0(1i64))).unwrap();
+        assert_eq!(*output[0], tensor2(&[[0i32, 2, 3], [0, 0, 6], [0, 0, 0]]));
+    }
+
+    #[test]
+    fn lower_batched() {
+        let op = Trilu::new(false);
+        let input = tensor3(&[[[1f32, 2.], [3., 4.]], [[5., 6.], [7., 8.]]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), rctensor0(0i64))).unwrap();
+        assert_eq!(*output[0], tensor3(&[[[1f32, 0.], [3., 4.]], [[5., 0.], [7., 8.]]]));
+    }
+}
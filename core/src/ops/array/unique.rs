@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+
+use crate::internal::*;
+use ndarray::*;
+
+/// Unique elements (or slices along `axis`) of a tensor.
+///
+/// Outputs are the unique values, the i64 index of their first occurrence in
+/// the input, the i64 index in the unique values of each input element (or
+/// slice), and the i64 number of occurrences of each unique value.
+///
+/// Without an axis, the input is flattened. Unique values come sorted if
+/// `sorted` is set, by order of first occurrence otherwise. Their count
+/// depending on the data, it is represented by `num_unique`.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Unique {
+    pub axis: Option<usize>,
+    pub sorted: bool,
+    pub num_unique: Symbol,
+}
+
+impl_dyn_hash!(Unique);
+
+impl Op for Unique {
+    fn name(&self) -> Cow<str> {
+        "Unique".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {:?} sorted: {}", self.axis, self.sorted)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+fn compare<T: PartialOrd>(a: &ArrayViewD<T>, b: &ArrayViewD<T>) -> Ordering {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+impl Unique {
+    fn eval_t<T: Datum + PartialOrd>(&self, input: &Tensor) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = input.to_array_view::<T>()?;
+        let (input, axis) = if let Some(axis) = self.axis {
+            (input, Axis(axis))
+        } else {
+            let len = input.len();
+            (input.into_shape(IxDyn(&[len]))?, Axis(0))
+        };
+        let items: Vec<ArrayViewD<T>> = input.axis_iter(axis).collect();
+        // stable sort, so the first item of each group is its first occurrence
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by(|&a, &b| compare(&items[a], &items[b]));
+        let mut groups: Vec<Vec<usize>> = vec![];
+        for ix in order {
+            match groups.last_mut() {
+                Some(group) if compare(&items[group[0]], &items[ix]) == Ordering::Equal => {
+                    group.push(ix)
+                }
+                _ => groups.push(vec![ix]),
+            }
+        }
+        if !self.sorted {
+            groups.sort_by_key(|group| group[0]);
+        }
+        let first: Vec<usize> = groups.iter().map(|group| group[0]).collect();
+        let mut inverse = vec![0i64; items.len()];
+        for (ix, group) in groups.iter().enumerate() {
+            for &item in group {
+                inverse[item] = ix as i64;
+            }
+        }
+        let values = input.select(axis, &first).into_tensor();
+        let first = tensor1(&first.iter().map(|&ix| ix as i64).collect::<Vec<_>>());
+        let counts = tensor1(&groups.iter().map(|group| group.len() as i64).collect::<Vec<_>>());
+        Ok(tvec!(
+            values.into_arc_tensor(),
+            first.into_arc_tensor(),
+            tensor1(&inverse).into_arc_tensor(),
+            counts.into_arc_tensor()
+        ))
+    }
+}
+
+impl EvalOp for Unique {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = &inputs[0];
+        match input.datum_type() {
+            DatumType::Bool => self.eval_t::<bool>(input),
+            DatumType::String => self.eval_t::<String>(input),
+            dt => dispatch_numbers!(Self::eval_t(dt)(self, input)),
+        }
+    }
+}
+
+impl TypedOp for Unique {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let num_unique = self.num_unique.to_dim();
+        let (values, items) = if let Some(axis) = self.axis {
+            if axis >= inputs[0].rank() {
+                bail!("Invalid axis {} for {:?}", axis, inputs[0])
+            }
+            let mut shape = inputs[0].shape.to_tvec();
+            let items = std::mem::replace(&mut shape[axis], num_unique.clone());
+            (shape, items)
+        } else {
+            (tvec!(num_unique.clone()), inputs[0].shape.iter().product())
+        };
+        Ok(tvec!(
+            TypedFact::dt_shape(inputs[0].datum_type, &*values),
+            TypedFact::dt_shape(i64::datum_type(), &[num_unique.clone()]),
+            TypedFact::dt_shape(i64::datum_type(), &[items]),
+            TypedFact::dt_shape(i64::datum_type(), &[num_unique]),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flat_unsorted() {
+        let op = Unique::new(None, false, Symbol::new('u'));
+        let input = tensor2(&[[2f32, 1.], [1., 3.]]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor1(&[2f32, 1., 3.]));
+        assert_eq!(*output[1], tensor1(&[0i64, 1, 3]));
+        assert_eq!(*output[2], tensor1(&[0i64, 1, 1, 2]));
+        assert_eq!(*output[3], tensor1(&[1i64, 2, 1]));
+    }
+
+    #[test]
+    fn axis_sorted() {
+        let op = Unique::new(Some(0), true, Symbol::new('u'));
+        let input = tensor2(&[[1i32, 1], [1, 0], [1, 0]]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor2(&[[1i32, 0], [1, 1]]));
+        assert_eq!(*output[1], tensor1(&[1i64, 0]));
+        assert_eq!(*output[2], tensor1(&[1i64, 0, 0]));
+        assert_eq!(*output[3], tensor1(&[2i64, 1]));
+    }
+}
@@ -137,6 +137,26 @@ impl EvalOp for TypedBinOp {
         debug_assert_eq!(a.rank(), b.rank());
         Ok(tvec!(self.0.eval(a, b)?.into_arc_tensor()))
     }
+
+    fn eval_into(&self, inputs: &[&Tensor], outputs: &mut [&mut Tensor]) -> TractResult<bool> {
+        eval_bin_into(&*self.0, inputs[0], inputs[1], outputs[0])
+    }
+}
+
+/// Out of place evaluation, for plain (not quantized) operands and result of
+/// the same type.
+fn eval_bin_into(
+    mini_op: &dyn BinMiniOp,
+    a: &Tensor,
+    b: &Tensor,
+    c: &mut Tensor,
+) -> TractResult<bool> {
+    let dt = c.datum_type();
+    if a.datum_type() != dt || b.datum_type() != dt || dt.is_quantized() {
+        return Ok(false);
+    }
+    mini_op.eval_out_of_place(c, a, b)?;
+    Ok(true)
 }
 
 impl TypedOp for TypedBinOp {
@@ -365,6 +385,10 @@ impl EvalOp for UnaryOp {
         debug_assert_eq!(self.a.rank(), inputs[0].rank());
         Ok(tvec!(self.mini_op.eval(self.a.clone(), inputs.remove(0))?.into_arc_tensor()))
     }
+
+    fn eval_into(&self, inputs: &[&Tensor], outputs: &mut [&mut Tensor]) -> TractResult<bool> {
+        eval_bin_into(&*self.mini_op, &self.a, inputs[0], outputs[0])
+    }
 }
 
 impl TypedOp for UnaryOp {
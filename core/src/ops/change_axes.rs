@@ -476,6 +476,15 @@ impl EvalOp for AxisOp {
         Ok(tvec!(input.into_arc_tensor()))
     }
 
+    fn eval_into(&self, inputs: &[&Tensor], outputs: &mut [&mut Tensor]) -> TractResult<bool> {
+        if let AxisOp::Move(..) = self {
+            return Ok(false);
+        }
+        // element order does not change: copy the data, the shape is the output one
+        unsafe { outputs[0].as_bytes_mut().copy_from_slice(inputs[0].as_bytes()) };
+        Ok(true)
+    }
+
     fn state(
         &self,
         _session: &mut SessionState,
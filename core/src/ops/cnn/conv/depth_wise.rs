@@ -2,6 +2,8 @@ use crate::internal::*;
 use crate::ops::cnn::Patch;
 use crate::ops::nn::DataShape;
 use ndarray::*;
+use std::ops::Range;
+use tract_linalg::multithread::{current_tract_executor, Executor};
 
 #[derive(Debug, Clone, new, Hash)]
 pub struct DepthWise {
@@ -44,51 +46,84 @@ impl DepthWise {
     ) -> TractResult<TVec<Arc<Tensor>>> {
         let img = args_1!(inputs);
         let img = img.to_array_view::<T>()?;
-        let iptr = img.as_ptr();
         let mut output = unsafe { ArrayD::<T>::uninit(&*self.output_shape.shape).assume_init() };
-        let optr = output.as_mut_ptr();
+        let channels = *self.input_shape.c();
+        if channels == 0 {
+            return Ok(tvec!(output.into_arc_tensor()));
+        }
         let kernel_chw = self.kernel_chw.to_array_view::<T>()?;
-        let k_stride_o = kernel_chw.strides()[0];
-        let k_stride_i = kernel_chw.strides()[1];
+        let bias = self.bias.as_ref().map(|b| b.as_slice::<T>()).transpose()?;
+        let ptrs = Ptrs(img.as_ptr(), output.as_mut_ptr(), kernel_chw.as_ptr());
+        let k_strides = (kernel_chw.strides()[0], kernel_chw.strides()[1]);
+        let executor = current_tract_executor();
+        let chunks = executor.threads().min(channels);
+        let chunk_len = (channels + chunks - 1) / chunks;
+        let job = |start: usize| unsafe {
+            self.eval_channels(ptrs, k_strides, bias, start..(start + chunk_len).min(channels))
+        };
+        match executor {
+            Executor::MultiThread(pool) if chunks > 1 => {
+                use rayon::prelude::*;
+                pool.install(|| (0..channels).into_par_iter().step_by(chunk_len).for_each(job))
+            }
+            _ => job(0),
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+
+    /// Compute the output channels stemming from the input channels in
+    /// `channels`. Disjoint ranges write to disjoint parts of the output.
+    unsafe fn eval_channels<T: Datum + Copy + num_traits::Zero + ndarray::LinalgScalar>(
+        &self,
+        ptrs: Ptrs<T>,
+        (k_stride_o, k_stride_i): (isize, isize),
+        bias: Option<&[T]>,
+        channels: Range<usize>,
+    ) {
+        let Ptrs(iptr, optr, kptr) = ptrs;
         let mult = *self.output_shape.c() / *self.input_shape.c();
         let n = *self.input_shape.n().unwrap_or(&1);
         let n_stride_i = *self.input_shape.n_stride().unwrap_or(&0);
         let n_stride_o = *self.output_shape.n_stride().unwrap_or(&0);
         let c_stride_i = *self.input_shape.c_stride();
         let c_stride_o = *self.output_shape.c_stride();
-        let bias = self.bias.as_ref().map(|b| b.as_slice::<T>()).transpose()?;
-        unsafe {
-            self.patch.visit_output(|visitor| {
-                for n in 0..n {
-                    let input_offset = n_stride_i * n;
-                    let output_offset = n_stride_o * n;
-                    for c in 0..*self.input_shape.c() {
-                        let input_offset = input_offset + c_stride_i * c;
-                        for m in 0..mult {
-                            let mut sum = if let Some(b) = &bias {
-                                *b.get_unchecked(m + c * mult)
-                            } else {
-                                T::zero()
-                            };
-                            let output_offset = output_offset + c_stride_o * (m + c * mult);
-                            let kptr = kernel_chw
-                                .as_ptr()
-                                .offset(k_stride_i * c as isize + k_stride_o * m as isize);
-                            for (ix, v) in visitor.valid_offsets_with_indexes() {
-                                let k = *kptr.offset(ix as isize);
-                                let i = *iptr.offset(input_offset as isize + v);
-                                sum = sum + k * i;
-                            }
-                            let ptr = optr.offset(output_offset as isize + visitor.output_offset);
-                            *ptr = sum;
+        self.patch.visit_output(|visitor| {
+            for n in 0..n {
+                let input_offset = n_stride_i * n;
+                let output_offset = n_stride_o * n;
+                for c in channels.clone() {
+                    let input_offset = input_offset + c_stride_i * c;
+                    for m in 0..mult {
+                        let mut sum = if let Some(b) = &bias {
+                            *b.get_unchecked(m + c * mult)
+                        } else {
+                            T::zero()
+                        };
+                        let output_offset = output_offset + c_stride_o * (m + c * mult);
+                        let kptr = kptr.offset(k_stride_i * c as isize + k_stride_o * m as isize);
+                        for (ix, v) in visitor.valid_offsets_with_indexes() {
+                            let k = *kptr.offset(ix as isize);
+                            let i = *iptr.offset(input_offset as isize + v);
+                            sum = sum + k * i;
                         }
+                        let ptr = optr.offset(output_offset as isize + visitor.output_offset);
+                        *ptr = sum;
                     }
                 }
-            });
-        }
-        Ok(tvec!(output.into_arc_tensor()))
+            }
+        });
+    }
+}
+
+struct Ptrs<T>(*const T, *mut T, *const T);
+impl<T> Clone for Ptrs<T> {
+    fn clone(&self) -> Ptrs<T> {
+        Ptrs(self.0, self.1, self.2)
     }
 }
+impl<T> Copy for Ptrs<T> {}
+unsafe impl<T> Send for Ptrs<T> {}
+unsafe impl<T> Sync for Ptrs<T> {}
 
 impl TypedOp for DepthWise {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
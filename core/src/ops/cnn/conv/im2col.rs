@@ -1,4 +1,5 @@
 use tract_linalg::frame::{MatMatMul, Packer};
+use tract_linalg::multithread::{current_tract_executor, Executor};
 
 use crate::internal::*;
 use ndarray::prelude::*;
@@ -6,6 +7,7 @@ use ndarray::prelude::*;
 use crate::ops::cnn::pools::{ConcretePoolGeometry, PoolGeometry};
 use crate::ops::cnn::{GeometryBound, PoolSpec, ResolveTo};
 use crate::ops::nn::{BaseDataShape, DataFormat, DataShape};
+use std::ops::Range;
 
 #[derive(Debug, Clone, PartialEq, Educe)]
 #[educe(Hash)]
@@ -133,6 +135,31 @@ impl Im2Col {
         output_shape.push(b_pack.len(n).into());
         Ok(output_shape)
     }
+
+    /// Split the packing in jobs over (batch, group) pairs, and over input
+    /// channel ranges if there are not enough pairs to occupy the threads.
+    fn jobs(
+        &self,
+        geometry: &ConcreteGeometry,
+        threads: usize,
+    ) -> Vec<(usize, usize, Range<usize>)> {
+        let pairs = *geometry.input_shape_with_n.n().unwrap_or(&1) * self.group;
+        let chunks = if threads > pairs && geometry.patcher != Patcher::Generic {
+            ((threads + pairs - 1) / pairs).min(geometry.ci_per_group)
+        } else {
+            1
+        };
+        let chunk_len = (geometry.ci_per_group + chunks - 1) / chunks;
+        let mut jobs = vec![];
+        for i in 0..*geometry.input_shape_with_n.n().unwrap_or(&1) {
+            for g in 0..self.group {
+                for start in (0..geometry.ci_per_group).step_by(chunk_len.max(1)) {
+                    jobs.push((i, g, start..(start + chunk_len).min(geometry.ci_per_group)));
+                }
+            }
+        }
+        jobs
+    }
 }
 
 impl Op for Im2Col {
@@ -167,24 +194,33 @@ impl EvalOp for Im2Col {
             if !self.pool_spec.data_format.has_n() {
                 input.insert_axis(0)?;
             }
-            // in the loop, we have normalized the input so that N is
+            // in the jobs, we have normalized the input so that N is
             // always here, and output so that N and G are there.
             if !geometry.pool.output_shape.shape.iter().any(|d| *d == 0) {
-                for i in 0..*geometry.input_shape_with_n.n().unwrap_or(&1) {
-                    let input = input.view_at_prefix(&[i])?;
-                    for g in 0..self.group {
-                        let full_prefix = [i, g];
-                        let actual_prefix = &full_prefix[..=(self.group > 1) as usize];
-                        let mut packed = output.view_at_prefix_mut(actual_prefix)?;
-                        dispatch_copy_by_size!(Patcher::patch(input.datum_type())(
-                            &geometry.patcher,
-                            &geometry,
-                            &input,
-                            &mut packed,
-                            g,
-                            pad_value.as_deref()
-                        ))?
+                let executor = current_tract_executor();
+                let jobs = self.jobs(&geometry, executor.threads());
+                let output = &output;
+                let job = |(i, g, ci): &(usize, usize, Range<usize>)| -> TractResult<()> {
+                    let input = input.view_at_prefix(&[*i])?;
+                    let full_prefix = [*i, *g];
+                    let actual_prefix = &full_prefix[..=(self.group > 1) as usize];
+                    let mut packed = TensorView::at_prefix(output, actual_prefix)?;
+                    dispatch_copy_by_size!(Patcher::patch(input.datum_type())(
+                        &geometry.patcher,
+                        &geometry,
+                        &input,
+                        &mut packed,
+                        *g,
+                        ci.clone(),
+                        pad_value.as_deref()
+                    ))
+                };
+                match executor {
+                    Executor::MultiThread(pool) if jobs.len() > 1 => {
+                        use rayon::prelude::*;
+                        pool.install(|| jobs.par_iter().try_for_each(job))?
                     }
+                    _ => jobs.iter().try_for_each(job)?,
                 }
             }
             output.set_shape_unchecked(&geometry.packed_shape);
@@ -240,25 +276,30 @@ impl Patcher {
         input: &'i TensorView,
         pack: &'p mut TensorView,
         g: usize,
+        ci: Range<usize>,
         pad_value: Option<&Tensor>,
     ) -> TractResult<()> {
         match self {
-            Patcher::Valid1d => Self::valid_1d::<T>(geo, input, pack, g),
-            Patcher::Valid2d => Self::valid_2d::<T>(geo, input, pack, g),
+            Patcher::Valid1d => Self::valid_1d::<T>(geo, input, pack, g, ci),
+            Patcher::Valid2d => Self::valid_2d::<T>(geo, input, pack, g, ci),
             Patcher::Padded2d => Self::padded_2d::<T>(
                 geo,
                 input,
                 pack,
                 g,
+                ci,
                 pad_value.unwrap_or(&Tensor::zero_scalar::<T>()?),
             ),
-            _ => Self::generic::<T>(
-                geo,
-                input,
-                pack,
-                g,
-                pad_value.unwrap_or(&Tensor::zero_scalar::<T>()?),
-            ),
+            _ => {
+                debug_assert_eq!(ci, 0..geo.ci_per_group);
+                Self::generic::<T>(
+                    geo,
+                    input,
+                    pack,
+                    g,
+                    pad_value.unwrap_or(&Tensor::zero_scalar::<T>()?),
+                )
+            }
         }
     }
 
@@ -302,18 +343,23 @@ impl Patcher {
         input: &'i TensorView,
         pack: &'p mut TensorView,
         g: usize,
+        ci_range: Range<usize>,
     ) -> TractResult<()> {
         unsafe {
             let x_stride = *geometry.input_shape_with_n.h_stride() as isize
                 * geometry.pool.patch.spec.strides[0] as isize;
             let c_stride = *geometry.input_shape_with_n.c_stride() as isize;
-            let pack = pack.as_slice_mut_unchecked::<T>();
-            let mut writer = geometry.b_pack.write_with_k_outer(pack, geometry.n);
+            let kernel_len = geometry.pool.patch.standard_layout_data_field.len();
+            let mut writer = geometry.b_pack.write_with_k_outer_from(
+                pack.as_ptr_mut_unchecked::<T>(),
+                geometry.n,
+                ci_range.start * kernel_len,
+            );
             let iptr = input.as_ptr_unchecked::<T>();
             let iptr = iptr.offset(
                 (g * geometry.ci_per_group * geometry.input_shape_with_n.c_stride()) as isize,
             );
-            for ci in 0..geometry.ci_per_group {
+            for ci in ci_range {
                 let iptr = iptr.offset(ci as isize * c_stride);
                 for koffset in &geometry.pool.patch.standard_layout_data_field {
                     let iptr = iptr.offset(*koffset as isize);
@@ -332,11 +378,11 @@ impl Patcher {
         input: &'i TensorView,
         pack: &'p mut TensorView,
         g: usize,
+        ci_range: Range<usize>,
         pad_value: &Tensor,
     ) -> TractResult<()> {
         unsafe {
             let pad_value = *pad_value.to_scalar_unchecked();
-            let pack = pack.as_slice_mut_unchecked::<T>();
             let y_stride = geometry.pool.patch.spec.strides[0] as isize;
             let x_stride = geometry.pool.patch.spec.strides[1] as isize;
             let shape = &geometry.input_shape_with_n;
@@ -346,10 +392,14 @@ impl Patcher {
             let input_heigth = shape.hw_dims()[0] as isize;
             let input_width = shape.hw_dims()[1] as isize;
             let kernel_len = geometry.pool.patch.standard_layout_data_field.len();
-            let mut writer = geometry.b_pack.write_with_k_outer(pack, geometry.n);
+            let mut writer = geometry.b_pack.write_with_k_outer_from(
+                pack.as_ptr_mut_unchecked::<T>(),
+                geometry.n,
+                ci_range.start * kernel_len,
+            );
             let iptr = input.as_ptr_unchecked::<T>();
             let iptr = iptr.offset((g * geometry.ci_per_group * shape.c_stride()) as isize);
-            for ci in 0..geometry.ci_per_group {
+            for ci in ci_range {
                 let iptr = iptr.offset(ci as isize * c_stride_ptr);
                 for kitem in 0..kernel_len {
                     let dy = *geometry.pool.patch.data_field.as_ptr().offset(kitem as isize * 2);
@@ -388,19 +438,24 @@ impl Patcher {
         input: &'i TensorView,
         pack: &'p mut TensorView,
         g: usize,
+        ci_range: Range<usize>,
     ) -> TractResult<()> {
         unsafe {
-            let pack = pack.as_slice_mut_unchecked::<T>();
             let shape = &geometry.input_shape_with_n;
             let y_stride = geometry.pool.patch.spec.strides[0] as isize;
             let x_stride = geometry.pool.patch.spec.strides[1] as isize;
             let y_stride_ptr = y_stride * *shape.h_stride() as isize;
             let x_stride_ptr = x_stride * *shape.w_stride() as isize;
             let c_stride_ptr = *shape.c_stride() as isize;
-            let mut writer = geometry.b_pack.write_with_k_outer(pack, geometry.n);
+            let kernel_len = geometry.pool.patch.standard_layout_data_field.len();
+            let mut writer = geometry.b_pack.write_with_k_outer_from(
+                pack.as_ptr_mut_unchecked::<T>(),
+                geometry.n,
+                ci_range.start * kernel_len,
+            );
             let iptr = input.as_ptr_unchecked::<T>();
             let iptr = iptr.offset((g * geometry.ci_per_group * shape.c_stride()) as isize);
-            for ci in 0..geometry.ci_per_group {
+            for ci in ci_range {
                 let iptr = iptr.offset(ci as isize * c_stride_ptr);
                 for koffset in &geometry.pool.patch.standard_layout_data_field {
                     let iptr = iptr.offset(*koffset as isize);
@@ -6,6 +6,7 @@ pub mod proptest;
 mod proptest_q;
 mod q_sum_b;
 mod unary;
+pub mod winograd;
 
 use crate::internal::*;
 
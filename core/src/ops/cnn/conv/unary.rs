@@ -8,6 +8,7 @@ use crate::ops::matmul::mir_quant::QParamKind;
 
 use super::depth_wise::DepthWise;
 use super::im2col::Im2Col;
+use super::winograd::{self, WinogradInput, WinogradOutput, WinogradTile};
 use crate::ops::cnn::conv::KernelFormat;
 use crate::ops::cnn::pools::{ConcretePoolGeometry, PoolGeometry, PoolSpec};
 use crate::ops::matmul::lir_unary::{
@@ -466,6 +467,82 @@ impl ConvUnary {
         Ok(wire)
     }
 
+    /// Pick a Winograd tile and transform the kernel, if the convolution is
+    /// eligible and the transformed kernel passes the f32 accuracy guard.
+    /// F(4x4, 3x3) falls back to F(2x2, 3x3) if it is not accurate enough.
+    fn winograd_kernel(
+        &self,
+        input_fact: &TypedFact,
+    ) -> TractResult<Option<(WinogradTile, DataShape, Tensor)>> {
+        if input_fact.datum_type != f32::datum_type()
+            || self.kernel.datum_type() != f32::datum_type()
+            || self.q_params.is_some()
+        {
+            return Ok(None);
+        }
+        let input_shape = if let Some(shape) = input_fact.shape.as_concrete() {
+            self.pool_spec.data_format.shape(shape.into())?
+        } else {
+            return Ok(None);
+        };
+        let mut tile = if let Some(tile) = winograd::should_use_winograd(
+            &input_shape,
+            &self.pool_spec,
+            self.group,
+            self.output_channels(),
+        ) {
+            tile
+        } else {
+            return Ok(None);
+        };
+        let (ci, co) = (self.input_channels(), self.output_channels());
+        let kernel = self.kernel_as_group_o_ihw()?;
+        let kernel = kernel.as_slice::<f32>()?;
+        loop {
+            let u = tile.transform_kernel(kernel, co, ci)?;
+            if tile.is_accurate(kernel, u.as_slice::<f32>()?, co, ci) {
+                return Ok(Some((tile, input_shape, u)));
+            }
+            if tile == WinogradTile::F4x4 {
+                tile = WinogradTile::F2x2;
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn wire_as_winograd(
+        &self,
+        model: &mut TypedModel,
+        name: &str,
+        wire: OutletId,
+        tile: WinogradTile,
+        input_shape: DataShape,
+        kernel: Tensor,
+    ) -> TractResult<OutletId> {
+        use crate::ops::matmul::MatMulUnary;
+        let output_shape = self.pool_spec.output_shape(&*input_shape.shape)?;
+        let padding =
+            self.pool_spec.padding.compute(input_shape.hw_dims(), &[3, 3], &[1, 1], &[1, 1]);
+        let pad_before = padding.iter().map(|d| d.pad_before).collect();
+        let wire = model.wire_node(
+            format!("{}.winograd_input", name),
+            WinogradInput::new(tile, input_shape, output_shape.clone(), pad_before),
+            &[wire],
+        )?[0];
+        let wire = model.wire_node(
+            format!("{}.winograd_matmul", name),
+            MatMulUnary::new(kernel.into_arc_tensor(), false, false, false),
+            &[wire],
+        )?[0];
+        let wire = model.wire_node(
+            name,
+            WinogradOutput::new(tile, output_shape, self.bias.clone()),
+            &[wire],
+        )?[0];
+        Ok(wire)
+    }
+
     fn compute_geo(
         &self,
         input_fact: &TypedFact,
@@ -1049,6 +1126,15 @@ impl TypedOp for ConvUnary {
                 patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
                 patch.obliterate(node.id)?;
                 return Ok(Some(patch));
+            } else if let Some((tile, input_shape, kernel)) = self.winograd_kernel(input_fact)? {
+                let mut patch = TypedModelPatch::default();
+                let wire = patch.tap_model(model, node.inputs[0])?;
+                let wire = self
+                    .wire_as_winograd(&mut patch, &*node.name, wire, tile, input_shape, kernel)
+                    .context("in wire_as_winograd")?;
+                patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
+                patch.obliterate(node.id)?;
+                return Ok(Some(patch));
             } else if input_fact
                 .shape
                 .as_concrete()
@@ -1150,6 +1236,104 @@ mod test {
         assert_eq!(&*output[0], &tensor4(&[[[[8i32, 12], [20, 24]]]]));
     }
 
+    #[test]
+    fn conv_f16_codegen() -> TractResult<()> {
+        let op = ConvUnary {
+            pool_spec: PoolSpec::new(NCHW, tvec!(3, 3), PaddingSpec::Valid, None, None, Some(2)),
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: Tensor::from_shape(
+                &[2, 1, 3, 3],
+                &(0..18).map(|i| f16::from(i as f32 / 4.0)).collect::<Vec<_>>(),
+            )?
+            .into_arc_tensor(),
+            group: 1,
+            bias: None,
+            q_params: None,
+        };
+        let mut model = TypedModel::default();
+        let source =
+            model.add_source("s", TypedFact::dt_shape(f16::datum_type(), &[1, 1, 4, 5]))?;
+        let conv = model.wire_node("conv", op, &[source])?;
+        model.set_output_outlets(&conv)?;
+        let input = Tensor::from_shape(
+            &[1, 1, 4, 5],
+            &(0..20).map(|i| f16::from(i as f32 / 8.0)).collect::<Vec<_>>(),
+        )?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?.remove(0);
+        let optimized = model.into_optimized()?;
+        assert!(optimized.nodes().iter().any(|n| n.op_is::<LirMatMulUnary>()));
+        let found = optimized.into_runnable()?.run(tvec!(input))?.remove(0);
+        found.close_enough(&expected, true)
+    }
+
+    fn check_multithread(
+        fmt: DataFormat,
+        padding: PaddingSpec,
+        input_shape: &[usize],
+        kernel_shape: &[usize],
+        group: usize,
+        op_check: impl Fn(&TypedNode) -> bool,
+    ) -> TractResult<()> {
+        let input_shape = fmt.shape(input_shape)?;
+        let co = kernel_shape[0];
+        let op = ConvUnary {
+            pool_spec: PoolSpec::new(fmt, kernel_shape[2..].into(), padding, None, None, Some(co)),
+            kernel_fmt: KernelFormat::OIHW,
+            kernel: Tensor::from_shape(
+                kernel_shape,
+                &(0..kernel_shape.iter().product())
+                    .map(|i| (i % 7) as f32 - 3.0)
+                    .collect::<Vec<_>>(),
+            )?
+            .into_arc_tensor(),
+            group,
+            bias: None,
+            q_params: None,
+        };
+        let mut model = TypedModel::default();
+        let source =
+            model.add_source("s", TypedFact::dt_shape(f32::datum_type(), input_shape.shape))?;
+        let conv = model.wire_node("conv", op, &[source])?;
+        model.set_output_outlets(&conv)?;
+        let plan = model.into_optimized()?.into_runnable()?;
+        assert!(plan.model().nodes().iter().any(op_check));
+        let input = Tensor::from_shape(
+            input_shape.shape,
+            &(0..input_shape.shape.iter().product()).map(|i| (i % 5) as f32).collect::<Vec<_>>(),
+        )?;
+        let expected = plan.run(tvec!(input.clone()))?.remove(0);
+        let mut state = SimpleState::new(&plan)?;
+        state.set_executor(tract_linalg::multithread::Executor::multithread(3)?);
+        let found = state.run(tvec!(input))?.remove(0);
+        found.close_enough(&expected, false)
+    }
+
+    #[test]
+    fn im2col_multithread_over_batch_and_group() -> TractResult<()> {
+        check_multithread(NCHW, PaddingSpec::Valid, &[2, 4, 5, 6], &[4, 2, 2, 2], 2, |n| {
+            n.op_is::<Im2Col>()
+        })
+    }
+
+    #[test]
+    fn im2col_multithread_over_channels() -> TractResult<()> {
+        check_multithread(HWC, PaddingSpec::SameUpper, &[5, 6, 7], &[3, 7, 3, 2], 1, |n| {
+            n.op_is::<Im2Col>()
+        })
+    }
+
+    #[test]
+    fn im2col_multithread_over_channels_1d() -> TractResult<()> {
+        check_multithread(CHW, PaddingSpec::Valid, &[5, 8], &[3, 5, 3], 1, |n| n.op_is::<Im2Col>())
+    }
+
+    #[test]
+    fn depth_wise_multithread() -> TractResult<()> {
+        check_multithread(NHWC, PaddingSpec::SameUpper, &[2, 5, 6, 7], &[7, 1, 3, 3], 7, |n| {
+            n.op_is::<DepthWise>()
+        })
+    }
+
     #[test]
     fn conv_vs_direct_arm_ml_kws_cnn_m_0() {
         let input = NHWC.from_n_c_hw(1, 1, &[49, 10]).unwrap();
@@ -0,0 +1,541 @@
+//! Winograd minimal filtering for 3x3, stride 1 convolutions.
+//!
+//! F(m x m, 3 x 3) computes a m x m output tile from a (m + 2) x (m + 2)
+//! input tile with (m + 2)² multiplications per channel pair instead of 9 m².
+//! With U = G.g.Gt the transformed kernel and V = Bt.d.B the transformed
+//! input tile, the output tile is Y = At.(U ⊙ V).A (Lavin & Gray, 2015).
+//!
+//! The convolution is lowered to three nodes: WinogradInput (input tiles to
+//! the transformed domain, one matrix per position in the tile), a batched
+//! MatMulUnary with the pre-transformed kernel as constant, and
+//! WinogradOutput (back to the output tiles, plus bias).
+use crate::internal::*;
+use crate::ops::cnn::PoolSpec;
+use crate::ops::nn::DataShape;
+use num_traits::Float;
+
+/// Maximum error of the transformed computation on a probe tile, relative
+/// to the sum of the absolute values of the products.
+pub const F32_TOLERANCE: f64 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WinogradTile {
+    F2x2,
+    F4x4,
+}
+
+#[rustfmt::skip]
+const F2X2_BT: [f32; 16] = [
+    1.0,  0.0, -1.0,  0.0,
+    0.0,  1.0,  1.0,  0.0,
+    0.0, -1.0,  1.0,  0.0,
+    0.0,  1.0,  0.0, -1.0,
+];
+
+#[rustfmt::skip]
+const F2X2_G: [f64; 12] = [
+    1.0,  0.0, 0.0,
+    0.5,  0.5, 0.5,
+    0.5, -0.5, 0.5,
+    0.0,  0.0, 1.0,
+];
+
+#[rustfmt::skip]
+const F2X2_AT: [f32; 8] = [
+    1.0, 1.0,  1.0,  0.0,
+    0.0, 1.0, -1.0, -1.0,
+];
+
+#[rustfmt::skip]
+const F4X4_BT: [f32; 36] = [
+    4.0,  0.0, -5.0,  0.0, 1.0, 0.0,
+    0.0, -4.0, -4.0,  1.0, 1.0, 0.0,
+    0.0,  4.0, -4.0, -1.0, 1.0, 0.0,
+    0.0, -2.0, -1.0,  2.0, 1.0, 0.0,
+    0.0,  2.0, -1.0, -2.0, 1.0, 0.0,
+    0.0,  4.0,  0.0, -5.0, 0.0, 1.0,
+];
+
+#[rustfmt::skip]
+const F4X4_G: [f64; 18] = [
+    1.0 / 4.0,   0.0,         0.0,
+    -1.0 / 6.0,  -1.0 / 6.0,  -1.0 / 6.0,
+    -1.0 / 6.0,  1.0 / 6.0,   -1.0 / 6.0,
+    1.0 / 24.0,  1.0 / 12.0,  1.0 / 6.0,
+    1.0 / 24.0,  -1.0 / 12.0, 1.0 / 6.0,
+    0.0,         0.0,         1.0,
+];
+
+#[rustfmt::skip]
+const F4X4_AT: [f32; 24] = [
+    1.0, 1.0,  1.0, 1.0,  1.0, 0.0,
+    0.0, 1.0, -1.0, 2.0, -2.0, 0.0,
+    0.0, 1.0,  1.0, 4.0,  4.0, 0.0,
+    0.0, 1.0, -1.0, 8.0, -8.0, 1.0,
+];
+
+impl WinogradTile {
+    /// Output tile side.
+    pub fn m(&self) -> usize {
+        match self {
+            WinogradTile::F2x2 => 2,
+            WinogradTile::F4x4 => 4,
+        }
+    }
+
+    /// Input tile side.
+    pub fn alpha(&self) -> usize {
+        self.m() + 2
+    }
+
+    fn bt(&self) -> &'static [f32] {
+        match self {
+            WinogradTile::F2x2 => &F2X2_BT,
+            WinogradTile::F4x4 => &F4X4_BT,
+        }
+    }
+
+    fn g(&self) -> &'static [f64] {
+        match self {
+            WinogradTile::F2x2 => &F2X2_G,
+            WinogradTile::F4x4 => &F4X4_G,
+        }
+    }
+
+    fn at(&self) -> &'static [f32] {
+        match self {
+            WinogradTile::F2x2 => &F2X2_AT,
+            WinogradTile::F4x4 => &F4X4_AT,
+        }
+    }
+
+    /// Transform a (co, ci, 3, 3) kernel to a (alpha², co, ci) tensor.
+    ///
+    /// The transformation is performed in f64, and rounded once to f32.
+    pub fn transform_kernel(&self, kernel: &[f32], co: usize, ci: usize) -> TractResult<Tensor> {
+        let alpha = self.alpha();
+        let mut u = Tensor::zero::<f32>(&[alpha * alpha, co, ci])?;
+        let u_slice = u.as_slice_mut::<f32>()?;
+        let mut g = [0f64; 9];
+        let mut tmp = vec![0f64; alpha * 3];
+        let mut transformed = vec![0f64; alpha * alpha];
+        for o in 0..co {
+            for i in 0..ci {
+                for (ix, g) in g.iter_mut().enumerate() {
+                    *g = kernel[(o * ci + i) * 9 + ix] as f64;
+                }
+                sandwich(self.g(), alpha, 3, &g, &mut tmp, &mut transformed);
+                for (xi, v) in transformed.iter().enumerate() {
+                    u_slice[(xi * co + o) * ci + i] = *v as f32;
+                }
+            }
+        }
+        Ok(u)
+    }
+
+    /// Accuracy guard: compare the transformed computation in f32 to a direct
+    /// f64 convolution on a probe input tile.
+    pub fn is_accurate(&self, kernel: &[f32], u: &[f32], co: usize, ci: usize) -> bool {
+        let (m, alpha) = (self.m(), self.alpha());
+        let mut seed = 0x2545_f491u32;
+        let probe: Vec<f32> = (0..ci * alpha * alpha)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1 << 23) as f32 - 1.0
+            })
+            .collect();
+        let mut tmp = vec![0f32; alpha * alpha];
+        let mut v = vec![0f32; ci * alpha * alpha];
+        for i in 0..ci {
+            let tile = &probe[i * alpha * alpha..][..alpha * alpha];
+            sandwich(self.bt(), alpha, alpha, tile, &mut tmp, &mut v[i * alpha * alpha..]);
+        }
+        let mut product = vec![0f32; alpha * alpha];
+        let mut y = vec![0f32; m * m];
+        for o in 0..co {
+            for xi in 0..alpha * alpha {
+                product[xi] =
+                    (0..ci).map(|i| u[(xi * co + o) * ci + i] * v[i * alpha * alpha + xi]).sum();
+            }
+            sandwich(self.at(), m, alpha, &product, &mut tmp, &mut y);
+            for p in 0..m {
+                for q in 0..m {
+                    let mut exact = 0f64;
+                    let mut bound = 0f64;
+                    for i in 0..ci {
+                        for kh in 0..3 {
+                            for kw in 0..3 {
+                                let g = kernel[(o * ci + i) * 9 + kh * 3 + kw] as f64;
+                                let d = probe[(i * alpha + p + kh) * alpha + q + kw] as f64;
+                                exact += g * d;
+                                bound += (g * d).abs();
+                            }
+                        }
+                    }
+                    if (y[p * m + q] as f64 - exact).abs() > F32_TOLERANCE * bound {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// out = left . x . left^T, with left a rows x cols matrix, x a cols x cols
+/// matrix, all in row major order. tmp must hold rows x cols elements.
+fn sandwich<T: Float>(left: &[T], rows: usize, cols: usize, x: &[T], tmp: &mut [T], out: &mut [T]) {
+    for r in 0..rows {
+        for k in 0..cols {
+            let mut sum = T::zero();
+            for j in 0..cols {
+                sum = sum + left[r * cols + j] * x[j * cols + k];
+            }
+            tmp[r * cols + k] = sum;
+        }
+    }
+    for r in 0..rows {
+        for s in 0..rows {
+            let mut sum = T::zero();
+            for k in 0..cols {
+                sum = sum + tmp[r * cols + k] * left[s * cols + k];
+            }
+            out[r * rows + s] = sum;
+        }
+    }
+}
+
+/// Pick a tile size for a convolution, if the Winograd lowering applies and
+/// is worth it.
+pub fn should_use_winograd(
+    input_shape: &DataShape,
+    pool_spec: &PoolSpec,
+    group: usize,
+    output_channels: usize,
+) -> Option<WinogradTile> {
+    if input_shape.hw_rank() != 2
+        || group != 1
+        || &*pool_spec.kernel_shape != &[3, 3]
+        || (0..2).any(|ax| pool_spec.stride(ax) != 1 || pool_spec.dilation(ax) != 1)
+    {
+        return None;
+    }
+    // below that, the transforms cost more than they save on the products
+    if *input_shape.c() < 8 || output_channels < 8 {
+        return None;
+    }
+    let output = pool_spec.padding.compute(input_shape.hw_dims(), &[3, 3], &[1, 1], &[1, 1]);
+    if output.iter().all(|d| d.convoluted >= 8) {
+        Some(WinogradTile::F4x4)
+    } else if output.iter().all(|d| d.convoluted >= 2) {
+        Some(WinogradTile::F2x2)
+    } else {
+        None
+    }
+}
+
+fn tiles(tile: WinogradTile, output_shape: &DataShape) -> (usize, usize) {
+    let m = tile.m();
+    let hw = output_shape.hw_dims();
+    ((hw[0] + m - 1) / m, (hw[1] + m - 1) / m)
+}
+
+/// Input transform: from the image to a (alpha², c, n x tiles) tensor.
+#[derive(Debug, Clone, new, Hash)]
+pub struct WinogradInput {
+    pub tile: WinogradTile,
+    pub input_shape: DataShape,
+    pub output_shape: DataShape,
+    pub pad_before: TVec<usize>,
+}
+
+impl_dyn_hash!(WinogradInput);
+
+impl WinogradInput {
+    fn output_tensor_shape(&self) -> TVec<usize> {
+        let (th, tw) = tiles(self.tile, &self.output_shape);
+        let n = *self.input_shape.n().unwrap_or(&1);
+        let alpha = self.tile.alpha();
+        tvec!(alpha * alpha, *self.input_shape.c(), n * th * tw)
+    }
+}
+
+impl Op for WinogradInput {
+    fn name(&self) -> Cow<str> {
+        "WinogradInput".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{:?}", self.tile)])
+    }
+
+    op_core_lir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for WinogradInput {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.as_slice::<f32>()?;
+        let (m, alpha) = (self.tile.m(), self.tile.alpha());
+        let (th, tw) = tiles(self.tile, &self.output_shape);
+        let shape = self.output_tensor_shape();
+        let (c, t) = (shape[1], shape[2]);
+        let mut output = Tensor::zero::<f32>(&shape)?;
+        let out = output.as_slice_mut::<f32>()?;
+        let n_stride = *self.input_shape.n_stride().unwrap_or(&0);
+        let c_stride = *self.input_shape.c_stride();
+        let (h_stride, w_stride) =
+            (self.input_shape.hw_strides()[0], self.input_shape.hw_strides()[1]);
+        let (h, w) = (self.input_shape.hw_dims()[0], self.input_shape.hw_dims()[1]);
+        let mut d = vec![0f32; alpha * alpha];
+        let mut tmp = vec![0f32; alpha * alpha];
+        let mut v = vec![0f32; alpha * alpha];
+        for n in 0..*self.input_shape.n().unwrap_or(&1) {
+            for ci in 0..c {
+                let offset = n * n_stride + ci * c_stride;
+                for ty in 0..th {
+                    for tx in 0..tw {
+                        for i in 0..alpha {
+                            let y = (ty * m + i) as isize - self.pad_before[0] as isize;
+                            for j in 0..alpha {
+                                let x = (tx * m + j) as isize - self.pad_before[1] as isize;
+                                d[i * alpha + j] =
+                                    if y >= 0 && (y as usize) < h && x >= 0 && (x as usize) < w {
+                                        input
+                                            [offset + y as usize * h_stride + x as usize * w_stride]
+                                    } else {
+                                        0.0
+                                    };
+                            }
+                        }
+                        sandwich(self.tile.bt(), alpha, alpha, &d, &mut tmp, &mut v);
+                        let tile_ix = (n * th + ty) * tw + tx;
+                        for (xi, v) in v.iter().enumerate() {
+                            out[(xi * c + ci) * t + tile_ix] = *v;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for WinogradInput {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*self.output_tensor_shape())))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let shape = self.output_tensor_shape();
+        Ok(tvec!((
+            Cost::FMA(inputs[0].datum_type),
+            (shape[1] * shape[2] * 2 * self.tile.alpha().pow(3)).to_dim()
+        )))
+    }
+
+    as_op!();
+}
+
+/// Output transform: from the (alpha², co, n x tiles) products to the output
+/// image, adding the bias.
+#[derive(Debug, Clone, new, Hash)]
+pub struct WinogradOutput {
+    pub tile: WinogradTile,
+    pub output_shape: DataShape,
+    pub bias: Option<Arc<Tensor>>,
+}
+
+impl_dyn_hash!(WinogradOutput);
+
+impl Op for WinogradOutput {
+    fn name(&self) -> Cow<str> {
+        "WinogradOutput".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{:?}", self.tile)])
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_core_lir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for WinogradOutput {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let t = input.shape()[2];
+        let input = input.as_slice::<f32>()?;
+        let (m, alpha) = (self.tile.m(), self.tile.alpha());
+        let (th, tw) = tiles(self.tile, &self.output_shape);
+        let mut output = Tensor::zero::<f32>(&self.output_shape.shape)?;
+        let out = output.as_slice_mut::<f32>()?;
+        let co = *self.output_shape.c();
+        let n_stride = *self.output_shape.n_stride().unwrap_or(&0);
+        let c_stride = *self.output_shape.c_stride();
+        let (h_stride, w_stride) =
+            (self.output_shape.hw_strides()[0], self.output_shape.hw_strides()[1]);
+        let (h, w) = (self.output_shape.hw_dims()[0], self.output_shape.hw_dims()[1]);
+        let bias = self.bias.as_ref().map(|b| b.cast_to::<f32>()).transpose()?;
+        let bias = bias.as_ref().map(|b| b.as_slice::<f32>()).transpose()?;
+        let mut product = vec![0f32; alpha * alpha];
+        let mut tmp = vec![0f32; alpha * m];
+        let mut y = vec![0f32; m * m];
+        for n in 0..*self.output_shape.n().unwrap_or(&1) {
+            for o in 0..co {
+                let offset = n * n_stride + o * c_stride;
+                let b = bias.map(|b| b[o]).unwrap_or(0.0);
+                for ty in 0..th {
+                    for tx in 0..tw {
+                        let tile_ix = (n * th + ty) * tw + tx;
+                        for (xi, p) in product.iter_mut().enumerate() {
+                            *p = input[(xi * co + o) * t + tile_ix];
+                        }
+                        sandwich(self.tile.at(), m, alpha, &product, &mut tmp, &mut y);
+                        for i in 0..m.min(h - ty * m) {
+                            for j in 0..m.min(w - tx * m) {
+                                out[offset + (ty * m + i) * h_stride + (tx * m + j) * w_stride] =
+                                    y[i * m + j] + b;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for WinogradOutput {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*self.output_shape.shape)))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let (th, tw) = tiles(self.tile, &self.output_shape);
+        let n = *self.output_shape.n().unwrap_or(&1);
+        let alpha = self.tile.alpha();
+        Ok(tvec!((
+            Cost::FMA(inputs[0].datum_type),
+            (n * th
+                * tw
+                * *self.output_shape.c()
+                * (alpha + self.tile.m())
+                * alpha
+                * self.tile.m())
+            .to_dim()
+        )))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::{ConvUnary, KernelFormat, PaddingSpec};
+    use crate::ops::nn::DataFormat;
+
+    fn values(len: usize, seed: usize) -> Vec<f32> {
+        (0..len).map(|i| ((i * 7 + seed) % 13) as f32 / 4.0 - 1.5).collect()
+    }
+
+    fn check(
+        fmt: DataFormat,
+        padding: PaddingSpec,
+        hw: &[usize],
+        ci: usize,
+        co: usize,
+        tile: WinogradTile,
+    ) -> TractResult<()> {
+        let input_shape = fmt.from_n_c_hw(2, ci, hw)?;
+        let kernel = Tensor::from_shape(&[co, ci, 3, 3], &values(co * ci * 9, 1))?;
+        let op = ConvUnary::new(
+            PoolSpec::new(fmt, tvec!(3, 3), padding, None, None, Some(co)),
+            KernelFormat::OIHW,
+            kernel.into_arc_tensor(),
+            1,
+            Some(rctensor1(&values(co, 2))),
+            None,
+        );
+        let mut model = TypedModel::default();
+        let source =
+            model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &*input_shape.shape))?;
+        let conv = model.wire_node("conv", op, &[source])?;
+        model.set_output_outlets(&conv)?;
+        let input = Tensor::from_shape(
+            &*input_shape.shape,
+            &values(input_shape.shape.iter().product(), 3),
+        )?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?.remove(0);
+        let optimized = model.into_optimized()?;
+        let found_tile =
+            optimized.nodes().iter().find_map(|n| n.op_as::<WinogradInput>()).map(|op| op.tile);
+        assert_eq!(found_tile, Some(tile));
+        let found = optimized.into_runnable()?.run(tvec!(input))?.remove(0);
+        found.close_enough(&expected, true)
+    }
+
+    #[test]
+    fn f4x4_nchw_valid() -> TractResult<()> {
+        check(DataFormat::NCHW, PaddingSpec::Valid, &[11, 13], 8, 9, WinogradTile::F4x4)
+    }
+
+    #[test]
+    fn f4x4_nhwc_same() -> TractResult<()> {
+        check(DataFormat::NHWC, PaddingSpec::SameUpper, &[9, 10], 9, 8, WinogradTile::F4x4)
+    }
+
+    #[test]
+    fn f2x2_nchw_same() -> TractResult<()> {
+        check(DataFormat::NCHW, PaddingSpec::SameUpper, &[5, 6], 8, 8, WinogradTile::F2x2)
+    }
+
+    #[test]
+    fn f2x2_nhwc_valid() -> TractResult<()> {
+        check(DataFormat::NHWC, PaddingSpec::Valid, &[7, 4], 10, 8, WinogradTile::F2x2)
+    }
+
+    #[test]
+    fn small_channels_are_not_lowered() {
+        let input_shape = DataFormat::NCHW.from_n_c_hw(1, 4, &[16, 16]).unwrap();
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(3, 3), PaddingSpec::Valid, None, None, Some(4));
+        assert_eq!(should_use_winograd(&input_shape, &pool_spec, 1, 4), None);
+    }
+
+    #[test]
+    fn guard_accepts_transformed_kernels() -> TractResult<()> {
+        let (co, ci) = (8, 16);
+        let kernel = values(co * ci * 9, 5);
+        for tile in &[WinogradTile::F2x2, WinogradTile::F4x4] {
+            let u = tile.transform_kernel(&kernel, co, ci)?;
+            assert!(tile.is_accurate(&kernel, u.as_slice::<f32>()?, co, ci));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn guard_rejects_inaccurate_kernels() -> TractResult<()> {
+        let (co, ci) = (8, 16);
+        let kernel = values(co * ci * 9, 5);
+        let tile = WinogradTile::F4x4;
+        let mut u = tile.transform_kernel(&kernel, co, ci)?;
+        u.as_slice_mut::<f32>()?[17] *= 1.001;
+        assert!(!tile.is_accurate(&kernel, u.as_slice::<f32>()?, co, ci));
+        Ok(())
+    }
+}
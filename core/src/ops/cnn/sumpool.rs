@@ -1,6 +1,7 @@
 use crate::internal::*;
 use num_traits::AsPrimitive;
 use std::iter::Sum;
+use std::ops::Mul;
 
 use crate::ops::cnn::pools::{ConcretePoolGeometry, PoolGeometry, PoolSpec};
 
@@ -105,6 +106,16 @@ impl EvalOp for LirSumPool {
                 geo.as_ref()
             ))?;
             values
+        } else if !self.normalize {
+            let mut values =
+                unsafe { Tensor::uninitialized_dt(input.datum_type(), &*geo.output_shape.shape)? };
+            dispatch_numbers!(Self::eval_sum_t(input.datum_type())(
+                self,
+                &*input,
+                values.as_ptr_mut()?,
+                geo.as_ref()
+            ))?;
+            values
         } else {
             let mut values =
                 unsafe { Tensor::uninitialized_dt(DatumType::F32, &*geo.output_shape.shape)? };
@@ -135,6 +146,35 @@ impl LirSumPool {
     where
         usize: AsPrimitive<T>,
     {
+        let field_len = geo.patch.standard_layout_data_field.len();
+        self.sum_t(input, values_ptr, geo, |valid_count| {
+            if self.normalize {
+                Some(if self.count_include_pad { field_len } else { valid_count }.as_().recip())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Plain sums, exact for integers.
+    fn eval_sum_t<T: Copy + Datum + Sum + Mul<Output = T>>(
+        &self,
+        input: &Tensor,
+        values_ptr: *mut T,
+        geo: &ConcretePoolGeometry,
+    ) -> TractResult<()> {
+        self.sum_t(input, values_ptr, geo, |_| None)
+    }
+
+    /// Sum over each window, multiplied by the factor `div` gives for the
+    /// window count of valid input positions, if any.
+    fn sum_t<T: Copy + Datum + Sum + Mul<Output = T>>(
+        &self,
+        input: &Tensor,
+        values_ptr: *mut T,
+        geo: &ConcretePoolGeometry,
+        div: impl Fn(usize) -> Option<T>,
+    ) -> TractResult<()> {
         let input_ptr = input.as_ptr::<T>()?;
 
         let n = *geo.input_shape.n().unwrap_or(&1);
@@ -142,18 +182,7 @@ impl LirSumPool {
         let n_stride_o = geo.output_shape.n_stride().unwrap_or(&0);
         unsafe {
             geo.patch.visit_output(|visitor| {
-                let div: Option<T> = if self.normalize {
-                    Some(
-                        if self.count_include_pad {
-                            geo.patch.standard_layout_data_field.len().as_()
-                        } else {
-                            visitor.valid_count().as_()
-                        }
-                        .recip(),
-                    )
-                } else {
-                    None
-                };
+                let div = div(visitor.valid_count());
                 for n in 0..n {
                     let input_offset = n * n_stride_i;
                     let output_offset = n * n_stride_o;
@@ -164,11 +193,8 @@ impl LirSumPool {
                             .valid_offsets()
                             .map(|v| *input_ptr.offset(v + input_offset as isize))
                             .sum::<T>();
-
-                        if let Some(div) = div {
-                            *values_ptr.offset(output_offset as isize + visitor.output_offset) =
-                                sum * div;
-                        }
+                        *values_ptr.offset(output_offset as isize + visitor.output_offset) =
+                            if let Some(div) = div { sum * div } else { sum };
                     }
                 }
             });
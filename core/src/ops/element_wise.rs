@@ -103,6 +103,17 @@ impl EvalOp for ElementWiseOp {
             Ok(inputs)
         }
     }
+
+    fn eval_into(&self, inputs: &[&Tensor], outputs: &mut [&mut Tensor]) -> TractResult<bool> {
+        if self.0.output_type(inputs[0].datum_type()).is_some()
+            || inputs[0].datum_type() != outputs[0].datum_type()
+        {
+            return Ok(false);
+        }
+        unsafe { outputs[0].as_bytes_mut().copy_from_slice(inputs[0].as_bytes()) };
+        self.0.eval_in_place(outputs[0])?;
+        Ok(true)
+    }
 }
 
 impl TypedOp for ElementWiseOp {
@@ -0,0 +1,263 @@
+use crate::internal::*;
+use num_traits::Float;
+use rustfft::{FftDirection, FftNum, FftPlanner};
+use tract_ndarray::{ArrayD, ArrayViewMutD, Axis};
+
+fn fft_lanes<T: FftNum>(
+    fft: &dyn rustfft::Fft<T>,
+    data: &mut ArrayViewMutD<Complex<T>>,
+    axis: usize,
+) {
+    let mut buffer = vec![Complex::new(T::zero(), T::zero()); fft.len()];
+    for mut lane in data.lanes_mut(Axis(axis)) {
+        buffer.iter_mut().zip(lane.iter()).for_each(|(b, x)| *b = *x);
+        fft.process(&mut buffer);
+        lane.iter_mut().zip(buffer.iter()).for_each(|(x, b)| *x = *b);
+    }
+}
+
+fn check_complex_float(fact: &TypedFact, axis: usize, op: &str) -> TractResult<()> {
+    if fact.datum_type != DatumType::ComplexF32 && fact.datum_type != DatumType::ComplexF64 {
+        bail!("{} expects complex float input, got {:?}", op, fact)
+    }
+    if axis >= fact.rank() {
+        bail!("{} axis {} is invalid for {:?}", op, axis, fact)
+    }
+    Ok(())
+}
+
+/// Discrete Fourier transform of a complex tensor along `axis`.
+///
+/// The inverse transform is not normalized: it is up to the caller to divide
+/// by the transform length.
+#[derive(Clone, Debug, Hash, new)]
+pub struct Fft {
+    pub axis: usize,
+    pub inverse: bool,
+}
+
+impl_dyn_hash!(Fft);
+
+impl Op for Fft {
+    fn name(&self) -> Cow<str> {
+        "Fft".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} inverse: {}", self.axis, self.inverse)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl Fft {
+    fn eval_t<T: FftNum>(&self, tensor: &mut Tensor) -> TractResult<()>
+    where
+        Complex<T>: Datum,
+    {
+        let len = tensor.shape()[self.axis];
+        if len == 0 {
+            return Ok(());
+        }
+        let direction = if self.inverse { FftDirection::Inverse } else { FftDirection::Forward };
+        let fft = FftPlanner::new().plan_fft(len, direction);
+        fft_lanes(&*fft, &mut tensor.to_array_view_mut::<Complex<T>>()?, self.axis);
+        Ok(())
+    }
+}
+
+impl EvalOp for Fft {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut tensor = args_1!(inputs).into_tensor();
+        match tensor.datum_type() {
+            DatumType::ComplexF32 => self.eval_t::<f32>(&mut tensor)?,
+            DatumType::ComplexF64 => self.eval_t::<f64>(&mut tensor)?,
+            dt => bail!("Fft does not support {:?}", dt),
+        }
+        Ok(tvec!(tensor.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Fft {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        check_complex_float(inputs[0], self.axis, "Fft")?;
+        Ok(tvec!(inputs[0].without_value()))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..inputs[0].rank()).filter(|&ax| ax != self.axis).map(AxisInfo::simple).collect())
+    }
+}
+
+/// Short-time Fourier transform of a complex tensor along `axis`.
+///
+/// Frames of `frame` samples are taken every `stride` samples, multiplied by
+/// the optional real window and transformed. The `axis` of the output
+/// enumerates the frames and is followed by a new axis of `frame` frequency
+/// bins.
+#[derive(Clone, Debug, Hash, new)]
+pub struct Stft {
+    pub axis: usize,
+    pub frame: usize,
+    pub stride: usize,
+    pub window: Option<Arc<Tensor>>,
+}
+
+impl_dyn_hash!(Stft);
+
+impl Op for Stft {
+    fn name(&self) -> Cow<str> {
+        "Stft".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "axis: {} frame: {} stride: {} window: {}",
+            self.axis,
+            self.frame,
+            self.stride,
+            self.window.is_some()
+        )])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl Stft {
+    fn eval_t<T: FftNum + Float + Datum>(&self, input: &Tensor) -> TractResult<Tensor>
+    where
+        Complex<T>: Datum,
+    {
+        let input = input.to_array_view::<Complex<T>>()?;
+        let len = input.shape()[self.axis];
+        if len < self.frame {
+            bail!("Stft needs at least {} samples, got {}", self.frame, len)
+        }
+        let frames = (len - self.frame) / self.stride + 1;
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[self.axis] = frames;
+        shape.insert(self.axis + 1, self.frame);
+        let mut output = ArrayD::from_elem(&*shape, Complex::new(T::zero(), T::zero()));
+        let window = self.window.as_ref().map(|w| w.cast_to::<T>()).transpose()?;
+        let window = window.as_ref().map(|w| w.as_slice::<T>()).transpose()?;
+        let fft = FftPlanner::new().plan_fft_forward(self.frame);
+        for f in 0..frames {
+            let start = f * self.stride;
+            let mut frame = output.index_axis_mut(Axis(self.axis), f);
+            frame.assign(&input.slice_axis(Axis(self.axis), (start..start + self.frame).into()));
+            if let Some(window) = window {
+                for mut lane in frame.lanes_mut(Axis(self.axis)) {
+                    lane.iter_mut().zip(window.iter()).for_each(|(x, w)| *x = *x * *w);
+                }
+            }
+            fft_lanes(&*fft, &mut frame, self.axis);
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+impl EvalOp for Stft {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = match input.datum_type() {
+            DatumType::ComplexF32 => self.eval_t::<f32>(&input)?,
+            DatumType::ComplexF64 => self.eval_t::<f64>(&input)?,
+            dt => bail!("Stft does not support {:?}", dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Stft {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        check_complex_float(inputs[0], self.axis, "Stft")?;
+        if self.frame == 0 || self.stride == 0 {
+            bail!("Stft frame and stride must be positive")
+        }
+        if let Some(window) = &self.window {
+            if window.len() != self.frame {
+                bail!("Stft window of {} values for frames of {}", window.len(), self.frame)
+            }
+        }
+        let mut shape: TVec<TDim> = inputs[0].shape.iter().collect();
+        shape[self.axis] = (shape[self.axis].clone() - self.frame.to_dim()) / self.stride + 1;
+        shape.insert(self.axis + 1, self.frame.to_dim());
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..inputs[0].rank())
+            .filter(|&ax| ax != self.axis)
+            .map(|ax| AxisInfo {
+                inputs: tvec!(Some(ax)),
+                outputs: tvec!(Some(if ax < self.axis { ax } else { ax + 1 })),
+                period: 1,
+                disposable: true,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn complex(values: &[f32]) -> Tensor {
+        let values = values.iter().map(|&re| Complex::new(re, 0f32)).collect::<Vec<_>>();
+        tensor1(&values)
+    }
+
+    fn close(a: &Tensor, b: &[Complex<f32>]) -> bool {
+        a.as_slice::<Complex<f32>>()
+            .unwrap()
+            .iter()
+            .zip(b.iter())
+            .all(|(a, b)| (a - b).norm() < 1e-5)
+    }
+
+    #[test]
+    fn fft_then_inverse() -> TractResult<()> {
+        let input = complex(&[1., 2., 3., 4.]);
+        let spectrum = Fft::new(0, false).eval(tvec!(input.clone().into_arc_tensor()))?;
+        let c = |re, im| Complex::new(re, im);
+        assert!(close(&spectrum[0], &[c(10., 0.), c(-2., 2.), c(-2., 0.), c(-2., -2.)]));
+        let back = Fft::new(0, true).eval(spectrum)?;
+        let expected = [c(4., 0.), c(8., 0.), c(12., 0.), c(16., 0.)];
+        assert!(close(&back[0], &expected));
+        Ok(())
+    }
+
+    #[test]
+    fn stft_frames() -> TractResult<()> {
+        let input = complex(&[1., 2., 3., 4., 5.]);
+        let window = rctensor1(&[1f32, 0.]);
+        let stft = Stft::new(0, 2, 2, Some(window));
+        let output = stft.eval(tvec!(input.into_arc_tensor()))?;
+        assert_eq!(output[0].shape(), &[2, 2]);
+        let c = |re| Complex::new(re, 0f32);
+        assert!(close(&output[0], &[c(1.), c(1.), c(3.), c(3.)]));
+        Ok(())
+    }
+}
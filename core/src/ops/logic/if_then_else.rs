@@ -0,0 +1,334 @@
+//! Conditional execution of one of two sub-models.
+use crate::internal::*;
+
+/// Runs `then_body` or `else_body` depending on its first input, a scalar
+/// boolean condition.
+///
+/// Each body input is fed by the outer input whose index is given by the
+/// input mapping of the body. Both bodies must compute outputs of the same
+/// types and shapes.
+#[derive(Debug, Clone, Hash)]
+pub struct If {
+    pub then_body: TypedModel,
+    pub then_input_mapping: Vec<usize>,
+    pub else_body: TypedModel,
+    pub else_input_mapping: Vec<usize>,
+    decluttered: bool,
+}
+
+impl_dyn_hash!(If);
+
+impl If {
+    pub fn new(
+        then_body: TypedModel,
+        then_input_mapping: Vec<usize>,
+        else_body: TypedModel,
+        else_input_mapping: Vec<usize>,
+    ) -> If {
+        If { then_body, then_input_mapping, else_body, else_input_mapping, decluttered: false }
+    }
+
+    pub fn to_codegen_op(&self, optimize_inner: bool) -> TractResult<LirIf> {
+        let (mut then_body, mut else_body) = (self.then_body.clone(), self.else_body.clone());
+        if optimize_inner {
+            then_body = then_body.into_optimized()?;
+            else_body = else_body.into_optimized()?;
+        }
+        Ok(LirIf(Arc::new(LirIfParams {
+            then_plan: Arc::new(SimplePlan::new(then_body)?),
+            then_input_mapping: self.then_input_mapping.clone(),
+            else_plan: Arc::new(SimplePlan::new(else_body)?),
+            else_input_mapping: self.else_input_mapping.clone(),
+        })))
+    }
+
+    fn declutter_bodies(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if !self.decluttered {
+            let mut new = self.clone();
+            new.then_body = self.then_body.clone().into_decluttered()?;
+            new.else_body = self.else_body.clone().into_decluttered()?;
+            new.decluttered = true;
+            Ok(Some(TypedModelPatch::replace_single_op(model, node, &node.inputs, new)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn branch(&self, cond: bool) -> (&TypedModel, &[usize]) {
+        if cond {
+            (&self.then_body, &self.then_input_mapping)
+        } else {
+            (&self.else_body, &self.else_input_mapping)
+        }
+    }
+
+    /// Wire the body selected by a constant condition in place of the op.
+    fn inline_branch(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        cond: bool,
+    ) -> TractResult<TypedModelPatch> {
+        let (body, input_mapping) = self.branch(cond);
+        let mut patch = TypedModelPatch::new(format!("Inline {} branch", cond));
+        let mut mapping: HashMap<OutletId, OutletId> = HashMap::default();
+        for (outlet, &ix) in body.input_outlets()?.iter().zip(input_mapping) {
+            mapping.insert(*outlet, patch.tap_model(model, node.inputs[ix])?);
+        }
+        for id in body.eval_order()? {
+            let inner = body.node(id);
+            if body.input_outlets()?.iter().any(|o| o.node == id) {
+                continue;
+            }
+            let inputs = inner.inputs.iter().map(|o| mapping[o]).collect::<TVec<_>>();
+            let name = format!("{}.{}", node.name, inner.name);
+            let outputs = patch.wire_node(name, inner.op.clone(), &inputs)?;
+            for (slot, outlet) in outputs.into_iter().enumerate() {
+                mapping.insert(OutletId::new(id, slot), outlet);
+            }
+        }
+        for (ix, outlet) in body.output_outlets()?.iter().enumerate() {
+            patch.shunt_outside(model, OutletId::new(node.id, ix), mapping[outlet])?;
+        }
+        Ok(patch)
+    }
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for If {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        self.to_codegen_op(false)?.state(session, node_id)
+    }
+}
+
+impl TypedOp for If {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].datum_type != bool::datum_type() || inputs[0].rank() != 0 {
+            bail!("If condition must be a boolean scalar, got {:?}", inputs[0])
+        }
+        let then_outputs = self.then_body.output_outlets()?.len();
+        let else_outputs = self.else_body.output_outlets()?.len();
+        if then_outputs != else_outputs {
+            bail!("If branches compute {} and {} outputs", then_outputs, else_outputs)
+        }
+        (0..then_outputs)
+            .map(|ix| {
+                let then_fact = self.then_body.output_fact(ix)?;
+                let else_fact = self.else_body.output_fact(ix)?;
+                if then_fact.datum_type != else_fact.datum_type
+                    || then_fact.shape != else_fact.shape
+                {
+                    bail!("If branches disagree on output {}: {:?} {:?}", ix, then_fact, else_fact)
+                }
+                Ok(TypedFact::dt_shape(then_fact.datum_type, then_fact.shape.clone()))
+            })
+            .collect()
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if let Some(cond) = &model.outlet_fact(node.inputs[0])?.konst {
+            return Ok(Some(self.inline_branch(model, node, cond.cast_to_scalar::<bool>()?)?));
+        }
+        self.declutter_bodies(model, node)
+    }
+
+    fn concretize_dims(
+        &self,
+        _source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        values: &SymbolValues,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs = node.inputs.iter().map(|o| mapping[&o]).collect::<TVec<_>>();
+        let op = Self {
+            then_body: self.then_body.concretize_dims(values)?,
+            else_body: self.else_body.concretize_dims(values)?,
+            ..self.clone()
+        };
+        target.wire_node(&node.name, op, &inputs)
+    }
+
+    fn codegen(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        Ok(Some(TypedModelPatch::replace_single_op(
+            &model,
+            node,
+            &node.inputs,
+            self.to_codegen_op(true)?,
+        )?))
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LirIfParams {
+    pub then_plan: Arc<TypedSimplePlan<TypedModel>>,
+    pub then_input_mapping: Vec<usize>,
+    pub else_plan: Arc<TypedSimplePlan<TypedModel>>,
+    pub else_input_mapping: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LirIf(Arc<LirIfParams>);
+
+impl std::ops::Deref for LirIf {
+    type Target = LirIfParams;
+    fn deref(&self) -> &LirIfParams {
+        &self.0
+    }
+}
+
+impl_dyn_hash!(LirIf);
+
+impl Op for LirIf {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_core_lir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for LirIf {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(State {
+            then_state: TypedSimpleState::new(Arc::clone(&self.then_plan))?,
+            else_state: TypedSimpleState::new(Arc::clone(&self.else_plan))?,
+            op: Arc::clone(&self.0),
+        })))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct State {
+    op: Arc<LirIfParams>,
+    then_state: TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+    else_state: TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+}
+
+impl OpState for State {
+    fn eval(
+        &mut self,
+        _session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let (state, input_mapping) = if inputs[0].cast_to_scalar::<bool>()? {
+            (&mut self.then_state, &self.op.then_input_mapping)
+        } else {
+            (&mut self.else_state, &self.op.else_input_mapping)
+        };
+        let inputs = input_mapping.iter().map(|&ix| inputs[ix].clone().into_tensor()).collect();
+        state.run(inputs)
+    }
+}
+
+impl TypedOp for LirIf {
+    as_op!();
+
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let body = self.then_plan.model();
+        (0..body.output_outlets()?.len())
+            .map(|ix| {
+                let fact = body.output_fact(ix)?;
+                Ok(TypedFact::dt_shape(fact.datum_type, fact.shape.clone()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    // x + y if the condition holds, x otherwise
+    fn model(cond: Option<bool>) -> TractResult<TypedModel> {
+        let scalar = TypedFact::dt_shape(f32::datum_type(), &[0usize; 0]);
+        let mut then_body = TypedModel::default();
+        let x = then_body.add_source("x", scalar.clone())?;
+        let y = then_body.add_source("y", scalar.clone())?;
+        let sum = then_body.wire_node("add", math::add::bin_typed(), &[x, y])?;
+        then_body.set_output_outlets(&sum)?;
+        let mut else_body = TypedModel::default();
+        let x = else_body.add_source("x", scalar.clone())?;
+        else_body.set_output_outlets(&[x])?;
+
+        let mut model = TypedModel::default();
+        let cond = if let Some(cond) = cond {
+            model.add_const("cond", tensor0(cond))?
+        } else {
+            model.add_source("cond", TypedFact::dt_shape(bool::datum_type(), &[0usize; 0]))?
+        };
+        let x = model.add_source("x", scalar)?;
+        let y = model.add_const("y", tensor0(2f32))?;
+        let op = If::new(then_body, vec![1, 2], else_body, vec![1]);
+        let outputs = model.wire_node("if", op, &[cond, x, y])?;
+        model.set_output_outlets(&outputs)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn branches() -> TractResult<()> {
+        let model = model(None)?.into_runnable()?;
+        assert_eq!(model.run(tvec!(tensor0(true), tensor0(1f32)))?[0], rctensor0(3f32));
+        assert_eq!(model.run(tvec!(tensor0(false), tensor0(1f32)))?[0], rctensor0(1f32));
+        Ok(())
+    }
+
+    #[test]
+    fn constant_condition_is_inlined() -> TractResult<()> {
+        let model = model(Some(true))?.into_decluttered()?;
+        assert!(!model.nodes().iter().any(|n| n.op_is::<If>()));
+        assert_eq!(model.into_runnable()?.run(tvec!(tensor0(1f32)))?[0], rctensor0(3f32));
+        Ok(())
+    }
+
+    #[test]
+    fn codegen_branches() -> TractResult<()> {
+        let model = model(None)?.into_optimized()?;
+        assert!(model.nodes().iter().any(|n| n.op_is::<LirIf>()));
+        let mut state = SimpleState::new(model.into_runnable()?)?;
+        assert_eq!(state.run(tvec!(tensor0(true), tensor0(1f32)))?[0], rctensor0(3f32));
+        assert_eq!(state.run(tvec!(tensor0(false), tensor0(1f32)))?[0], rctensor0(1f32));
+        assert_eq!(state.run(tvec!(tensor0(true), tensor0(2f32)))?[0], rctensor0(4f32));
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::internal::*;
+use num_traits::Float;
+
+/// Reinterpret a real tensor with a trailing axis of size 2 as a complex
+/// tensor: the trailing axis holds the real and imaginary parts.
+#[derive(Clone, Debug, Hash)]
+pub struct InnerDimToComplex;
+
+impl_dyn_hash!(InnerDimToComplex);
+
+impl Op for InnerDimToComplex {
+    fn name(&self) -> Cow<str> {
+        "InnerDimToComplex".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl InnerDimToComplex {
+    fn eval_t<T: Datum + Float>(input: &Tensor) -> TractResult<Tensor>
+    where
+        Complex<T>: Datum,
+    {
+        let values = input
+            .as_slice::<T>()?
+            .chunks(2)
+            .map(|pair| Complex::new(pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        let shape = &input.shape()[..input.rank() - 1];
+        Tensor::from_shape(shape, &values)
+    }
+}
+
+impl EvalOp for InnerDimToComplex {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = match input.datum_type() {
+            DatumType::F32 => Self::eval_t::<f32>(&input)?,
+            DatumType::F64 => Self::eval_t::<f64>(&input)?,
+            dt => bail!("InnerDimToComplex does not support {:?}", dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for InnerDimToComplex {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let fact = inputs[0];
+        if fact.shape.last() != Some(&2.to_dim()) {
+            bail!("InnerDimToComplex expects a trailing axis of size 2, got {:?}", fact)
+        }
+        let dt = match fact.datum_type {
+            DatumType::F32 => DatumType::ComplexF32,
+            DatumType::F64 => DatumType::ComplexF64,
+            dt => bail!("InnerDimToComplex does not support {:?}", dt),
+        };
+        let shape = &fact.shape[..fact.rank() - 1];
+        Ok(tvec!(TypedFact::dt_shape(dt, shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..inputs[0].rank() - 1).map(AxisInfo::simple).collect())
+    }
+}
+
+/// Inverse of InnerDimToComplex: split a complex tensor into its real and
+/// imaginary parts, along a new trailing axis of size 2.
+#[derive(Clone, Debug, Hash)]
+pub struct ComplexToInnerDim;
+
+impl_dyn_hash!(ComplexToInnerDim);
+
+impl Op for ComplexToInnerDim {
+    fn name(&self) -> Cow<str> {
+        "ComplexToInnerDim".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl ComplexToInnerDim {
+    fn eval_t<T: Datum + Float>(input: &Tensor) -> TractResult<Tensor>
+    where
+        Complex<T>: Datum,
+    {
+        let values = input
+            .as_slice::<Complex<T>>()?
+            .iter()
+            .flat_map(|c| [c.re, c.im])
+            .collect::<Vec<_>>();
+        let mut shape: TVec<usize> = input.shape().into();
+        shape.push(2);
+        Tensor::from_shape(&shape, &values)
+    }
+}
+
+impl EvalOp for ComplexToInnerDim {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = match input.datum_type() {
+            DatumType::ComplexF32 => Self::eval_t::<f32>(&input)?,
+            DatumType::ComplexF64 => Self::eval_t::<f64>(&input)?,
+            dt => bail!("ComplexToInnerDim does not support {:?}", dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for ComplexToInnerDim {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let fact = inputs[0];
+        let dt = match fact.datum_type {
+            DatumType::ComplexF32 => DatumType::F32,
+            DatumType::ComplexF64 => DatumType::F64,
+            dt => bail!("ComplexToInnerDim does not support {:?}", dt),
+        };
+        let mut shape: TVec<TDim> = fact.shape.iter().collect();
+        shape.push(2.to_dim());
+        Ok(tvec!(TypedFact::dt_shape(dt, shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..inputs[0].rank()).map(AxisInfo::simple).collect())
+    }
+}
@@ -10,6 +10,9 @@ use tract_linalg::mmm::RoundingPolicy;
 use tract_linalg::ScaleShiftAndRound;
 use tract_num_traits::AsPrimitive;
 
+mod complex;
+pub use self::complex::{ComplexToInnerDim, InnerDimToComplex};
+
 bin_to_super_type!(add, Add,
     declutter_unary: declutter_unary_add,
     flip:commute,
@@ -533,7 +536,8 @@ q: [i8, u8] => f32::sinh);
 
 element_wise!(tanh, Tanh,
  [f32] => |_, xs| { (tract_linalg::ops().tanh_f32)().run(xs) },
- [f16, f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.tanh()); Ok(()) };
+ [f16] => |_, xs| { (tract_linalg::ops().tanh_f16)().run(xs) },
+ [f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.tanh()); Ok(()) };
  q: [i8, u8] => f32::tanh;
  cost: |dt| {tvec!((Cost::FMA(dt), 11), (Cost::Div(dt), 1))}
 );
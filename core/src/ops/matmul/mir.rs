@@ -75,6 +75,9 @@ impl TypedOp for MatMul {
         model: &TypedModel,
         node: &TypedNode,
     ) -> TractResult<Option<TypedModelPatch>> {
+        if let Some(patch) = crate::ops::nn::attention::declutter_attention(model, node)? {
+            return Ok(Some(patch));
+        }
         let a_fact = model.outlet_fact(node.inputs[0])?;
         let b_fact = model.outlet_fact(node.inputs[1])?;
         let konst_ix = if a_fact.konst.is_some() {
@@ -188,4 +191,84 @@ mod test {
         model.into_decluttered()?.into_optimized()?.into_runnable()?.run(tvec!(input))?;
         Ok(())
     }
+
+    #[test]
+    fn multithread_executor() -> TractResult<()> {
+        let (m, k, n) = (33, 17, 29);
+        let mut model = TypedModel::default();
+        let wire = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[k, n]))?;
+        let a =
+            Tensor::from_shape(&[m, k], &(0..m * k).map(|i| i as f32 / 100.0).collect::<Vec<_>>())?;
+        let wire = model.wire_node(
+            "m",
+            MatMulUnary::new(a.into_arc_tensor(), false, false, false),
+            &[wire],
+        )?;
+        model.set_output_outlets(&wire)?;
+        let plan = model.into_optimized()?.into_runnable()?;
+        let input =
+            Tensor::from_shape(&[k, n], &(0..k * n).map(|i| i as f32 / 10.0).collect::<Vec<_>>())?;
+        let expected = plan.run(tvec!(input.clone()))?.remove(0);
+        let mut state = SimpleState::new(&plan)?;
+        state.set_executor(tract_linalg::multithread::Executor::multithread(3)?);
+        let found = state.run(tvec!(input))?.remove(0);
+        found.close_enough(&expected, true)
+    }
+
+    #[test]
+    fn f16_codegen() -> TractResult<()> {
+        let (m, k, n) = (5, 7, 6);
+        let mut model = TypedModel::default();
+        let wire = model.add_source("s", TypedFact::dt_shape(f16::datum_type(), &[k, n]))?;
+        let a = Tensor::from_shape(
+            &[m, k],
+            &(0..m * k).map(|i| f16::from((i % 8) as f32 / 4.0)).collect::<Vec<_>>(),
+        )?;
+        let wire = model.wire_node(
+            "m",
+            MatMulUnary::new(a.into_arc_tensor(), false, false, false),
+            &[wire],
+        )?;
+        model.set_output_outlets(&wire)?;
+        let input = Tensor::from_shape(
+            &[k, n],
+            &(0..k * n).map(|i| f16::from((i % 5) as f32 / 2.0)).collect::<Vec<_>>(),
+        )?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?.remove(0);
+        let optimized = model.into_optimized()?;
+        assert!(optimized
+            .nodes()
+            .iter()
+            .any(|n| n.op_is::<crate::ops::matmul::lir_unary::LirMatMulUnary>()));
+        let found = optimized.into_runnable()?.run(tvec!(input))?.remove(0);
+        found.close_enough(&expected, true)
+    }
+
+    #[test]
+    fn f64_codegen() -> TractResult<()> {
+        let (m, k, n) = (9, 7, 6);
+        let mut model = TypedModel::default();
+        let wire = model.add_source("s", TypedFact::dt_shape(f64::datum_type(), &[k, n]))?;
+        let a =
+            Tensor::from_shape(&[m, k], &(0..m * k).map(|i| i as f64 / 10.0).collect::<Vec<_>>())?;
+        let wire = model.wire_node(
+            "m",
+            MatMulUnary::new(a.into_arc_tensor(), false, false, false),
+            &[wire],
+        )?;
+        let bias = Tensor::from_shape(&[m, 1], &(0..m).map(|i| i as f64).collect::<Vec<_>>())?;
+        let wire =
+            model.wire_node("b", crate::ops::math::add::unary(bias.into_arc_tensor()), &wire)?;
+        model.set_output_outlets(&wire)?;
+        let input =
+            Tensor::from_shape(&[k, n], &(0..k * n).map(|i| i as f64 / 3.0).collect::<Vec<_>>())?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?.remove(0);
+        let optimized = model.into_optimized()?;
+        assert!(optimized
+            .nodes()
+            .iter()
+            .any(|n| n.op_is::<crate::ops::matmul::lir_unary::LirMatMulUnary>()));
+        let found = optimized.into_runnable()?.run(tvec!(input))?.remove(0);
+        found.close_enough(&expected, true)
+    }
 }
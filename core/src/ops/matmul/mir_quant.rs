@@ -744,7 +744,7 @@ pub(crate) fn requant(
     clamp_and_cast_to(model, name, dt, wire)
 }
 
-pub(crate) fn clamp_and_cast_to(
+pub fn clamp_and_cast_to(
     model: &mut TypedModel,
     name: &str,
     dt: DatumType,
@@ -20,6 +20,7 @@ pub mod change_axes;
 pub mod cnn;
 pub mod downsample;
 pub mod dummy;
+pub mod fft;
 pub mod identity;
 pub mod konst;
 pub mod logic;
@@ -27,11 +28,16 @@ pub mod math;
 pub mod matmul;
 pub mod nn;
 pub mod quant;
+pub mod random;
+pub mod resize;
 pub mod scan;
+pub mod sequence;
 pub mod source;
 pub mod unimpl;
+pub mod upsample;
 
 pub use downsample::Downsample;
+pub use upsample::Upsample;
 pub use invariants::*;
 
 /// Level of precision to be expected in implementations comparisons.
@@ -91,6 +97,15 @@ pub trait EvalOp {
     }
 
     fn is_stateless(&self) -> bool;
+
+    /// Evaluate a stateless op in preallocated outputs (slices of a memory
+    /// arena, see `ArenaState`) without allocating.
+    ///
+    /// Returns false if the op does not support it.
+    #[allow(unused_variables)]
+    fn eval_into(&self, inputs: &[&Tensor], outputs: &mut [&mut Tensor]) -> TractResult<bool> {
+        Ok(false)
+    }
 }
 
 /// A base operation
@@ -0,0 +1,456 @@
+use crate::internal::*;
+use crate::ops::binary::UnaryOp;
+use crate::ops::math::{Add, Div, Exp, Mul, Recip, Sub};
+use crate::ops::matmul::MatMul;
+use crate::ops::nn::patterns::*;
+use crate::ops::nn::Reducer;
+use ndarray::*;
+use num_traits::{Float, FromPrimitive};
+use tract_linalg::mmm::{BinOp, FusedSpec};
+use tract_linalg::multithread::{current_tract_executor, Executor};
+
+/// Scaled dot-product attention: softmax(scale * q.kᵀ + mask).v, the softmax
+/// running over the last axis.
+///
+/// Inputs are q [.., Lq, d], k [.., Lk, d] and v [.., Lk, dv] with the same
+/// batch dimensions, then an optional additive mask broadcastable to
+/// [.., Lq, Lk]. Scores are only materialized for one batch item at a time.
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct Attention {
+    #[educe(Hash(method = "hash_f32"))]
+    pub scale: f32,
+}
+
+impl_dyn_hash!(Attention);
+
+impl Op for Attention {
+    fn name(&self) -> Cow<str> {
+        "Attention".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("scale: {}", self.scale)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl Attention {
+    fn eval_t<T: Datum + Float + FromPrimitive>(
+        &self,
+        inputs: &[Arc<Tensor>],
+    ) -> TractResult<Tensor> {
+        let (q, k, v) = (&inputs[0], &inputs[1], &inputs[2]);
+        let rank = q.rank();
+        let batch_shape = &q.shape()[..rank - 2];
+        let [lq, d] = [q.shape()[rank - 2], q.shape()[rank - 1]];
+        let [lk, dv] = [v.shape()[rank - 2], v.shape()[rank - 1]];
+        let scores_shape: TVec<usize> = batch_shape.iter().copied().chain([lq, lk]).collect();
+        let mask = inputs.get(3).map(|m| m.to_array_view::<T>()).transpose()?;
+        let mask = mask
+            .as_ref()
+            .map(|m| m.broadcast(&*scores_shape).context("Mask does not broadcast to scores"))
+            .transpose()?;
+        let output_shape: TVec<usize> = batch_shape.iter().copied().chain([lq, dv]).collect();
+        let output = unsafe { Tensor::uninitialized::<T>(&output_shape)? };
+        if output.len() == 0 {
+            return Ok(output);
+        }
+        let dt = T::datum_type();
+        let qk = tract_linalg::ops()
+            .mmm(dt, dt, dt, Some(lq), Some(d), Some(lk))
+            .with_context(|| format!("No matrix multiplier for {:?}", dt))?;
+        let pv = tract_linalg::ops()
+            .mmm(dt, dt, dt, Some(lq), Some(lk), Some(dv))
+            .with_context(|| format!("No matrix multiplier for {:?}", dt))?;
+        let scale = tensor0(T::from_f32(self.scale).unwrap());
+        let output_ref = &output;
+        // scores are only materialized for the batch item at hand
+        let job = |coords: &IxDyn| -> TractResult<()> {
+            unsafe {
+                let mut scores = Tensor::uninitialized::<T>(&[lq, lk])?;
+                let (q_pack, k_pack) = (qk.a_pack(d), qk.b_pack(d));
+                let mut packed_q =
+                    Tensor::uninitialized_aligned_dt(dt, &[q_pack.len(lq)], q_pack.alignment())?;
+                let mut packed_k =
+                    Tensor::uninitialized_aligned_dt(dt, &[k_pack.len(lk)], k_pack.alignment())?;
+                q_pack.pack(packed_q.view_mut(), &q.view_at_prefix(coords.slice())?, 1, 0);
+                k_pack.pack(packed_k.view_mut(), &k.view_at_prefix(coords.slice())?, 1, 0);
+                qk.run(
+                    lq,
+                    lk,
+                    &[
+                        FusedSpec::AddMatMul {
+                            a: qk.a_packed(dt.size_of(), d).wrap(&packed_q.view()),
+                            b: qk.b_packed(dt.size_of(), d).wrap(&packed_k.view()),
+                            k: d,
+                        },
+                        FusedSpec::BinScalar(&scale, BinOp::Mul),
+                        FusedSpec::Store(
+                            qk.c_from_data_and_strides(dt.size_of(), lk as isize, 1)
+                                .wrap(&scores.view_mut()),
+                        ),
+                    ],
+                )?;
+                let mut scores_view = scores.to_array_view_mut_unchecked::<T>();
+                if let Some(mask) = &mask {
+                    let mut mask = mask.view();
+                    for &c in coords.slice() {
+                        mask = mask.index_axis_move(Axis(0), c);
+                    }
+                    scores_view.zip_mut_with(&mask, |s, m| *s = *s + *m);
+                }
+                for mut row in scores_view.outer_iter_mut() {
+                    let max = row.iter().fold(T::neg_infinity(), |acc, &x| acc.max(x));
+                    row.mapv_inplace(|x| (x - max).exp());
+                    let sum = row.iter().fold(T::zero(), |acc, &x| acc + x);
+                    row.mapv_inplace(|x| x / sum);
+                }
+                let (p_pack, v_pack) = (pv.a_pack(lk), pv.b_pack(lk));
+                let mut packed_p =
+                    Tensor::uninitialized_aligned_dt(dt, &[p_pack.len(lq)], p_pack.alignment())?;
+                let mut packed_v =
+                    Tensor::uninitialized_aligned_dt(dt, &[v_pack.len(dv)], v_pack.alignment())?;
+                p_pack.pack(packed_p.view_mut(), &scores.view(), 1, 0);
+                v_pack.pack(packed_v.view_mut(), &v.view_at_prefix(coords.slice())?, 0, 1);
+                pv.run(
+                    lq,
+                    dv,
+                    &[
+                        FusedSpec::AddMatMul {
+                            a: pv.a_packed(dt.size_of(), lk).wrap(&packed_p.view()),
+                            b: pv.b_packed(dt.size_of(), lk).wrap(&packed_v.view()),
+                            k: lk,
+                        },
+                        FusedSpec::Store(
+                            pv.c_from_data_and_strides(dt.size_of(), dv as isize, 1)
+                                .wrap(&TensorView::at_prefix(output_ref, coords.slice())?),
+                        ),
+                    ],
+                )?;
+            }
+            Ok(())
+        };
+        let jobs = indices(batch_shape).into_iter().collect::<Vec<_>>();
+        match current_tract_executor() {
+            Executor::MultiThread(pool) if jobs.len() > 1 => {
+                use rayon::prelude::*;
+                pool.install(|| jobs.par_iter().try_for_each(job))?
+            }
+            _ => jobs.iter().try_for_each(job)?,
+        }
+        Ok(output)
+    }
+}
+
+impl EvalOp for Attention {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let dt = inputs[0].datum_type();
+        let output = if dt == f16::datum_type() {
+            let inputs = inputs
+                .iter()
+                .map(|i| Ok(i.cast_to::<f32>()?.into_owned().into_arc_tensor()))
+                .collect::<TractResult<TVec<_>>>()?;
+            self.eval_t::<f32>(&inputs)?.cast_to::<f16>()?.into_owned()
+        } else {
+            dispatch_floatlike!(Self::eval_t(dt)(self, &inputs))?
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Attention {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs.len() != 3 && inputs.len() != 4 {
+            bail!("Attention expects q, k, v and an optional mask, got {} inputs", inputs.len())
+        }
+        let rank = inputs[0].rank();
+        if rank < 2
+            || inputs[1].rank() != rank
+            || inputs[2].rank() != rank
+            || inputs[0].shape[..rank - 2] != inputs[1].shape[..rank - 2]
+            || inputs[0].shape[..rank - 2] != inputs[2].shape[..rank - 2]
+            || inputs[0].shape[rank - 1] != inputs[1].shape[rank - 1]
+            || inputs[1].shape[rank - 2] != inputs[2].shape[rank - 2]
+        {
+            bail!("Inconsistent attention inputs: {:?}", inputs)
+        }
+        let mut shape = inputs[0].shape.to_tvec();
+        shape[rank - 1] = inputs[2].shape[rank - 1].clone();
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        let rank = inputs[0].rank();
+        let axes = (0..rank - 2)
+            .map(|axis| {
+                let mut axis_inputs = tvec!(Some(axis), Some(axis), Some(axis));
+                if let Some(mask) = inputs.get(3) {
+                    let offset = rank - mask.rank();
+                    axis_inputs.push(
+                        Some(axis)
+                            .filter(|&ax| ax >= offset && !mask.shape[ax - offset].is_one())
+                            .map(|ax| ax - offset),
+                    );
+                }
+                AxisInfo {
+                    inputs: axis_inputs,
+                    outputs: tvec!(Some(axis)),
+                    period: 1,
+                    disposable: true,
+                }
+            })
+            .collect::<TVec<_>>();
+        Ok(axes.into())
+    }
+}
+
+enum Mask {
+    Wire(OutletId),
+    Konst(Arc<Tensor>),
+}
+
+struct AttentionPattern {
+    q: OutletId,
+    k: OutletId,
+    transpose_k: bool,
+    v: OutletId,
+    mask: Option<Mask>,
+    scale: f32,
+}
+
+/// Recognise attention from its expanded form, starting from the product of
+/// the probabilities by v:
+///
+/// p = softmax(q.kᵀ * scale + mask)
+/// y = p.v
+pub(crate) fn declutter_attention(
+    model: &TypedModel,
+    node: &TypedNode,
+) -> TractResult<Option<TypedModelPatch>> {
+    let found = if let Some(found) = attention_pattern(model, node) {
+        found
+    } else {
+        return Ok(None);
+    };
+    let mut patch = TypedModelPatch::default();
+    let q = patch.tap_model(model, found.q)?;
+    let mut k = patch.tap_model(model, found.k)?;
+    if found.transpose_k {
+        let rank = model.outlet_fact(found.k)?.rank();
+        let op = AxisOp::Move(rank - 1, rank - 2);
+        k = patch.wire_node(format!("{}.k", node.name), op, &[k])?[0];
+    }
+    let v = patch.tap_model(model, found.v)?;
+    let mut inputs = tvec!(q, k, v);
+    match found.mask {
+        Some(Mask::Wire(mask)) => inputs.push(patch.tap_model(model, mask)?),
+        Some(Mask::Konst(mask)) => {
+            inputs.push(patch.add_const(format!("{}.mask", node.name), mask)?)
+        }
+        None => (),
+    }
+    let output = patch.wire_node(&node.name, Attention::new(found.scale), &inputs)?;
+    patch.shunt_outside(model, node.id.into(), output[0])?;
+    Ok(Some(patch))
+}
+
+/// Check that the only consumer of outlet is the given node.
+fn only_feeds(model: &TypedModel, outlet: OutletId, node: &TypedNode) -> Option<()> {
+    check(sole_successor(model, outlet)?.id == node.id)
+}
+
+fn attention_pattern(model: &TypedModel, node: &TypedNode) -> Option<AttentionPattern> {
+    let op = node.op_as::<MatMul>()?;
+    check(!op.a_trans && !op.b_trans && !op.c_trans)?;
+    let v = node.inputs[1];
+    only_feeds(model, node.inputs[0], node)?;
+    let scores = softmax_input(model, node.inputs[0])?;
+    let scores_fact = model.outlet_fact(scores).ok()?;
+    let rank = scores_fact.rank();
+
+    let mut current = model.node(scores.node);
+    let mut mask = None;
+    let is_product =
+        |node: &TypedNode| node.op_is::<MatMul>() || uniform_unary::<Mul>(node).is_some();
+    if is_bin::<Add>(current) {
+        let ix = (0..2).find(|&ix| is_product(model.node(current.inputs[ix].node)))?;
+        mask = Some(Mask::Wire(current.inputs[1 - ix]));
+        only_feeds(model, current.inputs[ix], current)?;
+        current = model.node(current.inputs[ix].node);
+    } else if let Some(add) = current.op_as::<UnaryOp>().filter(|op| op.mini_op.is::<Add>()) {
+        mask = Some(Mask::Konst(add.a.clone()));
+        only_feeds(model, current.inputs[0], current)?;
+        current = model.node(current.inputs[0].node);
+    }
+    let mut scale = 1.0;
+    if let Some(s) = uniform_unary::<Mul>(current) {
+        scale = s;
+        only_feeds(model, current.inputs[0], current)?;
+        current = model.node(current.inputs[0].node);
+    }
+
+    let qk = current.op_as::<MatMul>()?;
+    check(!qk.a_trans && !qk.c_trans)?;
+    let q = current.inputs[0];
+    let (k, transpose_k) = if qk.b_trans {
+        (current.inputs[1], false)
+    } else {
+        let kt = model.node(current.inputs[1].node);
+        match kt.op_as::<AxisOp>() {
+            Some(AxisOp::Move(from, to))
+                if *from.max(to) == rank - 1
+                    && *from.min(to) == rank - 2
+                    && only_feeds(model, kt.id.into(), current).is_some() =>
+            {
+                (kt.inputs[0], false)
+            }
+            _ => (current.inputs[1], true),
+        }
+    };
+
+    let facts =
+        [q, k, v].iter().map(|o| model.outlet_fact(*o).ok()).collect::<Option<TVec<_>>>()?;
+    check(facts[0].datum_type.is_float())?;
+    check(
+        facts.iter().all(|f| f.rank() == rank && f.shape[..rank - 2] == facts[0].shape[..rank - 2]),
+    )?;
+    let mask_shape = match &mask {
+        Some(Mask::Wire(m)) => Some(model.outlet_fact(*m).ok()?.shape.to_tvec()),
+        Some(Mask::Konst(m)) => Some(m.shape().iter().map(|d| d.to_dim()).collect()),
+        None => None,
+    };
+    if let Some(mask_shape) = mask_shape {
+        let scores_shape = scores_fact.shape.to_tvec();
+        check(mask_shape.len() <= rank)?;
+        check(crate::broadcast::multi_broadcast(&[&scores_shape, &mask_shape])? == scores_shape)?;
+    }
+    Some(AttentionPattern { q, k, transpose_k, v, mask, scale })
+}
+
+/// The input of a softmax over the last axis, expanded as:
+///
+/// e = exp(x - max(x))
+/// y = e * recip(sum(e)) or e / sum(e)
+fn softmax_input(model: &TypedModel, probs: OutletId) -> Option<OutletId> {
+    let last = tvec!(model.outlet_fact(probs).ok()?.rank() - 1);
+    let div = model.node(probs.node);
+    let (exp, sum) = if is_bin::<Mul>(div) {
+        let ix =
+            (0..2).find(|&ix| is_element_wise::<Recip>(model.node(div.inputs[1 - ix].node)))?;
+        let recip = model.node(div.inputs[1 - ix].node);
+        only_feeds(model, recip.id.into(), div)?;
+        only_feeds(model, recip.inputs[0], recip)?;
+        (div.inputs[ix], recip.inputs[0])
+    } else {
+        check(is_bin::<Div>(div))?;
+        only_feeds(model, div.inputs[1], div)?;
+        (div.inputs[0], div.inputs[1])
+    };
+    let sum = model.node(sum.node);
+    check(reduced_axes(sum, Reducer::Sum)? == last && sum.inputs[0] == exp)?;
+    let exp = model.node(exp.node);
+    check(is_element_wise::<Exp>(exp))?;
+    check(model.outlet_successors(exp.id.into()).len() == 2)?;
+    check(!model.output_outlets().ok()?.contains(&exp.id.into()))?;
+
+    let sub = model.node(exp.inputs[0].node);
+    check(is_bin::<Sub>(sub))?;
+    only_feeds(model, sub.id.into(), exp)?;
+    let (x, max) = (sub.inputs[0], model.node(sub.inputs[1].node));
+    check(reduced_axes(max, Reducer::Max)? == last && max.inputs[0] == x)?;
+    only_feeds(model, max.id.into(), sub)?;
+    check(model.outlet_successors(x).len() == 2)?;
+    check(!model.output_outlets().ok()?.contains(&x))?;
+    Some(x)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+    use crate::ops::nn::Reduce;
+
+    #[test]
+    fn eval_uniform_scores() {
+        // q is zero: the probabilities are uniform, output is the mean of v
+        let op = Attention::new(1.0);
+        let q = tensor2(&[[0f32, 0.]]);
+        let k = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let v = tensor2(&[[1f32, 2.], [3., 6.]]);
+        let output =
+            op.eval(tvec!(q.into_arc_tensor(), k.into_arc_tensor(), v.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor2(&[[2f32, 4.]]));
+    }
+
+    #[test]
+    fn declutter_expanded() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[2, 3, 4]);
+        let q = model.add_source("q", fact.clone())?;
+        let k = model.add_source("k", fact.clone())?;
+        let v = model.add_source("v", fact)?;
+        let mask = model.add_source("mask", TypedFact::dt_shape(f32::datum_type(), &[1, 3, 3]))?;
+        let kt = model.wire_node("kt", AxisOp::Move(2, 1), &[k])?;
+        let qk = model.wire_node("qk", MatMul::default(), &[q, kt[0]])?;
+        let scaled = model.wire_node("scaled", math::mul::unary(rctensor3(&[[[0.5f32]]])), &qk)?;
+        let x = model.wire_node("masked", math::add::bin_typed(), &[scaled[0], mask])?;
+        let max = model.wire_node("max", Reduce::new(tvec!(2), Reducer::Max), &x)?;
+        let sub = model.wire_node("sub", math::sub::bin_typed(), &[x[0], max[0]])?;
+        let exp = model.wire_node("exp", math::exp(), &sub)?;
+        let sum = model.wire_node("sum", Reduce::new(tvec!(2), Reducer::Sum), &exp)?;
+        let probs = model.wire_node("probs", math::div::bin_typed(), &[exp[0], sum[0]])?;
+        let y = model.wire_node("y", MatMul::default(), &[probs[0], v])?;
+        model.set_output_outlets(&y)?;
+        let inputs = tvec!(
+            Tensor::from_shape(&[2, 3, 4], &(0..24).map(|i| (i % 5) as f32).collect::<Vec<_>>())?,
+            Tensor::from_shape(&[2, 3, 4], &(0..24).map(|i| (i % 7) as f32).collect::<Vec<_>>())?,
+            Tensor::from_shape(&[2, 3, 4], &(0..24).map(|i| i as f32).collect::<Vec<_>>())?,
+            tensor3(&[[[0f32, -100., -100.], [0., 0., -100.], [0., 0., 0.]]])
+        );
+        let expected = model.clone().into_runnable()?.run(inputs.clone())?;
+        let decluttered = model.into_decluttered()?;
+        assert_eq!(decluttered.nodes().len(), 5);
+        let attention = decluttered.nodes().iter().find_map(|n| n.op_as::<Attention>()).unwrap();
+        assert_eq!(attention.scale, 0.5);
+        let found = decluttered.into_runnable()?.run(inputs)?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    #[test]
+    fn multithread_executor() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[3, 2, 5, 4]);
+        let q = model.add_source("q", fact.clone())?;
+        let k = model.add_source("k", fact.clone())?;
+        let v = model.add_source("v", fact)?;
+        let mask = model.add_source("mask", TypedFact::dt_shape(f32::datum_type(), &[5, 5]))?;
+        let y = model.wire_node("y", Attention::new(0.5), &[q, k, v, mask])?;
+        model.set_output_outlets(&y)?;
+        let plan = model.into_runnable()?;
+        let inputs: TVec<Tensor> = (0..3)
+            .map(|i| {
+                let data = (0..120).map(|x| ((x + i) % 11) as f32 / 10.0).collect::<Vec<_>>();
+                Tensor::from_shape(&[3, 2, 5, 4], &data)
+            })
+            .chain(std::iter::once(Ok(tensor2(&[[0f32, -100., -100., -100., -100.]; 5]))))
+            .collect::<TractResult<_>>()?;
+        let expected = plan.run(inputs.clone())?.remove(0);
+        let mut state = SimpleState::new(&plan)?;
+        state.set_executor(tract_linalg::multithread::Executor::multithread(3)?);
+        let found = state.run(inputs)?.remove(0);
+        found.close_enough(&expected, true)
+    }
+}
@@ -0,0 +1,280 @@
+use crate::internal::*;
+use ndarray::*;
+use num_traits::{Float, FromPrimitive};
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum GridSampleMode {
+    Nearest,
+    Bilinear,
+    Bicubic,
+}
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum GridSamplePadding {
+    /// out of bound pixels are zero
+    Zeros,
+    /// out of bound pixels are the closest border ones
+    Border,
+    /// out of bound pixels are mirrored by the borders
+    Reflection,
+}
+
+/// Sample a NCHW input at the positions given by a flow field.
+///
+/// The grid is [N, H_out, W_out, 2], the last axis holding (x, y) normalized
+/// to [-1, 1]. With `align_corners`, -1 and 1 are the centers of the corner
+/// pixels, otherwise their outer edges. The output is [N, C, H_out, W_out].
+#[derive(Debug, Clone, new, Hash)]
+pub struct GridSample {
+    pub mode: GridSampleMode,
+    pub padding: GridSamplePadding,
+    pub align_corners: bool,
+}
+
+impl_dyn_hash!(GridSample);
+
+impl Op for GridSample {
+    fn name(&self) -> Cow<str> {
+        "GridSample".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "{:?} padding: {:?} align_corners: {}",
+            self.mode, self.padding, self.align_corners
+        )])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+fn round_half_to_even<T: Float>(x: T) -> T {
+    let floor = x.floor();
+    let two = T::one() + T::one();
+    let diff = x - floor;
+    if diff > two.recip() || (diff == two.recip() && floor % two != T::zero()) {
+        floor + T::one()
+    } else {
+        floor
+    }
+}
+
+impl GridSample {
+    fn t<T: FromPrimitive>(x: f32) -> T {
+        T::from_f32(x).unwrap()
+    }
+
+    /// From [-1, 1] to pixel coordinates.
+    fn unnormalize<T: Float + FromPrimitive>(&self, x: T, len: usize) -> T {
+        let len = T::from_usize(len).unwrap();
+        if self.align_corners {
+            (x + T::one()) / Self::t(2.0) * (len - T::one())
+        } else {
+            ((x + T::one()) * len - T::one()) / Self::t(2.0)
+        }
+    }
+
+    /// Apply border or reflection padding on a pixel coordinate.
+    fn pad<T: Float + FromPrimitive>(&self, x: T, len: usize) -> T {
+        let max = T::from_usize(len - 1).unwrap();
+        match self.padding {
+            GridSamplePadding::Zeros => x,
+            GridSamplePadding::Border => x.max(T::zero()).min(max),
+            GridSamplePadding::Reflection => {
+                let (low, high) = if self.align_corners {
+                    (T::zero(), max)
+                } else {
+                    (Self::t(-0.5), max + Self::t(0.5))
+                };
+                let span = high - low;
+                let x = if span <= T::zero() {
+                    T::zero()
+                } else {
+                    let x = (x - low).abs();
+                    let flips = (x / span).floor();
+                    let extra = x - flips * span;
+                    if flips % Self::t(2.0) == T::zero() {
+                        extra + low
+                    } else {
+                        span - extra + low
+                    }
+                };
+                x.max(T::zero()).min(max)
+            }
+        }
+    }
+
+    /// Taps of the sample at (x, y) in pixel coordinates: offsets in a H*W
+    /// plane and weights. Out of bound taps are skipped.
+    fn taps<T: Float + FromPrimitive>(
+        &self,
+        x: T,
+        y: T,
+        height: usize,
+        width: usize,
+        taps: &mut Vec<(usize, T)>,
+    ) {
+        taps.clear();
+        let mut push = |x: T, y: T, weight: T| {
+            if x >= T::zero()
+                && y >= T::zero()
+                && x < T::from_usize(width).unwrap()
+                && y < T::from_usize(height).unwrap()
+            {
+                taps.push((y.to_usize().unwrap() * width + x.to_usize().unwrap(), weight));
+            }
+        };
+        match self.mode {
+            GridSampleMode::Nearest => {
+                let (x, y) = (self.pad(x, width), self.pad(y, height));
+                push(round_half_to_even(x), round_half_to_even(y), T::one())
+            }
+            GridSampleMode::Bilinear => {
+                let (x, y) = (self.pad(x, width), self.pad(y, height));
+                let (x0, y0) = (x.floor(), y.floor());
+                let (lx, ly) = (x - x0, y - y0);
+                push(x0, y0, (T::one() - lx) * (T::one() - ly));
+                push(x0 + T::one(), y0, lx * (T::one() - ly));
+                push(x0, y0 + T::one(), (T::one() - lx) * ly);
+                push(x0 + T::one(), y0 + T::one(), lx * ly);
+            }
+            GridSampleMode::Bicubic => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let wx = cubic_weights(x - x0);
+                let wy = cubic_weights(y - y0);
+                for (j, wy) in wy.iter().enumerate() {
+                    let yj = self.pad(y0 + T::from_usize(j).unwrap() - T::one(), height);
+                    for (i, wx) in wx.iter().enumerate() {
+                        let xi = self.pad(x0 + T::from_usize(i).unwrap() - T::one(), width);
+                        push(xi.floor(), yj.floor(), *wx * *wy);
+                    }
+                }
+            }
+        }
+    }
+
+    fn eval_t<T: Datum + Float + FromPrimitive>(
+        &self,
+        input: &Tensor,
+        grid: &Tensor,
+    ) -> TractResult<Tensor> {
+        let input = input.to_array_view::<T>()?.into_dimensionality::<Ix4>()?;
+        let grid = grid.cast_to::<T>()?;
+        let grid = grid.to_array_view::<T>()?.into_dimensionality::<Ix4>()?;
+        let (batch, channels, height, width) = input.dim();
+        let (grid_batch, out_height, out_width, coords) = grid.dim();
+        if grid_batch != batch || coords != 2 {
+            bail!("Inconsistent input {:?} and grid {:?}", input.shape(), grid.shape())
+        }
+        let mut output = Array4::<T>::zeros((batch, channels, out_height, out_width));
+        if height == 0 || width == 0 {
+            // nothing to sample from, with any padding
+            return Ok(output.into_tensor());
+        }
+        let mut taps = Vec::with_capacity(16);
+        for n in 0..batch {
+            for oy in 0..out_height {
+                for ox in 0..out_width {
+                    let x = self.unnormalize(grid[(n, oy, ox, 0)], width);
+                    let y = self.unnormalize(grid[(n, oy, ox, 1)], height);
+                    self.taps(x, y, height, width, &mut taps);
+                    for c in 0..channels {
+                        let plane = input.slice(s![n, c, .., ..]);
+                        output[(n, c, oy, ox)] = taps.iter().fold(T::zero(), |acc, &(pos, w)| {
+                            acc + w * plane[(pos / width, pos % width)]
+                        });
+                    }
+                }
+            }
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+/// Cubic convolution weights of the four neighbours at -1, 0, 1 and 2 of a
+/// point at distance t of the second one.
+fn cubic_weights<T: Float + FromPrimitive>(t: T) -> [T; 4] {
+    let a = T::from_f32(-0.75).unwrap();
+    let (one, two) = (T::one(), T::one() + T::one());
+    let near = |x: T| ((a + two) * x - (a + two + one)) * x * x + one;
+    let far = |x: T| {
+        ((a * x - T::from_f32(5.0).unwrap() * a) * x + T::from_f32(8.0).unwrap() * a) * x
+            - T::from_f32(4.0).unwrap() * a
+    };
+    [far(t + one), near(t), near(one - t), far(two - t)]
+}
+
+impl EvalOp for GridSample {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let output = dispatch_floatlike!(Self::eval_t(inputs[0].datum_type())(
+            self, &inputs[0], &inputs[1]
+        ))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for GridSample {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].rank() != 4 || inputs[1].rank() != 4 {
+            bail!("GridSample expects a NCHW input and a [N, H_out, W_out, 2] grid")
+        }
+        Ok(tvec!(TypedFact::dt_shape(
+            inputs[0].datum_type,
+            &[
+                inputs[0].shape[0].clone(),
+                inputs[0].shape[1].clone(),
+                inputs[1].shape[1].clone(),
+                inputs[1].shape[2].clone()
+            ]
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(op: GridSample, grid: Tensor) -> Tensor {
+        let input = tensor4(&[[[[0f32, 1.], [2., 3.]]]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), grid.into_arc_tensor())).unwrap();
+        output[0].clone().into_tensor()
+    }
+
+    #[test]
+    fn bilinear_align_corners() {
+        let op = GridSample::new(GridSampleMode::Bilinear, GridSamplePadding::Zeros, true);
+        let grid = tensor4(&[[[[-1f32, -1.], [0., 0.], [1., -1.]]]]);
+        assert_eq!(run(op, grid), tensor4(&[[[[0f32, 1.5, 1.]]]]));
+    }
+
+    #[test]
+    fn bilinear_zeros_padding() {
+        // the left edge is half a pixel out of the map
+        let op = GridSample::new(GridSampleMode::Bilinear, GridSamplePadding::Zeros, false);
+        let grid = tensor4(&[[[[-1f32, 0.5]]]]);
+        assert_eq!(run(op, grid), tensor4(&[[[[1f32]]]]));
+    }
+
+    #[test]
+    fn nearest_border() {
+        let op = GridSample::new(GridSampleMode::Nearest, GridSamplePadding::Border, false);
+        let grid = tensor4(&[[[[3f32, 3.], [-3., 0.5]]]]);
+        assert_eq!(run(op, grid), tensor4(&[[[[3f32, 2.]]]]));
+    }
+
+    #[test]
+    fn empty_input_plane() {
+        let op = GridSample::new(GridSampleMode::Bilinear, GridSamplePadding::Border, true);
+        let input = unsafe { Tensor::uninitialized::<f32>(&[1, 1, 0, 2]).unwrap() };
+        let grid = tensor4(&[[[[0f32, 0.], [1., 1.]]]]);
+        let output = op.eval(tvec!(input.into_arc_tensor(), grid.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor4(&[[[[0f32, 0.]]]]));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::internal::*;
+use crate::ops::math::{Add, Div, Mul, Rsqrt, Sqrt, Square, Sub};
+use crate::ops::nn::patterns::*;
+use crate::ops::nn::Reducer;
+use num_traits::{Float, FromPrimitive};
+
+/// Normalization of the trailing axes, starting at `axis`, to zero mean and
+/// unit variance: (x - mean) / sqrt(var + epsilon).
+///
+/// Scale and bias are left to the element-wise operators following it.
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct LayerNorm {
+    pub axis: usize,
+    #[educe(Hash(method = "hash_f32"))]
+    pub epsilon: f32,
+}
+
+impl_dyn_hash!(LayerNorm);
+
+impl Op for LayerNorm {
+    fn name(&self) -> Cow<str> {
+        "LayerNorm".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} epsilon: {}", self.axis, self.epsilon)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl LayerNorm {
+    fn eval_t<T: Datum + Float + FromPrimitive>(&self, input: &mut Tensor) -> TractResult<()> {
+        let len: usize = input.shape()[self.axis..].iter().product();
+        if len == 0 {
+            return Ok(());
+        }
+        let n = T::from_usize(len).unwrap();
+        let epsilon = T::from_f32(self.epsilon).unwrap();
+        for lane in input.as_slice_mut::<T>()?.chunks_mut(len) {
+            let mean = lane.iter().fold(T::zero(), |acc, &x| acc + x) / n;
+            let var = lane.iter().fold(T::zero(), |acc, &x| acc + (x - mean) * (x - mean)) / n;
+            let norm = (var + epsilon).sqrt().recip();
+            lane.iter_mut().for_each(|x| *x = (*x - mean) * norm);
+        }
+        Ok(())
+    }
+}
+
+impl EvalOp for LayerNorm {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = if input.datum_type() == f16::datum_type() {
+            let mut output = input.cast_to::<f32>()?.into_owned();
+            self.eval_t::<f32>(&mut output)?;
+            output.cast_to::<f16>()?.into_owned()
+        } else {
+            let mut output = input.into_tensor();
+            dispatch_floatlike!(Self::eval_t(output.datum_type())(self, &mut output))?;
+            output
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for LayerNorm {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if self.axis >= inputs[0].rank() {
+            bail!("LayerNorm axis {} is invalid for {:?}", self.axis, inputs[0])
+        }
+        Ok(tvec!(inputs[0].without_value()))
+    }
+
+    fn invariants(
+        &self,
+        _inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..self.axis).map(AxisInfo::simple).collect())
+    }
+}
+
+/// Recognise the expanded form of a layer normalization, starting from the
+/// sum computing the mean:
+///
+/// d = x - sum(x) / n
+/// y = d * rsqrt(sum(d²) / n + epsilon)
+pub(crate) fn declutter_layer_norm(
+    model: &TypedModel,
+    node: &TypedNode,
+) -> TractResult<Option<TypedModelPatch>> {
+    if let Some((x, op, last)) = layer_norm_pattern(model, node) {
+        let mut patch = TypedModelPatch::default();
+        let x = patch.tap_model(model, x)?;
+        let output = patch.wire_node(&last.name, op, &[x])?;
+        patch.shunt_outside(model, last.id.into(), output[0])?;
+        Ok(Some(patch))
+    } else {
+        Ok(None)
+    }
+}
+
+fn layer_norm_pattern<'m>(
+    model: &'m TypedModel,
+    node: &'m TypedNode,
+) -> Option<(OutletId, LayerNorm, &'m TypedNode)> {
+    let axes = reduced_axes(node, Reducer::Sum)?;
+    let x = node.inputs[0];
+    let fact = model.outlet_fact(x).ok()?;
+    let rank = fact.rank();
+    check(fact.datum_type.is_float() && !axes.is_empty())?;
+    check(axes.iter().copied().eq(rank - axes.len()..rank))?;
+    let axis = axes[0];
+    let len = fact.shape[axis..].iter().product::<TDim>().to_usize().ok()? as f32;
+    let is_mean_norm = |node: &TypedNode| {
+        uniform_unary::<Mul>(node).map(|a| (a * len - 1.0).abs() < 1e-4).unwrap_or(false)
+    };
+
+    let mean = sole_successor(model, node.id.into())?;
+    check(is_mean_norm(mean))?;
+    let sub = sole_successor(model, mean.id.into())?;
+    check(is_bin::<Sub>(sub) && sub.inputs[..] == [x, mean.id.into()])?;
+
+    // d is used twice: squared for the variance, and normalized
+    let d: OutletId = sub.id.into();
+    let succs = model.outlet_successors(d);
+    check(succs.len() == 2 && !model.output_outlets().ok()?.contains(&d))?;
+    let is_square = |node: &TypedNode| {
+        is_element_wise::<Square>(node) || is_bin::<Mul>(node) && node.inputs[..] == [d, d]
+    };
+    let (square, last) = if is_square(model.node(succs[0].node)) {
+        (model.node(succs[0].node), model.node(succs[1].node))
+    } else {
+        (model.node(succs[1].node), model.node(succs[0].node))
+    };
+    check(is_square(square) && square.id != last.id)?;
+
+    let var_sum = sole_successor(model, square.id.into())?;
+    check(reduced_axes(var_sum, Reducer::Sum)? == axes)?;
+    let var = sole_successor(model, var_sum.id.into())?;
+    check(is_mean_norm(var))?;
+    let mut std = sole_successor(model, var.id.into())?;
+    let epsilon = if let Some(epsilon) = uniform_unary::<Add>(std) {
+        std = sole_successor(model, std.id.into())?;
+        epsilon
+    } else {
+        0.0
+    };
+    let std_outlet: OutletId = std.id.into();
+    if is_element_wise::<Rsqrt>(std) {
+        check(is_bin::<Mul>(last))?;
+        check(last.inputs[..] == [d, std_outlet] || last.inputs[..] == [std_outlet, d])?;
+    } else {
+        check(is_element_wise::<Sqrt>(std))?;
+        check(is_bin::<Div>(last) && last.inputs[..] == [d, std_outlet])?;
+    }
+    check(sole_successor(model, std_outlet)?.id == last.id)?;
+    Some((x, LayerNorm::new(axis, epsilon), last))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+    use crate::ops::nn::Reduce;
+
+    #[test]
+    fn eval() {
+        let op = LayerNorm::new(1, 0.0);
+        let input = tensor2(&[[1f32, 3.], [0., 4.]]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor2(&[[-1f32, 1.], [-1., 1.]]));
+    }
+
+    #[test]
+    fn declutter_expanded() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", TypedFact::dt_shape(f32::datum_type(), &[2, 3, 4]))?;
+        let sum = model.wire_node("mean.sum", Reduce::new(tvec!(1, 2), Reducer::Sum), &[x])?;
+        let mean = model.wire_node("mean", math::mul::unary(rctensor3(&[[[1f32 / 12.]]])), &sum)?;
+        let d = model.wire_node("d", math::sub::bin_typed(), &[x, mean[0]])?;
+        let sq = model.wire_node("sq", math::square(), &d)?;
+        let var = model.wire_node("var.sum", Reduce::new(tvec!(1, 2), Reducer::Sum), &sq)?;
+        let var = model.wire_node("var", math::mul::unary(rctensor3(&[[[1f32 / 12.]]])), &var)?;
+        let var = model.wire_node("var.eps", math::add::unary(rctensor3(&[[[1e-5f32]]])), &var)?;
+        let std = model.wire_node("std", math::sqrt(), &var)?;
+        let y = model.wire_node("y", math::div::bin_typed(), &[d[0], std[0]])?;
+        model.set_output_outlets(&y)?;
+        let input = Tensor::from_shape(&[2, 3, 4], &(0..24).map(|i| i as f32).collect::<Vec<_>>())?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+        let decluttered = model.into_decluttered()?;
+        assert_eq!(decluttered.nodes().len(), 2);
+        let op = decluttered.node(1).op_as::<LayerNorm>().unwrap();
+        assert_eq!(op.axis, 1);
+        let found = decluttered.into_runnable()?.run(tvec!(input))?;
+        found[0].close_enough(&expected[0], true)
+    }
+}
@@ -1,13 +1,24 @@
+pub(crate) mod attention;
 mod data_formats;
+mod grid_sample;
+mod layer_norm;
+mod non_max_suppression;
+mod patterns;
 mod reduce;
+mod roi_align;
 
+pub use self::attention::Attention;
 pub use self::data_formats::{BaseDataShape, DataFormat, DataShape, SymDataShape};
+pub use self::grid_sample::{GridSample, GridSampleMode, GridSamplePadding};
+pub use self::layer_norm::LayerNorm;
+pub use self::non_max_suppression::{BoxRepr, NonMaxSuppression};
 pub use self::reduce::{Reduce, Reducer};
+pub use self::roi_align::{RoiAlign, RoiAlignMode};
 
 pub use crate::internal::*;
 
-element_wise!(sigmoid, Sigmoid, [f32] => |_, xs| {
-    (tract_linalg::ops().sigmoid_f32)().run(xs)
-};
+element_wise!(sigmoid, Sigmoid,
+ [f32] => |_, xs| { (tract_linalg::ops().sigmoid_f32)().run(xs) },
+ [f16] => |_, xs| { (tract_linalg::ops().sigmoid_f16)().run(xs) };
     cost: |dt| {tvec!((Cost::FMA(dt), 11), (Cost::Div(dt), 1))}
 );
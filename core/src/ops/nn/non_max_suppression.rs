@@ -0,0 +1,171 @@
+use crate::internal::*;
+use ndarray::*;
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum BoxRepr {
+    /// [y1, x1, y2, x2], any diagonal pair of corners
+    TwoPoints,
+    /// [x_center, y_center, width, height]
+    CenterWidthHeight,
+}
+
+impl BoxRepr {
+    fn corners(&self, b: ArrayView1<f32>) -> [f32; 4] {
+        match self {
+            BoxRepr::TwoPoints => [b[0].min(b[2]), b[1].min(b[3]), b[0].max(b[2]), b[1].max(b[3])],
+            BoxRepr::CenterWidthHeight => {
+                [b[0] - b[2] / 2.0, b[1] - b[3] / 2.0, b[0] + b[2] / 2.0, b[1] + b[3] / 2.0]
+            }
+        }
+    }
+}
+
+/// Greedy per-class selection of boxes by decreasing score.
+///
+/// Inputs are boxes [batch, boxes, 4], scores [batch, classes, boxes], and the
+/// max_output_boxes_per_class, iou_threshold and score_threshold scalars.
+/// The output is [num_selected, 3] i64 of (batch, class, box) triplets, its
+/// length depending on the data, it is represented by `num_selected`.
+#[derive(Debug, Clone, new, Hash)]
+pub struct NonMaxSuppression {
+    pub box_repr: BoxRepr,
+    pub num_selected: Symbol,
+}
+
+impl_dyn_hash!(NonMaxSuppression);
+
+impl Op for NonMaxSuppression {
+    fn name(&self) -> Cow<str> {
+        "NonMaxSuppression".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{:?}", self.box_repr)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+fn suppress_by_iou(a: &[f32; 4], b: &[f32; 4], iou_threshold: f32) -> bool {
+    let inter_y = a[2].min(b[2]) - a[0].max(b[0]);
+    let inter_x = a[3].min(b[3]) - a[1].max(b[1]);
+    if inter_y <= 0.0 || inter_x <= 0.0 {
+        return false;
+    }
+    let inter = inter_y * inter_x;
+    let area_a = (a[2] - a[0]) * (a[3] - a[1]);
+    let area_b = (b[2] - b[0]) * (b[3] - b[1]);
+    let union = area_a + area_b - inter;
+    if area_a <= 0.0 || area_b <= 0.0 || union <= 0.0 {
+        return false;
+    }
+    inter / union > iou_threshold
+}
+
+impl EvalOp for NonMaxSuppression {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let boxes = inputs[0].cast_to::<f32>()?;
+        let boxes = boxes.to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let scores = inputs[1].cast_to::<f32>()?;
+        let scores = scores.to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let max_per_class = inputs[2].cast_to_scalar::<i64>()?.max(0) as usize;
+        let iou_threshold = inputs[3].cast_to_scalar::<f32>()?;
+        let score_threshold = inputs[4].cast_to_scalar::<f32>()?;
+        if boxes.shape()[0] != scores.shape()[0] || boxes.shape()[1] != scores.shape()[2] {
+            bail!("Inconsistent boxes {:?} and scores {:?}", boxes.shape(), scores.shape())
+        }
+        let mut selected: Vec<i64> = vec![];
+        let mut candidates: Vec<(usize, f32)> = vec![];
+        let mut kept: Vec<[f32; 4]> = vec![];
+        for batch in 0..scores.shape()[0] {
+            let corners: Vec<[f32; 4]> = boxes
+                .index_axis(Axis(0), batch)
+                .outer_iter()
+                .map(|b| self.box_repr.corners(b))
+                .collect();
+            for class in 0..scores.shape()[1] {
+                candidates.clear();
+                kept.clear();
+                candidates.extend(
+                    scores
+                        .slice(s![batch, class, ..])
+                        .iter()
+                        .cloned()
+                        .enumerate()
+                        .filter(|(_, score)| *score > score_threshold),
+                );
+                candidates
+                    .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+                for &(ix, _) in &candidates {
+                    if kept.len() >= max_per_class {
+                        break;
+                    }
+                    if kept.iter().all(|k| !suppress_by_iou(k, &corners[ix], iou_threshold)) {
+                        kept.push(corners[ix]);
+                        selected.extend(&[batch as i64, class as i64, ix as i64]);
+                    }
+                }
+            }
+        }
+        let len = selected.len() / 3;
+        Ok(tvec!(tensor1(&selected).into_shape(&[len, 3])?.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for NonMaxSuppression {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs.len() != 5 {
+            bail!("NonMaxSuppression expects 5 inputs, got {}", inputs.len())
+        }
+        Ok(tvec!(TypedFact::dt_shape(i64::datum_type(), &[self.num_selected.to_dim(), 3.to_dim()])))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(op: &NonMaxSuppression, boxes: Tensor, scores: Tensor, max: i64) -> Tensor {
+        let inputs = tvec!(
+            boxes.into_arc_tensor(),
+            scores.into_arc_tensor(),
+            rctensor1(&[max]),
+            rctensor1(&[0.5f32]),
+            rctensor1(&[0.0f32])
+        );
+        op.eval(inputs).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn suppress_by_iou() {
+        let op = NonMaxSuppression::new(BoxRepr::TwoPoints, Symbol::from('n'));
+        let boxes = tensor3(&[[
+            [0f32, 0., 1., 1.],
+            [0., 0.1, 1., 1.1],
+            [0., -0.1, 1., 0.9],
+            [0., 10., 1., 11.],
+            [0., 10.1, 1., 11.1],
+            [0., 100., 1., 101.],
+        ]]);
+        let scores = tensor3(&[[[0.9f32, 0.75, 0.6, 0.95, 0.5, 0.3]]]);
+        let output = run(&op, boxes, scores, 3);
+        assert_eq!(output, tensor2(&[[0i64, 0, 3], [0, 0, 0], [0, 0, 5]]));
+    }
+
+    #[test]
+    fn center_point_box() {
+        let op = NonMaxSuppression::new(BoxRepr::CenterWidthHeight, Symbol::from('n'));
+        let boxes =
+            tensor3(&[[[0.5f32, 0.5, 1.0, 1.0], [0.5, 0.6, 1.0, 1.0], [0.5, 10.5, 1.0, 1.0]]]);
+        let scores = tensor3(&[[[0.9f32, 0.75, 0.6]]]);
+        let output = run(&op, boxes, scores, 2);
+        assert_eq!(output, tensor2(&[[0i64, 0, 0], [0, 0, 2]]));
+    }
+}
@@ -0,0 +1,46 @@
+//! Helpers for recognising expanded subgraphs in declutter.
+
+use crate::internal::*;
+use crate::ops::binary::{BinMiniOp, TypedBinOp, UnaryOp};
+use crate::ops::element_wise::ElementWiseOp;
+use crate::ops::nn::{Reduce, Reducer};
+
+/// Turns a condition into an Option, to be used with `?` in matchers.
+pub(super) fn check(condition: bool) -> Option<()> {
+    if condition {
+        Some(())
+    } else {
+        None
+    }
+}
+
+/// The single consumer of an outlet, if it is not a model output.
+pub(super) fn sole_successor(model: &TypedModel, outlet: OutletId) -> Option<&TypedNode> {
+    let succs = model.outlet_successors(outlet);
+    check(succs.len() == 1 && !model.output_outlets().ok()?.contains(&outlet))?;
+    Some(model.node(succs[0].node))
+}
+
+/// The scalar value of a unary op with a uniform constant.
+pub(super) fn uniform_unary<M: BinMiniOp>(node: &TypedNode) -> Option<f32> {
+    let op = node.op_as::<UnaryOp>()?;
+    check(op.mini_op.is::<M>())?;
+    op.a.as_uniform()?.cast_to_scalar::<f32>().ok()
+}
+
+pub(super) fn is_bin<M: BinMiniOp>(node: &TypedNode) -> bool {
+    node.op_as::<TypedBinOp>().map(|op| op.0.is::<M>()).unwrap_or(false)
+}
+
+pub(super) fn is_element_wise<M: ElementWiseMiniOp>(node: &TypedNode) -> bool {
+    node.op_as::<ElementWiseOp>().map(|op| op.0.is::<M>()).unwrap_or(false)
+}
+
+/// The sorted axes of a reduction of the given kind.
+pub(super) fn reduced_axes(node: &TypedNode, reducer: Reducer) -> Option<TVec<usize>> {
+    let op = node.op_as::<Reduce>()?;
+    check(op.reducer == reducer)?;
+    let mut axes = op.axes.clone();
+    axes.sort();
+    Some(axes)
+}
@@ -283,6 +283,14 @@ impl TypedOp for Reduce {
         Ok(tvec!(TypedFact::dt_shape(dt, shape)))
     }
 
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        super::layer_norm::declutter_layer_norm(model, node)
+    }
+
     fn invariants(
         &self,
         inputs: &[&TypedFact],
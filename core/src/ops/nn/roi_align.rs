@@ -0,0 +1,257 @@
+use crate::internal::*;
+use ndarray::*;
+use num_traits::{Float, FromPrimitive};
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum RoiAlignMode {
+    Avg,
+    Max,
+}
+
+/// Region of interest pooling with bilinear sampling (Mask R-CNN).
+///
+/// Inputs are the NCHW feature map, the regions [num_rois, 4] as
+/// (x1, y1, x2, y2) in input image coordinates, and the i64 batch index of
+/// each region. The output is [num_rois, C, output_height, output_width].
+///
+/// If `aligned` is set, pixels are shifted by half a pixel before sampling,
+/// otherwise regions are made at least one pixel wide and high.
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct RoiAlign {
+    pub mode: RoiAlignMode,
+    pub output_height: usize,
+    pub output_width: usize,
+    pub sampling_ratio: usize,
+    #[educe(Hash(method = "hash_f32"))]
+    pub spatial_scale: f32,
+    pub aligned: bool,
+}
+
+impl_dyn_hash!(RoiAlign);
+
+impl Op for RoiAlign {
+    fn name(&self) -> Cow<str> {
+        "RoiAlign".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!(
+                "{:?} output: {}x{} sampling_ratio: {}",
+                self.mode, self.output_height, self.output_width, self.sampling_ratio
+            ),
+            format!("spatial_scale: {} aligned: {}", self.spatial_scale, self.aligned),
+        ])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+/// Bilinear interpolation of one sample point: four positions (as offsets in
+/// a H*W plane) and their weights. Points falling out of the map have all
+/// their weights set to zero.
+#[derive(Debug, Clone, Copy)]
+struct Sample<T> {
+    pos: [usize; 4],
+    weights: [T; 4],
+}
+
+/// Lower and upper neighbours of a coordinate, and its distance to the
+/// lower one, clamped to the map.
+fn neighbours<T: Float + FromPrimitive>(v: T, len: usize) -> (usize, usize, T) {
+    let v = v.max(T::zero());
+    let low = v.to_usize().unwrap();
+    if low >= len - 1 {
+        (len - 1, len - 1, T::zero())
+    } else {
+        (low, low + 1, v - T::from_usize(low).unwrap())
+    }
+}
+
+impl RoiAlign {
+    fn samples<T: Float + FromPrimitive>(
+        &self,
+        roi: ArrayView1<T>,
+        height: usize,
+        width: usize,
+    ) -> (Vec<Sample<T>>, usize) {
+        let t = |x: f32| T::from_f32(x).unwrap();
+        let offset = if self.aligned { t(0.5) } else { T::zero() };
+        let scale = t(self.spatial_scale);
+        let start_w = roi[0] * scale - offset;
+        let start_h = roi[1] * scale - offset;
+        let mut roi_w = roi[2] * scale - offset - start_w;
+        let mut roi_h = roi[3] * scale - offset - start_h;
+        if !self.aligned {
+            roi_w = roi_w.max(T::one());
+            roi_h = roi_h.max(T::one());
+        }
+        let bin_h = roi_h / T::from_usize(self.output_height).unwrap();
+        let bin_w = roi_w / T::from_usize(self.output_width).unwrap();
+        let grid = |bin: T| {
+            if self.sampling_ratio > 0 {
+                self.sampling_ratio
+            } else {
+                bin.ceil().to_usize().unwrap_or(0)
+            }
+        };
+        let (grid_h, grid_w) = (grid(bin_h), grid(bin_w));
+        let (h, w) = (T::from_usize(height).unwrap(), T::from_usize(width).unwrap());
+        let mut samples =
+            Vec::with_capacity(self.output_height * self.output_width * grid_h * grid_w);
+        for ph in 0..self.output_height {
+            for pw in 0..self.output_width {
+                for iy in 0..grid_h {
+                    let y = start_h
+                        + T::from_usize(ph).unwrap() * bin_h
+                        + (T::from_usize(iy).unwrap() + t(0.5)) * bin_h
+                            / T::from_usize(grid_h).unwrap();
+                    for ix in 0..grid_w {
+                        let x = start_w
+                            + T::from_usize(pw).unwrap() * bin_w
+                            + (T::from_usize(ix).unwrap() + t(0.5)) * bin_w
+                                / T::from_usize(grid_w).unwrap();
+                        if y < -T::one() || y > h || x < -T::one() || x > w {
+                            samples.push(Sample { pos: [0; 4], weights: [T::zero(); 4] });
+                            continue;
+                        }
+                        let (y_low, y_high, ly) = neighbours(y, height);
+                        let (x_low, x_high, lx) = neighbours(x, width);
+                        let (hy, hx) = (T::one() - ly, T::one() - lx);
+                        samples.push(Sample {
+                            pos: [
+                                y_low * width + x_low,
+                                y_low * width + x_high,
+                                y_high * width + x_low,
+                                y_high * width + x_high,
+                            ],
+                            weights: [hy * hx, hy * lx, ly * hx, ly * lx],
+                        });
+                    }
+                }
+            }
+        }
+        (samples, grid_h * grid_w)
+    }
+
+    fn eval_t<T: Datum + Float + FromPrimitive>(
+        &self,
+        input: &Tensor,
+        rois: &Tensor,
+        batch_indices: &Tensor,
+    ) -> TractResult<Tensor> {
+        let input = input.to_array_view::<T>()?.into_dimensionality::<Ix4>()?;
+        let rois = rois.cast_to::<T>()?;
+        let rois = rois.to_array_view::<T>()?.into_dimensionality::<Ix2>()?;
+        let batch_indices = batch_indices.cast_to::<i64>()?;
+        let batch_indices = batch_indices.as_slice::<i64>()?;
+        let (batch, channels, height, width) = input.dim();
+        if rois.shape()[1] != 4 || batch_indices.len() != rois.shape()[0] {
+            bail!("Inconsistent rois {:?} and batch indices {:?}", rois.shape(), batch_indices)
+        }
+        let mut output =
+            Array4::<T>::zeros((rois.shape()[0], channels, self.output_height, self.output_width));
+        for (n, roi) in rois.outer_iter().enumerate() {
+            let b = batch_indices[n];
+            if b < 0 || b as usize >= batch {
+                bail!("Invalid batch index {} for roi {}", b, n)
+            }
+            let (samples, per_bin) = self.samples(roi, height, width);
+            if per_bin == 0 {
+                continue;
+            }
+            let count = T::from_usize(per_bin).unwrap();
+            for c in 0..channels {
+                let plane = input.slice(s![b as usize, c, .., ..]);
+                let plane = plane.as_standard_layout();
+                let plane = plane.as_slice().unwrap();
+                let mut out = output.slice_mut(s![n, c, .., ..]);
+                for (bin, out) in samples.chunks(per_bin).zip(out.iter_mut()) {
+                    *out = match self.mode {
+                        RoiAlignMode::Avg => {
+                            bin.iter()
+                                .map(|s| {
+                                    (0..4).fold(T::zero(), |acc, i| {
+                                        acc + s.weights[i] * plane[s.pos[i]]
+                                    })
+                                })
+                                .fold(T::zero(), |acc, v| acc + v)
+                                / count
+                        }
+                        RoiAlignMode::Max => bin
+                            .iter()
+                            .map(|s| {
+                                (1..4).fold(s.weights[0] * plane[s.pos[0]], |acc, i| {
+                                    acc.max(s.weights[i] * plane[s.pos[i]])
+                                })
+                            })
+                            .fold(T::neg_infinity(), |acc, v| acc.max(v)),
+                    }
+                }
+            }
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+impl EvalOp for RoiAlign {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let output = dispatch_floatlike!(Self::eval_t(inputs[0].datum_type())(
+            self, &inputs[0], &inputs[1], &inputs[2]
+        ))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for RoiAlign {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].rank() != 4 || inputs[1].rank() != 2 {
+            bail!("RoiAlign expects a NCHW input and [num_rois, 4] regions")
+        }
+        Ok(tvec!(TypedFact::dt_shape(
+            inputs[0].datum_type,
+            &[
+                inputs[1].shape[0].clone(),
+                inputs[0].shape[1].clone(),
+                self.output_height.to_dim(),
+                self.output_width.to_dim()
+            ]
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn avg_identity() {
+        // one sample per bin at the center of each pixel: plain copy
+        let op = RoiAlign::new(RoiAlignMode::Avg, 2, 2, 1, 1.0, true);
+        let input = tensor4(&[[[[1f32, 2.], [3., 4.]]]]);
+        let rois = tensor2(&[[0f32, 0., 2., 2.]]);
+        let output = op
+            .eval(tvec!(input.into_arc_tensor(), rois.into_arc_tensor(), rctensor1(&[0i64])))
+            .unwrap();
+        assert_eq!(*output[0], tensor4(&[[[[1f32, 2.], [3., 4.]]]]));
+    }
+
+    #[test]
+    fn avg_half_pixel() {
+        let op = RoiAlign::new(RoiAlignMode::Avg, 1, 1, 1, 1.0, false);
+        let input = tensor4(&[[[[1f32, 2.], [3., 4.]]]]);
+        let rois = tensor2(&[[0f32, 0., 1., 1.]]);
+        let output = op
+            .eval(tvec!(input.into_arc_tensor(), rois.into_arc_tensor(), rctensor1(&[0i64])))
+            .unwrap();
+        assert_eq!(*output[0], tensor4(&[[[[2.5f32]]]]));
+    }
+}
@@ -0,0 +1,364 @@
+//! Random generators, built on Philox.
+//!
+//! Ops with an explicit seed always produce the same sequence of values.
+//! Others take their seed from the session (see
+//! `SimpleState::set_random_seed`), each node using its own stream, and
+//! fall back to entropy if no seed has been set. In both cases, generators
+//! advance from one run to the next, and restart when op states are reset.
+mod philox;
+
+use std::hash::{BuildHasher, Hasher};
+
+use crate::internal::*;
+use ndarray::*;
+
+pub use self::philox::Philox4x32x10;
+
+/// Stream of random numbers from a Philox generator.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    philox: Philox4x32x10,
+    buffer: [u32; 4],
+    used: usize,
+}
+
+impl Rng {
+    pub fn new(philox: Philox4x32x10) -> Rng {
+        Rng { philox, buffer: [0; 4], used: 4 }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.used == 4 {
+            self.buffer = self.philox.next_as_u32s();
+            self.used = 0;
+        }
+        self.used += 1;
+        self.buffer[self.used - 1]
+    }
+
+    /// Uniform in [0, 1), from 23 random mantissa bits as TensorFlow does.
+    pub fn next_f32(&mut self) -> f32 {
+        f32::from_bits(127 << 23 | (self.next_u32() & 0x7fffff)) - 1.0
+    }
+
+    /// Standard normal, by Box-Muller transform.
+    pub fn next_normal(&mut self) -> f32 {
+        let radius = (-2.0 * (1.0 - self.next_f32()).ln()).sqrt();
+        let angle = 2.0 * std::f32::consts::PI * self.next_f32();
+        radius * angle.cos()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Distribution {
+    Uniform { low: f32, high: f32 },
+    Normal { mean: f32, scale: f32 },
+}
+
+impl Hash for Distribution {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Distribution::Uniform { low, high } => (0u8, low.to_bits(), high.to_bits()).hash(state),
+            Distribution::Normal { mean, scale } => {
+                (1u8, mean.to_bits(), scale.to_bits()).hash(state)
+            }
+        }
+    }
+}
+
+/// Generator state of a random op, created on first evaluation so the
+/// session seed can be set after the state.
+#[derive(Clone, Debug)]
+struct RandomState {
+    node_id: usize,
+    rng: Option<Rng>,
+}
+
+impl RandomState {
+    fn rng(&mut self, session: &SessionState, seed: Option<u64>) -> &mut Rng {
+        let node_id = self.node_id;
+        self.rng.get_or_insert_with(|| {
+            let philox = if let Some(seed) = seed {
+                Philox4x32x10::for_seed(seed)
+            } else {
+                let seed = session.random_seed.unwrap_or_else(|| {
+                    std::collections::hash_map::RandomState::new().build_hasher().finish()
+                });
+                Philox4x32x10::weird_tf_constructor(seed, node_id as u64)
+            };
+            Rng::new(philox)
+        })
+    }
+}
+
+impl OpState for RandomState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let output = if let Some(op) = op.downcast_ref::<Random>() {
+            op.sample(session, self.rng(session, op.seed))?
+        } else if let Some(op) = op.downcast_ref::<Bernoulli>() {
+            op.sample(self.rng(session, op.seed), &inputs[0])?
+        } else if let Some(op) = op.downcast_ref::<Multinomial>() {
+            op.sample(self.rng(session, op.seed), &inputs[0])?
+        } else {
+            bail!("Wrong op")
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+macro_rules! random_op {
+    ($op: ty) => {
+        impl EvalOp for $op {
+            fn is_stateless(&self) -> bool {
+                false
+            }
+
+            fn state(
+                &self,
+                _session: &mut SessionState,
+                node_id: usize,
+            ) -> TractResult<Option<Box<dyn OpState>>> {
+                Ok(Some(Box::new(RandomState { node_id, rng: None })))
+            }
+        }
+    };
+}
+
+/// Tensor of the given type and shape, filled from a distribution.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Random {
+    pub fact: TypedFact,
+    pub dist: Distribution,
+    pub seed: Option<u64>,
+}
+
+impl_dyn_hash!(Random);
+
+impl Random {
+    fn sample(&self, session: &SessionState, rng: &mut Rng) -> TractResult<Tensor> {
+        let shape = self
+            .fact
+            .shape
+            .iter()
+            .map(|d| d.eval(&session.resolved_symbols).to_usize())
+            .collect::<TractResult<TVec<_>>>()?;
+        let output = ArrayD::from_shape_simple_fn(IxDyn(&shape), || match self.dist {
+            Distribution::Uniform { low, high } => low + (high - low) * rng.next_f32(),
+            Distribution::Normal { mean, scale } => mean + scale * rng.next_normal(),
+        });
+        output.into_tensor().cast_to_dt(self.fact.datum_type).map(|t| t.into_owned())
+    }
+}
+
+impl Op for Random {
+    fn name(&self) -> Cow<str> {
+        "Random".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{:?} seed: {:?}", self.dist, self.seed)])
+    }
+
+    fn validation(&self) -> Validation {
+        if self.seed.is_some() {
+            Validation::Accurate
+        } else {
+            Validation::Random
+        }
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+random_op!(Random);
+
+impl TypedOp for Random {
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(self.fact.datum_type, &*self.fact.shape)))
+    }
+
+    as_op!();
+}
+
+/// Draw ones with the probabilities of the input, zeros otherwise.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Bernoulli {
+    pub dt: DatumType,
+    pub seed: Option<u64>,
+}
+
+impl_dyn_hash!(Bernoulli);
+
+impl Bernoulli {
+    fn sample(&self, rng: &mut Rng, input: &Tensor) -> TractResult<Tensor> {
+        let input = input.cast_to::<f32>()?;
+        let output = input.to_array_view::<f32>()?.map(|p| (rng.next_f32() < *p) as u8 as f32);
+        output.into_tensor().cast_to_dt(self.dt).map(|t| t.into_owned())
+    }
+}
+
+impl Op for Bernoulli {
+    fn name(&self) -> Cow<str> {
+        "Bernoulli".into()
+    }
+
+    fn validation(&self) -> Validation {
+        if self.seed.is_some() {
+            Validation::Accurate
+        } else {
+            Validation::Random
+        }
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+random_op!(Bernoulli);
+
+impl TypedOp for Bernoulli {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::dt_shape(self.dt, &*inputs[0].shape)))
+    }
+
+    as_op!();
+}
+
+/// Draw `sample_size` class indices per batch from a [batch, classes]
+/// input of unnormalized log-probabilities.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Multinomial {
+    pub dt: DatumType,
+    pub sample_size: usize,
+    pub seed: Option<u64>,
+}
+
+impl_dyn_hash!(Multinomial);
+
+impl Multinomial {
+    fn sample(&self, rng: &mut Rng, input: &Tensor) -> TractResult<Tensor> {
+        let input = input.cast_to::<f32>()?;
+        let input = input.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let mut output = Array2::<i64>::zeros((input.shape()[0], self.sample_size));
+        for (logits, mut samples) in input.outer_iter().zip(output.outer_iter_mut()) {
+            let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let cumulative: Vec<f32> = logits
+                .iter()
+                .scan(0.0, |sum, logit| {
+                    *sum += (logit - max).exp();
+                    Some(*sum)
+                })
+                .collect();
+            let total = cumulative.last().cloned().unwrap_or(0.0);
+            for sample in samples.iter_mut() {
+                let threshold = rng.next_f32() * total;
+                let class = cumulative.iter().position(|c| *c > threshold);
+                *sample = class.unwrap_or(cumulative.len().saturating_sub(1)) as i64;
+            }
+        }
+        output.into_tensor().cast_to_dt(self.dt).map(|t| t.into_owned())
+    }
+}
+
+impl Op for Multinomial {
+    fn name(&self) -> Cow<str> {
+        "Multinomial".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("sample_size: {} seed: {:?}", self.sample_size, self.seed)])
+    }
+
+    fn validation(&self) -> Validation {
+        if self.seed.is_some() {
+            Validation::Accurate
+        } else {
+            Validation::Random
+        }
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+random_op!(Multinomial);
+
+impl TypedOp for Multinomial {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].rank() != 2 {
+            bail!("Multinomial expects a [batch, classes] input, got {:?}", inputs[0])
+        }
+        Ok(tvec!(TypedFact::dt_shape(
+            self.dt,
+            &[inputs[0].shape[0].clone(), self.sample_size.to_dim()]
+        )))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(op: impl TypedOp, seed: Option<u64>, input: Option<Tensor>) -> TractResult<Tensor> {
+        let mut model = TypedModel::default();
+        let inputs = if let Some(input) = &input {
+            tvec!(model
+                .add_source("input", TypedFact::dt_shape(input.datum_type(), input.shape()))?)
+        } else {
+            tvec!()
+        };
+        let wire = model.wire_node("random", op, &inputs)?;
+        model.set_output_outlets(&wire)?;
+        let mut state = SimpleState::new(model.into_runnable()?)?;
+        if let Some(seed) = seed {
+            state.set_random_seed(seed);
+        }
+        Ok(state.run(input.into_iter().collect())?.remove(0).into_tensor())
+    }
+
+    #[test]
+    fn uniform_bounds() -> TractResult<()> {
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[100]);
+        let op = Random::new(fact, Distribution::Uniform { low: 2.0, high: 3.0 }, Some(12));
+        let output = run(op, None, None)?;
+        assert!(output.as_slice::<f32>()?.iter().all(|x| (2.0..3.0).contains(x)));
+        Ok(())
+    }
+
+    #[test]
+    fn session_seed_is_reproducible() -> TractResult<()> {
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[4]);
+        let op = Random::new(fact, Distribution::Normal { mean: 0.0, scale: 1.0 }, None);
+        let a = run(op.clone(), Some(42), None)?;
+        let b = run(op.clone(), Some(42), None)?;
+        let c = run(op, Some(43), None)?;
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        Ok(())
+    }
+
+    #[test]
+    fn bernoulli_extremes() -> TractResult<()> {
+        let op = Bernoulli::new(i32::datum_type(), Some(0));
+        let output = run(op, None, Some(tensor1(&[0f32, 1., 0., 1.])))?;
+        assert_eq!(output, tensor1(&[0i32, 1, 0, 1]));
+        Ok(())
+    }
+
+    #[test]
+    fn multinomial_certain_class() -> TractResult<()> {
+        let op = Multinomial::new(i64::datum_type(), 3, Some(0));
+        let input = tensor2(&[[0f32, -1000., -1000.], [-1000., -1000., 0.]]);
+        let output = run(op, None, Some(input))?;
+        assert_eq!(output, tensor2(&[[0i64, 0, 0], [2, 2, 2]]));
+        Ok(())
+    }
+}
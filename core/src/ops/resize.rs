@@ -0,0 +1,513 @@
+use crate::internal::*;
+use crate::ops::Upsample;
+use ndarray::prelude::*;
+
+/// Mapping from output coordinates to input coordinates, following the ONNX
+/// Resize coordinate_transformation_mode values.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum CoordTransformer {
+    HalfPixel,
+    HalfPixelSymmetric,
+    PytorchHalfPixel,
+    AlignCorners,
+    Asymmetric,
+    TfHalfPixelForNn,
+    TfCropAndResize,
+}
+
+impl CoordTransformer {
+    fn transform(&self, x: usize, axis: &ResizeAxis, len_in: usize, len_out: usize) -> f64 {
+        let x = x as f64;
+        let scale = axis.scale as f64;
+        let (len_in, len_out) = (len_in as f64, len_out as f64);
+        match self {
+            CoordTransformer::HalfPixel => (x + 0.5) / scale - 0.5,
+            CoordTransformer::HalfPixelSymmetric => {
+                let adjustment = len_out / (scale * len_in);
+                let offset = len_in / 2.0 * (1.0 - adjustment);
+                offset + (x + 0.5) / scale - 0.5
+            }
+            CoordTransformer::PytorchHalfPixel => {
+                if len_out > 1.0 {
+                    (x + 0.5) / scale - 0.5
+                } else {
+                    0.0
+                }
+            }
+            CoordTransformer::AlignCorners => {
+                if len_out > 1.0 {
+                    x * (len_in - 1.0) / (len_out - 1.0)
+                } else {
+                    0.0
+                }
+            }
+            CoordTransformer::Asymmetric => x / scale,
+            CoordTransformer::TfHalfPixelForNn => (x + 0.5) / scale,
+            CoordTransformer::TfCropAndResize => {
+                let (start, end) = (axis.roi_start as f64, axis.roi_end as f64);
+                if len_out > 1.0 {
+                    start * (len_in - 1.0) + x * (end - start) * (len_in - 1.0) / (len_out - 1.0)
+                } else {
+                    0.5 * (start + end) * (len_in - 1.0)
+                }
+            }
+        }
+    }
+
+    /// Whether a unit scale maps every output coordinate on itself.
+    fn unit_scale_is_identity(&self) -> bool {
+        *self != CoordTransformer::TfHalfPixelForNn
+    }
+}
+
+/// Rounding of the input coordinate for nearest interpolation.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Nearest {
+    RoundPreferFloor,
+    RoundPreferCeil,
+    Floor,
+    Ceil,
+}
+
+impl Nearest {
+    fn round(&self, x: f64) -> f64 {
+        match self {
+            Nearest::RoundPreferFloor if x - x.floor() == 0.5 => x.floor(),
+            Nearest::RoundPreferCeil if x - x.floor() == 0.5 => x.ceil(),
+            Nearest::RoundPreferFloor | Nearest::RoundPreferCeil => x.round(),
+            Nearest::Floor => x.floor(),
+            Nearest::Ceil => x.ceil(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Interpolator {
+    Nearest,
+    Linear,
+    Cubic,
+}
+
+/// Resizing parameters of one axis.
+///
+/// The output length is `size` if set, or the input length multiplied by the
+/// scale and by the extent of the region of interest.
+#[derive(Clone, Debug, Educe)]
+#[educe(Hash)]
+pub struct ResizeAxis {
+    #[educe(Hash(method = "hash_f32"))]
+    pub scale: f32,
+    pub size: Option<usize>,
+    #[educe(Hash(method = "hash_f32"))]
+    pub roi_start: f32,
+    #[educe(Hash(method = "hash_f32"))]
+    pub roi_end: f32,
+}
+
+impl ResizeAxis {
+    pub fn identity() -> ResizeAxis {
+        ResizeAxis { scale: 1.0, size: None, roi_start: 0.0, roi_end: 1.0 }
+    }
+
+    pub fn scaled(scale: f32) -> ResizeAxis {
+        ResizeAxis { scale, ..ResizeAxis::identity() }
+    }
+
+    pub fn sized(size: usize, scale: f32) -> ResizeAxis {
+        ResizeAxis { scale, size: Some(size), ..ResizeAxis::identity() }
+    }
+
+    pub fn output_dim(&self, input: &TDim) -> TractResult<TDim> {
+        if let Some(size) = self.size {
+            return Ok(size.to_dim());
+        }
+        let factor = (self.roi_end - self.roi_start) as f64 * self.scale as f64;
+        if let Ok(input) = input.to_usize() {
+            Ok(((input as f64 * factor).floor() as usize).to_dim())
+        } else if factor.fract() == 0.0 && factor >= 0.0 {
+            Ok(input.clone() * factor as usize)
+        } else {
+            bail!("Can not resize symbolic dimension {} by a factor of {}", input, factor)
+        }
+    }
+
+    fn is_trivial(&self) -> bool {
+        self.scale == 1.0 && self.size.is_none() && self.roi_start == 0.0 && self.roi_end == 1.0
+    }
+}
+
+/// Resize a tensor with nearest, linear or cubic interpolation, axis by axis.
+///
+/// Indices falling out of the input are clamped to its edges, except with
+/// TfCropAndResize, where outputs mapped out of the input take the
+/// extrapolation value.
+#[derive(Clone, Debug, new, Educe)]
+#[educe(Hash)]
+pub struct Resize {
+    pub axes: TVec<ResizeAxis>,
+    pub coord_transformer: CoordTransformer,
+    pub interpolator: Interpolator,
+    pub nearest: Nearest,
+    #[educe(Hash(method = "hash_f32"))]
+    pub cubic_coeff_a: f32,
+    pub exclude_outside: bool,
+    #[educe(Hash(method = "hash_f32"))]
+    pub extrapolation_value: f32,
+}
+
+impl_dyn_hash!(Resize);
+
+impl Op for Resize {
+    fn name(&self) -> Cow<str> {
+        "Resize".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!(
+                "{:?} interpolation, {:?} coordinates, nearest: {:?}",
+                self.interpolator, self.coord_transformer, self.nearest
+            ),
+            format!("axes: {:?}", self.axes),
+        ])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl Resize {
+    fn is_identity_axis(&self, axis: usize) -> bool {
+        self.axes[axis].is_trivial() && self.coord_transformer.unit_scale_is_identity()
+    }
+
+    /// Input indices and weights contributing to an output coordinate, or
+    /// None if it must be extrapolated.
+    fn taps(
+        &self,
+        axis: &ResizeAxis,
+        x: usize,
+        len_in: usize,
+        len_out: usize,
+    ) -> Option<TVec<(usize, f64)>> {
+        let x_in = self.coord_transformer.transform(x, axis, len_in, len_out);
+        if self.coord_transformer == CoordTransformer::TfCropAndResize
+            && (x_in < 0.0 || x_in > (len_in - 1) as f64)
+        {
+            return None;
+        }
+        let clamp = |i: f64| (i.max(0.0) as usize).min(len_in - 1);
+        let x0 = x_in.floor();
+        let ratio = x_in - x0;
+        let taps = match self.interpolator {
+            Interpolator::Nearest => tvec!((clamp(self.nearest.round(x_in)), 1.0)),
+            Interpolator::Linear => tvec!((clamp(x0), 1.0 - ratio), (clamp(x0 + 1.0), ratio)),
+            Interpolator::Cubic => {
+                let a = self.cubic_coeff_a as f64;
+                let far = |d: f64| ((a * d - 5.0 * a) * d + 8.0 * a) * d - 4.0 * a;
+                let near = |d: f64| ((a + 2.0) * d - (a + 3.0)) * d * d + 1.0;
+                let coeffs = [far(ratio + 1.0), near(ratio), near(1.0 - ratio), far(2.0 - ratio)];
+                let mut taps = (-1..3)
+                    .zip(coeffs.iter())
+                    .map(|(offset, &coeff)| (x0 + offset as f64, coeff))
+                    .collect::<TVec<_>>();
+                if self.exclude_outside {
+                    taps.iter_mut()
+                        .filter(|(i, _)| *i < 0.0 || *i > (len_in - 1) as f64)
+                        .for_each(|tap| tap.1 = 0.0);
+                    let sum: f64 = taps.iter().map(|tap| tap.1).sum();
+                    taps.iter_mut().for_each(|tap| tap.1 /= sum);
+                }
+                taps.into_iter().map(|(i, coeff)| (clamp(i), coeff)).collect()
+            }
+        };
+        Some(taps)
+    }
+
+    fn resize_axis<T>(&self, input: ArrayViewD<T>, axis: usize) -> TractResult<ArrayD<T>>
+    where
+        T: Datum + num_traits::Float + num_traits::FromPrimitive,
+    {
+        let len_in = input.shape()[axis];
+        let len_out = self.axes[axis].output_dim(&len_in.to_dim())?.to_usize()?;
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[axis] = len_out;
+        let mut output = ArrayD::<T>::zeros(&*shape);
+        if len_in == 0 {
+            return Ok(output);
+        }
+        let extrapolation = T::from_f32(self.extrapolation_value).unwrap();
+        for x in 0..len_out {
+            let mut slice = output.index_axis_mut(Axis(axis), x);
+            if let Some(taps) = self.taps(&self.axes[axis], x, len_in, len_out) {
+                for (i, weight) in taps {
+                    let weight = T::from_f64(weight).unwrap();
+                    slice.zip_mut_with(&input.index_axis(Axis(axis), i), |y, x| {
+                        *y = *y + *x * weight
+                    });
+                }
+            } else {
+                slice.fill(extrapolation);
+            }
+        }
+        Ok(output)
+    }
+
+    fn eval_t<T>(&self, input: &Tensor) -> TractResult<Tensor>
+    where
+        T: Datum + num_traits::Float + num_traits::FromPrimitive,
+    {
+        let mut data = input.to_array_view::<T>()?.to_owned();
+        for axis in 0..data.ndim() {
+            if !self.is_identity_axis(axis) {
+                data = self.resize_axis(data.view(), axis)?;
+            }
+        }
+        Ok(data.into_tensor())
+    }
+
+    fn eval_nearest<T: Datum>(&self, input: &Tensor) -> TractResult<Tensor> {
+        let extrapolation = if self.coord_transformer == CoordTransformer::TfCropAndResize {
+            Some(tensor0(self.extrapolation_value).cast_to::<T>()?.to_scalar::<T>()?.clone())
+        } else {
+            None
+        };
+        let mut data = input.to_array_view::<T>()?.to_owned();
+        for axis in 0..data.ndim() {
+            if self.is_identity_axis(axis) {
+                continue;
+            }
+            let len_in = data.shape()[axis];
+            let len_out = self.axes[axis].output_dim(&len_in.to_dim())?.to_usize()?;
+            if len_in == 0 && len_out > 0 {
+                bail!("Can not resize an empty axis to {}", len_out)
+            }
+            let sources = (0..len_out)
+                .map(|x| self.taps(&self.axes[axis], x, len_in, len_out).map(|taps| taps[0].0))
+                .collect::<Vec<_>>();
+            let mut shape: TVec<usize> = data.shape().into();
+            shape[axis] = len_out;
+            data = ArrayD::from_shape_fn(&*shape, |mut coords| {
+                if let Some(source) = sources[coords[axis]] {
+                    coords[axis] = source;
+                    data[coords].clone()
+                } else {
+                    extrapolation.clone().unwrap()
+                }
+            });
+        }
+        Ok(data.into_tensor())
+    }
+
+    /// Factor of the axis upsampling, if it amounts to repeating each input
+    /// element.
+    fn nearest_upsampling_factor(&self, axis: usize, len_in: &TDim) -> Option<usize> {
+        let resize_axis = &self.axes[axis];
+        let factor = resize_axis.scale as usize;
+        if self.interpolator != Interpolator::Nearest
+            || resize_axis.scale.fract() != 0.0
+            || factor < 1
+            || resize_axis.roi_start != 0.0
+            || resize_axis.roi_end != 1.0
+        {
+            return None;
+        }
+        let len_in = if let Ok(len_in) = len_in.to_usize() {
+            len_in
+        } else if matches!(
+            self.coord_transformer,
+            CoordTransformer::HalfPixel
+                | CoordTransformer::PytorchHalfPixel
+                | CoordTransformer::Asymmetric
+                | CoordTransformer::TfHalfPixelForNn
+        ) {
+            // these mappings do not depend on the input length
+            16
+        } else {
+            return None;
+        };
+        let len_out = len_in * factor;
+        if resize_axis.size.map(|size| size != len_out).unwrap_or(false) {
+            return None;
+        }
+        (0..len_out)
+            .all(|x| {
+                self.taps(resize_axis, x, len_in, len_out)
+                    .map(|taps| taps[0].0 == x / factor)
+                    .unwrap_or(false)
+            })
+            .then_some(factor)
+    }
+}
+
+impl EvalOp for Resize {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = match input.datum_type() {
+            dt if self.interpolator == Interpolator::Nearest => {
+                dispatch_datum!(Self::eval_nearest(dt)(self, &input))?
+            }
+            DatumType::F64 => self.eval_t::<f64>(&input)?,
+            DatumType::F32 => self.eval_t::<f32>(&input)?,
+            dt => {
+                let output = self.eval_t::<f32>(&*input.cast_to::<f32>()?)?;
+                output.cast_to_dt(dt)?.into_owned()
+            }
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Resize {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if self.axes.len() != inputs[0].rank() {
+            bail!("Resize has {} axes for input {:?}", self.axes.len(), inputs[0])
+        }
+        let shape = inputs[0]
+            .shape
+            .iter()
+            .zip(self.axes.iter())
+            .map(|(dim, axis)| axis.output_dim(&dim))
+            .collect::<TractResult<TVec<_>>>()?;
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, shape)))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..inputs[0].rank())
+            .filter(|&axis| self.is_identity_axis(axis))
+            .map(AxisInfo::simple)
+            .collect())
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let input_fact = model.outlet_fact(node.inputs[0])?;
+        let mut factors = tvec!();
+        for (axis, dim) in input_fact.shape.iter().enumerate() {
+            if self.is_identity_axis(axis) {
+                factors.push(1);
+            } else if let Some(factor) = self.nearest_upsampling_factor(axis, &dim) {
+                factors.push(factor);
+            } else {
+                return Ok(None);
+            }
+        }
+        let mut patch = TypedModelPatch::default();
+        let mut wire = patch.tap_model(model, node.inputs[0])?;
+        for (axis, &factor) in factors.iter().enumerate() {
+            if factor > 1 {
+                wire = patch.wire_node(
+                    format!("{}.upsample-{}", node.name, axis),
+                    Upsample::new(axis, factor),
+                    &[wire],
+                )?[0];
+            }
+        }
+        patch.shunt_outside(model, node.id.into(), wire)?;
+        Ok(Some(patch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resize(
+        axes: TVec<ResizeAxis>,
+        coord: CoordTransformer,
+        interpolator: Interpolator,
+    ) -> Resize {
+        Resize::new(axes, coord, interpolator, Nearest::RoundPreferFloor, -0.75, false, 0.0)
+    }
+
+    fn input_4x4() -> Tensor {
+        tensor1(&(1..=16).map(|x| x as f32).collect::<Vec<_>>()).into_shape(&[1, 1, 4, 4]).unwrap()
+    }
+
+    #[test]
+    fn upsample_linear_half_pixel() -> TractResult<()> {
+        let axes = tvec!(
+            ResizeAxis::identity(),
+            ResizeAxis::identity(),
+            ResizeAxis::scaled(2.0),
+            ResizeAxis::scaled(2.0)
+        );
+        let op = resize(axes, CoordTransformer::HalfPixel, Interpolator::Linear);
+        let output = op.eval(tvec!(rctensor4(&[[[[1f32, 2.], [3., 4.]]]])))?;
+        let expected = tensor4(&[[[
+            [1f32, 1.25, 1.75, 2.],
+            [1.5, 1.75, 2.25, 2.5],
+            [2.5, 2.75, 3.25, 3.5],
+            [3., 3.25, 3.75, 4.],
+        ]]]);
+        output[0].close_enough(&expected, true)
+    }
+
+    #[test]
+    fn upsample_cubic_half_pixel() -> TractResult<()> {
+        let axes = tvec!(
+            ResizeAxis::identity(),
+            ResizeAxis::identity(),
+            ResizeAxis::scaled(2.0),
+            ResizeAxis::scaled(2.0)
+        );
+        let op = resize(axes, CoordTransformer::HalfPixel, Interpolator::Cubic);
+        let output = op.eval(tvec!(input_4x4().into_arc_tensor()))?;
+        let first_row = output[0].to_array_view::<f32>()?.slice(s![0, 0, 0, ..]).to_owned();
+        let expected = tract_ndarray::arr1(&[
+            0.47265625f32,
+            0.76953125,
+            1.24609375,
+            1.875,
+            2.28125,
+            2.91015625,
+            3.38671875,
+            3.68359375,
+        ]);
+        first_row.into_tensor().close_enough(&expected.into_tensor(), true)
+    }
+
+    #[test]
+    fn crop_and_resize_with_extrapolation() -> TractResult<()> {
+        let roi =
+            |start, end| ResizeAxis { roi_start: start, roi_end: end, ..ResizeAxis::sized(3, 1.0) };
+        let axes =
+            tvec!(ResizeAxis::identity(), ResizeAxis::identity(), roi(0.4, 1.2), roi(0.6, 1.7));
+        let mut op = resize(axes, CoordTransformer::TfCropAndResize, Interpolator::Linear);
+        op.extrapolation_value = 10.0;
+        let output = op.eval(tvec!(input_4x4().into_arc_tensor()))?;
+        let expected = tensor4(&[[[[7.6f32, 10., 10.], [12.4, 10., 10.], [10., 10., 10.]]]]);
+        output[0].close_enough(&expected, true)
+    }
+
+    #[test]
+    fn nearest_upsampling_declutters_to_upsample() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("source", TypedFact::dt_shape(f32::datum_type(), &[2, 3]))?;
+        let axes = tvec!(ResizeAxis::scaled(3.0), ResizeAxis::scaled(2.0));
+        let op = resize(axes, CoordTransformer::HalfPixel, Interpolator::Nearest);
+        let resized = model.wire_node("resize", op.clone(), &[source])?;
+        model.set_output_outlets(&resized)?;
+        let decluttered = model.into_decluttered()?;
+        assert!(decluttered.nodes().iter().all(|n| !n.op_is::<Resize>()));
+        assert_eq!(decluttered.nodes().iter().filter(|n| n.op_is::<Upsample>()).count(), 2);
+        let input = tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]);
+        let expected = op.eval(tvec!(input.clone().into_arc_tensor()))?;
+        let output = decluttered.into_runnable()?.run(tvec!(input))?;
+        assert_eq!(output[0], expected[0]);
+        Ok(())
+    }
+}
@@ -0,0 +1,546 @@
+//! Generic `while` loop over a sub-model, a la ONNX Loop.
+//!
+//! Inputs are: an optional maximum trip count (i64), an optional initial
+//! condition (bool), the initial values of the loop-carried dependencies, and
+//! the values the body closes on.
+//!
+//! The body receives the iteration number, the current condition, the
+//! loop-carried dependencies and the closure values. It produces the next
+//! condition, the updated loop-carried dependencies, and the scan outputs.
+//!
+//! The loop outputs the final value of the loop-carried dependencies, then each
+//! scan output stacked over the iterations along a new leading axis.
+//!
+//! Loop-carried dependencies must keep the same type and rank from one
+//! iteration to the next. Their shape may change along the axes where the body
+//! output dimension differs from the (symbolic) body input dimension: the
+//! final values get the `grown` symbol on these axes.
+use crate::internal::*;
+use crate::ops::identity::Identity;
+
+#[derive(Debug, Clone, Hash)]
+pub struct Loop {
+    pub body: TypedModel,
+    decluttered: bool,
+    pub has_trip_count: bool,
+    pub has_cond: bool,
+    pub carried: usize,
+    pub iters: Symbol,
+    pub grown: Symbol,
+    /// For each body result (condition, carried dependencies, scan outputs),
+    /// the index of the body model output computing it.
+    pub output_slots: Vec<usize>,
+}
+
+impl_dyn_hash!(Loop);
+
+impl Loop {
+    pub fn new(
+        mut body: TypedModel,
+        has_trip_count: bool,
+        has_cond: bool,
+        carried: usize,
+    ) -> TractResult<Loop> {
+        if body.input_outlets()?.len() < 2 + carried {
+            bail!(
+                "Loop body expects at least {} inputs, got {}",
+                2 + carried,
+                body.input_outlets()?.len()
+            )
+        }
+        if body.output_outlets()?.len() < 1 + carried {
+            bail!(
+                "Loop body expects at least {} outputs, got {}",
+                1 + carried,
+                body.output_outlets()?.len()
+            )
+        }
+        let output_slots = Self::merge_aliased_outputs(&mut body)?;
+        Ok(Loop {
+            body,
+            decluttered: false,
+            has_trip_count,
+            has_cond,
+            carried,
+            iters: Symbol::new('i'),
+            grown: Symbol::new('g'),
+            output_slots,
+        })
+    }
+
+    /// Bodies often expose the same value twice (as a carried dependency and
+    /// as a scan output, through an Identity). The optimizer does not accept
+    /// models using the same outlet as two outputs, so we expose it once.
+    fn merge_aliased_outputs(body: &mut TypedModel) -> TractResult<Vec<usize>> {
+        let mut outlets: TVec<OutletId> = tvec!();
+        let mut slots = vec![];
+        for &outlet in body.output_outlets()? {
+            let mut outlet = outlet;
+            while body.node(outlet.node).op_is::<Identity>() {
+                outlet = body.node(outlet.node).inputs[0];
+            }
+            if let Some(slot) = outlets.iter().position(|o| *o == outlet) {
+                slots.push(slot);
+            } else {
+                slots.push(outlets.len());
+                outlets.push(outlet);
+            }
+        }
+        body.set_output_outlets(&outlets)?;
+        Ok(slots)
+    }
+
+    pub fn to_codegen_op(&self, optimize_inner: bool) -> TractResult<LirLoop> {
+        let mut model = self.body.clone();
+        if optimize_inner {
+            model = model.into_optimized()?;
+        }
+        let plan = SimplePlan::new(model)?;
+        Ok(LirLoop(Arc::new(LirLoopParams {
+            plan: Arc::new(plan),
+            has_trip_count: self.has_trip_count,
+            has_cond: self.has_cond,
+            carried: self.carried,
+            iters: self.iters,
+            grown: self.grown,
+            output_slots: self.output_slots.clone(),
+        })))
+    }
+
+    pub fn iteration_count(&self, inputs: &[&TypedFact]) -> Option<TDim> {
+        iteration_count(&self.body, &self.output_slots, self.has_trip_count, self.has_cond, inputs)
+    }
+
+    fn declutter_body(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if !self.decluttered {
+            let mut new = self.clone();
+            new.body = self.body.clone().into_decluttered()?;
+            new.decluttered = true;
+            Ok(Some(TypedModelPatch::replace_single_op(model, node, &node.inputs, new)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Number of iterations, when it can be known before running the loop.
+///
+/// This requires a constant trip count and a condition that can not turn false.
+fn iteration_count(
+    body: &TypedModel,
+    output_slots: &[usize],
+    has_trip_count: bool,
+    has_cond: bool,
+    inputs: &[&TypedFact],
+) -> Option<TDim> {
+    if !has_trip_count {
+        return None;
+    }
+    let trip_count = inputs[0].konst.as_ref()?.cast_to_scalar::<i64>().ok()?;
+    let is_true = |fact: &TypedFact| {
+        fact.konst.as_ref().and_then(|k| k.cast_to_scalar::<bool>().ok()) == Some(true)
+    };
+    let body_cond = body.output_outlets().ok()?[output_slots[0]];
+    let cond_holds = is_true(body.outlet_fact(body_cond).ok()?)
+        || (body_cond == body.input_outlets().ok()?[1] && (!has_cond || is_true(inputs[1])));
+    if cond_holds {
+        Some(trip_count.max(0).to_dim())
+    } else {
+        None
+    }
+}
+
+fn output_facts(
+    body: &TypedModel,
+    output_slots: &[usize],
+    carried: usize,
+    iters: Option<TDim>,
+    iters_symbol: Symbol,
+    grown: Symbol,
+) -> TractResult<TVec<TypedFact>> {
+    let mut outputs = tvec!();
+    for ix in 0..carried {
+        let input = body.input_fact(2 + ix)?;
+        let fact = body.output_fact(output_slots[1 + ix])?;
+        if input.rank() != fact.rank() {
+            bail!("Loop carried dependency #{} changes rank: {:?} to {:?}", ix, input, fact)
+        }
+        let shape: TVec<TDim> = input
+            .shape
+            .iter()
+            .zip(fact.shape.iter())
+            .map(|(i, o)| if i == o { o.clone() } else { grown.into() })
+            .collect();
+        outputs.push(TypedFact::dt_shape(fact.datum_type, shape));
+    }
+    let iters = iters.unwrap_or_else(|| iters_symbol.into());
+    for &slot in &output_slots[1 + carried..] {
+        let fact = body.output_fact(slot)?;
+        let shape: TVec<TDim> = std::iter::once(iters.clone()).chain(fact.shape.iter()).collect();
+        outputs.push(TypedFact::dt_shape(fact.datum_type, shape));
+    }
+    Ok(outputs)
+}
+
+impl Op for Loop {
+    fn name(&self) -> Cow<str> {
+        "Loop".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "trip count: {:?}, condition: {:?}, carried: {}",
+            self.has_trip_count, self.has_cond, self.carried
+        )])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for Loop {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        self.to_codegen_op(false)?.state(session, node_id)
+    }
+}
+
+impl TypedOp for Loop {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let iters = self.iteration_count(inputs);
+        output_facts(&self.body, &self.output_slots, self.carried, iters, self.iters, self.grown)
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        self.declutter_body(model, node)
+    }
+
+    fn concretize_dims(
+        &self,
+        _source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+        values: &SymbolValues,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs = node.inputs.iter().map(|o| mapping[&o]).collect::<TVec<_>>();
+        let op = Self { body: self.body.concretize_dims(values)?, ..self.clone() };
+        target.wire_node(&node.name, op, &inputs)
+    }
+
+    fn codegen(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        Ok(Some(TypedModelPatch::replace_single_op(
+            &model,
+            node,
+            &node.inputs,
+            self.to_codegen_op(true)?,
+        )?))
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LirLoopParams {
+    pub plan: Arc<TypedSimplePlan<TypedModel>>,
+    pub has_trip_count: bool,
+    pub has_cond: bool,
+    pub carried: usize,
+    pub iters: Symbol,
+    pub grown: Symbol,
+    pub output_slots: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct LirLoop(Arc<LirLoopParams>);
+
+impl std::ops::Deref for LirLoop {
+    type Target = LirLoopParams;
+    fn deref(&self) -> &LirLoopParams {
+        &self.0
+    }
+}
+
+impl_dyn_hash!(LirLoop);
+
+impl LirLoop {
+    pub fn iteration_count(&self, inputs: &[&TypedFact]) -> Option<TDim> {
+        let body = self.plan.model();
+        iteration_count(body, &self.output_slots, self.has_trip_count, self.has_cond, inputs)
+    }
+}
+
+impl Op for LirLoop {
+    fn name(&self) -> Cow<str> {
+        "Loop".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "trip count: {:?}, condition: {:?}, carried: {}",
+            self.has_trip_count, self.has_cond, self.carried
+        )])
+    }
+
+    op_core_lir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for LirLoop {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(State {
+            op: Arc::clone(&self.0),
+            model_state: TypedSimpleState::new(Arc::clone(&self.plan))?,
+        })))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct State {
+    op: Arc<LirLoopParams>,
+    model_state: TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+}
+
+impl OpState for State {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let State { op, ref mut model_state } = self;
+        let mut slot = 0;
+        let trip_count = if op.has_trip_count {
+            slot += 1;
+            inputs[0].cast_to_scalar::<i64>()?
+        } else {
+            i64::MAX
+        };
+        let mut cond = if op.has_cond {
+            slot += 1;
+            inputs[slot - 1].cast_to_scalar::<bool>()?
+        } else {
+            true
+        };
+        let mut carried: TVec<Tensor> =
+            inputs[slot..][..op.carried].iter().map(|t| t.clone().into_tensor()).collect();
+        let closures = &inputs[slot + op.carried..];
+
+        let body = op.plan.model();
+        let iter_rank = body.input_fact(0)?.rank();
+        let cond_rank = body.input_fact(1)?.rank();
+        let scan_slots = &op.output_slots[1 + op.carried..];
+        let mut scans: TVec<Vec<Tensor>> = tvec!(vec!(); scan_slots.len());
+
+        let mut iter = 0;
+        while iter < trip_count && cond {
+            let mut iter_inputs: TVec<Tensor> = tvec!(
+                tensor0(iter).broadcast_into_rank(iter_rank)?,
+                tensor0(cond).broadcast_into_rank(cond_rank)?
+            );
+            iter_inputs.extend(carried.drain(..));
+            iter_inputs.extend(closures.iter().map(|t| t.clone().into_tensor()));
+            trace!("iter_inputs: {:?}", iter_inputs);
+            let iter_outputs =
+                model_state.run(iter_inputs).with_context(|| "Evaluating inner body")?;
+            trace!("iter_outputs: {:?}", iter_outputs);
+            cond = iter_outputs[op.output_slots[0]].cast_to_scalar::<bool>()?;
+            carried.extend(
+                op.output_slots[1..][..op.carried]
+                    .iter()
+                    .map(|&slot| iter_outputs[slot].clone().into_tensor()),
+            );
+            for (scan, &slot) in scans.iter_mut().zip(scan_slots) {
+                let mut output = iter_outputs[slot].clone().into_tensor();
+                output.insert_axis(0)?;
+                scan.push(output);
+            }
+            iter += 1;
+        }
+
+        let mut outputs: TVec<Arc<Tensor>> = carried.into_iter().map(Arc::new).collect();
+        for (scan, &slot) in scans.into_iter().zip(scan_slots) {
+            let output = if scan.len() > 0 {
+                Tensor::stack_tensors(0, &scan)?
+            } else {
+                let fact = body.output_fact(slot)?;
+                let mut shape: TVec<usize> =
+                    fact.shape.eval_to_usize(&session.resolved_symbols)?.into_owned();
+                shape.insert(0, 0);
+                unsafe { Tensor::uninitialized_dt(fact.datum_type, &shape)? }
+            };
+            outputs.push(output.into_arc_tensor());
+        }
+        Ok(outputs)
+    }
+}
+
+impl TypedOp for LirLoop {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let iters = self.iteration_count(inputs);
+        output_facts(
+            self.plan.model(),
+            &self.output_slots,
+            self.carried,
+            iters,
+            self.iters,
+            self.grown,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    // sum the iteration numbers in a carried dependency, and scan the partial
+    // sums. Stops when the partial sum reaches the closure value.
+    fn partial_sums() -> TractResult<TypedModel> {
+        let mut body = TypedModel::default();
+        let i = body.add_source("i", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let _cond =
+            body.add_source("cond", TypedFact::dt_shape(bool::datum_type(), &[0usize; 0]))?;
+        let acc = body.add_source("acc", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let limit =
+            body.add_source("limit", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let acc = body.wire_node("add", math::add::bin_typed(), &[acc, i])?[0];
+        let cond = body.wire_node("cmp", crate::ops::logic::lesser::bin_typed(), &[acc, limit])?[0];
+        body.set_output_outlets(&[cond, acc, acc])?;
+
+        let mut model = TypedModel::default();
+        let trip =
+            model.add_source("trip", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let acc = model.add_const("acc", tensor0(0i64))?;
+        let limit =
+            model.add_source("limit", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let outputs =
+            model.wire_node("loop", Loop::new(body, true, false, 1)?, &[trip, acc, limit])?;
+        model.set_output_outlets(&outputs)?;
+        Ok(model)
+    }
+
+    fn run(model: TypedModel, trip: i64, limit: i64) -> TractResult<TVec<Arc<Tensor>>> {
+        model.into_runnable()?.run(tvec!(tensor0(trip), tensor0(limit)))
+    }
+
+    #[test]
+    fn trip_count_bound() -> TractResult<()> {
+        let outputs = run(partial_sums()?, 4, 100)?;
+        assert_eq!(outputs[0], rctensor0(6i64));
+        assert_eq!(outputs[1], rctensor1(&[0i64, 1, 3, 6]));
+        Ok(())
+    }
+
+    #[test]
+    fn cond_bound() -> TractResult<()> {
+        let outputs = run(partial_sums()?, 100, 10)?;
+        assert_eq!(outputs[0], rctensor0(10i64));
+        assert_eq!(outputs[1], rctensor1(&[0i64, 1, 3, 6, 10]));
+        Ok(())
+    }
+
+    #[test]
+    fn zero_iterations() -> TractResult<()> {
+        let outputs = run(partial_sums()?, 0, 10)?;
+        assert_eq!(outputs[0], rctensor0(0i64));
+        assert_eq!(outputs[1].shape(), &[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn optimized() -> TractResult<()> {
+        let model = partial_sums()?.into_optimized()?;
+        assert!(model.nodes().iter().any(|n| n.op_is::<LirLoop>()));
+        let outputs = run(model, 100, 10)?;
+        assert_eq!(outputs[1], rctensor1(&[0i64, 1, 3, 6, 10]));
+        Ok(())
+    }
+
+    // append the iteration number to a carried dependency growing along its
+    // only axis
+    fn growing() -> TractResult<TypedModel> {
+        let mut body = TypedModel::default();
+        let i = body.add_source("i", TypedFact::dt_shape(i64::datum_type(), &[1usize]))?;
+        let cond =
+            body.add_source("cond", TypedFact::dt_shape(bool::datum_type(), &[0usize; 0]))?;
+        let acc =
+            body.add_source("acc", TypedFact::dt_shape(i64::datum_type(), &[Symbol::new('g')]))?;
+        let acc =
+            body.wire_node("concat", crate::ops::array::TypedConcat::concat_vars(0, 2), &[acc, i])?
+                [0];
+        body.set_output_outlets(&[cond, acc])?;
+
+        let mut model = TypedModel::default();
+        let trip =
+            model.add_source("trip", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let acc = model.add_const("acc", tensor1(&[7i64]))?;
+        let outputs = model.wire_node("loop", Loop::new(body, true, false, 1)?, &[trip, acc])?;
+        model.set_output_outlets(&outputs)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn growing_carried_dependency() -> TractResult<()> {
+        let model = growing()?;
+        let grown = model.node_by_name("loop")?.op_as::<Loop>().unwrap().grown;
+        assert_eq!(model.output_fact(0)?.shape.to_tvec(), tvec!(grown.to_dim()));
+        let outputs = model.into_runnable()?.run(tvec!(tensor0(3i64)))?;
+        assert_eq!(outputs[0], rctensor1(&[7i64, 0, 1, 2]));
+        Ok(())
+    }
+
+    #[test]
+    fn growing_carried_dependency_optimized() -> TractResult<()> {
+        let model = growing()?.into_optimized()?;
+        let outputs = model.into_runnable()?.run(tvec!(tensor0(2i64)))?;
+        assert_eq!(outputs[0], rctensor1(&[7i64, 0, 1]));
+        Ok(())
+    }
+
+    #[test]
+    fn constant_trip_count() -> TractResult<()> {
+        let mut body = TypedModel::default();
+        let i = body.add_source("i", TypedFact::dt_shape(i64::datum_type(), &[0usize; 0]))?;
+        let cond =
+            body.add_source("cond", TypedFact::dt_shape(bool::datum_type(), &[0usize; 0]))?;
+        body.set_output_outlets(&[cond, i])?;
+        let mut model = TypedModel::default();
+        let trip = model.add_const("trip", tensor0(3i64))?;
+        let outputs = model.wire_node("loop", Loop::new(body, true, false, 0)?, &[trip])?;
+        model.set_output_outlets(&outputs)?;
+        assert_eq!(model.outlet_fact(outputs[0])?.shape.to_tvec(), tvec!(3.to_dim()));
+        let outputs = model.into_runnable()?.run(tvec!())?;
+        assert_eq!(outputs[0], rctensor1(&[0i64, 1, 2]));
+        Ok(())
+    }
+}
@@ -2,9 +2,11 @@ use crate::internal::*;
 use std::fmt;
 
 mod lir;
+mod looping;
 mod mir;
 
 pub use lir::LirScan;
+pub use looping::{LirLoop, LirLoopParams, Loop};
 pub use mir::Scan;
 
 #[derive(Clone, new, Hash)]
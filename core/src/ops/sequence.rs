@@ -0,0 +1,880 @@
+//! Ops on sequences: ordered lists of tensors travelling in the graph as
+//! scalar tensors of DatumType::Sequence.
+//!
+//! An optional is a sequence of zero or one element, flagged as such in its
+//! fact.
+//!
+//! Sequences built with a length known statically are decluttered away: their
+//! consumers are rewired to the elements.
+use crate::internal::*;
+use crate::ops::array::TypedConcat;
+use crate::ops::konst::Const;
+
+fn sequence_value(tensor: &Tensor) -> TractResult<&Sequence> {
+    if tensor.rank() != 0 {
+        bail!("Expected a sequence, got {:?}", tensor)
+    }
+    tensor.to_scalar::<Sequence>()
+}
+
+/// Normalized position in a sequence of length `len`. Insertion positions may
+/// be equal to `len`.
+fn position(pos: &Tensor, len: usize, insert: bool) -> TractResult<usize> {
+    let pos = pos.cast_to_scalar::<i64>()?;
+    let upper = len as i64 + insert as i64;
+    let normalized = if pos < 0 { pos + len as i64 } else { pos };
+    if normalized < 0 || normalized >= upper {
+        bail!("Position {} is out of bounds for a sequence of length {}", pos, len)
+    }
+    Ok(normalized as usize)
+}
+
+/// Wires to the elements of a sequence whose length is known statically: the
+/// inputs of a SequenceConstruct, or the elements of a constant.
+fn static_elements(
+    model: &TypedModel,
+    patch: &mut TypedModelPatch,
+    outlet: OutletId,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let producer = model.node(outlet.node);
+    if producer.op_is::<SequenceConstruct>() {
+        let wires = producer
+            .inputs
+            .iter()
+            .map(|i| patch.tap_model(model, *i))
+            .collect::<TractResult<TVec<_>>>()?;
+        return Ok(Some(wires));
+    }
+    if producer.op_is::<SequenceEmpty>() {
+        return Ok(Some(tvec!()));
+    }
+    if let Some(konst) = &model.outlet_fact(outlet)?.konst {
+        let wires = sequence_value(konst)?
+            .iter()
+            .enumerate()
+            .map(|(ix, t)| patch.add_const(format!("{}.{}", producer.name, ix), t.clone()))
+            .collect::<TractResult<TVec<_>>>()?;
+        return Ok(Some(wires));
+    }
+    Ok(None)
+}
+
+/// Build a sequence from element wires.
+fn wire_sequence(
+    patch: &mut TypedModelPatch,
+    name: &str,
+    fact: &SequenceFact,
+    elements: &[OutletId],
+) -> TractResult<OutletId> {
+    if elements.is_empty() {
+        let op = SequenceEmpty { datum_type: fact.datum_type, optional: fact.optional };
+        Ok(patch.wire_node(name, op, &[])?[0])
+    } else {
+        let op = SequenceConstruct { optional: fact.optional };
+        Ok(patch.wire_node(name, op, elements)?[0])
+    }
+}
+
+/// Build a sequence out of its inputs.
+#[derive(Debug, Clone, new, Default, Hash)]
+pub struct SequenceConstruct {
+    pub optional: bool,
+}
+
+impl_dyn_hash!(SequenceConstruct);
+
+impl Op for SequenceConstruct {
+    fn name(&self) -> Cow<str> {
+        "SequenceConstruct".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SequenceConstruct {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let dt = inputs[0].datum_type();
+        let sequence =
+            Sequence { datum_type: dt, optional: self.optional, tensors: inputs.into_vec() };
+        Ok(tvec!(tensor0(sequence).into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SequenceConstruct {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs.is_empty() || (self.optional && inputs.len() != 1) {
+            bail!("Invalid inputs for {:?}: {:?}", self, inputs)
+        }
+        let dt = inputs[0].datum_type;
+        if inputs.iter().any(|i| i.datum_type != dt) {
+            bail!("Sequence elements must have the same type, got {:?}", inputs)
+        }
+        let mut fact =
+            SequenceFact::from_element_shapes(dt, inputs.iter().map(|i| &i.shape), Some(inputs.len()));
+        fact.optional = self.optional;
+        Ok(tvec!(TypedFact::sequence(fact)))
+    }
+}
+
+/// An empty sequence of a given element type.
+#[derive(Debug, Clone, new, Hash)]
+pub struct SequenceEmpty {
+    pub datum_type: DatumType,
+    pub optional: bool,
+}
+
+impl_dyn_hash!(SequenceEmpty);
+
+impl Op for SequenceEmpty {
+    fn name(&self) -> Cow<str> {
+        "SequenceEmpty".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{:?}", self.datum_type)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SequenceEmpty {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, _inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let sequence =
+            Sequence { datum_type: self.datum_type, optional: self.optional, tensors: vec![] };
+        Ok(tvec!(tensor0(sequence).into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SequenceEmpty {
+    as_op!();
+
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let mut fact = SequenceFact::new(self.datum_type, None, Some(0));
+        fact.optional = self.optional;
+        Ok(tvec!(TypedFact::sequence(fact)))
+    }
+}
+
+/// Extract the element at the position given by the second input.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct SequenceAt;
+
+impl_dyn_hash!(SequenceAt);
+
+impl Op for SequenceAt {
+    fn name(&self) -> Cow<str> {
+        "SequenceAt".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SequenceAt {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (sequence, pos) = args_2!(inputs);
+        let sequence = sequence_value(&sequence)?;
+        let pos = position(&pos, sequence.len(), false)?;
+        Ok(tvec!(sequence[pos].clone()))
+    }
+}
+
+impl TypedOp for SequenceAt {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].sequence_fact()?.element_fact()?))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let pos = if let Some(pos) = &model.outlet_fact(node.inputs[1])?.konst {
+            pos.clone()
+        } else {
+            return Ok(None);
+        };
+        let mut patch = TypedModelPatch::default();
+        if let Some(elements) = static_elements(model, &mut patch, node.inputs[0])? {
+            let pos = position(&pos, elements.len(), false)?;
+            patch.shunt_outside(model, node.id.into(), elements[pos])?;
+            return Ok(Some(patch));
+        }
+        Ok(None)
+    }
+}
+
+/// Insert the second input in the sequence, at the position given by the
+/// third input if any, at the end otherwise.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct SequenceInsert;
+
+impl_dyn_hash!(SequenceInsert);
+
+impl Op for SequenceInsert {
+    fn name(&self) -> Cow<str> {
+        "SequenceInsert".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SequenceInsert {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut sequence = sequence_value(&inputs[0])?.clone();
+        if inputs[1].datum_type() != sequence.datum_type {
+            bail!("Can not insert {:?} in a sequence of {:?}", inputs[1], sequence.datum_type)
+        }
+        let pos = if let Some(pos) = inputs.get(2) {
+            position(pos, sequence.len(), true)?
+        } else {
+            sequence.len()
+        };
+        sequence.tensors.insert(pos, inputs[1].clone());
+        Ok(tvec!(tensor0(sequence).into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SequenceInsert {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let sequence = inputs[0].sequence_fact()?;
+        if inputs[1].datum_type != sequence.datum_type {
+            bail!("Can not insert {:?} in a sequence of {:?}", inputs[1], sequence.datum_type)
+        }
+        let shape = if sequence.len == Some(0) {
+            Some(inputs[1].shape.clone())
+        } else {
+            sequence.shape.as_ref().and_then(|s| SequenceFact::merge_shapes(s, &inputs[1].shape))
+        };
+        let fact = SequenceFact { shape, len: sequence.len.map(|l| l + 1), ..sequence.clone() };
+        Ok(tvec!(TypedFact::sequence(fact)))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let pos = if let Some(pos) = node.inputs.get(2) {
+            if let Some(pos) = &model.outlet_fact(*pos)?.konst {
+                Some(pos.clone())
+            } else {
+                return Ok(None);
+            }
+        } else {
+            None
+        };
+        let mut patch = TypedModelPatch::default();
+        if let Some(mut elements) = static_elements(model, &mut patch, node.inputs[0])? {
+            let pos = if let Some(pos) = pos {
+                position(&pos, elements.len(), true)?
+            } else {
+                elements.len()
+            };
+            elements.insert(pos, patch.tap_model(model, node.inputs[1])?);
+            let fact = model.outlet_fact(node.id.into())?.sequence_fact()?;
+            let wire = wire_sequence(&mut patch, &node.name, fact, &elements)?;
+            patch.shunt_outside(model, node.id.into(), wire)?;
+            return Ok(Some(patch));
+        }
+        Ok(None)
+    }
+}
+
+/// Remove the element at the position given by the second input if any, the
+/// last one otherwise.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct SequenceErase;
+
+impl_dyn_hash!(SequenceErase);
+
+impl Op for SequenceErase {
+    fn name(&self) -> Cow<str> {
+        "SequenceErase".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SequenceErase {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut sequence = sequence_value(&inputs[0])?.clone();
+        let pos = if let Some(pos) = inputs.get(1) {
+            position(pos, sequence.len(), false)?
+        } else if sequence.len() > 0 {
+            sequence.len() - 1
+        } else {
+            bail!("Can not erase from an empty sequence")
+        };
+        sequence.tensors.remove(pos);
+        Ok(tvec!(tensor0(sequence).into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SequenceErase {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let sequence = inputs[0].sequence_fact()?;
+        if sequence.len == Some(0) {
+            bail!("Can not erase from an empty sequence")
+        }
+        let fact = SequenceFact { len: sequence.len.map(|l| l - 1), ..sequence.clone() };
+        Ok(tvec!(TypedFact::sequence(fact)))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let pos = if let Some(pos) = node.inputs.get(1) {
+            if let Some(pos) = &model.outlet_fact(*pos)?.konst {
+                Some(pos.clone())
+            } else {
+                return Ok(None);
+            }
+        } else {
+            None
+        };
+        let mut patch = TypedModelPatch::default();
+        if let Some(mut elements) = static_elements(model, &mut patch, node.inputs[0])? {
+            let pos = if let Some(pos) = pos {
+                position(&pos, elements.len(), false)?
+            } else {
+                elements.len() - 1
+            };
+            elements.remove(pos);
+            let fact = model.outlet_fact(node.id.into())?.sequence_fact()?;
+            let wire = wire_sequence(&mut patch, &node.name, fact, &elements)?;
+            patch.shunt_outside(model, node.id.into(), wire)?;
+            return Ok(Some(patch));
+        }
+        Ok(None)
+    }
+}
+
+/// Number of elements in a sequence, as an i64 scalar.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct SequenceLength;
+
+impl_dyn_hash!(SequenceLength);
+
+impl Op for SequenceLength {
+    fn name(&self) -> Cow<str> {
+        "SequenceLength".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SequenceLength {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let sequence = args_1!(inputs);
+        let len = sequence_value(&sequence)?.len() as i64;
+        Ok(tvec!(rctensor0(len)))
+    }
+}
+
+impl TypedOp for SequenceLength {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        inputs[0].sequence_fact()?;
+        Ok(tvec!(TypedFact::scalar::<i64>()))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if let Some(len) = model.outlet_fact(node.inputs[0])?.sequence_fact()?.len {
+            let op = Const::new(rctensor0(len as i64));
+            return Ok(Some(TypedModelPatch::replace_single_op(model, node, &[], op)?));
+        }
+        Ok(None)
+    }
+}
+
+/// Split a tensor along an axis into a sequence.
+///
+/// The optional second input gives either the length of every chunk (as a
+/// scalar, the last chunk being possibly shorter) or the list of the chunk
+/// lengths. Without it, the tensor is split in chunks of size 1, and the
+/// axis is removed unless keepdims is set.
+#[derive(Debug, Clone, new, Hash)]
+pub struct SplitToSequence {
+    pub axis: usize,
+    pub keepdims: bool,
+}
+
+impl_dyn_hash!(SplitToSequence);
+
+impl SplitToSequence {
+    fn chunks(&self, dim: usize, split: Option<&Tensor>) -> TractResult<TVec<usize>> {
+        let split = if let Some(split) = split {
+            split.cast_to::<i64>()?.into_owned()
+        } else {
+            return Ok(tvec!(1; dim));
+        };
+        let split = split.as_slice::<i64>()?;
+        if split.iter().any(|&s| s < 0) {
+            bail!("Negative split {:?}", split)
+        }
+        let chunks: TVec<usize> = if split.len() == 1 && split[0] > 0 {
+            let chunk = split[0] as usize;
+            (0..dim).step_by(chunk).map(|start| chunk.min(dim - start)).collect()
+        } else {
+            split.iter().map(|&s| s as usize).collect()
+        };
+        if chunks.iter().sum::<usize>() != dim {
+            bail!("Split {:?} does not match dimension {}", split, dim)
+        }
+        Ok(chunks)
+    }
+
+    fn squeeze(&self, split: Option<&TypedFact>) -> bool {
+        split.is_none() && !self.keepdims
+    }
+}
+
+impl Op for SplitToSequence {
+    fn name(&self) -> Cow<str> {
+        "SplitToSequence".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} keepdims: {}", self.axis, self.keepdims)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for SplitToSequence {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = &inputs[0];
+        let chunks = self.chunks(input.shape()[self.axis], inputs.get(1).map(|t| &**t))?;
+        let squeeze = inputs.len() == 1 && !self.keepdims;
+        let mut start = 0;
+        let mut tensors = vec![];
+        for chunk in chunks {
+            let mut tensor = input.slice(self.axis, start, start + chunk)?;
+            if squeeze {
+                tensor.remove_axis(self.axis)?;
+            }
+            tensors.push(tensor.into_arc_tensor());
+            start += chunk;
+        }
+        let sequence = Sequence::new(input.datum_type(), tensors);
+        Ok(tvec!(tensor0(sequence).into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SplitToSequence {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if self.axis >= inputs[0].rank() {
+            bail!("Invalid axis {} for splitting {:?}", self.axis, inputs[0])
+        }
+        let dim = &inputs[0].shape[self.axis];
+        let chunks = match (dim.to_usize(), inputs.get(1)) {
+            (Ok(dim), None) => Some(self.chunks(dim, None)?),
+            (Ok(dim), Some(split)) if split.konst.is_some() => {
+                Some(self.chunks(dim, split.konst.as_deref())?)
+            }
+            _ => None,
+        };
+        let mut shape = inputs[0].shape.clone();
+        if self.squeeze(inputs.get(1).copied()) {
+            shape.remove_axis(self.axis)?;
+        } else if let Some(chunks) =
+            chunks.as_ref().filter(|chunks| chunks.iter().all(|&c| c == chunks[0]))
+        {
+            shape.set(self.axis, chunks.get(0).cloned().unwrap_or(0).to_dim());
+        } else {
+            shape.set(self.axis, Symbol::new('s').into());
+        }
+        let fact = SequenceFact::new(inputs[0].datum_type, Some(shape), chunks.map(|c| c.len()));
+        Ok(tvec!(TypedFact::sequence(fact)))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let input_facts = model.node_input_facts(node.id)?;
+        let dim = if let Ok(dim) = input_facts[0].shape[self.axis].to_usize() {
+            dim
+        } else {
+            return Ok(None);
+        };
+        let split = match input_facts.get(1) {
+            Some(split) if split.konst.is_none() => return Ok(None),
+            Some(split) => split.konst.as_deref(),
+            None => None,
+        };
+        let chunks = self.chunks(dim, split)?;
+        let squeeze = self.squeeze(input_facts.get(1).copied());
+        let mut patch = TypedModelPatch::default();
+        let input = patch.tap_model(model, node.inputs[0])?;
+        let mut elements = tvec!();
+        let mut start = 0;
+        for (ix, chunk) in chunks.into_iter().enumerate() {
+            let name = format!("{}.slice-{}", node.name, ix);
+            let op = crate::ops::array::Slice::new(self.axis, start, start + chunk);
+            let mut wire = patch.wire_node(&name, op, &[input])?[0];
+            if squeeze {
+                let name = format!("{}.rm-axis-{}", node.name, ix);
+                wire = patch.wire_node(name, AxisOp::Rm(self.axis), &[wire])?[0];
+            }
+            elements.push(wire);
+            start += chunk;
+        }
+        let fact = SequenceFact::new(input_facts[0].datum_type, None, Some(elements.len()));
+        let wire = wire_sequence(&mut patch, &node.name, &fact, &elements)?;
+        patch.shunt_outside(model, node.id.into(), wire)?;
+        Ok(Some(patch))
+    }
+}
+
+/// Concatenate the elements of a sequence along an axis, or stack them along
+/// a new axis.
+///
+/// The axis may be negative, counting from the end of the element (or output
+/// if new_axis is set) shape.
+#[derive(Debug, Clone, new, Hash)]
+pub struct ConcatFromSequence {
+    pub axis: i64,
+    pub new_axis: bool,
+}
+
+impl_dyn_hash!(ConcatFromSequence);
+
+impl ConcatFromSequence {
+    fn axis(&self, element_rank: usize) -> TractResult<usize> {
+        let rank = element_rank + self.new_axis as usize;
+        let axis = if self.axis < 0 { self.axis + rank as i64 } else { self.axis };
+        if axis < 0 || axis >= rank as i64 {
+            bail!("Invalid axis {} for concatenating elements of rank {}", self.axis, element_rank)
+        }
+        Ok(axis as usize)
+    }
+}
+
+impl Op for ConcatFromSequence {
+    fn name(&self) -> Cow<str> {
+        "ConcatFromSequence".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} new_axis: {}", self.axis, self.new_axis)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for ConcatFromSequence {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let sequence = args_1!(inputs);
+        let sequence = sequence_value(&sequence)?;
+        if sequence.len() == 0 {
+            bail!("Can not concatenate an empty sequence")
+        }
+        let axis = self.axis(sequence[0].rank())?;
+        let output = if self.new_axis {
+            let tensors = sequence
+                .iter()
+                .map(|t| {
+                    let mut t = t.clone().into_tensor();
+                    t.insert_axis(axis)?;
+                    Ok(t)
+                })
+                .collect::<TractResult<TVec<_>>>()?;
+            Tensor::stack_tensors(axis, &tensors)?
+        } else {
+            Tensor::stack_tensors(axis, &sequence)?
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for ConcatFromSequence {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let sequence = inputs[0].sequence_fact()?;
+        let mut shape = sequence.element_fact()?.shape;
+        let axis = self.axis(shape.rank())?;
+        let dim = if let Some(len) = sequence.len {
+            if self.new_axis {
+                len.to_dim()
+            } else {
+                shape[axis].clone() * len
+            }
+        } else {
+            Symbol::new('s').into()
+        };
+        if self.new_axis {
+            shape.insert_axis(axis)?;
+        }
+        shape.set(axis, dim);
+        Ok(tvec!(TypedFact::dt_shape(sequence.datum_type, shape)))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let mut patch = TypedModelPatch::default();
+        let mut elements = if let Some(elements) = static_elements(model, &mut patch, node.inputs[0])? {
+            elements
+        } else {
+            return Ok(None);
+        };
+        if elements.is_empty() {
+            return Ok(None);
+        }
+        let axis = self.axis(patch.outlet_fact(elements[0])?.rank())?;
+        if self.new_axis {
+            for (ix, element) in elements.iter_mut().enumerate() {
+                let name = format!("{}.add-axis-{}", node.name, ix);
+                *element = patch.wire_node(name, AxisOp::Add(axis), &[*element])?[0];
+            }
+        }
+        let op = TypedConcat::concat_vars(axis, elements.len());
+        let wire = patch.wire_node(&node.name, op, &elements)?[0];
+        patch.shunt_outside(model, node.id.into(), wire)?;
+        Ok(Some(patch))
+    }
+}
+
+/// Whether an optional holds a value. Anything else than an optional holds one.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct OptionalHasElement;
+
+impl_dyn_hash!(OptionalHasElement);
+
+impl Op for OptionalHasElement {
+    fn name(&self) -> Cow<str> {
+        "OptionalHasElement".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for OptionalHasElement {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let has_element = if input.datum_type() == DatumType::Sequence && input.rank() == 0 {
+            let sequence = sequence_value(&input)?;
+            !sequence.optional || sequence.len() > 0
+        } else {
+            true
+        };
+        Ok(tvec!(rctensor0(has_element)))
+    }
+}
+
+impl TypedOp for OptionalHasElement {
+    as_op!();
+
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(TypedFact::scalar::<bool>()))
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let has_element = match &model.outlet_fact(node.inputs[0])?.sequence {
+            Some(seq) if seq.optional => {
+                if let Some(len) = seq.len {
+                    len > 0
+                } else {
+                    return Ok(None);
+                }
+            }
+            _ => true,
+        };
+        let op = Const::new(rctensor0(has_element));
+        Ok(Some(TypedModelPatch::replace_single_op(model, node, &[], op)?))
+    }
+}
+
+/// Value of an optional. Anything else than an optional is passed through.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct OptionalGetElement;
+
+impl_dyn_hash!(OptionalGetElement);
+
+impl Op for OptionalGetElement {
+    fn name(&self) -> Cow<str> {
+        "OptionalGetElement".into()
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for OptionalGetElement {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        if input.datum_type() == DatumType::Sequence && input.rank() == 0 {
+            let sequence = sequence_value(&input)?;
+            if sequence.optional {
+                let element = sequence.get(0).context("Getting the element of an empty optional")?;
+                return Ok(tvec!(element.clone()));
+            }
+        }
+        Ok(tvec!(input))
+    }
+}
+
+impl TypedOp for OptionalGetElement {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        match &inputs[0].sequence {
+            Some(seq) if seq.optional => {
+                if seq.len == Some(0) {
+                    bail!("Getting the element of an empty optional")
+                }
+                Ok(tvec!(seq.element_fact()?))
+            }
+            _ => Ok(tvec!(inputs[0].without_value())),
+        }
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        match &model.outlet_fact(node.inputs[0])?.sequence {
+            Some(seq) if seq.optional => {
+                let mut patch = TypedModelPatch::default();
+                if let Some(elements) = static_elements(model, &mut patch, node.inputs[0])? {
+                    if let Some(element) = elements.get(0) {
+                        patch.shunt_outside(model, node.id.into(), *element)?;
+                        return Ok(Some(patch));
+                    }
+                }
+                Ok(None)
+            }
+            _ => Ok(Some(TypedModelPatch::shunt_one_op(model, node)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_at_and_concat_eval() -> TractResult<()> {
+        let input = rctensor2(&[[1f32, 2.], [3., 4.], [5., 6.]]);
+        let seq = SplitToSequence::new(0, false).eval(tvec!(input.clone()))?.remove(0);
+        assert_eq!(sequence_value(&seq)?.len(), 3);
+        let at = SequenceAt.eval(tvec!(seq.clone(), rctensor0(-1i64)))?;
+        assert_eq!(*at[0], tensor1(&[5f32, 6.]));
+        let stacked = ConcatFromSequence::new(0, true).eval(tvec!(seq))?;
+        assert_eq!(stacked[0], input);
+        Ok(())
+    }
+
+    #[test]
+    fn insert_erase_eval() -> TractResult<()> {
+        let seq = SequenceEmpty::new(DatumType::I64, false).eval(tvec!())?.remove(0);
+        let seq = SequenceInsert.eval(tvec!(seq, rctensor1(&[1i64])))?.remove(0);
+        let seq = SequenceInsert.eval(tvec!(seq, rctensor1(&[0i64]), rctensor0(0i64)))?.remove(0);
+        let seq = SequenceErase.eval(tvec!(seq))?.remove(0);
+        let len = SequenceLength.eval(tvec!(seq.clone()))?;
+        assert_eq!(*len[0], tensor0(1i64));
+        let at = SequenceAt.eval(tvec!(seq, rctensor0(0i64)))?;
+        assert_eq!(*at[0], tensor1(&[0i64]));
+        Ok(())
+    }
+
+    #[test]
+    fn static_sequence_is_decluttered() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("source", TypedFact::dt_shape(f32::datum_type(), &[4, 3]))?;
+        let split = model.wire_node("split", SplitToSequence::new(0, true), &[source])?;
+        let two = model.add_const("two", rctensor0(2i64))?;
+        let at = model.wire_node("at", SequenceAt, &[split[0], two])?;
+        let erased = model.wire_node("erase", SequenceErase, &split)?;
+        let concat = model.wire_node("concat", ConcatFromSequence::new(-1, false), &erased)?;
+        let len = model.wire_node("len", SequenceLength, &erased)?;
+        model.set_output_outlets(&[at[0], concat[0], len[0]])?;
+        let decluttered = model.into_decluttered()?;
+        assert!(decluttered
+            .nodes()
+            .iter()
+            .all(|n| decluttered.outlet_fact(n.id.into()).unwrap().sequence.is_none()));
+        let input = tensor2(&[[1f32, 2., 3.], [4., 5., 6.], [7., 8., 9.], [10., 11., 12.]]);
+        let outputs = decluttered.into_runnable()?.run(tvec!(input))?;
+        assert_eq!(*outputs[0], tensor2(&[[7f32, 8., 9.]]));
+        assert_eq!(*outputs[1], tensor2(&[[1f32, 2., 3., 4., 5., 6., 7., 8., 9.]]));
+        assert_eq!(*outputs[2], tensor0(3i64));
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::internal::*;
+use ndarray::prelude::*;
+
+/// Nearest neighbour upsampling by an integer factor: each element is
+/// repeated `factor` times along `axis`.
+#[derive(Debug, Clone, new, PartialEq, Hash)]
+pub struct Upsample {
+    pub axis: usize,
+    pub factor: usize,
+}
+
+impl_dyn_hash!(Upsample);
+
+impl Op for Upsample {
+    fn name(&self) -> Cow<str> {
+        "Upsample".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis:{} factor:{}", self.axis, self.factor)])
+    }
+
+    op_core_mir!();
+    impl_op_same_as!();
+    op_as_typed_op!();
+}
+
+impl Upsample {
+    fn eval_t<T: Datum>(&self, input: &Tensor) -> TractResult<Tensor> {
+        let input = input.to_array_view::<T>()?;
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[self.axis] *= self.factor;
+        let output = ArrayD::from_shape_fn(&*shape, |mut coords| {
+            coords[self.axis] /= self.factor;
+            input[coords].clone()
+        });
+        Ok(output.into_tensor())
+    }
+}
+
+impl EvalOp for Upsample {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let mut output = dispatch_datum!(Self::eval_t(input.datum_type())(self, &input))?;
+        unsafe { output.set_datum_type(input.datum_type()) };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Upsample {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if self.axis >= inputs[0].rank() {
+            bail!("Upsample axis {} is invalid for {:?}", self.axis, inputs[0])
+        }
+        let mut fact = inputs[0].without_value();
+        fact.shape.set(self.axis, fact.shape[self.axis].clone() * self.factor);
+        Ok(tvec!(fact))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        Ok((0..inputs[0].rank()).filter(|&ax| ax != self.axis).map(AxisInfo::simple).collect())
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if self.factor == 1 {
+            Ok(Some(TypedModelPatch::shunt_one_op(model, node)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,445 @@
+//! Inter-op parallel evaluation of a model.
+//!
+//! `ParallelPlan` turns the graph into a dependency DAG. `ParallelState` runs
+//! every node as soon as all its precursors are done, spreading independent
+//! branches over the executor thread pool.
+//!
+//! Stateless ops run without any lock. Stateful ops run one at a time against
+//! the state session, like in `SimplePlan`, so the session tensors (TensorFlow
+//! variables) they write are seen by the other branches and the next runs.
+//! Their kernels run on the calling thread: a worker holding the session must
+//! not steal another stateful node while waiting for its own kernel threads.
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::internal::*;
+use crate::model::order::eval_order_for_nodes;
+use crate::model::{Fact, Graph, OutletId};
+use crate::plan::resolve;
+use tract_linalg::multithread::{current_tract_executor, multithread_tract_scope, Executor};
+
+#[derive(Debug, Clone, Educe)]
+#[educe(Hash)]
+pub struct ParallelPlan<F, O, M>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash + Send + Sync,
+    M: Borrow<Graph<F, O>> + Hash,
+{
+    pub model: M,
+    pub outputs: Vec<OutletId>,
+    /// Nodes to run, in a valid sequential order.
+    pub order: Vec<usize>,
+    /// For each node, the nodes that must be run before it.
+    pub predecessors: Vec<TVec<usize>>,
+    /// For each node, the nodes waiting for it.
+    pub successors: Vec<TVec<usize>>,
+    /// For each node, the number of nodes consuming its outputs. Values are
+    /// dropped once all their consumers have run (unless they are outputs).
+    pub consumers: Vec<usize>,
+    pub has_unresolved_symbols: bool,
+    _casper: PhantomData<(F, O)>,
+}
+
+impl<F, O, M> ParallelPlan<F, O, M>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash + Send + Sync,
+    M: Borrow<Graph<F, O>> + Hash,
+{
+    /// This contructor returns a plan that will compute all the model default outputs in one pass.
+    pub fn new(model: M) -> TractResult<ParallelPlan<F, O, M>> {
+        let outputs = model.borrow().output_outlets()?.to_vec();
+        Self::new_for_outputs_and_deps(model, &outputs, &[])
+    }
+
+    pub fn new_for_outputs_and_deps(
+        model: M,
+        outputs: &[OutletId],
+        deps: &[(usize, usize)],
+    ) -> TractResult<ParallelPlan<F, O, M>> {
+        let graph = model.borrow();
+        let inputs = graph.input_outlets()?.iter().map(|n| n.node).collect::<Vec<usize>>();
+        let outputs_nodes = outputs.iter().map(|n| n.node).collect::<Vec<usize>>();
+        let order = eval_order_for_nodes(graph.nodes(), &inputs, &outputs_nodes, deps)?;
+        let mut predecessors: Vec<TVec<usize>> = vec![tvec!(); graph.nodes().len()];
+        let mut successors: Vec<TVec<usize>> = vec![tvec!(); graph.nodes().len()];
+        let mut consumers = vec![0; graph.nodes().len()];
+        for &node in &order {
+            if inputs.contains(&node) {
+                continue;
+            }
+            let mut precs: TVec<usize> = graph.node(node).inputs.iter().map(|i| i.node).collect();
+            precs.sort();
+            precs.dedup();
+            for &prec in &precs {
+                consumers[prec] += 1;
+            }
+            precs.extend(deps.iter().filter(|d| d.0 == node).map(|d| d.1));
+            precs.sort();
+            precs.dedup();
+            for &prec in &precs {
+                successors[prec].push(node);
+            }
+            predecessors[node] = precs;
+        }
+        let mut symbols: std::collections::HashSet<Symbol> = Default::default();
+        for node in &graph.nodes {
+            for output in &node.outputs {
+                if let Ok(fact) = output.fact.to_typed_fact() {
+                    symbols.extend(fact.shape.iter().flat_map(|d| d.symbols()))
+                }
+            }
+        }
+        Ok(ParallelPlan {
+            model,
+            outputs: outputs.to_vec(),
+            order,
+            predecessors,
+            successors,
+            consumers,
+            has_unresolved_symbols: !symbols.is_empty(),
+            _casper: PhantomData,
+        })
+    }
+
+    pub fn run(&self, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut state = ParallelState::new(self)?;
+        state.run(inputs)
+    }
+
+    pub fn model(&self) -> &Graph<F, O> {
+        self.model.borrow()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParallelState<F, O, M, P>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash + Send + Sync,
+    M: Borrow<Graph<F, O>> + Hash,
+    P: Borrow<ParallelPlan<F, O, M>>,
+{
+    plan: P,
+    pub states: Vec<Option<Box<dyn OpState>>>,
+    pub session_state: SessionState,
+    _phantom: PhantomData<(M, F, O)>,
+}
+
+/// What the workers share during a run.
+struct Run<'a, F, O>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash + Send + Sync,
+{
+    model: &'a Graph<F, O>,
+    successors: &'a [TVec<usize>],
+    has_unresolved_symbols: bool,
+    keep: Vec<bool>,
+    values: Vec<Mutex<Option<TVec<Arc<Tensor>>>>>,
+    pending_precursors: Vec<AtomicUsize>,
+    pending_consumers: Vec<AtomicUsize>,
+    states: Vec<Mutex<Option<Box<dyn OpState>>>>,
+    /// Session of the stateful ops.
+    session: Mutex<SessionState>,
+    resolved_symbols: Mutex<SymbolValues>,
+    failed: AtomicBool,
+    error: Mutex<Option<TractError>>,
+}
+
+impl<'a, F, O> Run<'a, F, O>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash + Send + Sync,
+{
+    fn eval_node(&self, node: usize) -> TractResult<()> {
+        let node = self.model.node(node);
+        let mut inputs: TVec<Arc<Tensor>> = tvec!();
+        for i in &node.inputs {
+            let prec = self.values[i.node].lock().unwrap();
+            let prec = prec.as_ref().ok_or_else(|| {
+                format_err!("Computing {}, precursor {} not done", node, self.model.node(i.node))
+            })?;
+            inputs.push(prec[i.slot].clone())
+        }
+        let mut precs: TVec<usize> = node.inputs.iter().map(|i| i.node).collect();
+        precs.sort();
+        precs.dedup();
+        for prec in precs {
+            if self.pending_consumers[prec].fetch_sub(1, Ordering::AcqRel) == 1 && !self.keep[prec]
+            {
+                *self.values[prec].lock().unwrap() = None;
+            }
+        }
+        let vs = if node.op().is_stateless() {
+            node.op().eval(inputs).with_context(|| format!("Evaluating {}", node))?
+        } else {
+            let mut session = self.session.lock().unwrap();
+            session.resolved_symbols = self.resolved_symbols.lock().unwrap().clone();
+            let mut state = self.states[node.id].lock().unwrap();
+            multithread_tract_scope(Executor::SingleThread, || {
+                crate::plan::eval(&mut session, state.as_mut().map(|s| &mut **s), node, inputs)
+            })?
+        };
+        if self.has_unresolved_symbols {
+            let mut resolved_symbols = self.resolved_symbols.lock().unwrap();
+            for (o, v) in node.outputs.iter().zip(vs.iter()) {
+                if let Ok(f) = o.fact.to_typed_fact() {
+                    for (dim_abstract, dim_concrete) in f.shape.iter().zip(v.shape()) {
+                        resolve(&mut resolved_symbols, &dim_abstract, *dim_concrete as i64);
+                    }
+                }
+            }
+        }
+        *self.values[node.id].lock().unwrap() = Some(vs);
+        Ok(())
+    }
+
+    fn fail(&self, e: TractError) {
+        self.failed.store(true, Ordering::Release);
+        self.error.lock().unwrap().get_or_insert(e);
+    }
+
+    fn spawn<'s>(&'s self, scope: &rayon::Scope<'s>, executor: &'s Executor, node: usize) {
+        scope.spawn(move |scope| {
+            if self.failed.load(Ordering::Acquire) {
+                return;
+            }
+            let result = multithread_tract_scope(executor.clone(), || self.eval_node(node));
+            if let Err(e) = result {
+                return self.fail(e);
+            }
+            for &succ in &self.successors[node] {
+                if self.pending_precursors[succ].fetch_sub(1, Ordering::AcqRel) == 1 {
+                    self.spawn(scope, executor, succ);
+                }
+            }
+        })
+    }
+}
+
+impl<F, O, M, P> ParallelState<F, O, M, P>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash + Send + Sync,
+    M: Borrow<Graph<F, O>> + Hash,
+    P: Borrow<ParallelPlan<F, O, M>> + Clone,
+{
+    pub fn new(plan: P) -> TractResult<ParallelState<F, O, M, P>> {
+        let mut session = SessionState::default();
+        let model = plan.borrow().model();
+        let states: Vec<Option<Box<dyn OpState>>> = model
+            .nodes()
+            .iter()
+            .map(|n: &Node<F, O>| n.op().state(&mut session, n.id))
+            .collect::<TractResult<_>>()?;
+        Ok(ParallelState { plan, states, session_state: session, _phantom: PhantomData })
+    }
+
+    /// Run independent nodes (and the parallelizable kernels) on `executor`.
+    pub fn set_executor(&mut self, executor: Executor) {
+        self.session_state.executor = Some(executor);
+    }
+
+    pub fn set_input(&mut self, input: usize, t: Tensor) -> TractResult<()> {
+        let model = self.plan.borrow().model();
+        let outlet: OutletId = *model
+            .input_outlets()?
+            .get(input)
+            .ok_or_else(|| format_err!("Invalid input id for model ({}).", input))?;
+        if let Ok(fact) = model.outlet_fact(outlet)?.to_typed_fact() {
+            for (expected, provided) in fact.shape.iter().zip(t.shape()) {
+                resolve(&mut self.session_state.resolved_symbols, &expected, *provided as i64)
+            }
+        }
+        model
+            .outlet_fact(outlet)?
+            .matches(&t, Some(&self.session_state.resolved_symbols))
+            .with_context(|| format!("Setting input {}", input))?;
+        self.session_state.inputs.insert(outlet.node, t.into());
+        Ok(())
+    }
+
+    pub fn run(&mut self, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        for (ix, t) in inputs.into_iter().enumerate() {
+            self.set_input(ix, t)?
+        }
+        let executor =
+            self.session_state.executor.clone().unwrap_or_else(|| current_tract_executor());
+        let ParallelState { ref plan, ref mut session_state, ref mut states, .. } = self;
+        let plan = plan.borrow();
+        let resolved_symbols = session_state.resolved_symbols.clone();
+        let model = plan.model();
+        let mut keep = vec![false; model.nodes().len()];
+        for output in &plan.outputs {
+            keep[output.node] = true;
+        }
+        let run = Run {
+            model,
+            successors: &plan.successors,
+            has_unresolved_symbols: plan.has_unresolved_symbols,
+            keep,
+            values: (0..model.nodes().len()).map(|_| Mutex::new(None)).collect(),
+            pending_precursors: plan
+                .predecessors
+                .iter()
+                .map(|precs| AtomicUsize::new(precs.len()))
+                .collect(),
+            pending_consumers: plan.consumers.iter().map(|&c| AtomicUsize::new(c)).collect(),
+            states: states.drain(..).map(Mutex::new).collect(),
+            session: Mutex::new(std::mem::take(session_state)),
+            resolved_symbols: Mutex::new(resolved_symbols),
+            failed: AtomicBool::new(false),
+            error: Mutex::new(None),
+        };
+        match &executor {
+            Executor::MultiThread(pool) if pool.current_num_threads() > 1 => {
+                let roots: Vec<usize> = plan
+                    .order
+                    .iter()
+                    .cloned()
+                    .filter(|&node| plan.predecessors[node].len() == 0)
+                    .collect();
+                pool.scope(|scope| {
+                    for node in roots {
+                        run.spawn(scope, &executor, node);
+                    }
+                });
+            }
+            _ => {
+                for &node in &plan.order {
+                    if let Err(e) = run.eval_node(node) {
+                        run.fail(e);
+                        break;
+                    }
+                }
+            }
+        }
+        let Run { states: node_states, session, resolved_symbols, values, error, .. } = run;
+        *states = node_states.into_iter().map(|s| s.into_inner().unwrap()).collect();
+        self.session_state = session.into_inner().unwrap();
+        self.session_state.resolved_symbols = resolved_symbols.into_inner().unwrap();
+        if let Some(e) = error.into_inner().unwrap() {
+            return Err(e);
+        }
+        let mut result = tvec!();
+        for output in &plan.outputs {
+            let value = values[output.node].lock().unwrap();
+            let value = value.as_ref().ok_or_else(|| {
+                format_err!("Output {:?} ({}) was not computed", output, model.node(output.node))
+            })?;
+            result.push(value[output.slot].clone())
+        }
+        Ok(result)
+    }
+
+    pub fn plan(&self) -> &ParallelPlan<F, O, M> {
+        self.plan.borrow()
+    }
+
+    pub fn model(&self) -> &Graph<F, O> {
+        self.plan().model()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    fn branchy_model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[3]))?;
+        let mut branches = tvec!();
+        for i in 0..6 {
+            let mut wire = tvec!(source);
+            for j in 0..4 {
+                wire = model.wire_node(
+                    format!("b{}.{}", i, j),
+                    math::add::unary(rctensor1(&[(i * j) as f32; 3])),
+                    &wire,
+                )?;
+            }
+            branches.push(wire[0]);
+        }
+        let mut wire = branches[0];
+        for (ix, b) in branches[1..].iter().enumerate() {
+            wire = model.wire_node(format!("sum.{}", ix), math::add::bin_typed(), &[wire, *b])?[0];
+        }
+        model.set_output_outlets(&[wire, branches[2]])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn same_results_as_simple_plan() -> TractResult<()> {
+        let model = branchy_model()?;
+        let input = tensor1(&[1f32, 2., 3.]);
+        let expected = SimplePlan::new(&model)?.run(tvec!(input.clone()))?;
+        let plan = ParallelPlan::new(&model)?;
+        let mut state = ParallelState::new(&plan)?;
+        state.set_executor(Executor::multithread(4)?);
+        for _ in 0..10 {
+            let found = state.run(tvec!(input.clone()))?;
+            assert_eq!(found, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_matmul_branches() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[32, 8]))?;
+        let mut branches = tvec!();
+        for i in 0..2 {
+            let a = Tensor::from_shape(&[64, 32], &*vec![i as f32 + 0.5; 64 * 32])?;
+            let mut wire = source;
+            for j in 0..3 {
+                let mm = crate::ops::matmul::MatMulUnary::new(
+                    a.clone().into_arc_tensor(),
+                    false,
+                    false,
+                    false,
+                );
+                wire = model.wire_node(format!("mm.{}.{}", i, j), mm, &[wire])?[0];
+                let slice = crate::ops::array::Slice::new(0, 0, 32);
+                wire = model.wire_node(format!("slice.{}.{}", i, j), slice, &[wire])?[0];
+            }
+            branches.push(wire);
+        }
+        let sum = model.wire_node("sum", math::add::bin_typed(), &branches)?;
+        model.set_output_outlets(&sum)?;
+        let model = model.into_optimized()?;
+        assert_eq!(
+            model
+                .nodes()
+                .iter()
+                .filter(|n| n.op_is::<crate::ops::matmul::lir_unary::LirMatMulUnary>())
+                .count(),
+            6
+        );
+        let input =
+            Tensor::from_shape(&[32, 8], &*(0..256).map(|x| x as f32 / 256.).collect::<Vec<_>>())?;
+        let expected = SimplePlan::new(&model)?.run(tvec!(input.clone()))?;
+        let plan = ParallelPlan::new(&model)?;
+        let mut state = ParallelState::new(&plan)?;
+        state.set_executor(Executor::multithread(4)?);
+        for _ in 0..20 {
+            let found = state.run(tvec!(input.clone()))?;
+            assert_eq!(found, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn single_thread_fallback() -> TractResult<()> {
+        let model = branchy_model()?;
+        let input = tensor1(&[1f32, 2., 3.]);
+        let expected = SimplePlan::new(&model)?.run(tvec!(input.clone()))?;
+        let found = ParallelPlan::new(&model)?.run(tvec!(input))?;
+        assert_eq!(found, expected);
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use std::marker::PhantomData;
 use crate::internal::*;
 use crate::model::order::eval_order_for_nodes;
 use crate::model::{Fact, Graph, OutletId};
+use tract_linalg::multithread::{multithread_tract_scope, Executor};
 
 #[derive(Default)]
 pub struct SessionState {
@@ -12,6 +13,12 @@ pub struct SessionState {
     pub resolved_symbols: SymbolValues,
     pub tensors: HashMap<String, Tensor>,
     pub cached_mmm_scratch_space: Option<Box<dyn tract_linalg::mmm::ScratchSpace>>,
+    /// Executor for the parallelizable kernels. Falls back to the
+    /// tract_linalg process-wide default if `None`.
+    pub executor: Option<Executor>,
+    /// Seed of the random generator ops that do not carry their own. Falls
+    /// back to entropy if `None`.
+    pub random_seed: Option<u64>,
 }
 
 impl Clone for SessionState {
@@ -21,6 +28,8 @@ impl Clone for SessionState {
             resolved_symbols: self.resolved_symbols.clone(),
             tensors: self.tensors.clone(),
             cached_mmm_scratch_space: None,
+            executor: self.executor.clone(),
+            random_seed: self.random_seed,
         }
     }
 }
@@ -173,11 +182,44 @@ where
         Ok(())
     }
 
+    /// Run the parallelizable kernels of this state on `executor`.
+    pub fn set_executor(&mut self, executor: Executor) {
+        self.session_state.executor = Some(executor);
+    }
+
+    /// Seed the random generator ops that do not carry their own seed, for
+    /// reproducible runs. Takes effect on ops that have not run yet, so it
+    /// should be called before the first run or after `reset_op_states`.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.session_state.random_seed = Some(seed);
+    }
+
     pub fn run(&mut self, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
         self.run_plan_with_eval(inputs, self::eval)
     }
 
     pub fn run_plan_with_eval<Eval, E>(
+        &mut self,
+        inputs: TVec<Tensor>,
+        eval: Eval,
+    ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        Eval: for<'a, 'b, 'c> FnMut(
+            &'a mut SessionState,
+            Option<&'b mut (dyn OpState + 'static)>,
+            &'c Node<F, O>,
+            TVec<Arc<Tensor>>,
+        ) -> Result<TVec<Arc<Tensor>>, E>,
+        E: Into<anyhow::Error> + Send + Sync + 'static,
+    {
+        if let Some(executor) = self.session_state.executor.clone() {
+            multithread_tract_scope(executor, || self.do_run_plan_with_eval(inputs, eval))
+        } else {
+            self.do_run_plan_with_eval(inputs, eval)
+        }
+    }
+
+    fn do_run_plan_with_eval<Eval, E>(
         &mut self,
         inputs: TVec<Tensor>,
         mut eval: Eval,
@@ -252,7 +294,7 @@ where
                     for (o, v) in node.outputs.iter().zip(vs.iter()) {
                         if let Ok(f) = o.fact.to_typed_fact() {
                             for (dim_abstract, dim_concrete) in f.shape.iter().zip(v.shape()) {
-                                Self::resolve(
+                                resolve(
                                     &mut session_state.resolved_symbols,
                                     &dim_abstract,
                                     *dim_concrete as i64,
@@ -305,14 +347,6 @@ where
         Ok(())
     }
 
-    fn resolve(symbols: &mut SymbolValues, expected: &TDim, provided: i64) {
-        match expected {
-            TDim::Sym(s) => symbols[*s] = Some(provided),
-            TDim::MulInt(x, expr) => Self::resolve(symbols, expr, provided / *x),
-            _ => (),
-        }
-    }
-
     pub fn set_input(&mut self, input: usize, t: Tensor) -> TractResult<()> {
         let outlet: OutletId = *self
             .model()
@@ -324,7 +358,7 @@ where
         let model = plan.model.borrow();
         if let Ok(fact) = model.outlet_fact(outlet)?.to_typed_fact() {
             for (expected, provided) in fact.shape.iter().zip(t.shape()) {
-                Self::resolve(&mut session_state.resolved_symbols, &expected, *provided as i64)
+                resolve(&mut session_state.resolved_symbols, &expected, *provided as i64)
             }
         }
         self.plan
@@ -453,6 +487,14 @@ where
     }
 }
 
+pub(crate) fn resolve(symbols: &mut SymbolValues, expected: &TDim, provided: i64) {
+    match expected {
+        TDim::Sym(s) => symbols[*s] = Some(provided),
+        TDim::MulInt(x, expr) => resolve(symbols, expr, provided / *x),
+        _ => (),
+    }
+}
+
 pub fn eval<F, O>(
     session_state: &mut SessionState,
     mut state: Option<&mut (dyn OpState + 'static)>,
@@ -0,0 +1,70 @@
+//! Running a model in a memory arena must not allocate once the state is
+//! built.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use tract_core::internal::*;
+use tract_core::model::ArenaState;
+use tract_core::ops::{change_axes::AxisOp, math};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|a| a.get())
+}
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let s = model.add_source("s", TypedFact::dt_shape(f32::datum_type(), &[4, 16]))?;
+    let a = model.wire_node("a", math::add::unary(rctensor2(&[[1f32]])), &[s])?[0];
+    let b = model.wire_node("b", math::mul::unary(rctensor2(&[[-2f32]])), &[s])?[0];
+    let b = model.wire_node("b.abs", math::abs(), &[b])?[0];
+    let c = model.wire_node("c", math::add::bin_typed(), &[a, b])?[0];
+    let d = model.wire_node(
+        "d",
+        AxisOp::Reshape(0, tvec!(4.to_dim(), 16.to_dim()), tvec!(64.to_dim())),
+        &[c],
+    )?[0];
+    let e = model.wire_node("e", math::mul::bin_typed(), &[d, d])?[0];
+    model.set_output_outlets(&[e])?;
+    Ok(model)
+}
+
+#[test]
+fn runs_without_allocating() -> TractResult<()> {
+    let model = model()?;
+    let input = Tensor::from_shape(&[4, 16], &*(0..64).map(|x| x as f32).collect::<Vec<_>>())?;
+    let before = allocations();
+    let expected = SimplePlan::new(&model)?.run(tvec!(input.clone()))?;
+    assert!(allocations() > before);
+
+    let mut state = ArenaState::new(&model)?;
+    assert!(state.plan().peak_memory() < state.plan().naive_memory());
+    for _ in 0..3 {
+        let before = allocations();
+        state.input_mut(0)?.as_slice_mut::<f32>()?.copy_from_slice(input.as_slice::<f32>()?);
+        state.run()?;
+        assert_eq!(allocations(), before);
+        assert_eq!(state.output(0)?, &*expected[0]);
+    }
+    assert!(state.allocating_nodes().is_empty());
+    Ok(())
+}
@@ -6,6 +6,7 @@ use crate::tensor::Tensor;
 use crate::TVec;
 use num_complex::Complex;
 use std::hash::Hash;
+use std::sync::Arc;
 use std::{fmt, ops};
 
 mod arrays;
@@ -36,6 +37,47 @@ impl std::str::FromStr for Blob {
     }
 }
 
+/// An ordered list of tensors of the same type: the runtime value of a
+/// sequence outlet. An optional is a sequence of zero or one element.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Sequence {
+    pub datum_type: DatumType,
+    pub optional: bool,
+    pub tensors: Vec<Arc<Tensor>>,
+}
+
+impl Sequence {
+    pub fn new(datum_type: DatumType, tensors: Vec<Arc<Tensor>>) -> Sequence {
+        Sequence { datum_type, optional: false, tensors }
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Sequence {
+        Sequence::new(DatumType::F32, vec![])
+    }
+}
+
+impl ops::Deref for Sequence {
+    type Target = [Arc<Tensor>];
+    fn deref(&self) -> &[Arc<Tensor>] {
+        &self.tensors
+    }
+}
+
+impl fmt::Display for Sequence {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Sequence of {} {:?}: {:?}", self.len(), self.datum_type, self.tensors)
+    }
+}
+
+impl std::str::FromStr for Sequence {
+    type Err = ();
+    fn from_str(_s: &str) -> Result<Sequence, ()> {
+        Err(())
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
 pub enum QParams {
     MinMax { min: f32, max: f32 },
@@ -96,6 +138,7 @@ pub enum DatumType {
     TDim,
     Blob,
     String,
+    Sequence,
     QI8(QParams),
     QU8(QParams),
     ComplexI16,
@@ -109,7 +152,12 @@ pub enum DatumType {
 impl DatumType {
     pub fn super_types(&self) -> TVec<DatumType> {
         use DatumType::*;
-        if *self == String || *self == TDim || *self == Blob || *self == Bool || self.is_quantized()
+        if *self == String
+            || *self == TDim
+            || *self == Blob
+            || *self == Sequence
+            || *self == Bool
+            || self.is_quantized()
         {
             tvec!(*self)
         } else if self.is_complex_float() {
@@ -306,6 +354,7 @@ impl std::str::FromStr for DatumType {
             "F64" | "f64" => Ok(DatumType::F64),
             "Bool" | "bool" => Ok(DatumType::Bool),
             "Blob" | "blob" => Ok(DatumType::Blob),
+            "Sequence" | "sequence" => Ok(DatumType::Sequence),
             "String" | "string" => Ok(DatumType::String),
             "TDim" | "tdim" => Ok(DatumType::TDim),
             "ComplexI16" | "complexi16" => Ok(DatumType::ComplexI16),
@@ -403,6 +452,7 @@ datum!(u64, U64);
 datum!(TDim, TDim);
 datum!(String, String);
 datum!(Blob, Blob);
+datum!(Sequence, Sequence);
 datum!(Complex<i16>, ComplexI16);
 datum!(Complex<i32>, ComplexI32);
 datum!(Complex<i64>, ComplexI64);
@@ -1,5 +1,5 @@
 use num_complex::Complex;
-use crate::datum::Blob;
+use crate::datum::{Blob, Sequence};
 use crate::dim::TDim;
 use crate::prelude::*;
 use crate::tensor::IntoTensor;
@@ -92,3 +92,4 @@ impl_stack_views_by_copy!(Complex<i64>);
 impl_stack_views_by_clone!(Blob);
 impl_stack_views_by_clone!(String);
 impl_stack_views_by_clone!(TDim);
+impl_stack_views_by_clone!(Sequence);
@@ -287,6 +287,12 @@ impl ops::Sub<&f16> for f16 {
     }
 }
 
+impl ops::SubAssign<f16> for f16 {
+    fn sub_assign(&mut self, other: f16) {
+        *self = *self - other
+    }
+}
+
 impl ops::Mul<f16> for f16 {
     type Output = f16;
     fn mul(self, other: f16) -> f16 {
@@ -301,6 +307,12 @@ impl ops::Mul<&f16> for f16 {
     }
 }
 
+impl ops::MulAssign<f16> for f16 {
+    fn mul_assign(&mut self, other: f16) {
+        *self = *self * other
+    }
+}
+
 impl ops::Div<f16> for f16 {
     type Output = f16;
     fn div(self, other: f16) -> f16 {
@@ -18,7 +18,7 @@ pub type TractResult<T> = anyhow::Result<T>;
 
 pub mod prelude {
     pub use crate::{ TractError, TractResult };
-    pub use crate::datum::{round_ties_to_even, Blob, Datum, DatumType, QParams};
+    pub use crate::datum::{round_ties_to_even, Blob, Datum, DatumType, QParams, Sequence};
     pub use crate::dim::{Symbol, SymbolValues, TDim, ToDim};
     pub use crate::f16::*;
     pub use crate::tensor::litteral::*;
@@ -1,5 +1,7 @@
 //! `Tensor`, tract main data object of interest.
-use crate::datum::{round_ties_to_even, scale_by, Blob, ClampCast, Datum, DatumType, QParams};
+use crate::datum::{
+    round_ties_to_even, scale_by, Blob, ClampCast, Datum, DatumType, QParams, Sequence,
+};
 use crate::dim::TDim;
 use crate::f16::f16;
 use crate::TVec;
@@ -55,6 +57,7 @@ impl Hash for Tensor {
                 TDim => self.as_slice_unchecked::<crate::dim::TDim>().hash(state),
                 String => self.as_slice_unchecked::<std::string::String>().hash(state),
                 Blob => self.as_slice_unchecked::<crate::datum::Blob>().hash(state),
+                Sequence => self.as_slice_unchecked::<crate::datum::Sequence>().hash(state),
                 QI8(_) => self.as_slice_unchecked::<i8>().hash(state),
                 QU8(_) => self.as_slice_unchecked::<u8>().hash(state),
                 ComplexI16 => self.as_slice_unchecked::<Complex<i16>>().hash(state),
@@ -106,6 +109,14 @@ impl Drop for Tensor {
                     .for_each(|s| std::ptr::drop_in_place(s as *mut TDim));
             }
         }
+        if self.dt == DatumType::Sequence {
+            unsafe {
+                self.as_slice_mut::<Sequence>()
+                    .unwrap()
+                    .iter_mut()
+                    .for_each(|s| std::ptr::drop_in_place(s as *mut Sequence));
+            }
+        }
         if !self.data.is_null() && self.layout.size() > 0 {
             unsafe { alloc::dealloc(self.data, self.layout) }
         }
@@ -143,12 +154,15 @@ impl Tensor {
             return Ok(ndarray::ArrayD::<Blob>::default(shape).into());
         } else if dt == TDim::datum_type() {
             return Ok(ndarray::ArrayD::<TDim>::default(shape).into());
+        } else if dt == Sequence::datum_type() {
+            return Ok(ndarray::ArrayD::<Sequence>::default(shape).into());
         }
         assert!(dt.is_copy());
         let bytes = shape.iter().cloned().product::<usize>() * dt.size_of();
         let layout = alloc::Layout::from_size_align(bytes, alignment)?;
         let data = if bytes == 0 {
-            std::ptr::null()
+            // dangling but aligned, like the pointer of an empty Vec
+            alignment as *const u8
         } else {
             let ptr = alloc::alloc(layout);
             assert!(!ptr.is_null());
@@ -167,6 +181,27 @@ impl Tensor {
         Ok(tensor)
     }
 
+    /// Create a tensor over memory it does not own, for instance a slice of
+    /// a memory arena.
+    ///
+    /// # Safety
+    ///
+    /// `data` must be aligned for `dt`, valid for the tensor size and outlive
+    /// the tensor. The tensor is wrapped in `ManuallyDrop` as dropping it would
+    /// free the memory.
+    pub unsafe fn from_raw_parts_unowned(
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+    ) -> anyhow::Result<std::mem::ManuallyDrop<Tensor>> {
+        anyhow::ensure!(dt.is_copy(), "Unowned tensors must have a Copy datum type, got {:?}", dt);
+        let bytes = shape.iter().cloned().product::<usize>() * dt.size_of();
+        let layout = alloc::Layout::from_size_align(bytes, dt.alignment())?;
+        let mut tensor = Tensor { strides: tvec!(), layout, dt, shape: shape.into(), data, len: 0 };
+        tensor.update_strides_and_len();
+        Ok(std::mem::ManuallyDrop::new(tensor))
+    }
+
     pub fn stack_tensors(
         axis: usize,
         tensors: &[impl std::borrow::Borrow<Tensor>],
@@ -194,6 +229,7 @@ impl Tensor {
                 DatumType::TDim => TDim::stack_tensors(axis, &tensors),
                 DatumType::Blob => Blob::stack_tensors(axis, &tensors),
                 DatumType::String => String::stack_tensors(axis, &tensors),
+                DatumType::Sequence => Sequence::stack_tensors(axis, &tensors),
                 DatumType::QI8(_) => i8::stack_tensors(axis, &tensors),
                 DatumType::QU8(_) => i8::stack_tensors(axis, &tensors),
                 DatumType::ComplexI16 => Complex::<i16>::stack_tensors(axis, &tensors),
@@ -872,6 +908,9 @@ impl Tensor {
                 }
                 return Ok(Cow::Owned(ints.cast_to_dt(dst_dt)?.into_owned()));
             }
+            if self.dt == DatumType::Sequence || dst_dt == DatumType::Sequence {
+                anyhow::bail!("Can not cast {:?} to {:?}", self.dt, dst_dt)
+            }
             if self.dt == bool::datum_type() && (dst_dt.is_integer() || dst_dt.is_float()) {
                 let slice = self.as_slice_unchecked::<bool>();
                 let mut ints = Self::uninitialized::<i8>(&self.shape)?;
@@ -1143,6 +1182,16 @@ impl Tensor {
             };
             std::mem::forget(data);
             t
+        } else if self.dt == DatumType::Sequence {
+            let data: Vec<Sequence> = self.as_slice::<Sequence>().unwrap().to_vec();
+            let t = Tensor {
+                data: data.as_ptr() as *mut u8,
+                shape: self.shape.clone(),
+                strides: self.strides.clone(),
+                ..*self
+            };
+            std::mem::forget(data);
+            t
         } else {
             unsafe {
                 let tensor = Tensor::uninitialized_dt(self.datum_type(), self.shape()).unwrap();
@@ -1404,4 +1453,12 @@ mod tests {
     fn t_2_2() {
         PermuteAxisProblem { shape: vec![2, 2], permutation: vec![1, 0] }.check().unwrap();
     }
+
+    #[test]
+    fn empty_tensor_views() {
+        let mut t = Tensor::zero::<f32>(&[0, 3]).unwrap();
+        assert!(t.as_slice::<f32>().unwrap().is_empty());
+        assert!(t.as_slice_mut::<f32>().unwrap().is_empty());
+        assert_eq!(t.to_array_view::<f32>().unwrap().shape(), &[0, 3]);
+    }
 }
@@ -15,6 +15,7 @@ mod conv_plus_conv;
 mod deconv;
 mod delay_plus_pool;
 mod pad_plus_conv;
+mod stft;
 
 #[allow(dead_code)]
 fn setup_test_logger() {
@@ -0,0 +1,65 @@
+use proptest::proptest;
+use proptest::test_runner::TestCaseResult;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::fft::Stft;
+use tract_hir::tract_core::ops::math::{ComplexToInnerDim, InnerDimToComplex};
+
+use super::*;
+
+#[derive(Debug, Clone)]
+struct StftProblem {
+    input: Vec<f32>,
+    pulse: usize,
+    frame: usize,
+    stride: usize,
+}
+
+impl Arbitrary for StftProblem {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> BoxedStrategy<Self> {
+        (1usize..5, 1usize..4, 1usize..4)
+            .prop_flat_map(|(frame, factor, stride)| {
+                (Just(frame), Just(factor), Just(stride), vec(frame..frame + 10))
+            })
+            .prop_map(|(frame, factor, stride, input)| StftProblem {
+                input,
+                pulse: factor * stride,
+                frame,
+                stride,
+            })
+            .boxed()
+    }
+}
+
+impl StftProblem {
+    pub fn run(&self) -> TestCaseResult {
+        let mut model = TypedModel::default();
+        let s = stream_symbol();
+        let a = model
+            .add_source("a", TypedFact::dt_shape(f32::datum_type(), &[s.into(), 2.to_dim()]))
+            .unwrap();
+        let complex = model.wire_node("complex", InnerDimToComplex, &[a]).unwrap();
+        let window: Vec<f32> = (0..self.frame).map(|i| 1.0 + i as f32).collect();
+        let stft = Stft::new(0, self.frame, self.stride, Some(rctensor1(&window)));
+        let stft = model.wire_node("stft", stft, &complex).unwrap();
+        let real = model.wire_node("real", ComplexToInnerDim, &stft).unwrap();
+        model.set_output_outlets(&real).unwrap();
+        let input: Vec<f32> = self.input.iter().flat_map(|&x| [x, -x]).collect();
+        let input = arr1(&input).into_shape((self.input.len(), 2)).unwrap().into_dyn();
+        proptest_regular_against_pulse(model, self.pulse as _, input, 0)
+    }
+}
+
+proptest! {
+    #[test]
+    fn proptest(pb in StftProblem::arbitrary()) { pb.run().unwrap() }
+}
+
+#[test]
+fn test_overlapping_frames() {
+    StftProblem { input: vec![1.0, 2.0, 0.0, -1.0, 3.0], pulse: 2, frame: 3, stride: 1 }
+        .run()
+        .unwrap()
+}
@@ -23,6 +23,8 @@ pub struct InferenceFact {
     pub datum_type: TypeFactoid,
     pub shape: ShapeFactoid,
     pub value: ValueFact,
+    /// element information, for sequences
+    pub sequence: Option<Arc<SequenceFact>>,
 }
 
 impl InferenceFact {
@@ -83,6 +85,14 @@ impl InferenceFact {
     pub fn without_value(self) -> InferenceFact {
         InferenceFact { value: GenericFactoid::Any, ..self }
     }
+
+    /// Scalar fact of DatumType::Sequence with the given element information.
+    pub fn sequence(fact: SequenceFact) -> InferenceFact {
+        InferenceFact {
+            sequence: Some(Arc::new(fact)),
+            ..InferenceFact::dt_shape(DatumType::Sequence, ShapeFactoid::closed(tvec!()))
+        }
+    }
 }
 
 impl Factoid for InferenceFact {
@@ -99,6 +109,9 @@ impl Factoid for InferenceFact {
             datum_type: self.datum_type.unify(&other.datum_type)?,
             shape: self.shape.unify(&other.shape)?,
             value: self.value.unify(&other.value)?,
+            // element information is only ever declared, never inferred: keep
+            // the first one found
+            sequence: self.sequence.clone().or_else(|| other.sequence.clone()),
         };
 
         trace!("Unifying {:?} with {:?} into {:?}.", self, other, tensor);
@@ -154,7 +167,11 @@ impl<'a> TryFrom<&'a InferenceFact> for TypedFact {
             let shape = ShapeFact::from_dims(shape);
             let konst = fact.value.concretize();
             let uniform = konst.as_ref().and_then(|k| k.as_uniform()).map(Arc::new);
-            Ok(TypedFact { datum_type, shape, konst, uniform })
+            let sequence = konst
+                .as_ref()
+                .and_then(|k| TypedFact::from(k.clone()).sequence)
+                .or_else(|| fact.sequence.clone());
+            Ok(TypedFact { datum_type, shape, konst, uniform, sequence })
         } else {
             bail!("Can not make a TypedFact out of {:?}", fact)
         }
@@ -173,6 +190,7 @@ impl<'a> From<&'a TypedFact> for InferenceFact {
         if let Some(k) = &t.konst {
             fact.value = k.clone().into_arc_tensor().into();
         }
+        fact.sequence = t.sequence.clone();
         fact
     }
 }
@@ -64,9 +64,9 @@ pub trait Factoid: fmt::Debug + Clone + PartialEq + Default + Hash {
             for i in 0..facts.len() - 1 {
                 for j in i + 1..facts.len() {
                     let (left, right) = facts.split_at_mut(j);
-                    let c = left[i].unify_with(right[0])?;
+                    let c = left[i].unify_with_mut(right[0])?;
                     changed = changed || c;
-                    overall_changed = changed || c;
+                    overall_changed = overall_changed || c;
                 }
             }
             if !changed {
@@ -445,6 +445,21 @@ mod tests {
         assert_eq!(dt.unify(&TypeFactoid::Any).unwrap(), dt);
     }
 
+    #[test]
+    fn unify_all_updates_every_fact() {
+        let mut facts = [TypeFactoid::Only(DatumType::I32), TypeFactoid::Any, TypeFactoid::Any];
+        let mut refs = facts.iter_mut().collect::<Vec<_>>();
+        assert!(Factoid::unify_all(&mut refs).unwrap());
+        assert_eq!(facts, [TypeFactoid::Only(DatumType::I32); 3]);
+    }
+
+    #[test]
+    fn unify_all_unchanged() {
+        let mut facts = [TypeFactoid::Only(DatumType::F32); 3];
+        let mut refs = facts.iter_mut().collect::<Vec<_>>();
+        assert!(!Factoid::unify_all(&mut refs).unwrap());
+    }
+
     #[test]
     fn unify_same_shape_1() {
         let s = ShapeFactoid::closed(tvec![]);
@@ -105,6 +105,7 @@ pub fn infer_forward_basic(
         datum_type,
         shape: infer_shape_broadcasting(&input_shapes)?.unwrap_or(shapefactoid![..]),
         value: valuefact!(_),
+        sequence: None,
     };
 
     Ok(Some(tvec![output]))
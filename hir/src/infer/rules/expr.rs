@@ -334,7 +334,8 @@ where
             ))?;
             */
 
-            let div = m.div(*k);
+            // symbolic dims only divide by positive integers
+            let div = if *k < 0 { (m * -1).div(-*k) } else { m.div(*k) };
             self.1.set(context, div)
         }
     }
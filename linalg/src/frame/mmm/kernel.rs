@@ -32,6 +32,46 @@ macro_rules! test_mmm_kernel_f32 {
     };
 }
 
+#[macro_export]
+macro_rules! test_mmm_kernel_f64 {
+    ($k: ty, $id: ident, $cond: expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $id {
+            mmm_kernel_tests!($cond, $k, f64, f64, f64, f64);
+            mmm_frame_tests!($cond, $k, f64, f64, f64, f64);
+            mmm_kernel_fuse_tests!($cond, $k, f64, f64);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! test_mmm_kernel_f16 {
+    ($k: ty, $id: ident, $cond: expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $id {
+            mmm_kernel_tests!(
+                $cond,
+                $k,
+                tract_data::prelude::f16,
+                tract_data::prelude::f16,
+                tract_data::prelude::f16,
+                tract_data::prelude::f16
+            );
+            mmm_frame_tests!(
+                $cond,
+                $k,
+                tract_data::prelude::f16,
+                tract_data::prelude::f16,
+                tract_data::prelude::f16,
+                tract_data::prelude::f16
+            );
+            mmm_kernel_fuse_tests!($cond, $k, tract_data::prelude::f16, tract_data::prelude::f16);
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! test_mmm_kernel_i8 {
     ($k: ty, $id: ident, $cond: expr) => {
@@ -91,7 +131,7 @@ pub mod test {
     macro_rules! mmm_kernel_tests {
         ($cond:expr, $ker:ty, $ta:ty, $tb:ty, $tc:ty, $ti: ty) => {
             mod kernel {
-                use num_traits::Zero;
+                use num_traits::{One, Zero};
                 use proptest::prelude::*;
                 #[allow(unused_imports)]
                 use crate::frame::mmm::kernel::test;
@@ -166,9 +206,9 @@ pub mod test {
                     if $cond {
                         let mut pa = vec!(<$ta>::zero(); <$ker>::mr() * 3);
                         let len = pa.len() - 1;
-                        pa[len] = 1 as _;
+                        pa[len] = <$ta>::one();
                         let pb = PackedOffsetsProblem::<$ker, $ta, $tb, $tc, $ti>::new(pa,
-                                                                                       vec!(0 as _, 1 as _),
+                                                                                       vec!(<$tb>::zero(), <$tb>::one()),
                                                                                        vec!(0usize; <$ker>::nr()),
                                                                                        vec!(1usize, 0, 0),
                                                                                        true);
@@ -1,6 +1,7 @@
 use super::ScratchSpaceFusedNonLinear;
 use super::*;
 use crate::frame::Packer;
+use crate::multithread::{current_tract_executor, Executor};
 use anyhow::Context;
 use num_traits::{AsPrimitive, Zero};
 use std::fmt;
@@ -252,6 +253,11 @@ where
         scratch: &mut dyn ScratchSpace,
         non_linear: &[FusedSpec],
     ) -> anyhow::Result<()> {
+        if let Executor::MultiThread(pool) = current_tract_executor() {
+            if (m > K::mr() || n > K::nr()) && pool.current_num_threads() > 1 {
+                return self.run_with_pool(&pool, m, n, non_linear);
+            }
+        }
         let mr = K::mr();
         let nr = K::nr();
         if n == 1 && K::nr() == 1 {
@@ -292,6 +298,63 @@ where
     }
 }
 
+impl<K, TI> MatMatMulImpl<K, TI>
+where
+    TI: Datum + Copy + Add + Mul<Output = TI> + Zero + Debug + 'static + Neg<Output = TI>,
+    K: MatMatMulKer<TI> + 'static,
+    i32: AsPrimitive<TI>,
+    usize: AsPrimitive<TI>,
+{
+    /// Split the m x n tile grid over the pool threads. Each rayon job gets
+    /// its own scratch space, tiles write to disjoint parts of the output.
+    unsafe fn run_with_pool(
+        &self,
+        pool: &rayon::ThreadPool,
+        m: usize,
+        n: usize,
+        non_linear: &[FusedSpec],
+    ) -> anyhow::Result<()> {
+        use rayon::prelude::*;
+        let (mr, nr) = (K::mr(), K::nr());
+        let (tiles_m, tiles_n) = ((m + mr - 1) / mr, (n + nr - 1) / nr);
+        let specs = SharedSpecs(non_linear);
+        pool.install(|| {
+            (0..tiles_m * tiles_n).into_par_iter().for_each_init(
+                || {
+                    let mut scratch = ScratchSpaceFusedNonLinear::<TI>::default();
+                    scratch.prepare::<K>(specs.0);
+                    scratch
+                },
+                |scratch, tile| {
+                    let (ia, ib) = (tile / tiles_n, tile % tiles_n);
+                    let non_linear = specs.0;
+                    if ia < m / mr && ib < n / nr {
+                        scratch.for_valid_tile::<K>(non_linear, ia, ib);
+                        let err = K::kernel(&scratch.uspecs());
+                        debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                    } else {
+                        scratch.for_border_tile::<K>(non_linear, ia, ib);
+                        let err = K::kernel(&scratch.uspecs());
+                        debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                        let height = (m - ia * mr).min(mr);
+                        let width = (n - ib * nr).min(nr);
+                        scratch.postprocess_tile::<K>(non_linear, ia, ib, height, width);
+                    }
+                },
+            )
+        });
+        Ok(())
+    }
+}
+
+// FusedSpec holds raw pointers to the operands. They are only read from
+// during the run, and each tile writes to its own part of the output, so
+// sharing them across the pool threads is sound.
+#[derive(Clone, Copy)]
+struct SharedSpecs<'s, 't>(&'s [FusedSpec<'t>]);
+unsafe impl<'s, 't> Send for SharedSpecs<'s, 't> {}
+unsafe impl<'s, 't> Sync for SharedSpecs<'s, 't> {}
+
 impl<K, TI> fmt::Display for MatMatMulImpl<K, TI>
 where
     TI: Copy + Add + Mul + Zero + Debug + 'static,
@@ -91,10 +91,11 @@ impl OutputStore {
         width: usize,
         tile: &OutputStoreKer,
     ) {
-        if self.item_size() == 1 {
-            self.set_from_tile_t::<i8>(down, right, height, width, tile)
-        } else {
-            self.set_from_tile_t::<i32>(down, right, height, width, tile)
+        match self.item_size() {
+            1 => self.set_from_tile_t::<i8>(down, right, height, width, tile),
+            2 => self.set_from_tile_t::<i16>(down, right, height, width, tile),
+            8 => self.set_from_tile_t::<i64>(down, right, height, width, tile),
+            _ => self.set_from_tile_t::<i32>(down, right, height, width, tile),
         }
     }
 
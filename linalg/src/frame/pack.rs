@@ -102,6 +102,17 @@ impl Packer {
         KOutWriter::new(pb, self.r, mn, self.k)
     }
 
+    /// Writer starting at row `k_start` of a k-outer packing. Writers
+    /// covering disjoint row ranges of the same buffer can run concurrently.
+    pub unsafe fn write_with_k_outer_from<'p, T: Copy + Debug>(
+        &self,
+        pb: *mut T,
+        mn: usize,
+        k_start: usize,
+    ) -> KOutWriter<'p, T> {
+        KOutWriter::from_raw(pb.add(k_start * self.r), self.r, mn, self.k)
+    }
+
     pub fn write_with_k_inner<'p, T: Copy + Debug>(
         &self,
         pb: &'p mut [T],
@@ -132,10 +143,19 @@ where
     T: Copy + std::fmt::Debug,
 {
     pub fn new(data: &'p mut [T], panel_width: usize, mn: usize, k: usize) -> KOutWriter<'p, T> {
+        unsafe { Self::from_raw(data.as_mut_ptr(), panel_width, mn, k) }
+    }
+
+    pub unsafe fn from_raw(
+        ptr: *mut T,
+        panel_width: usize,
+        mn: usize,
+        k: usize,
+    ) -> KOutWriter<'p, T> {
         let panels = (mn + panel_width - 1) / panel_width;
         let last_panel_width = mn - (panels - 1) * panel_width;
         KOutWriter {
-            ptr: data.as_mut_ptr(),
+            ptr,
             panels,
             panel_width,
             last_panel_width,
@@ -314,6 +334,27 @@ mod test {
         }
     }
 
+    #[test]
+    fn k_outer_from_split_rows() {
+        let (k, mn, r) = (5, 7, 3);
+        let input = Array2::from_shape_fn((k, mn), |(k, x)| (k * mn + x) as u32);
+        let pb = PackProblem { k, mn, is_a: false, r, input: input.clone() };
+        let packer = super::Packer::new(k, r, 1, 0);
+        for split in 0..=k {
+            let mut output = vec![0u32; packer.len(mn)];
+            for rows in &[0..split, split..k] {
+                let mut writer =
+                    unsafe { packer.write_with_k_outer_from(output.as_mut_ptr(), mn, rows.start) };
+                for k in rows.clone() {
+                    for x in 0..mn {
+                        writer.write(input[(k, x)]);
+                    }
+                }
+            }
+            assert_eq!(pb.reference(), output);
+        }
+    }
+
     #[test]
     fn simple_b_1() {
         let pb = PackProblem { k: 2, mn: 1, is_a: false, r: 1, input: arr2(&[[0], [1]]) };
@@ -2,16 +2,21 @@
 #[macro_use]
 pub mod test {
     use crate::frame::element_wise::*;
+    use crate::test::LADatum;
+    use num_traits::AsPrimitive;
     use proptest::test_runner::TestCaseResult;
 
     #[macro_export]
     macro_rules! sigmoid_frame_tests {
         ($cond:expr, $ker:ty) => {
+            sigmoid_frame_tests!($cond, f32, $ker);
+        };
+        ($cond:expr, $t:ty, $ker:ty) => {
             proptest::proptest! {
                 #[test]
                 fn sigmoid(xs in proptest::collection::vec(-25f32..25.0, 0..100)) {
                     if $cond {
-                        crate::frame::sigmoid::test::test_sigmoid::<$ker>(&*xs).unwrap()
+                        crate::frame::sigmoid::test::test_sigmoid::<$ker, $t>(&*xs).unwrap()
                     }
                 }
             }
@@ -19,7 +24,7 @@ pub mod test {
             #[test]
             fn sigmoid_4_magic() {
                 if $cond {
-                    crate::frame::sigmoid::test::test_sigmoid::<$ker>(&[0f32, -20.0, 20.0, 0.0])
+                    crate::frame::sigmoid::test::test_sigmoid::<$ker, $t>(&[0f32, -20.0, 20.0, 0.0])
                         .unwrap()
                 }
             }
@@ -27,34 +32,46 @@ pub mod test {
             #[test]
             fn sigmoid_4zeros() {
                 if $cond {
-                    crate::frame::sigmoid::test::test_sigmoid::<$ker>(&[0.0; 4]).unwrap();
+                    crate::frame::sigmoid::test::test_sigmoid::<$ker, $t>(&[0.0; 4]).unwrap();
                 }
             }
 
             #[test]
             fn sigmoid_20_ones() {
                 if $cond {
-                    crate::frame::sigmoid::test::test_sigmoid::<$ker>(&[1.0; 20]).unwrap();
+                    crate::frame::sigmoid::test::test_sigmoid::<$ker, $t>(&[1.0; 20]).unwrap();
                 }
             }
 
             #[test]
             fn sigmoid_18_zeros() {
                 if $cond {
-                    crate::frame::sigmoid::test::test_sigmoid::<$ker>(&[0.0; 18]).unwrap();
+                    crate::frame::sigmoid::test::test_sigmoid::<$ker, $t>(&[0.0; 18]).unwrap();
                 }
             }
         };
     }
 
-    pub fn test_sigmoid<K: ElementWiseKer<f32>>(values: &[f32]) -> TestCaseResult {
-        let op = ElementWiseImpl::<K, f32>::new();
-        let mut found = values.to_vec();
+    pub fn test_sigmoid<K: ElementWiseKer<T>, T: LADatum + AsPrimitive<f32>>(
+        values: &[f32],
+    ) -> TestCaseResult
+    where
+        f32: AsPrimitive<T>,
+    {
+        let op = ElementWiseImpl::<K, T>::new();
+        let values: Vec<T> = values.iter().map(|x| x.as_()).collect();
+        let mut found = values.clone();
         while found.len() < K::nr() {
-            found.push(0f32);
+            found.push(T::zero());
         }
         op.run(&mut found).unwrap();
-        let expected = values.iter().map(|x| 1.0 / (1.0 + (-x).exp())).collect::<Vec<_>>();
+        let expected = values
+            .iter()
+            .map(|x| {
+                let x: f32 = x.as_();
+                (1.0 / (1.0 + (-x).exp())).as_()
+            })
+            .collect::<Vec<T>>();
         crate::test::check_close(&found[..values.len()], &*expected)
     }
 }
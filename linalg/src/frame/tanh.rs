@@ -2,16 +2,21 @@
 #[macro_use]
 pub mod test {
     use crate::frame::element_wise::*;
+    use crate::test::LADatum;
+    use num_traits::AsPrimitive;
     use proptest::test_runner::TestCaseResult;
 
     #[macro_export]
     macro_rules! tanh_frame_tests {
         ($cond:expr, $ker:ty) => {
+            tanh_frame_tests!($cond, f32, $ker);
+        };
+        ($cond:expr, $t:ty, $ker:ty) => {
             proptest::proptest! {
                 #[test]
                 fn tanh(xs in proptest::collection::vec(-25f32..25.0, 0..100)) {
                     if $cond {
-                        crate::frame::tanh::test::test_tanh::<$ker>(&*xs).unwrap()
+                        crate::frame::tanh::test::test_tanh::<$ker, $t>(&*xs).unwrap()
                     }
                 }
             }
@@ -19,38 +24,51 @@ pub mod test {
             #[test]
             fn tanh_4_magic() {
                 if $cond {
-                    crate::frame::tanh::test::test_tanh::<$ker>(&[0f32, -20.0, 20.0, 0.0]).unwrap()
+                    crate::frame::tanh::test::test_tanh::<$ker, $t>(&[0f32, -20.0, 20.0, 0.0])
+                        .unwrap()
                 }
             }
 
             #[test]
             fn tanh_4zeros() {
                 if $cond {
-                    crate::frame::tanh::test::test_tanh::<$ker>(&[0.0; 4]).unwrap();
+                    crate::frame::tanh::test::test_tanh::<$ker, $t>(&[0.0; 4]).unwrap();
                 }
             }
 
             #[test]
             fn tanh_20_ones() {
                 if $cond {
-                    crate::frame::tanh::test::test_tanh::<$ker>(&[1.0; 20]).unwrap();
+                    crate::frame::tanh::test::test_tanh::<$ker, $t>(&[1.0; 20]).unwrap();
                 }
             }
 
             #[test]
             fn tanh_18_zeros() {
                 if $cond {
-                    crate::frame::tanh::test::test_tanh::<$ker>(&[0.0; 18]).unwrap();
+                    crate::frame::tanh::test::test_tanh::<$ker, $t>(&[0.0; 18]).unwrap();
                 }
             }
         };
     }
 
-    pub fn test_tanh<K: ElementWiseKer<f32>>(values: &[f32]) -> TestCaseResult {
-        let op = ElementWiseImpl::<K, f32>::new();
-        let mut found = values.to_vec();
+    pub fn test_tanh<K: ElementWiseKer<T>, T: LADatum + AsPrimitive<f32>>(
+        values: &[f32],
+    ) -> TestCaseResult
+    where
+        f32: AsPrimitive<T>,
+    {
+        let op = ElementWiseImpl::<K, T>::new();
+        let values: Vec<T> = values.iter().map(|x| x.as_()).collect();
+        let mut found = values.clone();
         op.run(&mut found).unwrap();
-        let expected = values.iter().map(|x| x.tanh()).collect::<Vec<_>>();
+        let expected = values
+            .iter()
+            .map(|x| {
+                let x: f32 = x.as_();
+                x.tanh().as_()
+            })
+            .collect::<Vec<T>>();
         crate::test::check_close(&*found, &*expected)
     }
 }
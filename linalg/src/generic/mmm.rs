@@ -656,7 +656,9 @@ where
 {
     match tile.item_size {
         1 => store_t::<u8, _, _>(tile, ab),
+        2 => store_t::<u16, _, _>(tile, ab),
         4 => store_t::<u32, _, _>(tile, ab),
+        8 => store_t::<u64, _, _>(tile, ab),
         _ => unimplemented!(),
     }
 }
@@ -694,9 +696,13 @@ where
 
 test_mmm_kernel_f32!(crate::generic::mmm::GenericMmm4x4<f32, f32, f32>, test_GenericMmm4x4_f32, true);
 test_mmm_kernel_i8!(crate::generic::mmm::GenericMmm4x4<i8, i8, i32>, test_GenericMmm4x4_i8, true);
+test_mmm_kernel_f64!(crate::generic::mmm::GenericMmm4x4<f64, f64, f64>, test_GenericMmm4x4_f64, true);
+test_mmm_kernel_f16!(crate::generic::mmm::GenericMmm4x4<tract_data::prelude::f16, tract_data::prelude::f16, tract_data::prelude::f16>, test_GenericMmm4x4_f16, true);
 
 test_mmm_kernel_f32!(crate::generic::mmm::GenericMmm4x1<f32, f32, f32>, test_GenericMmm4x1_f32, true);
 test_mmm_kernel_i8!(crate::generic::mmm::GenericMmm4x1<i8, i8, i32>, test_GenericMmm4x1_i8, true);
+test_mmm_kernel_f64!(crate::generic::mmm::GenericMmm4x1<f64, f64, f64>, test_GenericMmm4x1_f64, true);
+test_mmm_kernel_f16!(crate::generic::mmm::GenericMmm4x1<tract_data::prelude::f16, tract_data::prelude::f16, tract_data::prelude::f16>, test_GenericMmm4x1_f16, true);
 
 test_mmm_kernel_f32!(crate::generic::mmm::GenericMmmTest3x2<f32, f32, f32>, test_GenericMmmTest3x2_f32, true);
 test_mmm_kernel_i8!(crate::generic::mmm::GenericMmmTest3x2<i8, i8, i32>, test_GenericMmmTest3x2_i8, true);
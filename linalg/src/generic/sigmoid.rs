@@ -1,4 +1,5 @@
 use crate::frame::element_wise::ElementWiseKer;
+use tract_data::internal::f16;
 
 const LOW: f32 = -18.0;
 const HIGH: f32 = 18.0;
@@ -63,8 +64,39 @@ impl ElementWiseKer<f32> for SSigmoid4 {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct HSigmoid8;
+
+impl ElementWiseKer<f16> for HSigmoid8 {
+    fn name() -> &'static str {
+        "generic"
+    }
+
+    fn alignment_bytes() -> usize {
+        16
+    }
+
+    fn alignment_items() -> usize {
+        8
+    }
+
+    fn nr() -> usize {
+        8
+    }
+
+    fn run(x: &mut [f16]) {
+        debug_assert!(x.len() % Self::nr() == 0);
+        debug_assert!(x.as_ptr() as usize % Self::alignment_bytes() == 0);
+        x.iter_mut().for_each(|px| *px = ssigmoid(px.0.to_f32()).into())
+    }
+}
+
 #[cfg(test)]
 #[macro_use]
 pub mod test {
     sigmoid_frame_tests!(true, crate::generic::sigmoid::SSigmoid4);
+
+    mod f16 {
+        sigmoid_frame_tests!(true, tract_data::internal::f16, crate::generic::sigmoid::HSigmoid8);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::frame::element_wise::ElementWiseKer;
+use tract_data::internal::f16;
 
 const LOW: f32 = -9.0;
 const HIGH: f32 = 9.0;
@@ -61,8 +62,39 @@ impl ElementWiseKer<f32> for STanh4 {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct HTanh8;
+
+impl ElementWiseKer<f16> for HTanh8 {
+    fn name() -> &'static str {
+        "generic"
+    }
+
+    fn alignment_bytes() -> usize {
+        16
+    }
+
+    fn alignment_items() -> usize {
+        8
+    }
+
+    fn nr() -> usize {
+        8
+    }
+
+    fn run(x: &mut [f16]) {
+        debug_assert!(x.len() % Self::nr() == 0);
+        debug_assert!(x.as_ptr() as usize % Self::alignment_bytes() == 0);
+        x.iter_mut().for_each(|px| *px = stanh(px.0.to_f32()).into())
+    }
+}
+
 #[cfg(test)]
 #[macro_use]
 pub mod test {
     tanh_frame_tests!(true, crate::generic::tanh::STanh4);
+
+    mod f16 {
+        tanh_frame_tests!(true, tract_data::internal::f16, crate::generic::tanh::HTanh8);
+    }
 }
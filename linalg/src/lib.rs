@@ -16,6 +16,7 @@ include!(concat!(env!("OUT_DIR"), "/extern_kernel_macro.rs"));
 #[macro_use]
 pub mod frame;
 mod generic;
+pub mod multithread;
 pub use generic::ScaleShiftAndRound;
 #[cfg(target_arch = "x86_64")]
 pub mod x86_64_fma;
@@ -29,6 +30,7 @@ pub mod arm32;
 pub use self::frame::{element_wise, lut, mmm};
 
 use tract_data::prelude::*;
+use tract_data::prelude::f16;
 
 pub struct Ops {
     mmm_f32: Box<
@@ -37,6 +39,18 @@ pub struct Ops {
             + Sync,
     >,
     mmv_f32: Box<dyn Fn(Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
+    mmm_f64: Box<
+        dyn Fn(Option<usize>, Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul>
+            + Send
+            + Sync,
+    >,
+    mmv_f64: Box<dyn Fn(Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
+    mmm_f16: Box<
+        dyn Fn(Option<usize>, Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul>
+            + Send
+            + Sync,
+    >,
+    mmv_f16: Box<dyn Fn(Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
     qmmm_i32: Box<
         dyn Fn(Option<usize>, Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul>
             + Send
@@ -45,6 +59,8 @@ pub struct Ops {
     qmmv_i32: Box<dyn Fn(Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
     pub sigmoid_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
     pub tanh_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
+    pub sigmoid_f16: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f16>> + Send + Sync>,
+    pub tanh_f16: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f16>> + Send + Sync>,
     pub lut_u8: Box<dyn Fn(&[u8]) -> Box<dyn lut::Lut> + Send + Sync>,
 }
 
@@ -63,6 +79,12 @@ impl Ops {
             (F32, F32, F32) => {
                 Some(if n == Some(1) { (self.mmv_f32)(m, k) } else { (self.mmm_f32)(m, k, n) })
             }
+            (F64, F64, F64) => {
+                Some(if n == Some(1) { (self.mmv_f64)(m, k) } else { (self.mmm_f64)(m, k, n) })
+            }
+            (F16, F16, F16) => {
+                Some(if n == Some(1) { (self.mmv_f16)(m, k) } else { (self.mmm_f16)(m, k, n) })
+            }
             (I8, I8, I32) => {
                 Some(if n == Some(1) { (self.qmmv_i32)(m, k) } else { (self.qmmm_i32)(m, k, n) })
             }
@@ -82,6 +104,18 @@ pub fn generic() -> Ops {
         mmv_f32: Box::new(|_, _| {
             Box::new(mmm::MatMatMulImpl::<generic::GenericMmm4x1<f32, f32, f32>, f32>::new())
         }),
+        mmm_f64: Box::new(|_, _, _| {
+            Box::new(mmm::MatMatMulImpl::<generic::GenericMmm4x4<f64, f64, f64>, f64>::new())
+        }),
+        mmv_f64: Box::new(|_, _| {
+            Box::new(mmm::MatMatMulImpl::<generic::GenericMmm4x1<f64, f64, f64>, f64>::new())
+        }),
+        mmm_f16: Box::new(|_, _, _| {
+            Box::new(mmm::MatMatMulImpl::<generic::GenericMmm4x4<f16, f16, f16>, f16>::new())
+        }),
+        mmv_f16: Box::new(|_, _| {
+            Box::new(mmm::MatMatMulImpl::<generic::GenericMmm4x1<f16, f16, f16>, f16>::new())
+        }),
         qmmm_i32: Box::new(|_, _, _| {
             Box::new(mmm::MatMatMulImpl::<generic::GenericMmm4x4<i8, i8, i32>, i32>::new())
         }),
@@ -94,6 +128,12 @@ pub fn generic() -> Ops {
         tanh_f32: Box::new(|| {
             Box::new(element_wise::ElementWiseImpl::<generic::STanh4, f32>::new())
         }),
+        sigmoid_f16: Box::new(|| {
+            Box::new(element_wise::ElementWiseImpl::<generic::HSigmoid8, f16>::new())
+        }),
+        tanh_f16: Box::new(|| {
+            Box::new(element_wise::ElementWiseImpl::<generic::HTanh8, f16>::new())
+        }),
         lut_u8: Box::new(|table: &[u8]| Box::new(lut::LutImpl::<generic::GenericLut8>::new(table))),
     }
 }
@@ -126,6 +166,7 @@ mod test {
     use proptest::prelude::*;
     use std::fmt::Debug;
     use std::ops::*;
+    use tract_data::prelude::f16;
 
     pub trait LADatum:
         Sized
@@ -157,6 +198,25 @@ mod test {
         }
     }
 
+    impl LADatum for f64 {
+        fn strat() -> BoxedStrategy<Self> {
+            (-1000isize..1000).prop_map(|i| i as f64 / 1000.0).boxed()
+        }
+        fn close(&self, other: &Self) -> bool {
+            (self - other).abs() < 0.001
+        }
+    }
+
+    impl LADatum for f16 {
+        fn strat() -> BoxedStrategy<Self> {
+            // quarters in [-2, 2) keep products and sums exact in half precision
+            (-8isize..8).prop_map(|i| (i as f32 / 4.0).as_()).boxed()
+        }
+        fn close(&self, other: &Self) -> bool {
+            (self.0.to_f32() - other.0.to_f32()).abs() < 0.002
+        }
+    }
+
     impl LADatum for u8 {
         fn strat() -> BoxedStrategy<Self> {
             any::<u8>().boxed()
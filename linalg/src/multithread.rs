@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tract_data::anyhow;
+use tract_data::internal::*;
+
+/// Where linalg kernels run their work.
+///
+/// `SingleThread` (the default) runs everything on the calling thread.
+/// `MultiThread` splits the work of the parallelizable operators (matrix
+/// multiplication tile grid, mostly) over a rayon thread pool.
+#[derive(Clone)]
+pub enum Executor {
+    SingleThread,
+    MultiThread(Arc<ThreadPool>),
+}
+
+impl Executor {
+    /// Build an executor backed by a fresh pool of `n` threads.
+    pub fn multithread(n: usize) -> TractResult<Executor> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(n)
+            .thread_name(|ix| format!("tract-linalg-{}", ix))
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build thread pool: {}", e))?;
+        Ok(Executor::MultiThread(Arc::new(pool)))
+    }
+
+    pub fn threads(&self) -> usize {
+        match self {
+            Executor::SingleThread => 1,
+            Executor::MultiThread(pool) => pool.current_num_threads(),
+        }
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Executor {
+        Executor::SingleThread
+    }
+}
+
+impl fmt::Debug for Executor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Executor::SingleThread => write!(f, "SingleThread"),
+            Executor::MultiThread(pool) => {
+                write!(f, "MultiThread({} threads)", pool.current_num_threads())
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_EXECUTOR: Mutex<Executor> = Mutex::new(Executor::SingleThread);
+}
+
+thread_local! {
+    static TLS_EXECUTOR_OVERRIDE: RefCell<Option<Executor>> = RefCell::new(None);
+}
+
+/// The executor in effect on the calling thread: the scoped override if any,
+/// the process-wide default otherwise.
+pub fn current_tract_executor() -> Executor {
+    if let Some(executor) = TLS_EXECUTOR_OVERRIDE.with(|tls| tls.borrow().clone()) {
+        executor
+    } else {
+        DEFAULT_EXECUTOR.lock().unwrap().clone()
+    }
+}
+
+/// Set the process-wide default executor.
+pub fn set_default_executor(executor: Executor) {
+    *DEFAULT_EXECUTOR.lock().unwrap() = executor;
+}
+
+/// Run `f` with `executor` as the current executor for the calling thread.
+pub fn multithread_tract_scope<R, F: FnOnce() -> R>(executor: Executor, f: F) -> R {
+    struct Restore(Option<Executor>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            TLS_EXECUTOR_OVERRIDE.with(|tls| *tls.borrow_mut() = previous);
+        }
+    }
+    let previous = TLS_EXECUTOR_OVERRIDE.with(|tls| tls.borrow_mut().replace(executor));
+    let _restore = Restore(previous);
+    f()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scope_overrides_and_restores() {
+        assert_eq!(current_tract_executor().threads(), 1);
+        let executor = Executor::multithread(2).unwrap();
+        multithread_tract_scope(executor, || {
+            assert_eq!(current_tract_executor().threads(), 2);
+            multithread_tract_scope(Executor::SingleThread, || {
+                assert_eq!(current_tract_executor().threads(), 1);
+            });
+            assert_eq!(current_tract_executor().threads(), 2);
+        });
+        assert_eq!(current_tract_executor().threads(), 1);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::frame::ElementWiseImpl;
 use crate::frame::MatMatMulImpl;
 use crate::Ops;
+use tract_data::internal::f16;
 
 pub mod mmm;
 pub mod sigmoid;
@@ -15,6 +16,12 @@ pub fn plug(ops: &mut Ops) {
         ops.sigmoid_f32 = Box::new(|| Box::new(ElementWiseImpl::<sigmoid::SigmoidF32, f32>::new()));
         ops.tanh_f32 = Box::new(|| Box::new(ElementWiseImpl::<tanh::TanhF32, f32>::new()));
         log::info!("mmm_f32, sigmoid_f32, tanh_f32: x86_64/fma activated");
+        if is_x86_feature_detected!("f16c") {
+            ops.sigmoid_f16 =
+                Box::new(|| Box::new(ElementWiseImpl::<sigmoid::SigmoidF16, f16>::new()));
+            ops.tanh_f16 = Box::new(|| Box::new(ElementWiseImpl::<tanh::TanhF16, f16>::new()));
+            log::info!("sigmoid_f16, tanh_f16: x86_64/f16c activated");
+        }
     }
     if is_x86_feature_detected!("avx2") {
         ops.qmmm_i32 =
@@ -23,4 +30,19 @@ pub fn plug(ops: &mut Ops) {
             Box::new(|_, _, _| Box::new(MatMatMulImpl::<mmm::MatMatMulI8xI32x8x8, i32>::new()));
         log::info!("mmm_i8_i8 and mmm_i8_i32: x86_64/avx2 activated");
     }
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        ops.mmm_f64 =
+            Box::new(|_, _, _| Box::new(MatMatMulImpl::<mmm::MatMatMulF64x8x4, f64>::new()));
+        log::info!("mmm_f64: x86_64/avx2 activated");
+    }
+    if is_x86_feature_detected!("avx512f") {
+        ops.mmm_f32 =
+            Box::new(|_, _, _| Box::new(MatMatMulImpl::<mmm::MatMatMulF32x16x12, f32>::new()));
+        log::info!("mmm_f32: x86_64/avx512f activated");
+    }
+    if is_x86_feature_detected!("avx512vnni") && is_x86_feature_detected!("avx512vbmi") {
+        ops.qmmm_i32 =
+            Box::new(|_, _, _| Box::new(MatMatMulImpl::<mmm::MatMatMulI8xI32x16x8, i32>::new()));
+        log::info!("mmm_i8_i32: x86_64/avx512vnni activated");
+    }
 }
@@ -2,12 +2,19 @@ use crate::frame::mmm::*;
 
 extern_kernel!(fn fma_mmm_f32_16x6(op: *const FusedKerSpec<f32>) -> isize);
 extern_kernel!(fn fma_mmm_f32_64x1(op: *const FusedKerSpec<f32>) -> isize);
+extern_kernel!(fn avx512_mmm_f32_16x12(op: *const FusedKerSpec<f32>) -> isize);
+extern_kernel!(fn fma_mmm_f64_8x4(op: *const FusedKerSpec<f64>) -> isize);
 extern_kernel!(fn fma_mmm_i8_8x8(op: *const FusedKerSpec<i32>) -> isize);
+extern_kernel!(fn avx512_mmm_i8_16x8(op: *const FusedKerSpec<i32>) -> isize);
 
 MMMKernel!(MatMatMulF32x16x6<f32>, "fma", fma_mmm_f32_16x6; 16, 6; 32, 4; 0, 0);
 MMMKernel!(MatMatMulF32x64x1<f32>, "fma", fma_mmm_f32_64x1; 64, 1; 32, 4; 0, 0);
+MMMKernel!(MatMatMulF32x16x12<f32>, "avx512f", avx512_mmm_f32_16x12; 16, 12; 64, 4; 0, 0);
+MMMKernel!(MatMatMulF64x8x4<f64>, "avx2", fma_mmm_f64_8x4; 8, 4; 32, 8; 0, 0);
 MMMKernel!(MatMatMulI8x8x8<i32>, "avx2", fma_mmm_i8_8x8; 8, 8; 32, 4; 0, 0);
 MMMKernel!(MatMatMulI8xI32x8x8<i32>, "avx2", fma_mmm_i8_8x8; 8, 8; 32, 4; 0, 0);
+MMMKernel!(MatMatMulI8x16x8<i32>, "avx512vnni", avx512_mmm_i8_16x8; 16, 8; 64, 4; 0, 0);
+MMMKernel!(MatMatMulI8xI32x16x8<i32>, "avx512vnni", avx512_mmm_i8_16x8; 16, 8; 64, 4; 0, 0);
 
 test_mmm_kernel_f32!(
     crate::x86_64_fma::mmm::MatMatMulF32x16x6,
@@ -21,6 +28,18 @@ test_mmm_kernel_f32!(
     is_x86_feature_detected!("fma")
 );
 
+test_mmm_kernel_f32!(
+    crate::x86_64_fma::mmm::MatMatMulF32x16x12,
+    test_MatMatMulF32x16x12,
+    is_x86_feature_detected!("avx512f")
+);
+
+test_mmm_kernel_f64!(
+    crate::x86_64_fma::mmm::MatMatMulF64x8x4,
+    test_MatMatMulF64x8x4,
+    is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2")
+);
+
 test_mmm_kernel_i8!(
     crate::x86_64_fma::mmm::MatMatMulI8x8x8,
     test_MatMatMulI8x8x8,
@@ -32,3 +51,15 @@ test_mmm_kernel_i8_i32!(
     test_MatMatMulI8xI32x8x8,
     is_x86_feature_detected!("avx2")
 );
+
+test_mmm_kernel_i8!(
+    crate::x86_64_fma::mmm::MatMatMulI8x16x8,
+    test_MatMatMulI8x16x8,
+    is_x86_feature_detected!("avx512vnni") && is_x86_feature_detected!("avx512vbmi")
+);
+
+test_mmm_kernel_i8_i32!(
+    crate::x86_64_fma::mmm::MatMatMulI8xI32x16x8,
+    test_MatMatMulI8xI32x16x8,
+    is_x86_feature_detected!("avx512vnni") && is_x86_feature_detected!("avx512vbmi")
+);
@@ -1,4 +1,6 @@
 use crate::element_wise::ElementWiseKer;
+use std::arch::x86_64::*;
+use tract_data::internal::f16;
 
 extern_kernel!(fn fma_sigmoid_f32(ptr: *mut f32, count: usize) -> ());
 
@@ -28,7 +30,59 @@ impl ElementWiseKer<f32> for SigmoidF32 {
     }
 }
 
+/// Half precision variant: F16C converts each group of 8 values to single
+/// precision, the fma kernel does the math.
+#[derive(Copy, Clone, Debug)]
+pub struct SigmoidF16;
+
+impl ElementWiseKer<f16> for SigmoidF16 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "f16c"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_items() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        16
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f16]) {
+        unsafe { sigmoid_f16(buf) }
+    }
+}
+
+#[target_feature(enable = "f16c,avx")]
+unsafe fn sigmoid_f16(buf: &mut [f16]) {
+    #[repr(align(32))]
+    struct Tmp([f32; 8]);
+    let mut tmp = Tmp([0f32; 8]);
+    for chunk in buf.chunks_exact_mut(8) {
+        let ptr = chunk.as_mut_ptr() as *mut __m128i;
+        _mm256_store_ps(tmp.0.as_mut_ptr(), _mm256_cvtph_ps(_mm_load_si128(ptr)));
+        fma_sigmoid_f32(tmp.0.as_mut_ptr(), 8);
+        _mm_store_si128(
+            ptr,
+            _mm256_cvtps_ph(_mm256_load_ps(tmp.0.as_ptr()), _MM_FROUND_TO_NEAREST_INT),
+        );
+    }
+}
+
 #[cfg(test)]
 mod test_simd {
     sigmoid_frame_tests!(is_x86_feature_detected!("fma"), crate::x86_64_fma::sigmoid::SigmoidF32);
+
+    mod f16 {
+        sigmoid_frame_tests!(
+            is_x86_feature_detected!("fma") && is_x86_feature_detected!("f16c"),
+            tract_data::internal::f16,
+            crate::x86_64_fma::sigmoid::SigmoidF16
+        );
+    }
 }
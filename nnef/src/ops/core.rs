@@ -1,17 +1,27 @@
 use crate::internal::*;
 use tract_core::ops;
 
+mod attention;
 mod broadcast;
 mod cast;
 mod downsample;
 mod gather;
+mod grid_sample;
+mod layer_norm;
+mod non_max_suppression;
 mod one_hot;
 mod qconv;
 mod qmatmul;
 mod reduce;
+mod reverse_sequence;
+mod roi_align;
 mod scan;
 mod scatter;
 mod source;
+mod topk;
+mod trilu;
+mod unique;
+mod upsample;
 
 pub fn register(registry: &mut Registry) {
     registry.register_unit_element_wise("tract_core_tan", &ops::math::Tan {});
@@ -38,15 +48,25 @@ pub fn register(registry: &mut Registry) {
         &ops::math::ShiftRight,
         &ops::math::FlippedShiftRight,
     );
+    attention::register(registry);
     broadcast::register(registry);
     cast::register(registry);
     downsample::register(registry);
     gather::register(registry);
+    grid_sample::register(registry);
+    layer_norm::register(registry);
+    non_max_suppression::register(registry);
     one_hot::register(registry);
     qconv::register(registry);
     qmatmul::register(registry);
     reduce::register(registry);
+    reverse_sequence::register(registry);
+    roi_align::register(registry);
     scatter::register(registry);
     scan::register(registry);
     source::register(registry);
+    topk::register(registry);
+    trilu::register(registry);
+    unique::register(registry);
+    upsample::register(registry);
 }
@@ -0,0 +1,48 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::Attention;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Attention>(), attention_dump);
+    registry.register_primitive("tract_core_attention", &attention_parameters(), attention_load);
+}
+
+fn attention_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("q"),
+        TypeName::Scalar.tensor().named("k"),
+        TypeName::Scalar.tensor().named("v"),
+        TypeName::Scalar.tensor().named("mask"),
+        TypeName::Scalar.named("scale").default(1.0),
+    ]
+}
+
+fn attention_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Attention>().unwrap();
+    let mut inputs = node.inputs.iter().map(|i| ast.mapping[i].clone()).collect::<TVec<_>>();
+    // a uniform mask would be dumped as a scalar, which broadcasts just as well,
+    // but q, k and v must keep their shapes
+    for ix in 0..3 {
+        if let Some(k) = &ast.model.outlet_fact(node.inputs[ix])?.konst {
+            inputs[ix] = ast.konst_variable(format!("{}.input_{}", node.name, ix), k)?;
+        }
+    }
+    Ok(Some(invocation("tract_core_attention", &inputs, &[("scale", numeric(op.scale))])))
+}
+
+/// The mask is optional: it is only looked up when given positionally.
+fn attention_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let mut inputs: TVec<OutletId> = tvec!(
+        invocation.named_arg_as(builder, "q")?,
+        invocation.named_arg_as(builder, "k")?,
+        invocation.named_arg_as(builder, "v")?,
+    );
+    if invocation.get_named_arg("mask").is_some() {
+        inputs.push(invocation.named_arg_as(builder, "mask")?);
+    }
+    let scale = invocation.named_arg_as(builder, "scale")?;
+    builder.wire(Attention::new(scale), &inputs)
+}
@@ -0,0 +1,69 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::{GridSample, GridSampleMode, GridSamplePadding};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<GridSample>(), grid_sample_dump);
+    registry.register_primitive(
+        "tract_core_grid_sample",
+        &grid_sample_parameters(),
+        grid_sample_load,
+    );
+}
+
+fn grid_sample_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("grid"),
+        TypeName::String.named("mode").default("bilinear"),
+        TypeName::String.named("padding").default("zeros"),
+        TypeName::Logical.named("align_corners").default(false),
+    ]
+}
+
+fn grid_sample_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<GridSample>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let grid = ast.mapping[&node.inputs[1]].clone();
+    let mode = match op.mode {
+        GridSampleMode::Nearest => "nearest",
+        GridSampleMode::Bilinear => "bilinear",
+        GridSampleMode::Bicubic => "bicubic",
+    };
+    let padding = match op.padding {
+        GridSamplePadding::Zeros => "zeros",
+        GridSamplePadding::Border => "border",
+        GridSamplePadding::Reflection => "reflection",
+    };
+    Ok(Some(invocation(
+        "tract_core_grid_sample",
+        &[input, grid],
+        &[
+            ("mode", string(mode)),
+            ("padding", string(padding)),
+            ("align_corners", logical(op.align_corners)),
+        ],
+    )))
+}
+
+fn grid_sample_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let grid = invocation.named_arg_as(builder, "grid")?;
+    let mode = match &*invocation.named_arg_as::<String>(builder, "mode")? {
+        "nearest" => GridSampleMode::Nearest,
+        "bilinear" => GridSampleMode::Bilinear,
+        "bicubic" => GridSampleMode::Bicubic,
+        other => bail!("Unsupported GridSample mode {}", other),
+    };
+    let padding = match &*invocation.named_arg_as::<String>(builder, "padding")? {
+        "zeros" => GridSamplePadding::Zeros,
+        "border" => GridSamplePadding::Border,
+        "reflection" => GridSamplePadding::Reflection,
+        other => bail!("Unsupported GridSample padding {}", other),
+    };
+    let align_corners = invocation.named_arg_as(builder, "align_corners")?;
+    builder.wire(GridSample::new(mode, padding, align_corners), &[input, grid])
+}
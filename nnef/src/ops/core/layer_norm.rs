@@ -0,0 +1,36 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::LayerNorm;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<LayerNorm>(), layer_norm_dump);
+    registry.register_primitive("tract_core_layer_norm", &layer_norm_parameters(), layer_norm_load);
+}
+
+fn layer_norm_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Integer.named("axis"),
+        TypeName::Scalar.named("epsilon").default(0.0),
+    ]
+}
+
+fn layer_norm_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<LayerNorm>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    Ok(Some(invocation(
+        "tract_core_layer_norm",
+        &[input],
+        &[("axis", numeric(op.axis)), ("epsilon", numeric(op.epsilon))],
+    )))
+}
+
+fn layer_norm_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let axis = invocation.named_arg_as(builder, "axis")?;
+    let epsilon = invocation.named_arg_as(builder, "epsilon")?;
+    builder.wire(LayerNorm::new(axis, epsilon), &[input])
+}
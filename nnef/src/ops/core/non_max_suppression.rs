@@ -0,0 +1,47 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::{BoxRepr, NonMaxSuppression};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<NonMaxSuppression>(), nms_dump);
+    registry.register_primitive("tract_core_non_max_suppression", &nms_parameters(), nms_load);
+}
+
+fn nms_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("boxes"),
+        TypeName::Scalar.tensor().named("scores"),
+        TypeName::Integer.tensor().named("max_output_boxes_per_class"),
+        TypeName::Scalar.tensor().named("iou_threshold"),
+        TypeName::Scalar.tensor().named("score_threshold"),
+        TypeName::Logical.named("center_point_box").default(false),
+    ]
+}
+
+fn nms_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<NonMaxSuppression>().unwrap();
+    let inputs = node.inputs.iter().map(|i| ast.mapping[i].clone()).collect::<TVec<_>>();
+    Ok(Some(invocation(
+        "tract_core_non_max_suppression",
+        &inputs,
+        &[("center_point_box", logical(op.box_repr == BoxRepr::CenterWidthHeight))],
+    )))
+}
+
+fn nms_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let boxes = invocation.named_arg_as(builder, "boxes")?;
+    let scores = invocation.named_arg_as(builder, "scores")?;
+    let max_output_boxes_per_class =
+        invocation.named_arg_as(builder, "max_output_boxes_per_class")?;
+    let iou_threshold = invocation.named_arg_as(builder, "iou_threshold")?;
+    let score_threshold = invocation.named_arg_as(builder, "score_threshold")?;
+    let center_point_box: bool = invocation.named_arg_as(builder, "center_point_box")?;
+    let box_repr = if center_point_box { BoxRepr::CenterWidthHeight } else { BoxRepr::TwoPoints };
+    builder.wire(
+        NonMaxSuppression::new(box_repr, Symbol::new('n')),
+        &[boxes, scores, max_output_boxes_per_class, iou_threshold, score_threshold],
+    )
+}
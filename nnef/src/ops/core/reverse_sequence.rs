@@ -0,0 +1,48 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::array::ReverseSequence;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<ReverseSequence>(), reverse_sequence_dump);
+    registry.register_primitive(
+        "tract_core_reverse_sequence",
+        &reverse_sequence_parameters(),
+        reverse_sequence_load,
+    );
+}
+
+fn reverse_sequence_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Integer.tensor().named("sequence_lens"),
+        TypeName::Integer.named("batch_axis"),
+        TypeName::Integer.named("time_axis"),
+    ]
+}
+
+fn reverse_sequence_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<ReverseSequence>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    // uniform lengths would be dumped as a scalar, but they are never broadcast
+    let lens = if let Some(k) = &ast.model.outlet_fact(node.inputs[1])?.konst {
+        ast.konst_variable(format!("{}.sequence_lens", node.name), k)?
+    } else {
+        ast.mapping[&node.inputs[1]].clone()
+    };
+    Ok(Some(invocation(
+        "tract_core_reverse_sequence",
+        &[input, lens],
+        &[("batch_axis", numeric(op.batch_axis)), ("time_axis", numeric(op.time_axis))],
+    )))
+}
+
+fn reverse_sequence_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let lens = invocation.named_arg_as(builder, "sequence_lens")?;
+    let batch_axis = invocation.named_arg_as(builder, "batch_axis")?;
+    let time_axis = invocation.named_arg_as(builder, "time_axis")?;
+    builder.wire(ReverseSequence::new(batch_axis, time_axis), &[input, lens])
+}
@@ -0,0 +1,73 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::{RoiAlign, RoiAlignMode};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<RoiAlign>(), roi_align_dump);
+    registry.register_primitive("tract_core_roi_align", &roi_align_parameters(), roi_align_load);
+}
+
+fn roi_align_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("rois"),
+        TypeName::Integer.tensor().named("batch_indices"),
+        TypeName::String.named("mode").default("avg"),
+        TypeName::Integer.named("output_height").default(1),
+        TypeName::Integer.named("output_width").default(1),
+        TypeName::Integer.named("sampling_ratio").default(0),
+        TypeName::Scalar.named("spatial_scale").default(1.0),
+        TypeName::Logical.named("aligned").default(false),
+    ]
+}
+
+fn roi_align_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<RoiAlign>().unwrap();
+    let mut inputs = node.inputs.iter().map(|i| ast.mapping[i].clone()).collect::<TVec<_>>();
+    // uniform constants are dumped as scalars, but regions and batch indices
+    // are never broadcast
+    for ix in 1..3 {
+        if let Some(k) = &ast.model.outlet_fact(node.inputs[ix])?.konst {
+            inputs[ix] = ast.konst_variable(format!("{}.input_{}", node.name, ix), k)?;
+        }
+    }
+    let mode = match op.mode {
+        RoiAlignMode::Avg => "avg",
+        RoiAlignMode::Max => "max",
+    };
+    Ok(Some(invocation(
+        "tract_core_roi_align",
+        &inputs,
+        &[
+            ("mode", string(mode)),
+            ("output_height", numeric(op.output_height)),
+            ("output_width", numeric(op.output_width)),
+            ("sampling_ratio", numeric(op.sampling_ratio)),
+            ("spatial_scale", numeric(op.spatial_scale)),
+            ("aligned", logical(op.aligned)),
+        ],
+    )))
+}
+
+fn roi_align_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let rois = invocation.named_arg_as(builder, "rois")?;
+    let batch_indices = invocation.named_arg_as(builder, "batch_indices")?;
+    let mode = match &*invocation.named_arg_as::<String>(builder, "mode")? {
+        "avg" => RoiAlignMode::Avg,
+        "max" => RoiAlignMode::Max,
+        other => bail!("Unsupported RoiAlign mode {}", other),
+    };
+    let op = RoiAlign::new(
+        mode,
+        invocation.named_arg_as(builder, "output_height")?,
+        invocation.named_arg_as(builder, "output_width")?,
+        invocation.named_arg_as(builder, "sampling_ratio")?,
+        invocation.named_arg_as(builder, "spatial_scale")?,
+        invocation.named_arg_as(builder, "aligned")?,
+    );
+    builder.wire(op, &[input, rois, batch_indices])
+}
@@ -0,0 +1,39 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::array::TopK;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<TopK>(), topk_dump);
+    registry.register_primitive("tract_core_topk", &topk_parameters(), topk_load);
+}
+
+fn topk_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Integer.tensor().named("k"),
+        TypeName::Integer.named("axis"),
+        TypeName::Logical.named("largest"),
+    ]
+}
+
+fn topk_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<TopK>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let k = ast.mapping[&node.inputs[1]].clone();
+    Ok(Some(invocation(
+        "tract_core_topk",
+        &[input, k],
+        &[("axis", numeric(op.axis)), ("largest", logical(op.largest))],
+    )))
+}
+
+fn topk_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let k = invocation.named_arg_as(builder, "k")?;
+    let axis = invocation.named_arg_as(builder, "axis")?;
+    let largest = invocation.named_arg_as(builder, "largest")?;
+    builder.wire(TopK::new(axis, largest, Symbol::new('k').to_dim()), &[input, k])
+}
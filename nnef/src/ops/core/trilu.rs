@@ -0,0 +1,33 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::array::Trilu;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Trilu>(), trilu_dump);
+    registry.register_primitive("tract_core_trilu", &trilu_parameters(), trilu_load);
+}
+
+fn trilu_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Integer.tensor().named("k"),
+        TypeName::Logical.named("upper").default(true),
+    ]
+}
+
+fn trilu_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Trilu>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let k = ast.mapping[&node.inputs[1]].clone();
+    Ok(Some(invocation("tract_core_trilu", &[input, k], &[("upper", logical(op.upper))])))
+}
+
+fn trilu_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let k = invocation.named_arg_as(builder, "k")?;
+    let upper = invocation.named_arg_as(builder, "upper")?;
+    builder.wire(Trilu::new(upper), &[input, k])
+}
@@ -0,0 +1,36 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::array::Unique;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<Unique>(), unique_dump);
+    registry.register_primitive("tract_core_unique", &unique_parameters(), unique_load);
+}
+
+fn unique_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Integer.spec().named("axis"),
+        TypeName::Logical.named("sorted").default(true),
+    ]
+}
+
+fn unique_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<Unique>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let mut named_args = vec![("sorted", logical(op.sorted))];
+    if let Some(axis) = op.axis {
+        named_args.push(("axis", numeric(axis)));
+    }
+    Ok(Some(invocation("tract_core_unique", &[input], &named_args)))
+}
+
+fn unique_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let axis: Option<usize> = invocation.named_arg_as(builder, "axis").ok();
+    let sorted = invocation.named_arg_as(builder, "sorted")?;
+    builder.wire(Unique::new(axis, sorted, Symbol::new('u')), &[input])
+}
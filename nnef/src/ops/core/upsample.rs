@@ -0,0 +1,36 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<ops::Upsample>(), ser_upsample);
+    registry.register_primitive(
+        "tract_core_upsample",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.named("axis"),
+            TypeName::Integer.named("factor"),
+        ],
+        de_upsample,
+    );
+}
+
+fn ser_upsample(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op().downcast_ref::<ops::Upsample>().unwrap();
+    let wire = ast.mapping[&node.inputs[0]].clone();
+    Ok(Some(invocation(
+        "tract_core_upsample",
+        &[wire],
+        &[("axis", numeric(op.axis)), ("factor", numeric(op.factor))],
+    )))
+}
+
+fn de_upsample(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let wire = invocation.named_arg_as(builder, "input")?;
+    let axis = invocation.named_arg_as(builder, "axis")?;
+    let factor = invocation.named_arg_as(builder, "factor")?;
+    builder.wire(ops::Upsample { axis, factor }, &[wire])
+}
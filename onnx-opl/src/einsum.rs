@@ -0,0 +1,319 @@
+use std::fmt;
+use std::str::FromStr;
+
+use tract_ndarray::prelude::*;
+use tract_nnef::internal::*;
+use tract_nnef::ser::array;
+use tract_num_traits::{One, Zero};
+
+/// An einsum equation, with one label per axis of each input and of the
+/// output. Ellipsis have been replaced by explicit labels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Expr {
+    pub inputs: TVec<TVec<char>>,
+    pub output: TVec<char>,
+}
+
+impl Expr {
+    /// Parse an equation in numpy/ONNX syntax for inputs of the given ranks.
+    ///
+    /// Ellipsis are expanded to fresh labels, right-aligned across inputs.
+    /// Without an explicit output, it is made of the ellipsis labels followed
+    /// by the labels appearing only once, in alphabetical order.
+    pub fn parse(equation: &str, ranks: &[usize]) -> TractResult<Expr> {
+        let equation: String = equation.chars().filter(|c| !c.is_whitespace()).collect();
+        let (lhs, rhs) = match equation.find("->") {
+            Some(ix) => (&equation[..ix], Some(&equation[ix + 2..])),
+            None => (&*equation, None),
+        };
+        let terms: Vec<&str> = lhs.split(',').collect();
+        if terms.len() != ranks.len() {
+            bail!("Equation {} has {} inputs, got {}", equation, terms.len(), ranks.len())
+        }
+        if let Some(c) = equation.chars().find(|c| !c.is_ascii_alphabetic() && !".,->".contains(*c))
+        {
+            bail!("Invalid label {:?} in equation {}", c, equation)
+        }
+        let labels = |s: &str| s.chars().filter(|c| c.is_ascii_alphabetic()).collect::<TVec<_>>();
+        let mut ellipsis_ranks = tvec!();
+        for (term, &rank) in terms.iter().zip(ranks.iter()) {
+            let explicit = labels(term).len();
+            if term.contains("...") {
+                if explicit > rank {
+                    bail!("Term {} has too many labels for rank {}", term, rank)
+                }
+                ellipsis_ranks.push(rank - explicit);
+            } else if explicit != rank {
+                bail!("Term {} does not match rank {}", term, rank)
+            } else {
+                ellipsis_ranks.push(0);
+            }
+        }
+        let ellipsis_rank = ellipsis_ranks.iter().copied().max().unwrap_or(0);
+        let ellipsis: TVec<char> = ('A'..='Z')
+            .chain('a'..='z')
+            .filter(|c| !equation.contains(*c))
+            .take(ellipsis_rank)
+            .collect();
+        if ellipsis.len() < ellipsis_rank {
+            bail!("Not enough free labels to expand ellipsis in {}", equation)
+        }
+        let expand = |term: &str, rank: usize| -> TVec<char> {
+            if let Some(ix) = term.find("...") {
+                labels(&term[..ix])
+                    .into_iter()
+                    .chain(ellipsis[ellipsis_rank - rank..].iter().copied())
+                    .chain(labels(&term[ix + 3..]))
+                    .collect()
+            } else {
+                labels(term)
+            }
+        };
+        let inputs: TVec<TVec<char>> =
+            terms.iter().zip(ellipsis_ranks.iter()).map(|(t, &r)| expand(t, r)).collect();
+        let output = if let Some(rhs) = rhs {
+            expand(rhs, ellipsis_rank)
+        } else {
+            let mut once: Vec<char> = inputs
+                .iter()
+                .flatten()
+                .copied()
+                .filter(|c| {
+                    !ellipsis.contains(c)
+                        && inputs.iter().flatten().filter(|d| *d == c).count() == 1
+                })
+                .collect();
+            once.sort();
+            ellipsis.iter().copied().chain(once.into_iter()).collect()
+        };
+        for (ix, c) in output.iter().enumerate() {
+            if output[..ix].contains(c) {
+                bail!("Output label {} is repeated in {}", c, equation)
+            }
+            if !inputs.iter().any(|i| i.contains(c)) {
+                bail!("Output label {} does not appear in inputs of {}", c, equation)
+            }
+        }
+        Ok(Expr { inputs, output })
+    }
+
+    /// All labels, output ones first, then the summed ones.
+    pub fn labels(&self) -> TVec<char> {
+        let mut labels = self.output.clone();
+        for c in self.inputs.iter().flatten() {
+            if !labels.contains(c) {
+                labels.push(*c)
+            }
+        }
+        labels
+    }
+
+    /// The dimension of each label. Dimensions of 1 broadcast.
+    pub fn label_dims<D: DimLike>(&self, shapes: &[&[D]]) -> TractResult<HashMap<char, D>> {
+        let mut dims = HashMap::<char, D>::new();
+        for (labels, shape) in self.inputs.iter().zip(shapes.iter()) {
+            if labels.len() != shape.len() {
+                bail!("Rank mismatch for {:?} and {:?}", labels, shape)
+            }
+            for (c, d) in labels.iter().zip(shape.iter()) {
+                let one = D::from(1);
+                match dims.get(c) {
+                    Some(prev) if *prev != one && *d != one && prev != d => {
+                        bail!("Inconsistent dimensions for {}: {:?} and {:?}", c, prev, d)
+                    }
+                    Some(prev) if *prev != one => (),
+                    _ => {
+                        dims.insert(*c, d.clone());
+                    }
+                }
+            }
+        }
+        Ok(dims)
+    }
+
+    pub fn output_shape<D: DimLike>(&self, shapes: &[&[D]]) -> TractResult<TVec<D>> {
+        let dims = self.label_dims(shapes)?;
+        Ok(self.output.iter().map(|c| dims[c].clone()).collect())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inputs: Vec<String> = self.inputs.iter().map(|i| i.iter().collect()).collect();
+        write!(f, "{}->{}", inputs.join(","), self.output.iter().collect::<String>())
+    }
+}
+
+impl FromStr for Expr {
+    type Err = TractError;
+    fn from_str(s: &str) -> TractResult<Expr> {
+        if s.contains("...") || !s.contains("->") {
+            bail!("Expected an explicit equation without ellipsis, got {}", s)
+        }
+        let lhs = &s[..s.find("->").unwrap()];
+        let ranks: TVec<usize> =
+            lhs.split(',').map(|t| t.chars().filter(|c| c.is_ascii_alphabetic()).count()).collect();
+        Expr::parse(s, &ranks)
+    }
+}
+
+/// Generic einsum, summing the product of the inputs over all the labels
+/// absent from the output.
+///
+/// It is a naive loop over the full index space: equations that can be
+/// mapped to MatMul and Reduce should be lowered to these instead.
+#[derive(Debug, Clone, Hash)]
+pub struct EinSum {
+    pub expr: Expr,
+}
+
+impl_dyn_hash!(EinSum);
+
+impl Op for EinSum {
+    fn name(&self) -> Cow<str> {
+        "EinSum".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![self.expr.to_string()])
+    }
+
+    op_onnx!();
+    op_as_typed_op!();
+}
+
+impl EinSum {
+    fn eval_t<T: Datum + Copy + Zero + One>(&self, inputs: &[Arc<Tensor>]) -> TractResult<Tensor> {
+        let shapes: TVec<&[usize]> = inputs.iter().map(|i| i.shape()).collect();
+        let dims = self.expr.label_dims(&shapes)?;
+        let labels = self.expr.labels();
+        let full_shape: TVec<usize> = labels.iter().map(|c| dims[c]).collect();
+        let views: TVec<ArrayViewD<T>> =
+            inputs.iter().map(|i| i.to_array_view::<T>()).collect::<TractResult<_>>()?;
+        // for each input axis, the position of its label, or None if broadcast
+        let positions: TVec<TVec<Option<usize>>> = self
+            .expr
+            .inputs
+            .iter()
+            .zip(shapes.iter())
+            .map(|(input, shape)| {
+                input
+                    .iter()
+                    .zip(shape.iter())
+                    .map(|(c, &d)| {
+                        if d == 1 && dims[c] != 1 {
+                            None
+                        } else {
+                            labels.iter().position(|l| l == c)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let out_rank = self.expr.output.len();
+        let mut output = ArrayD::<T>::zeros(&full_shape[..out_rank]);
+        let mut input_coords: TVec<Vec<usize>> =
+            positions.iter().map(|p| vec![0; p.len()]).collect();
+        for coords in tract_ndarray::indices(&*full_shape) {
+            let coords = coords.slice();
+            let mut product = T::one();
+            for ((view, positions), input_coords) in
+                views.iter().zip(positions.iter()).zip(input_coords.iter_mut())
+            {
+                for (ix, pos) in positions.iter().enumerate() {
+                    input_coords[ix] = pos.map(|p| coords[p]).unwrap_or(0);
+                }
+                product = product * view[&**input_coords];
+            }
+            let out = &mut output[&coords[..out_rank]];
+            *out = *out + product;
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+impl EvalOp for EinSum {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let dt = inputs[0].datum_type();
+        let output = dispatch_numbers!(Self::eval_t(dt)(self, &inputs))?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for EinSum {
+    as_op!();
+
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs.len() != self.expr.inputs.len() {
+            bail!("{} expects {} inputs, got {}", self.expr, self.expr.inputs.len(), inputs.len())
+        }
+        let shapes: TVec<TVec<TDim>> = inputs.iter().map(|i| i.shape.to_tvec()).collect();
+        let shapes: TVec<&[TDim]> = shapes.iter().map(|s| &**s).collect();
+        let shape = self.expr.output_shape(&shapes)?;
+        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &*shape)))
+    }
+}
+
+pub fn parameters() -> Vec<Parameter> {
+    vec![TypeName::Scalar.tensor().array().named("inputs"), TypeName::String.named("expr")]
+}
+
+pub fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<EinSum>().unwrap();
+    let inputs = node.inputs.iter().map(|i| (*ast.mapping[i]).clone()).collect::<Vec<_>>();
+    Ok(Some(invocation(
+        "tract_onnx_einsum",
+        &[array(inputs).into()],
+        &[("expr", string(op.expr.to_string()))],
+    )))
+}
+
+pub fn load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let inputs: TVec<OutletId> = invocation.named_arg_as(builder, "inputs")?;
+    let expr: String = invocation.named_arg_as(builder, "expr")?;
+    builder.wire(EinSum { expr: expr.parse()? }, &inputs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn expr(eq: &str, ranks: &[usize]) -> String {
+        Expr::parse(eq, ranks).unwrap().to_string()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(expr("ij,jk->ik", &[2, 2]), "ij,jk->ik");
+        assert_eq!(expr("ij,jk", &[2, 2]), "ij,jk->ik");
+        assert_eq!(expr("ji", &[2]), "ji->ij");
+        assert_eq!(expr("...ij,...jk->...ik", &[4, 3]), "ABij,Bjk->ABik");
+        assert_eq!(expr("...ii", &[3]), "Aii->A");
+        assert!(Expr::parse("ij,jk->ik", &[2]).is_err());
+        assert!(Expr::parse("ij->k", &[2]).is_err());
+    }
+
+    #[test]
+    fn eval_diagonal() {
+        let op = EinSum { expr: "ii->i".parse().unwrap() };
+        let input = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor1(&[1f32, 4.]));
+    }
+
+    #[test]
+    fn eval_broadcast_batch_matmul() {
+        let op = EinSum { expr: "Aij,Ajk->Aik".parse().unwrap() };
+        let a = tensor3(&[[[1f32, 2.]], [[3., 4.]]]);
+        let b = tensor3(&[[[1f32], [1.]]]);
+        let output = op.eval(tvec!(a.into_arc_tensor(), b.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor3(&[[[3f32]], [[7.]]]));
+    }
+}
@@ -6,11 +6,13 @@ use tract_nnef::internal::*;
 #[macro_use]
 mod macros;
 
+pub mod einsum;
 pub mod erf;
 pub mod is_inf;
 pub mod is_nan;
 pub mod lrn;
 pub mod ml;
+pub mod text;
 
 pub trait WithOnnx {
     fn with_onnx(self) -> Self;
@@ -27,6 +29,9 @@ impl WithOnnx for tract_nnef::framework::Nnef {
 fn onnx_opl_registry() -> Registry {
     let mut registry: Registry = Registry::new("tract_onnx");
     ml::register(&mut registry);
+    text::register(&mut registry);
+    registry.register_dumper(TypeId::of::<einsum::EinSum>(), einsum::dump);
+    registry.register_primitive("tract_onnx_einsum", &einsum::parameters(), einsum::load);
     registry.register_unit_element_wise("tract_onnx_erf", &erf::Erf {});
     registry.register_element_wise(
         "tract_onnx_isinf",
@@ -60,7 +60,7 @@ impl EvalOp for DirectLookup {
 
     fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
         let input = args_1!(inputs);
-        let output = dispatch_hash!(Self::eval_t(self.values.datum_type())(self, &input))?;
+        let output = dispatch_datum!(Self::eval_t(self.values.datum_type())(self, &input))?;
         Ok(tvec!(output.into_arc_tensor()))
     }
 }
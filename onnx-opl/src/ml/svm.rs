@@ -0,0 +1,530 @@
+use tract_ndarray::{Array2, ArrayView2, Axis};
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive(
+        "tract_onnx_ml_svm_classifier",
+        &parameters_classifier(),
+        load_classifier,
+    );
+    registry.register_primitive(
+        "tract_onnx_ml_svm_regressor",
+        &parameters_regressor(),
+        load_regressor,
+    );
+    registry.register_dumper(TypeId::of::<SvmClassifier>(), dump_classifier);
+    registry.register_dumper(TypeId::of::<SvmRegressor>(), dump_regressor);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KernelType {
+    Linear,
+    Poly,
+    Rbf,
+    Sigmoid,
+}
+
+pub fn parse_kernel_type(s: &str) -> TractResult<KernelType> {
+    match s {
+        "LINEAR" => Ok(KernelType::Linear),
+        "POLY" => Ok(KernelType::Poly),
+        "RBF" => Ok(KernelType::Rbf),
+        "SIGMOID" => Ok(KernelType::Sigmoid),
+        _ => bail!("Invalid kernel type: {}", s),
+    }
+}
+
+pub fn format_kernel_type(kernel_type: KernelType) -> &'static str {
+    match kernel_type {
+        KernelType::Linear => "LINEAR",
+        KernelType::Poly => "POLY",
+        KernelType::Rbf => "RBF",
+        KernelType::Sigmoid => "SIGMOID",
+    }
+}
+
+#[derive(Clone, Debug, Educe)]
+#[educe(Hash)]
+pub struct Kernel {
+    pub kernel_type: KernelType,
+    #[educe(Hash(method = "hash_f32"))]
+    pub gamma: f32,
+    #[educe(Hash(method = "hash_f32"))]
+    pub coef0: f32,
+    pub degree: i32,
+}
+
+impl Kernel {
+    /// Kernel values between each input row [N, F] and each support vector
+    /// [S, F], as a [N, S] matrix.
+    pub fn matrix(
+        &self,
+        input: &ArrayView2<f32>,
+        support_vectors: &ArrayView2<f32>,
+    ) -> Array2<f32> {
+        match self.kernel_type {
+            KernelType::Linear => input.dot(&support_vectors.t()),
+            KernelType::Poly => input
+                .dot(&support_vectors.t())
+                .mapv(|x| (self.gamma * x + self.coef0).powi(self.degree)),
+            KernelType::Sigmoid => {
+                input.dot(&support_vectors.t()).mapv(|x| (self.gamma * x + self.coef0).tanh())
+            }
+            KernelType::Rbf => {
+                let mut matrix = Array2::zeros((input.nrows(), support_vectors.nrows()));
+                for (x, mut row) in input.outer_iter().zip(matrix.outer_iter_mut()) {
+                    for (sv, k) in support_vectors.outer_iter().zip(row.iter_mut()) {
+                        let d2 =
+                            x.iter().zip(sv.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f32>();
+                        *k = (-self.gamma * d2).exp();
+                    }
+                }
+                matrix
+            }
+        }
+    }
+}
+
+/// One-vs-one support vector classifier, in the libsvm layout.
+///
+/// Outputs the index of the class with the most votes, and either the
+/// pairwise decision values or, when Platt scaling parameters are present,
+/// the class probabilities.
+#[derive(Clone, Debug, Hash)]
+pub struct SvmClassifier {
+    pub kernel: Kernel,
+    // f32 [S, F], grouped by class
+    pub support_vectors: Arc<Tensor>,
+    // f32 [C - 1, S]
+    pub coefficients: Arc<Tensor>,
+    // f32 [C * (C - 1) / 2], one per class pair
+    pub rho: Arc<Tensor>,
+    pub vectors_per_class: TVec<usize>,
+    pub prob_a: Option<Arc<Tensor>>,
+    pub prob_b: Option<Arc<Tensor>>,
+}
+
+impl_dyn_hash!(SvmClassifier);
+
+impl SvmClassifier {
+    pub fn n_classes(&self) -> usize {
+        self.vectors_per_class.len()
+    }
+
+    fn n_scores(&self) -> usize {
+        let n = self.n_classes();
+        if self.prob_a.is_some() {
+            n
+        } else {
+            n * (n - 1) / 2
+        }
+    }
+
+    fn eval_2d(&self, input: &ArrayView2<f32>) -> TractResult<(Tensor, Tensor)> {
+        let n_classes = self.n_classes();
+        let support_vectors = self.support_vectors.to_array_view::<f32>()?.into_dimensionality()?;
+        let coefficients = self.coefficients.to_array_view::<f32>()?.into_dimensionality()?;
+        let rho = self.rho.as_slice::<f32>()?;
+        let kernels = self.kernel.matrix(input, &support_vectors);
+        let starts: TVec<usize> = self
+            .vectors_per_class
+            .iter()
+            .scan(0, |acc, n| {
+                *acc += n;
+                Some(*acc - n)
+            })
+            .collect();
+        let mut winners = vec![0i32; input.nrows()];
+        let mut scores = Array2::<f32>::zeros((input.nrows(), self.n_scores()));
+        let mut decisions = vec![0f32; rho.len()];
+        for (ix, kernel) in kernels.outer_iter().enumerate() {
+            let mut votes = vec![0usize; n_classes];
+            let mut pair = 0;
+            for i in 0..n_classes {
+                for j in i + 1..n_classes {
+                    let class_i = starts[i]..starts[i] + self.vectors_per_class[i];
+                    let class_j = starts[j]..starts[j] + self.vectors_per_class[j];
+                    let sum = class_i.map(|s| coefficients[(j - 1, s)] * kernel[s]).sum::<f32>()
+                        + class_j.map(|s| coefficients[(i, s)] * kernel[s]).sum::<f32>()
+                        + rho[pair];
+                    decisions[pair] = sum;
+                    votes[if sum > 0.0 { i } else { j }] += 1;
+                    pair += 1;
+                }
+            }
+            // first class wins ties, as in libsvm
+            winners[ix] = (0..n_classes).rev().max_by_key(|&c| votes[c]).unwrap() as i32;
+            let mut scores = scores.index_axis_mut(Axis(0), ix);
+            if let (Some(a), Some(b)) = (&self.prob_a, &self.prob_b) {
+                let probs = multiclass_probability(
+                    n_classes,
+                    &decisions,
+                    a.as_slice::<f32>()?,
+                    b.as_slice::<f32>()?,
+                );
+                scores.iter_mut().zip(probs).for_each(|(s, p)| *s = p);
+            } else {
+                scores.iter_mut().zip(&decisions).for_each(|(s, d)| *s = *d);
+            }
+        }
+        Ok((tensor1(&winners), scores.into_tensor()))
+    }
+}
+
+/// Platt scaling of a decision value, in its numerically stable form.
+fn sigmoid_predict(decision: f32, a: f32, b: f32) -> f32 {
+    let f_apb = decision * a + b;
+    if f_apb >= 0.0 {
+        (-f_apb).exp() / (1.0 + (-f_apb).exp())
+    } else {
+        1.0 / (1.0 + f_apb.exp())
+    }
+}
+
+/// Class probabilities from the pairwise ones, following the second method
+/// of Wu, Lin and Weng as implemented in libsvm.
+fn multiclass_probability(k: usize, decisions: &[f32], a: &[f32], b: &[f32]) -> Vec<f32> {
+    let mut r = vec![vec![0f32; k]; k];
+    let mut pair = 0;
+    for i in 0..k {
+        for j in i + 1..k {
+            let p = sigmoid_predict(decisions[pair], a[pair], b[pair]).max(1e-7).min(1.0 - 1e-7);
+            r[i][j] = p;
+            r[j][i] = 1.0 - p;
+            pair += 1;
+        }
+    }
+    let mut p = vec![1.0 / k as f32; k];
+    let mut q = vec![vec![0f32; k]; k];
+    for t in 0..k {
+        for j in 0..k {
+            if j != t {
+                q[t][t] += r[j][t] * r[j][t];
+                q[t][j] = -r[j][t] * r[t][j];
+            }
+        }
+    }
+    let mut qp = vec![0f32; k];
+    let eps = 0.005 / k as f32;
+    for _ in 0..100.max(k) {
+        let mut pqp = 0.0;
+        for t in 0..k {
+            qp[t] = (0..k).map(|j| q[t][j] * p[j]).sum();
+            pqp += p[t] * qp[t];
+        }
+        if qp.iter().all(|qp| (qp - pqp).abs() < eps) {
+            break;
+        }
+        for t in 0..k {
+            let diff = (-qp[t] + pqp) / q[t][t];
+            p[t] += diff;
+            pqp = (pqp + diff * (diff * q[t][t] + 2.0 * qp[t])) / (1.0 + diff) / (1.0 + diff);
+            for j in 0..k {
+                qp[j] = (qp[j] + diff * q[t][j]) / (1.0 + diff);
+                p[j] /= 1.0 + diff;
+            }
+        }
+    }
+    p
+}
+
+impl Op for SvmClassifier {
+    fn name(&self) -> Cow<str> {
+        "SvmClassifier".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for SvmClassifier {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<f32>()?;
+        let input = input.to_array_view::<f32>()?.into_dimensionality()?;
+        let (winners, scores) = self.eval_2d(&input)?;
+        Ok(tvec!(winners.into_arc_tensor(), scores.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SvmClassifier {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].rank() != 2 {
+            bail!("SvmClassifier expects a 2D input, got {:?}", inputs[0])
+        }
+        let n = &inputs[0].shape[0];
+        Ok(tvec!(
+            TypedFact::dt_shape(i32::datum_type(), &[n.clone()]),
+            TypedFact::dt_shape(f32::datum_type(), &[n.clone(), self.n_scores().into()])
+        ))
+    }
+
+    as_op!();
+}
+
+/// Support vector regression, or one-class classification when `one_class`
+/// is set, in which case the output is 1 for inliers and -1 for outliers.
+#[derive(Clone, Debug, Educe)]
+#[educe(Hash)]
+pub struct SvmRegressor {
+    pub kernel: Kernel,
+    // f32 [S, F]
+    pub support_vectors: Arc<Tensor>,
+    // f32 [S]
+    pub coefficients: Arc<Tensor>,
+    #[educe(Hash(method = "hash_f32"))]
+    pub rho: f32,
+    pub one_class: bool,
+}
+
+impl_dyn_hash!(SvmRegressor);
+
+impl Op for SvmRegressor {
+    fn name(&self) -> Cow<str> {
+        "SvmRegressor".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for SvmRegressor {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<f32>()?;
+        let input = input.to_array_view::<f32>()?.into_dimensionality()?;
+        let support_vectors = self.support_vectors.to_array_view::<f32>()?.into_dimensionality()?;
+        let coefficients = self
+            .coefficients
+            .to_array_view::<f32>()?
+            .into_dimensionality::<tract_ndarray::Ix1>()?;
+        let mut output = self.kernel.matrix(&input, &support_vectors).dot(&coefficients);
+        output.mapv_inplace(|y| y + self.rho);
+        if self.one_class {
+            output.mapv_inplace(|y| if y > 0.0 { 1.0 } else { -1.0 });
+        }
+        Ok(tvec!(output.insert_axis(Axis(1)).into_arc_tensor()))
+    }
+}
+
+impl TypedOp for SvmRegressor {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].rank() != 2 {
+            bail!("SvmRegressor expects a 2D input, got {:?}", inputs[0])
+        }
+        Ok(tvec!(TypedFact::dt_shape(f32::datum_type(), &[inputs[0].shape[0].clone(), 1.into()])))
+    }
+
+    as_op!();
+}
+
+fn kernel_parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::String.named("kernel_type"),
+        TypeName::Scalar.named("gamma"),
+        TypeName::Scalar.named("coef0"),
+        TypeName::Integer.named("degree"),
+    ]
+}
+
+fn parameters_classifier() -> Vec<Parameter> {
+    let mut params = vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("support_vectors"),
+        TypeName::Scalar.tensor().named("coefficients"),
+        TypeName::Scalar.tensor().named("rho"),
+        TypeName::Integer.tensor().named("vectors_per_class"),
+        TypeName::Scalar.tensor().named("prob_a"),
+        TypeName::Scalar.tensor().named("prob_b"),
+    ];
+    params.extend(kernel_parameters());
+    params
+}
+
+fn parameters_regressor() -> Vec<Parameter> {
+    let mut params = vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("support_vectors"),
+        TypeName::Scalar.tensor().named("coefficients"),
+        TypeName::Scalar.named("rho"),
+        TypeName::Logical.named("one_class"),
+    ];
+    params.extend(kernel_parameters());
+    params
+}
+
+fn dump_kernel(kernel: &Kernel) -> Vec<(&'static str, RValue)> {
+    vec![
+        ("kernel_type", string(format_kernel_type(kernel.kernel_type))),
+        ("gamma", numeric(kernel.gamma)),
+        ("coef0", numeric(kernel.coef0)),
+        ("degree", numeric(kernel.degree)),
+    ]
+}
+
+fn load_kernel(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Kernel> {
+    let kernel_type: String = invocation.named_arg_as(builder, "kernel_type")?;
+    Ok(Kernel {
+        kernel_type: parse_kernel_type(&kernel_type)?,
+        gamma: invocation.named_arg_as(builder, "gamma")?,
+        coef0: invocation.named_arg_as(builder, "coef0")?,
+        degree: invocation.named_arg_as::<i64>(builder, "degree")? as i32,
+    })
+}
+
+fn dump_classifier(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<SvmClassifier>().context("wrong op")?;
+    let mut inputs = vec![ast.mapping[&node.inputs[0]].clone()];
+    inputs.push(ast.konst_variable(format!("{}_support_vectors", node.name), &op.support_vectors)?);
+    inputs.push(ast.konst_variable(format!("{}_coefficients", node.name), &op.coefficients)?);
+    inputs.push(ast.konst_variable(format!("{}_rho", node.name), &op.rho)?);
+    let vectors_per_class: Vec<i64> = op.vectors_per_class.iter().map(|&n| n as i64).collect();
+    inputs.push(ast.konst_variable(
+        format!("{}_vectors_per_class", node.name),
+        &rctensor1(&vectors_per_class),
+    )?);
+    // Platt scaling parameters are optional: they are only dumped when present
+    if let (Some(a), Some(b)) = (&op.prob_a, &op.prob_b) {
+        inputs.push(ast.konst_variable(format!("{}_prob_a", node.name), a)?);
+        inputs.push(ast.konst_variable(format!("{}_prob_b", node.name), b)?);
+    }
+    let named = dump_kernel(&op.kernel);
+    let named = named.iter().map(|(k, v)| (*k, v.clone())).collect::<Vec<_>>();
+    Ok(Some(invocation("tract_onnx_ml_svm_classifier", &inputs, &named)))
+}
+
+fn load_classifier(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let vectors_per_class: Arc<Tensor> = invocation.named_arg_as(builder, "vectors_per_class")?;
+    let vectors_per_class = vectors_per_class
+        .cast_to::<i64>()?
+        .as_slice::<i64>()?
+        .iter()
+        .map(|&n| n as usize)
+        .collect();
+    let (prob_a, prob_b) = if invocation.get_named_arg("prob_a").is_some() {
+        (
+            Some(invocation.named_arg_as(builder, "prob_a")?),
+            Some(invocation.named_arg_as(builder, "prob_b")?),
+        )
+    } else {
+        (None, None)
+    };
+    let op = SvmClassifier {
+        kernel: load_kernel(builder, invocation)?,
+        support_vectors: invocation.named_arg_as(builder, "support_vectors")?,
+        coefficients: invocation.named_arg_as(builder, "coefficients")?,
+        rho: invocation.named_arg_as(builder, "rho")?,
+        vectors_per_class,
+        prob_a,
+        prob_b,
+    };
+    builder.wire(op, &[input])
+}
+
+fn dump_regressor(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<SvmRegressor>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let support_vectors =
+        ast.konst_variable(format!("{}_support_vectors", node.name), &op.support_vectors)?;
+    let coefficients =
+        ast.konst_variable(format!("{}_coefficients", node.name), &op.coefficients)?;
+    let mut named = vec![("rho", numeric(op.rho)), ("one_class", logical(op.one_class))];
+    named.extend(dump_kernel(&op.kernel));
+    Ok(Some(invocation(
+        "tract_onnx_ml_svm_regressor",
+        &[input, support_vectors, coefficients],
+        &named,
+    )))
+}
+
+fn load_regressor(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let op = SvmRegressor {
+        kernel: load_kernel(builder, invocation)?,
+        support_vectors: invocation.named_arg_as(builder, "support_vectors")?,
+        coefficients: invocation.named_arg_as(builder, "coefficients")?,
+        rho: invocation.named_arg_as(builder, "rho")?,
+        one_class: invocation.named_arg_as(builder, "one_class")?,
+    };
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tract_ndarray::prelude::*;
+
+    fn linear() -> Kernel {
+        Kernel { kernel_type: KernelType::Linear, gamma: 1.0, coef0: 0.0, degree: 1 }
+    }
+
+    #[test]
+    fn rbf_kernel() {
+        let kernel = Kernel { kernel_type: KernelType::Rbf, gamma: 0.5, coef0: 0.0, degree: 1 };
+        let matrix =
+            kernel.matrix(&arr2(&[[0f32, 0.]]).view(), &arr2(&[[0f32, 0.], [1., 1.]]).view());
+        assert_eq!(matrix, arr2(&[[1f32, (-1f32).exp()]]));
+    }
+
+    #[test]
+    fn one_vs_one_votes() {
+        // three classes with one support vector each, on the three axes
+        let op = SvmClassifier {
+            kernel: linear(),
+            support_vectors: rctensor2(&[[1f32, 0., 0.], [0., 1., 0.], [0., 0., 1.]]),
+            coefficients: rctensor2(&[[1f32, -1., -1.], [1., 1., -1.]]),
+            rho: rctensor1(&[0f32, 0., 0.]),
+            vectors_per_class: tvec!(1, 1, 1),
+            prob_a: None,
+            prob_b: None,
+        };
+        let input = tensor2(&[[2f32, 0., 0.], [0., 0., 3.], [0., 1., 0.]]);
+        let outputs = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*outputs[0], tensor1(&[0i32, 2, 1]));
+        assert_eq!(outputs[1].shape(), &[3, 3]);
+    }
+
+    #[test]
+    fn binary_probabilities() {
+        let probs = multiclass_probability(2, &[0.0], &[-1.0], &[0.0]);
+        assert!((probs[0] - 0.5).abs() < 1e-5 && (probs[1] - 0.5).abs() < 1e-5);
+        let probs = multiclass_probability(2, &[3.0], &[-1.0], &[0.0]);
+        // within the tolerance of the iterative coupling
+        assert!((probs[0] - sigmoid_predict(3.0, -1.0, 0.0)).abs() < 5e-3);
+    }
+
+    #[test]
+    fn one_class() {
+        let op = SvmRegressor {
+            kernel: linear(),
+            support_vectors: rctensor2(&[[1f32, 0.]]),
+            coefficients: rctensor1(&[1f32]),
+            rho: -0.5,
+            one_class: true,
+        };
+        let input = tensor2(&[[1f32, 0.], [0., 1.]]);
+        let outputs = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*outputs[0], tensor2(&[[1f32], [-1.]]));
+    }
+}
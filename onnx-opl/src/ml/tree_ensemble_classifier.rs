@@ -16,6 +16,15 @@ pub fn parse_aggregate(s: &str) -> TractResult<Aggregate> {
     }
 }
 
+pub fn format_aggregate(aggregate: Aggregate) -> &'static str {
+    match aggregate {
+        Aggregate::Min => "MIN",
+        Aggregate::Max => "MAX",
+        Aggregate::Sum => "SUM",
+        Aggregate::Avg => "AVERAGE",
+    }
+}
+
 #[derive(Debug, Clone, Hash)]
 pub struct TreeEnsembleClassifier {
     pub ensemble: TreeEnsemble,
@@ -79,12 +88,7 @@ fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>>
     let trees = ast.konst_variable(format!("{}_trees", node.name), &op.ensemble.data.trees)?;
     let nodes = ast.konst_variable(format!("{}_nodes", node.name), &op.ensemble.data.nodes)?;
     let leaves = ast.konst_variable(format!("{}_leaves", node.name), &op.ensemble.data.leaves)?;
-    let agg = match op.ensemble.aggregate_fn {
-        Aggregate::Min => "MIN",
-        Aggregate::Max => "MAX",
-        Aggregate::Sum => "SUM",
-        Aggregate::Avg => "AVERAGE",
-    };
+    let agg = format_aggregate(op.ensemble.aggregate_fn);
     Ok(Some(invocation(
         "tract_onnx_ml_tree_ensemble_classifier",
         &[input, trees, nodes, leaves],
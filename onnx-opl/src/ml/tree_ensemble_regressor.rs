@@ -0,0 +1,101 @@
+use super::tree::{TreeEnsemble, TreeEnsembleData};
+use super::tree_ensemble_classifier::{format_aggregate, parse_aggregate};
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive("tract_onnx_ml_tree_ensemble_regressor", &parameters(), load);
+    registry.register_dumper(TypeId::of::<TreeEnsembleRegressor>(), dump);
+}
+
+/// Raw target values of a tree ensemble, one column per target.
+#[derive(Debug, Clone, Hash)]
+pub struct TreeEnsembleRegressor {
+    pub ensemble: TreeEnsemble,
+}
+
+impl_dyn_hash!(TreeEnsembleRegressor);
+
+impl Op for TreeEnsembleRegressor {
+    fn name(&self) -> Cow<str> {
+        "TreeEnsembleRegressor".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for TreeEnsembleRegressor {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<f32>()?;
+        let input = input.to_array_view::<f32>()?;
+        let targets = self.ensemble.eval(input)?;
+        Ok(tvec!(targets.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for TreeEnsembleRegressor {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let n = &inputs[0].shape[0];
+        Ok(tvec!(TypedFact::dt_shape(
+            f32::datum_type(),
+            &[n.clone(), self.ensemble.n_classes().into()]
+        )))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("trees"),
+        TypeName::Scalar.tensor().named("nodes"),
+        TypeName::Scalar.tensor().named("leaves"),
+        TypeName::Integer.named("max_used_feature"),
+        TypeName::Integer.named("n_targets"),
+        TypeName::String.named("aggregate_fn"),
+    ]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<TreeEnsembleRegressor>().context("wrong op")?;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let trees = ast.konst_variable(format!("{}_trees", node.name), &op.ensemble.data.trees)?;
+    let nodes = ast.konst_variable(format!("{}_nodes", node.name), &op.ensemble.data.nodes)?;
+    let leaves = ast.konst_variable(format!("{}_leaves", node.name), &op.ensemble.data.leaves)?;
+    Ok(Some(invocation(
+        "tract_onnx_ml_tree_ensemble_regressor",
+        &[input, trees, nodes, leaves],
+        &[
+            ("max_used_feature", numeric(op.ensemble.max_used_feature)),
+            ("n_targets", numeric(op.ensemble.n_classes)),
+            ("aggregate_fn", string(format_aggregate(op.ensemble.aggregate_fn))),
+        ],
+    )))
+}
+
+fn load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let trees = invocation.named_arg_as(builder, "trees")?;
+    let nodes = invocation.named_arg_as(builder, "nodes")?;
+    let leaves = invocation.named_arg_as(builder, "leaves")?;
+    let max_used_feature = invocation.named_arg_as(builder, "max_used_feature")?;
+    let n_classes = invocation.named_arg_as(builder, "n_targets")?;
+    let aggregate_fn: String = invocation.named_arg_as(builder, "aggregate_fn")?;
+    let aggregate_fn = parse_aggregate(&aggregate_fn)?;
+    let data = TreeEnsembleData { trees, nodes, leaves };
+    let ensemble = TreeEnsemble { data, n_classes, max_used_feature, aggregate_fn };
+    let op = TreeEnsembleRegressor { ensemble };
+    builder.wire(op, &[input])
+}
@@ -0,0 +1,21 @@
+use tract_nnef::internal::*;
+
+pub mod regex_full_match;
+pub mod string_concat;
+pub mod string_normalizer;
+pub mod string_split;
+pub mod tfidf_vectorizer;
+
+pub use regex_full_match::RegexFullMatch;
+pub use string_concat::StringConcat;
+pub use string_normalizer::{CaseChange, StringNormalizer};
+pub use string_split::StringSplit;
+pub use tfidf_vectorizer::{TfIdfMode, TfIdfVectorizer};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_binary("tract_onnx_string_concat", &StringConcat);
+    regex_full_match::register(registry);
+    string_normalizer::register(registry);
+    string_split::register(registry);
+    tfidf_vectorizer::register(registry);
+}
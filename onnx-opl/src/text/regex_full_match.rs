@@ -0,0 +1,111 @@
+use regex::Regex;
+use std::hash::{Hash, Hasher};
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive("tract_onnx_regex_full_match", &parameters(), load);
+    registry.register_dumper(TypeId::of::<RegexFullMatch>(), dump);
+}
+
+/// Check whether each string of the input matches the pattern in its whole.
+#[derive(Clone, Debug)]
+pub struct RegexFullMatch {
+    pub pattern: String,
+    regex: Regex,
+}
+
+impl RegexFullMatch {
+    pub fn new(pattern: impl Into<String>) -> TractResult<RegexFullMatch> {
+        let pattern = pattern.into();
+        let regex = Regex::new(&format!("^(?:{})$", pattern))
+            .with_context(|| format!("Invalid regular expression {:?}", pattern))?;
+        Ok(RegexFullMatch { pattern, regex })
+    }
+}
+
+impl Hash for RegexFullMatch {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pattern.hash(state)
+    }
+}
+
+impl_dyn_hash!(RegexFullMatch);
+
+impl Op for RegexFullMatch {
+    fn name(&self) -> Cow<str> {
+        "RegexFullMatch".into()
+    }
+
+    op_onnx!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for RegexFullMatch {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = input.to_array_view::<String>()?.mapv(|s| self.regex.is_match(&s));
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for RegexFullMatch {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].datum_type != String::datum_type() {
+            bail!("RegexFullMatch expects strings, got {:?}", inputs[0])
+        }
+        Ok(tvec!(TypedFact::dt_shape(bool::datum_type(), inputs[0].shape.iter())))
+    }
+
+    fn invariants(&self, inputs: &[&TypedFact], outputs: &[&TypedFact]) -> TractResult<Invariants> {
+        Invariants::new_element_wise(inputs, outputs)
+    }
+
+    fn change_axes(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        _io: InOut,
+        change: &AxisOp,
+    ) -> TractResult<Option<AxisChangeConsequence>> {
+        Ok(Some(AxisChangeConsequence::new(model, node, None, change)))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![TypeName::String.tensor().named("input"), TypeName::String.named("pattern")]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<RegexFullMatch>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_regex_full_match",
+        &[input],
+        &[("pattern", string(&op.pattern))],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let pattern: String = invocation.named_arg_as(builder, "pattern")?;
+    builder.wire(RegexFullMatch::new(pattern)?, &[input])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_match_only() {
+        let op = RegexFullMatch::new("[a-z]+@[a-z]+\\.com").unwrap();
+        let input = tensor1(&["a@b.com".to_string(), "x a@b.com".to_string(), "a@b.comx".to_string()]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor1(&[true, false, false]));
+    }
+}
@@ -0,0 +1,20 @@
+use tract_nnef::internal::*;
+
+tract_core::bin_to_super_type!(string_concat, StringConcat,
+    [String] => |c, a, b| *c = format!("{}{}", a, b)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concat_broadcast() {
+        let a = tensor1(&["a".to_string(), "b".to_string()]);
+        let b = tensor1(&["x".to_string()]);
+        let output = string_concat::bin_typed()
+            .eval(tvec!(a.into_arc_tensor(), b.into_arc_tensor()))
+            .unwrap();
+        assert_eq!(*output[0], tensor1(&["ax".to_string(), "bx".to_string()]));
+    }
+}
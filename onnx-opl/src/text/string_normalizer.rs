@@ -0,0 +1,189 @@
+use tract_nnef::internal::*;
+use tract_nnef::ser::array;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive("tract_onnx_string_normalizer", &parameters(), load);
+    registry.register_dumper(TypeId::of::<StringNormalizer>(), dump);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CaseChange {
+    Lower,
+    Upper,
+}
+
+pub fn parse_case_change(s: &str) -> TractResult<Option<CaseChange>> {
+    match s {
+        "NONE" => Ok(None),
+        "LOWER" => Ok(Some(CaseChange::Lower)),
+        "UPPER" => Ok(Some(CaseChange::Upper)),
+        _ => bail!("Invalid case change action: {}", s),
+    }
+}
+
+fn format_case_change(c: Option<CaseChange>) -> &'static str {
+    match c {
+        None => "NONE",
+        Some(CaseChange::Lower) => "LOWER",
+        Some(CaseChange::Upper) => "UPPER",
+    }
+}
+
+/// Remove stop words from a [C] or [1, C] string tensor, then change the
+/// case of the remaining words.
+///
+/// If all words are removed, the output contains a single empty string.
+/// The output length is `len` unless there are no stop words.
+#[derive(Clone, Debug, Hash)]
+pub struct StringNormalizer {
+    pub case_change: Option<CaseChange>,
+    pub is_case_sensitive: bool,
+    pub stopwords: Vec<String>,
+    pub len: Symbol,
+}
+
+impl_dyn_hash!(StringNormalizer);
+
+impl StringNormalizer {
+    fn is_stopword(&self, s: &str) -> bool {
+        if self.is_case_sensitive {
+            self.stopwords.iter().any(|w| w == s)
+        } else {
+            let s = s.to_lowercase();
+            self.stopwords.iter().any(|w| w.to_lowercase() == s)
+        }
+    }
+
+    fn change_case(&self, s: &str) -> String {
+        match self.case_change {
+            None => s.to_string(),
+            Some(CaseChange::Lower) => s.to_lowercase(),
+            Some(CaseChange::Upper) => s.to_uppercase(),
+        }
+    }
+}
+
+impl Op for StringNormalizer {
+    fn name(&self) -> Cow<str> {
+        "StringNormalizer".into()
+    }
+
+    op_onnx!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for StringNormalizer {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let mut words: Vec<String> = input
+            .as_slice::<String>()?
+            .iter()
+            .filter(|s| !self.is_stopword(s))
+            .map(|s| self.change_case(s))
+            .collect();
+        if words.is_empty() {
+            words.push(String::new());
+        }
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[input.rank() - 1] = words.len();
+        let output = tract_ndarray::ArrayD::from_shape_vec(&*shape, words)?;
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for StringNormalizer {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let input = inputs[0];
+        if input.datum_type != String::datum_type() {
+            bail!("StringNormalizer expects strings, got {:?}", input)
+        }
+        if input.rank() == 0 || input.rank() > 2 || (input.rank() == 2 && !input.shape[0].is_one())
+        {
+            bail!("StringNormalizer expects a [C] or [1, C] input, got {:?}", input)
+        }
+        let mut fact = TypedFact::dt_shape(String::datum_type(), input.shape.iter());
+        if !self.stopwords.is_empty() {
+            fact.shape.set(input.rank() - 1, self.len.into());
+        }
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::String.tensor().named("input"),
+        TypeName::String.named("case_change_action").default("NONE"),
+        TypeName::Logical.named("is_case_sensitive").default(false),
+        TypeName::String.array().named("stopwords"),
+    ]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<StringNormalizer>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_string_normalizer",
+        &[input],
+        &[
+            ("case_change_action", string(format_case_change(op.case_change))),
+            ("is_case_sensitive", logical(op.is_case_sensitive)),
+            ("stopwords", array(op.stopwords.iter().map(string).collect::<Vec<_>>())),
+        ],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let case_change: String = invocation.named_arg_as(builder, "case_change_action")?;
+    let case_change = parse_case_change(&case_change)?;
+    let is_case_sensitive = invocation.named_arg_as(builder, "is_case_sensitive")?;
+    let stopwords: TVec<String> = invocation.named_arg_as(builder, "stopwords")?;
+    let op = StringNormalizer {
+        case_change,
+        is_case_sensitive,
+        stopwords: stopwords.into_vec(),
+        len: Symbol::new('w'),
+    };
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(s: &[&str]) -> Vec<String> {
+        s.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn remove_stopwords_and_lower() {
+        let op = StringNormalizer {
+            case_change: Some(CaseChange::Lower),
+            is_case_sensitive: false,
+            stopwords: strings(&["the", "a"]),
+            len: Symbol::new('w'),
+        };
+        let input = tensor1(&strings(&["The", "Quick", "fox", "A"])).into_shape(&[1, 4]).unwrap();
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor1(&strings(&["quick", "fox"])).into_shape(&[1, 2]).unwrap());
+    }
+
+    #[test]
+    fn all_removed() {
+        let op = StringNormalizer {
+            case_change: None,
+            is_case_sensitive: true,
+            stopwords: strings(&["a", "b"]),
+            len: Symbol::new('w'),
+        };
+        let input = tensor1(&strings(&["a", "b"]));
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor1(&strings(&[""])));
+    }
+}
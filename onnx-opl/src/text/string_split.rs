@@ -0,0 +1,156 @@
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive("tract_onnx_string_split", &parameters(), load);
+    registry.register_dumper(TypeId::of::<StringSplit>(), dump);
+}
+
+/// Split each string of the input into substrings.
+///
+/// First output has an extra trailing axis of length `len` (the maximum
+/// number of substrings), padded with empty strings. Second output is the
+/// number of substrings of each input string.
+///
+/// An empty delimiter splits on runs of whitespace, ignoring leading and
+/// trailing whitespace.
+#[derive(Clone, Debug, Hash)]
+pub struct StringSplit {
+    pub delimiter: String,
+    pub maxsplit: Option<usize>,
+    pub len: Symbol,
+}
+
+impl_dyn_hash!(StringSplit);
+
+impl StringSplit {
+    fn split<'s>(&self, s: &'s str) -> Vec<&'s str> {
+        let maxsplit = self.maxsplit.unwrap_or(usize::MAX);
+        if !self.delimiter.is_empty() {
+            return s.splitn(maxsplit.saturating_add(1), &*self.delimiter).collect();
+        }
+        let mut pieces = vec![];
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            if pieces.len() == maxsplit {
+                pieces.push(rest);
+                break;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            pieces.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        pieces
+    }
+}
+
+impl Op for StringSplit {
+    fn name(&self) -> Cow<str> {
+        "StringSplit".into()
+    }
+
+    op_onnx!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for StringSplit {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let pieces: Vec<Vec<&str>> =
+            input.as_slice::<String>()?.iter().map(|s| self.split(s)).collect();
+        let len = pieces.iter().map(|p| p.len()).max().unwrap_or(0);
+        let mut shape: TVec<usize> = input.shape().into();
+        let counts = tract_ndarray::ArrayD::from_shape_vec(
+            &*shape,
+            pieces.iter().map(|p| p.len() as i64).collect(),
+        )?;
+        shape.push(len);
+        let mut output = tract_ndarray::ArrayD::<String>::default(&*shape);
+        for (row, pieces) in output.as_slice_mut().unwrap().chunks_mut(len.max(1)).zip(&pieces) {
+            for (o, p) in row.iter_mut().zip(pieces) {
+                *o = p.to_string();
+            }
+        }
+        Ok(tvec!(output.into_arc_tensor(), counts.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for StringSplit {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        if inputs[0].datum_type != String::datum_type() {
+            bail!("StringSplit expects strings, got {:?}", inputs[0])
+        }
+        let mut shape = inputs[0].shape.to_tvec();
+        let counts = TypedFact::dt_shape(i64::datum_type(), &*shape);
+        shape.push(self.len.into());
+        Ok(tvec!(TypedFact::dt_shape(String::datum_type(), shape), counts))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::String.tensor().named("input"),
+        TypeName::String.named("delimiter").default(""),
+        TypeName::Integer.named("maxsplit").default(-1),
+    ]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<StringSplit>().context("wrong op")?;
+    Ok(Some(invocation(
+        "tract_onnx_string_split",
+        &[input],
+        &[
+            ("delimiter", string(&op.delimiter)),
+            ("maxsplit", numeric(op.maxsplit.map(|m| m as i64).unwrap_or(-1))),
+        ],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let delimiter = invocation.named_arg_as(builder, "delimiter")?;
+    let maxsplit: i64 = invocation.named_arg_as(builder, "maxsplit")?;
+    let maxsplit = if maxsplit < 0 { None } else { Some(maxsplit as usize) };
+    builder.wire(StringSplit { delimiter, maxsplit, len: Symbol::new('t') }, &[input])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(delimiter: &str, maxsplit: Option<usize>, s: &str) -> Vec<String> {
+        let op = StringSplit { delimiter: delimiter.to_string(), maxsplit, len: Symbol::new('t') };
+        op.split(s).into_iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn split_delimiter() {
+        assert_eq!(split(",", None, "a,,b"), vec!["a", "", "b"]);
+        assert_eq!(split(",", Some(1), "a,b,c"), vec!["a", "b,c"]);
+        assert_eq!(split("-", None, ""), vec![""]);
+    }
+
+    #[test]
+    fn split_whitespace() {
+        assert_eq!(split("", None, "  a  b c "), vec!["a", "b", "c"]);
+        assert_eq!(split("", Some(1), " a  b c "), vec!["a", "b c "]);
+        assert!(split("", None, "   ").is_empty());
+    }
+
+    #[test]
+    fn split_pads() {
+        let op = StringSplit { delimiter: " ".to_string(), maxsplit: None, len: Symbol::new('t') };
+        let input = tensor1(&["a b".to_string(), "c".to_string()]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        let strings = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(*output[0], tensor1(&strings(&["a", "b", "c", ""])).into_shape(&[2, 2]).unwrap());
+        assert_eq!(*output[1], tensor1(&[2i64, 1]));
+    }
+}
@@ -0,0 +1,316 @@
+use std::hash::Hash;
+use tract_nnef::internal::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_primitive("tract_onnx_tfidf_vectorizer", &parameters(), load);
+    registry.register_dumper(TypeId::of::<TfIdfVectorizer>(), dump);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TfIdfMode {
+    Tf,
+    Idf,
+    TfIdf,
+}
+
+pub fn parse_mode(s: &str) -> TractResult<TfIdfMode> {
+    match s {
+        "TF" => Ok(TfIdfMode::Tf),
+        "IDF" => Ok(TfIdfMode::Idf),
+        "TFIDF" => Ok(TfIdfMode::TfIdf),
+        _ => bail!("Invalid TfIdfVectorizer mode: {}", s),
+    }
+}
+
+fn format_mode(mode: TfIdfMode) -> &'static str {
+    match mode {
+        TfIdfMode::Tf => "TF",
+        TfIdfMode::Idf => "IDF",
+        TfIdfMode::TfIdf => "TFIDF",
+    }
+}
+
+/// Count n-grams of a [C] or [N, C] tensor of i64 or strings.
+///
+/// The pool lists the n-grams to look for, grouped by length: n-grams of
+/// length n start at `ngram_counts[n - 1]` in the pool. Each n-gram of the
+/// pool is mapped to an output column by `ngram_indexes`.
+#[derive(Clone, Debug, Hash)]
+pub struct TfIdfVectorizer {
+    pub mode: TfIdfMode,
+    pub min_gram_length: usize,
+    pub max_gram_length: usize,
+    pub max_skip_count: usize,
+    pub pool: Arc<Tensor>,
+    pub ngram_counts: TVec<usize>,
+    pub ngram_indexes: TVec<usize>,
+    pub weights: Option<Arc<Tensor>>,
+}
+
+impl_dyn_hash!(TfIdfVectorizer);
+
+impl TfIdfVectorizer {
+    pub fn new(
+        mode: TfIdfMode,
+        min_gram_length: usize,
+        max_gram_length: usize,
+        max_skip_count: usize,
+        pool: Arc<Tensor>,
+        ngram_counts: TVec<usize>,
+        ngram_indexes: TVec<usize>,
+        weights: Option<Arc<Tensor>>,
+    ) -> TractResult<TfIdfVectorizer> {
+        if min_gram_length == 0 || min_gram_length > max_gram_length {
+            bail!("Invalid n-gram lengths: {}..={}", min_gram_length, max_gram_length)
+        }
+        if pool.rank() != 1 {
+            bail!("Pool must be a vector, got {:?}", pool)
+        }
+        let op = TfIdfVectorizer {
+            mode,
+            min_gram_length,
+            max_gram_length,
+            max_skip_count,
+            pool,
+            ngram_counts,
+            ngram_indexes,
+            weights,
+        };
+        let ngrams = op.ngrams()?;
+        if ngrams.len() != op.ngram_indexes.len() {
+            bail!(
+                "Pool holds {} n-grams, but ngram_indexes has {} items",
+                ngrams.len(),
+                op.ngram_indexes.len()
+            )
+        }
+        if let Some(w) = &op.weights {
+            if w.len() < op.output_len() {
+                bail!("Expected {} weights, got {}", op.output_len(), w.len())
+            }
+        }
+        Ok(op)
+    }
+
+    pub fn output_len(&self) -> usize {
+        self.ngram_indexes.iter().max().map(|m| m + 1).unwrap_or(0)
+    }
+
+    /// Start and length of each n-gram in the pool.
+    fn ngrams(&self) -> TractResult<Vec<(usize, usize)>> {
+        let pool_len = self.pool.len();
+        let mut ngrams = vec![];
+        for (ix, &start) in self.ngram_counts.iter().enumerate() {
+            let n = ix + 1;
+            let end = self.ngram_counts.get(ix + 1).cloned().unwrap_or(pool_len);
+            if start > end || end > pool_len || (end - start) % n != 0 {
+                bail!("Inconsistent ngram_counts {:?} for pool of {}", self.ngram_counts, pool_len)
+            }
+            ngrams.extend((start..end).step_by(n).map(|s| (s, n)));
+        }
+        Ok(ngrams)
+    }
+
+    fn eval_t<T: Datum + Hash + Eq>(&self, input: &Tensor) -> TractResult<Tensor> {
+        let pool = self.pool.as_slice::<T>()?;
+        let mut index: HashMap<Vec<&T>, usize> = HashMap::default();
+        for ((start, n), &output_ix) in self.ngrams()?.into_iter().zip(self.ngram_indexes.iter()) {
+            if n >= self.min_gram_length && n <= self.max_gram_length {
+                index.insert(pool[start..][..n].iter().collect(), output_ix);
+            }
+        }
+        let input = input.to_array_view::<T>()?;
+        let rows = if input.ndim() == 1 { 1 } else { input.shape()[0] };
+        let row_len = input.shape()[input.ndim() - 1];
+        let items = input.as_slice().context("Expected a contiguous input")?;
+        let output_len = self.output_len();
+        let mut output = tract_ndarray::Array2::<f32>::zeros((rows, output_len));
+        let mut ngram = vec![];
+        for (row, mut counts) in items.chunks(row_len.max(1)).zip(output.outer_iter_mut()) {
+            for n in self.min_gram_length..=self.max_gram_length {
+                for skip in 0..=self.max_skip_count {
+                    // unigrams do not depend on the skip count
+                    if n == 1 && skip > 0 {
+                        break;
+                    }
+                    let span = (n - 1) * (skip + 1) + 1;
+                    for start in 0..(row.len() + 1).saturating_sub(span) {
+                        ngram.clear();
+                        ngram.extend(row[start..].iter().step_by(skip + 1).take(n));
+                        if let Some(&ix) = index.get(&ngram) {
+                            counts[ix] += 1.0;
+                        }
+                    }
+                }
+            }
+        }
+        let weights = self.weights.as_ref().map(|w| w.as_slice::<f32>()).transpose()?;
+        for mut row in output.outer_iter_mut() {
+            for (ix, count) in row.iter_mut().enumerate() {
+                let weight = weights.map(|w| w[ix]).unwrap_or(1.0);
+                *count = match self.mode {
+                    TfIdfMode::Tf => *count,
+                    TfIdfMode::Idf if *count > 0.0 => weight,
+                    TfIdfMode::Idf => 0.0,
+                    TfIdfMode::TfIdf => *count * weight,
+                }
+            }
+        }
+        let mut output = output.into_tensor();
+        if input.ndim() == 1 {
+            output = output.into_shape(&[output_len])?;
+        }
+        Ok(output)
+    }
+}
+
+impl Op for TfIdfVectorizer {
+    fn name(&self) -> Cow<str> {
+        "TfIdfVectorizer".into()
+    }
+
+    op_onnx!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for TfIdfVectorizer {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = match self.pool.datum_type() {
+            DatumType::I64 => self.eval_t::<i64>(&*input.cast_to::<i64>()?)?,
+            DatumType::String => self.eval_t::<String>(&input)?,
+            dt => bail!("Unsupported pool type {:?}", dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for TfIdfVectorizer {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let input = inputs[0];
+        if (input.datum_type == String::datum_type()) != (self.pool.datum_type() == DatumType::String)
+        {
+            bail!("Input {:?} does not match pool type {:?}", input, self.pool.datum_type())
+        }
+        let mut shape: TVec<TDim> = match input.rank() {
+            1 => tvec!(),
+            2 => tvec!(input.shape[0].clone()),
+            _ => bail!("TfIdfVectorizer expects a [C] or [N, C] input, got {:?}", input),
+        };
+        shape.push(self.output_len().into());
+        Ok(tvec!(TypedFact::dt_shape(f32::datum_type(), shape)))
+    }
+
+    as_op!();
+}
+
+fn parameters() -> Vec<Parameter> {
+    vec![
+        TypeName::Scalar.tensor().named("input"),
+        TypeName::Scalar.tensor().named("pool"),
+        TypeName::Integer.array().named("ngram_counts"),
+        TypeName::Integer.array().named("ngram_indexes"),
+        TypeName::Scalar.tensor().named("weights"),
+        TypeName::String.named("mode"),
+        TypeName::Integer.named("min_gram_length"),
+        TypeName::Integer.named("max_gram_length"),
+        TypeName::Integer.named("max_skip_count"),
+    ]
+}
+
+fn dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    use tract_nnef::ser::array;
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let op = node.op_as::<TfIdfVectorizer>().context("wrong op")?;
+    let pool = ast.konst_variable(format!("{}.pool", node.name), &op.pool)?;
+    let weights = if let Some(w) = &op.weights {
+        w.clone()
+    } else {
+        tensor1(&vec![1f32; op.output_len()]).into_arc_tensor()
+    };
+    let weights = ast.konst_variable(format!("{}.weights", node.name), &weights)?;
+    let ints = |v: &[usize]| array(v.iter().map(numeric).collect::<Vec<_>>());
+    Ok(Some(invocation(
+        "tract_onnx_tfidf_vectorizer",
+        &[input, pool],
+        &[
+            ("ngram_counts", ints(&op.ngram_counts)),
+            ("ngram_indexes", ints(&op.ngram_indexes)),
+            ("weights", (*weights).clone()),
+            ("mode", string(format_mode(op.mode))),
+            ("min_gram_length", numeric(op.min_gram_length)),
+            ("max_gram_length", numeric(op.max_gram_length)),
+            ("max_skip_count", numeric(op.max_skip_count)),
+        ],
+    )))
+}
+
+fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let pool = invocation.named_arg_as(builder, "pool")?;
+    let ngram_counts = invocation.named_arg_as(builder, "ngram_counts")?;
+    let ngram_indexes = invocation.named_arg_as(builder, "ngram_indexes")?;
+    let weights = invocation.named_arg_as(builder, "weights")?;
+    let mode: String = invocation.named_arg_as(builder, "mode")?;
+    let op = TfIdfVectorizer::new(
+        parse_mode(&mode)?,
+        invocation.named_arg_as(builder, "min_gram_length")?,
+        invocation.named_arg_as(builder, "max_gram_length")?,
+        invocation.named_arg_as(builder, "max_skip_count")?,
+        pool,
+        ngram_counts,
+        ngram_indexes,
+        Some(weights),
+    )?;
+    builder.wire(op, &[input])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // onnx test_tfidfvectorizer_tf_batch_uniandbigrams_skip5
+    #[test]
+    fn uni_and_bigrams_with_skip() {
+        let op = TfIdfVectorizer::new(
+            TfIdfMode::Tf,
+            1,
+            2,
+            5,
+            rctensor1(&[2i64, 3, 5, 4, 5, 6, 7, 8, 6, 7]),
+            tvec!(0, 4),
+            tvec!(0, 1, 2, 3, 4, 5, 6),
+            None,
+        )
+        .unwrap();
+        let input = tensor2(&[[1i32, 1, 3, 3, 3, 7], [8, 6, 7, 5, 6, 8]]);
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(
+            *output[0],
+            tensor2(&[[0f32, 3., 0., 0., 0., 0., 0.], [0., 0., 1., 0., 1., 1., 1.]])
+        );
+    }
+
+    #[test]
+    fn strings_idf() {
+        let strings = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let op = TfIdfVectorizer::new(
+            TfIdfMode::Idf,
+            1,
+            1,
+            0,
+            rctensor1(&strings(&["a", "b"])),
+            tvec!(0),
+            tvec!(1, 0),
+            Some(rctensor1(&[0.5f32, 2.0])),
+        )
+        .unwrap();
+        let input = tensor1(&strings(&["a", "a", "c"]));
+        let output = op.eval(tvec!(input.into_arc_tensor())).unwrap();
+        assert_eq!(*output[0], tensor1(&[0f32, 2.0]));
+    }
+}
@@ -35,8 +35,10 @@ pub fn optional_outputs(pb: &pb::NodeProto) -> impl Iterator<Item = Option<usize
 #[derive(Clone)]
 pub struct ParsingContext<'a> {
     pub onnx_operator_set_version: i64,
+    pub opset_versions: HashMap<String, i64>,
     pub framework: &'a Onnx,
     pub model: &'a pb::ModelProto,
+    pub model_dir: Option<&'a path::Path>,
     pub parent_graphs: Vec<&'a pb::GraphProto>,
 }
 
@@ -48,6 +50,11 @@ pub struct ParseResult {
 }
 
 impl<'a> ParsingContext<'a> {
+    /// Opset version imported by the model for `domain`, 0 if unknown.
+    pub fn opset_version(&self, domain: &str) -> i64 {
+        self.opset_versions.get(normalize_domain(domain)).cloned().unwrap_or(0)
+    }
+
     pub fn parse_graph(&self, graph: &pb::GraphProto) -> TractResult<ParseResult> {
         let mut ctx = self.clone();
         ctx.parent_graphs.push(graph);
@@ -57,7 +64,7 @@ impl<'a> ParsingContext<'a> {
         let mut initializers: HashMap<&str, Tensor> = graph
             .initializer
             .iter()
-            .map(|init| Ok((&*init.name, init.try_into()?)))
+            .map(|init| Ok((&*init.name, crate::tensor::load_tensor(init, self.model_dir)?)))
             .collect::<TractResult<_>>()?;
         for (k, v) in initializers.iter() {
             trace!("Initializer: {} {:?}", k, v);
@@ -69,13 +76,7 @@ impl<'a> ParsingContext<'a> {
                 let id = model.add_const(input.name.to_owned(), init)?;
                 outlets_by_name.insert(input.name.to_owned(), id);
             } else {
-                let fact = input.r#type.as_ref().unwrap().value.as_ref().unwrap();
-                #[allow(irrefutable_let_patterns)]
-                let fact: InferenceFact = if let pb::type_proto::Value::TensorType(fact) = fact {
-                    fact.try_into()?
-                } else {
-                    bail!("Can not parse tensor type");
-                };
+                let fact: InferenceFact = input.r#type.as_ref().unwrap().try_into()?;
                 trace!("Input: {} is a source ({:?})", input.name, fact);
                 let id = model.add_source(&*input.name, fact)?;
                 outlets_by_name.insert(input.name.to_owned(), id);
@@ -105,7 +106,12 @@ impl<'a> ParsingContext<'a> {
                 .map(|_| InferenceFact::default())
                 .collect();
             trace!("  outputs {:?}", pbnode.output);
-            let (op, closures) = match self.framework.op_register.0.get(&pbnode.op_type) {
+            let builder = self
+                .framework
+                .op_register
+                .get(&pbnode.domain, &pbnode.op_type, self.opset_version(&pbnode.domain))
+                .with_context(|| format!("Building node {} ({})", pbnode.name, pbnode.op_type))?;
+            let (op, closures) = match builder {
                 Some(builder) => (builder)(&ctx, pbnode).with_context(|| {
                     format!("Building node {} ({})", pbnode.name, pbnode.op_type)
                 })?,
@@ -152,8 +158,7 @@ impl<'a> ParsingContext<'a> {
         }
         let mut outputs = vec![];
         for output in graph.output.iter() {
-            let fact = if let Some(fact) = output.r#type.as_ref().and_then(|t| t.value.as_ref()) {
-                let pb::type_proto::Value::TensorType(fact) = fact;
+            let fact = if let Some(fact) = output.r#type.as_ref() {
                 fact.try_into()?
             } else {
                 InferenceFact::default()
@@ -169,43 +174,111 @@ impl<'a> ParsingContext<'a> {
     }
 }
 
+pub type OnnxOpBuilder =
+    fn(&ParsingContext, node: &pb::NodeProto) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>;
+
+/// Op builders, by domain and op type, each with the opset version it is
+/// valid from.
 #[derive(Clone, Default)]
-pub struct OnnxOpRegister(
-    pub  HashMap<
-        String,
-        fn(
-            &ParsingContext,
-            node: &pb::NodeProto,
-        ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>,
-    >,
-);
+pub struct OnnxOpRegister(pub HashMap<(String, String), Vec<(i64, OnnxOpBuilder)>>);
 
 impl OnnxOpRegister {
-    pub fn insert(
+    /// Register a builder for an op of the default domain, valid for all
+    /// opset versions.
+    pub fn insert(&mut self, s: &'static str, builder: OnnxOpBuilder) {
+        self.insert_since(s, 1, builder);
+    }
+
+    /// Register a builder for an op of the default domain, valid from
+    /// `since_version` until the next registered version.
+    pub fn insert_since(&mut self, s: &'static str, since_version: i64, builder: OnnxOpBuilder) {
+        self.insert_in_domain("", s, since_version, builder);
+    }
+
+    pub fn insert_in_domain(
         &mut self,
+        domain: &str,
         s: &'static str,
-        builder: fn(
-            &ParsingContext,
-            node: &pb::NodeProto,
-        ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)>,
+        since_version: i64,
+        builder: OnnxOpBuilder,
     ) {
-        self.0.insert(s.into(), builder);
+        let builders = self.0.entry((normalize_domain(domain).to_string(), s.into())).or_default();
+        builders.retain(|(since, _)| *since != since_version);
+        builders.push((since_version, builder));
+        builders.sort_by_key(|(since, _)| *since);
+    }
+
+    /// Find the builder for `op_type` in `domain` at opset `version`.
+    ///
+    /// An unknown version (0) picks the oldest builder, as models without an
+    /// opset import predate the version dispatch. Returns
+    /// `Ok(None)` if the op is not known at all, and an error if it is known,
+    /// but not for this opset.
+    pub fn get(
+        &self,
+        domain: &str,
+        op_type: &str,
+        version: i64,
+    ) -> TractResult<Option<OnnxOpBuilder>> {
+        let builders =
+            if let Some(b) = self.0.get(&(normalize_domain(domain).to_string(), op_type.into())) {
+                b
+            } else {
+                return Ok(None);
+            };
+        let builder = if version == 0 {
+            builders.first()
+        } else {
+            builders.iter().rev().find(|(since, _)| *since <= version)
+        };
+        if let Some((_, builder)) = builder {
+            Ok(Some(*builder))
+        } else if domain_is_default(domain) {
+            bail!("Unsupported opset {} for op {}", version, op_type)
+        } else {
+            bail!("Unsupported opset {} for op {}.{}", version, domain, op_type)
+        }
+    }
+}
+
+fn domain_is_default(domain: &str) -> bool {
+    domain == "" || domain == "ai.onnx"
+}
+
+fn normalize_domain(domain: &str) -> &str {
+    if domain_is_default(domain) {
+        ""
+    } else {
+        domain
     }
 }
 
 #[derive(Clone, Default)]
 pub struct Onnx {
     pub op_register: OnnxOpRegister,
+    pub dumper_register: crate::ser::OnnxDumperRegister,
 }
 
 impl Onnx {
     pub fn parse(&self, proto: &pb::ModelProto) -> TractResult<ParseResult> {
-        let onnx_operator_set_version = proto
+        self.parse_with_dir(proto, None)
+    }
+
+    /// Parse a proto model.
+    ///
+    /// `model_dir` is the directory containing the model file, used to
+    /// resolve tensors stored as external data.
+    pub fn parse_with_dir(
+        &self,
+        proto: &pb::ModelProto,
+        model_dir: Option<&path::Path>,
+    ) -> TractResult<ParseResult> {
+        let opset_versions: HashMap<String, i64> = proto
             .opset_import
             .iter()
-            .find(|import| import.domain == "" || import.domain == "ai.onnx")
-            .map(|op| op.version)
-            .unwrap_or(0);
+            .map(|import| (normalize_domain(&import.domain).to_string(), import.version))
+            .collect();
+        let onnx_operator_set_version = opset_versions.get("").cloned().unwrap_or(0);
         let graph =
             proto.graph.as_ref().ok_or_else(|| anyhow!("model proto does not contain a graph"))?;
         debug!("ONNX operator set version: {:?}", onnx_operator_set_version);
@@ -219,11 +292,40 @@ impl Onnx {
         let ctx = ParsingContext {
             framework: self,
             model: proto,
+            model_dir,
             parent_graphs: vec![],
             onnx_operator_set_version,
+            opset_versions,
         };
         ctx.parse_graph(graph)
     }
+
+    /// Translate a proto model, resolving external data relative to `model_dir`.
+    pub fn model_for_proto_model_with_dir(
+        &self,
+        proto: &pb::ModelProto,
+        model_dir: Option<&path::Path>,
+    ) -> TractResult<InferenceModel> {
+        let ParseResult { model, unresolved_inputs, .. } = self.parse_with_dir(proto, model_dir)?;
+        if unresolved_inputs.len() > 0 {
+            bail!("Could not resolve inputs at top-level: {:?}", unresolved_inputs)
+        }
+        Ok(model)
+    }
+
+    /// Translate a typed model of core ops to a proto model.
+    ///
+    /// Fails on the first node that has no registered ONNX dumper.
+    pub fn proto_model_for_typed_model(&self, model: &TypedModel) -> TractResult<pb::ModelProto> {
+        crate::ser::to_proto_model(self, model)
+    }
+
+    /// Serialize a typed model of core ops to ONNX.
+    pub fn write(&self, model: &TypedModel, mut w: impl std::io::Write) -> TractResult<()> {
+        let proto = self.proto_model_for_typed_model(model)?;
+        w.write_all(&proto.encode_to_vec())?;
+        Ok(())
+    }
 }
 
 impl Framework<pb::ModelProto, InferenceModel> for Onnx {
@@ -243,10 +345,114 @@ impl Framework<pb::ModelProto, InferenceModel> for Onnx {
     }
 
     fn model_for_proto_model(&self, proto: &pb::ModelProto) -> TractResult<InferenceModel> {
-        let ParseResult { model, unresolved_inputs, .. } = self.parse(proto)?;
-        if unresolved_inputs.len() > 0 {
-            bail!("Could not resolve inputs at top-level: {:?}", unresolved_inputs)
+        self.model_for_proto_model_with_dir(proto, None)
+    }
+
+    fn model_for_path(&self, p: impl AsRef<path::Path>) -> TractResult<InferenceModel> {
+        let proto = self.proto_model_for_path(p.as_ref())?;
+        self.model_for_proto_model_with_dir(&proto, p.as_ref().parent())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pb::*;
+
+    fn value_info(name: &str, fact: Option<TypedFact>) -> ValueInfoProto {
+        let r#type = fact.map(|f| (&f).try_into().unwrap());
+        ValueInfoProto { name: name.into(), r#type, ..ValueInfoProto::default() }
+    }
+
+    fn node(domain: &str, op_type: &str, input: &[&str], output: &str) -> NodeProto {
+        NodeProto {
+            domain: domain.into(),
+            op_type: op_type.into(),
+            input: input.iter().map(|s| s.to_string()).collect(),
+            output: vec![output.into()],
+            ..NodeProto::default()
         }
-        Ok(model)
+    }
+
+    fn model(opsets: &[(&str, i64)], nodes: Vec<NodeProto>, output: &str) -> ModelProto {
+        let a = Tensor::from(tensor2(&[[1f32, 2.], [3., 4.]]));
+        let mut a: TensorProto = (&a).try_into().unwrap();
+        a.name = "a".into();
+        let graph = GraphProto {
+            node: nodes,
+            initializer: vec![a],
+            input: vec![value_info("x", Some(TypedFact::dt_shape(f32::datum_type(), &[1, 2])))],
+            output: vec![value_info(output, None)],
+            ..GraphProto::default()
+        };
+        ModelProto {
+            opset_import: opsets
+                .iter()
+                .map(|(domain, version)| OperatorSetIdProto {
+                    domain: domain.to_string(),
+                    version: *version,
+                })
+                .collect(),
+            graph: Some(graph),
+            ..ModelProto::default()
+        }
+    }
+
+    #[test]
+    fn dispatch_on_opset_version() -> TractResult<()> {
+        let onnx = crate::onnx();
+        let known = |op: &str, domain: &str, version: i64| {
+            onnx.op_register.get(domain, op, version).map(|b| b.is_some())
+        };
+        assert!(known("Squeeze", "", 11)?);
+        assert!(known("Squeeze", "ai.onnx", 13)?);
+        assert!(!known("NotAnOp", "", 13)?);
+        assert!(known("ZipMap", "ai.onnx.ml", 1)?);
+        assert!(!known("ZipMap", "", 13)?);
+        let err = known("Pad", "", 1).unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported opset 1 for op Pad");
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_domains() -> TractResult<()> {
+        let mut fused = node("com.microsoft", "FusedMatMul", &["x", "a"], "y");
+        fused.attribute.push(AttributeProto {
+            name: "alpha".into(),
+            r#type: attribute_proto::AttributeType::Float as i32,
+            f: 2.,
+            ..AttributeProto::default()
+        });
+        let mut squeeze = node("", "Squeeze", &["y"], "z");
+        squeeze.attribute.push(AttributeProto {
+            name: "axes".into(),
+            r#type: attribute_proto::AttributeType::Ints as i32,
+            ints: vec![0],
+            ..AttributeProto::default()
+        });
+        let proto = model(&[("", 11), ("com.microsoft", 1)], vec![fused, squeeze], "z");
+        let model =
+            crate::onnx().model_for_proto_model(&proto)?.into_optimized()?.into_runnable()?;
+        let result = model.run(tvec!(tensor2(&[[1f32, 1.]])))?;
+        assert_eq!(*result[0], tensor1(&[8f32, 12.]));
+        Ok(())
+    }
+
+    #[test]
+    fn no_opset_import() -> TractResult<()> {
+        let matmul = node("", "MatMul", &["x", "a"], "y");
+        let mut squeeze = node("", "Squeeze", &["y"], "z");
+        squeeze.attribute.push(AttributeProto {
+            name: "axes".into(),
+            r#type: attribute_proto::AttributeType::Ints as i32,
+            ints: vec![0],
+            ..AttributeProto::default()
+        });
+        let proto = model(&[], vec![matmul, squeeze], "z");
+        let model =
+            crate::onnx().model_for_proto_model(&proto)?.into_optimized()?.into_runnable()?;
+        let result = model.run(tvec!(tensor2(&[[1f32, 1.]])))?;
+        assert_eq!(*result[0], tensor1(&[4f32, 6.]));
+        Ok(())
     }
 }
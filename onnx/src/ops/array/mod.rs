@@ -2,9 +2,13 @@ mod compress;
 mod nonzero;
 mod one_hot;
 mod pad;
+mod reverse_sequence;
 mod slice;
 mod split;
 mod squeeze;
+mod topk;
+mod trilu;
+mod unique;
 mod unsqueeze;
 
 use tract_hir::internal::*;
@@ -14,7 +18,7 @@ use crate::model::{OnnxOpRegister, ParsingContext};
 use crate::pb::*;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
-    reg.insert("ArrayFeatureExtractor", array_feature_extractor);
+    reg.insert_in_domain(super::ml::DOMAIN, "ArrayFeatureExtractor", 1, array_feature_extractor);
     reg.insert("Compress", compress::compress);
     reg.insert("Concat", concat);
     reg.insert("ConstantLike", constant_like);
@@ -28,19 +32,28 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("NonZero", |_, _| Ok((Box::new(nonzero::NonZero::non_zero()), vec![])));
     reg.insert("OneHot", one_hot::one_hot);
     reg.insert("Range", |_, _| Ok((Box::new(array::Range::default()), vec![])));
-    reg.insert("Pad", pad::pad);
+    reg.insert_since("Pad", 2, pad::pad_2);
+    reg.insert_since("Pad", 11, pad::pad_11);
     reg.insert("Reshape", |_, _| Ok((expand(array::Reshape::default()), vec![])));
+    reg.insert("ReverseSequence", reverse_sequence::reverse_sequence);
     reg.insert("Scatter", scatter_elements);
     reg.insert("ScatterElements", scatter_elements);
     reg.insert("ScatterND", |_, _| Ok((Box::new(array::ScatterNd), vec![])));
     reg.insert("Shape", |_, _| Ok((expand(array::Shape::new(DatumType::I64)), vec![])));
     reg.insert("Size", |_, _| Ok((expand(array::Size::new(DatumType::I64)), vec![])));
-    reg.insert("Slice", slice::slice);
-    reg.insert("Split", split::split);
-    reg.insert("Squeeze", squeeze::squeeze);
+    reg.insert_since("Slice", 1, slice::slice1);
+    reg.insert_since("Slice", 10, slice::slice10);
+    reg.insert_since("Split", 1, split::split1);
+    reg.insert_since("Split", 13, split::split13);
+    reg.insert_since("Squeeze", 1, squeeze::squeeze1);
+    reg.insert_since("Squeeze", 13, squeeze::squeeze13);
     reg.insert("Tile", |_, _| Ok((expand(array::Tile::default()), vec![])));
+    reg.insert("TopK", topk::topk);
     reg.insert("Transpose", transpose);
-    reg.insert("Unsqueeze", unsqueeze::unsqueeze);
+    reg.insert("Trilu", trilu::trilu);
+    reg.insert("Unique", unique::unique);
+    reg.insert_since("Unsqueeze", 1, unsqueeze::unsqueeze1);
+    reg.insert_since("Unsqueeze", 13, unsqueeze::unsqueeze13);
 }
 
 pub fn array_feature_extractor(
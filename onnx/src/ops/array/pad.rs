@@ -3,17 +3,6 @@ use crate::pb::*;
 use tract_hir::internal::*;
 use tract_hir::ops::array;
 
-pub fn pad(
-    ctx: &ParsingContext,
-    node: &NodeProto,
-) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    match ctx.onnx_operator_set_version {
-        2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 => pad_2(ctx, node),
-        v if v >= 10 => pad_11(ctx, node),
-        _ => bail!("Unsupported operator set for Pad operator"),
-    }
-}
-
 pub fn pad_mode(node: &NodeProto) -> TractResult<array::PadMode> {
     let value: f32 = node.get_attr_opt("value")?.unwrap_or(0.0);
     let mode = match node.get_attr_opt("mode")? {
@@ -0,0 +1,34 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::array::ReverseSequence;
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn reverse_sequence(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let batch_axis = node.get_attr_opt("batch_axis")?.unwrap_or(1);
+    let time_axis = node.get_attr_opt("time_axis")?.unwrap_or(0);
+    if batch_axis > 1 || time_axis > 1 || batch_axis == time_axis {
+        bail!("ReverseSequence batch_axis and time_axis must be 0 and 1")
+    }
+    Ok((inference_wrap(ReverseSequence::new(batch_axis, time_axis), 1, rules), vec![]))
+}
+
+fn rules<'r, 'p, 's>(
+    op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    let op = op.downcast_ref::<ReverseSequence>().unwrap();
+    check_input_arity(&inputs, 2)?;
+    check_output_arity(&outputs, 1)?;
+    s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+    s.equals(&inputs[0].shape, &outputs[0].shape)?;
+    s.equals(&inputs[1].datum_type, i64::datum_type())?;
+    s.equals(&inputs[1].rank, 1)?;
+    s.equals(&inputs[1].shape[0], &inputs[0].shape[op.batch_axis])?;
+    Ok(())
+}
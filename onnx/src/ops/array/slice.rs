@@ -2,19 +2,7 @@ use crate::model::ParsingContext;
 use crate::pb::*;
 use tract_hir::internal::*;
 
-pub fn slice(
-    ctx: &ParsingContext,
-    node: &NodeProto,
-) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let v = ctx.onnx_operator_set_version;
-    if v >= 1 && v < 10 {
-        slice1(ctx, node)
-    } else {
-        slice10(ctx, node)
-    }
-}
-
-fn slice1(
+pub fn slice1(
     _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
@@ -116,7 +104,7 @@ impl Expansion for Slice1 {
     }
 }
 
-fn slice10(
+pub fn slice10(
     _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
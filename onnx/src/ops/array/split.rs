@@ -4,14 +4,22 @@ use tract_hir::ops::array;
 use crate::model::ParsingContext;
 use crate::pb::*;
 
-pub fn split(
-    ctx: &ParsingContext,
+pub fn split1(
+    _ctx: &ParsingContext,
     node: &NodeProto,
     ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let axis = node.get_attr_opt("axis")?.unwrap_or(0);
-    if ctx.onnx_operator_set_version < 13 || node.input.len() == 1 {
-        let split = node.get_attr_opt_vec("split")?;
-        Ok((expand(array::Split::new(axis, node.output.len(), split)), vec![]))
+    let split = node.get_attr_opt_vec("split")?;
+    Ok((expand(array::Split::new(axis, node.output.len(), split)), vec![]))
+}
+
+pub fn split13(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+    ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(0);
+    if node.input.len() == 1 {
+        Ok((expand(array::Split::new(axis, node.output.len(), None)), vec![]))
     } else {
         Ok((expand(Split13 { axis, outputs: node.output.len() }), vec![]))
     }
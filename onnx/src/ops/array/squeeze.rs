@@ -3,16 +3,19 @@ use tract_hir::internal::*;
 use crate::model::ParsingContext;
 use crate::pb::*;
 
-pub fn squeeze(
-    ctx: &ParsingContext,
+pub fn squeeze1(
+    _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    if ctx.onnx_operator_set_version < 13 {
-        let axes = node.get_attr_vec::<i64>("axes")?.into_iter().map(|x| x as isize).collect();
-        Ok((expand(tract_hir::ops::array::Squeeze::new(Some(axes))), vec![]))
-    } else {
-        Ok((expand(Squeeze13), vec![]))
-    }
+    let axes = node.get_attr_vec::<i64>("axes")?.into_iter().map(|x| x as isize).collect();
+    Ok((expand(tract_hir::ops::array::Squeeze::new(Some(axes))), vec![]))
+}
+
+pub fn squeeze13(
+    _ctx: &ParsingContext,
+    _node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(Squeeze13), vec![]))
 }
 
 #[derive(Debug, Clone, Hash)]
@@ -0,0 +1,108 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn topk(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(-1);
+    let largest = node.get_attr_opt("largest")?.unwrap_or(true);
+    let k = if ctx.onnx_operator_set_version < 10 { Some(node.get_attr("k")?) } else { None };
+    Ok((Box::new(Topk::new(axis, largest, k, Symbol::new('k'))), vec![]))
+}
+
+#[derive(Debug, Clone, new, Hash)]
+struct Topk {
+    axis: i64,
+    largest: bool,
+    k: Option<i64>,
+    fallback_k: Symbol,
+}
+
+impl_dyn_hash!(Topk);
+
+impl Topk {
+    fn core_op(&self, rank: usize) -> tract_core::ops::array::TopK {
+        let axis = if self.axis < 0 { self.axis + rank as i64 } else { self.axis } as usize;
+        tract_core::ops::array::TopK::new(axis, self.largest, self.fallback_k.to_dim())
+    }
+}
+
+impl Op for Topk {
+    fn name(&self) -> Cow<str> {
+        "TopK".into()
+    }
+
+    op_onnx!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for Topk {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let k = if let Some(k) = self.k { rctensor1(&[k]) } else { inputs[1].clone() };
+        self.core_op(inputs[0].rank()).eval(tvec!(inputs[0].clone(), k))
+    }
+}
+
+impl InferenceRulesOp for Topk {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1 + self.k.is_none() as usize)?;
+        check_output_arity(&outputs, 2)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&outputs[1].datum_type, i64::datum_type())?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.equals(&inputs[0].rank, &outputs[1].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            let axis = if self.axis < 0 { self.axis + rank } else { self.axis } as usize;
+            for ix in 0..rank as usize {
+                if ix != axis {
+                    s.equals(&inputs[0].shape[ix], &outputs[0].shape[ix])?;
+                }
+                s.equals(&outputs[0].shape[ix], &outputs[1].shape[ix])?;
+            }
+            if let Some(k) = self.k {
+                s.equals(&outputs[0].shape[axis], k.to_dim())?;
+            } else {
+                s.given(&inputs[1].value, move |s, k| {
+                    let k = k.cast_to_scalar::<i64>()?;
+                    s.equals(&outputs[0].shape[axis], k.to_dim())
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let input = mapping[&node.inputs[0]];
+        let k = if let Some(k) = self.k {
+            target.add_const(format!("{}.k", node.name), rctensor1(&[k]))?
+        } else {
+            mapping[&node.inputs[1]]
+        };
+        let op = self.core_op(target.outlet_fact(input)?.rank());
+        target.wire_node(&*node.name, op, &[input, k])
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+
+    as_op!();
+}
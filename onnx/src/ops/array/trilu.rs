@@ -0,0 +1,59 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::array::Trilu as CoreTrilu;
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn trilu(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let upper = node.get_attr_opt("upper")?.unwrap_or(true);
+    Ok((expand(Trilu(upper)), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+struct Trilu(bool);
+
+impl_dyn_hash!(Trilu);
+
+impl Expansion for Trilu {
+    fn name(&self) -> Cow<str> {
+        "Trilu".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        if inputs.len() != 1 && inputs.len() != 2 {
+            bail!("Trilu expects one or two inputs, got {}", inputs.len())
+        }
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        if inputs.len() == 2 {
+            s.equals(&inputs[1].datum_type, i64::datum_type())?;
+            s.equals(&inputs[1].rank, 0)?;
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let k = if let Some(k) = inputs.get(1) {
+            *k
+        } else {
+            model.add_const(format!("{}.k", prefix), rctensor0(0i64))?
+        };
+        model.wire_node(prefix, CoreTrilu::new(self.0), &[inputs[0], k])
+    }
+}
@@ -0,0 +1,69 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::array::Unique as CoreUnique;
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn unique(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?;
+    let sorted = node.get_attr_opt("sorted")?.unwrap_or(true);
+    Ok((expand(Unique { axis, sorted }), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+struct Unique {
+    axis: Option<i64>,
+    sorted: bool,
+}
+
+impl_dyn_hash!(Unique);
+
+impl Expansion for Unique {
+    fn name(&self) -> Cow<str> {
+        "Unique".into()
+    }
+
+    op_onnx!();
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(4)
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        for output in &outputs[1..] {
+            s.equals(&output.datum_type, i64::datum_type())?;
+            s.equals(&output.rank, 1)?;
+        }
+        if let Some(axis) = self.axis {
+            s.equals(&inputs[0].rank, &outputs[0].rank)?;
+            s.given(&inputs[0].rank, move |s, rank| {
+                let axis = if axis < 0 { axis + rank } else { axis } as usize;
+                s.equals(&outputs[2].shape[0], &inputs[0].shape[axis])
+            })?;
+        } else {
+            s.equals(&outputs[0].rank, 1)?;
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as i64;
+        let axis = self.axis.map(|axis| if axis < 0 { axis + rank } else { axis } as usize);
+        model.wire_node(prefix, CoreUnique::new(axis, self.sorted, Symbol::new('u')), inputs)
+    }
+}
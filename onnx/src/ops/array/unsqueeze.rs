@@ -4,16 +4,19 @@ use tract_hir::ops::array;
 use crate::model::ParsingContext;
 use crate::pb::*;
 
-pub fn unsqueeze(
-    ctx: &ParsingContext,
+pub fn unsqueeze1(
+    _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    if ctx.onnx_operator_set_version < 13 {
-        let axes = node.get_attr_vec::<i64>("axes")?.into_iter().map(|x| x as isize).collect();
-        Ok((expand(array::AddDims::new(axes)), vec![]))
-    } else {
-        Ok((expand(Unsqueeze13), vec![]))
-    }
+    let axes = node.get_attr_vec::<i64>("axes")?.into_iter().map(|x| x as isize).collect();
+    Ok((expand(array::AddDims::new(axes)), vec![]))
+}
+
+pub fn unsqueeze13(
+    _ctx: &ParsingContext,
+    _node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(Unsqueeze13), vec![]))
 }
 
 #[derive(Debug, Clone, Hash)]
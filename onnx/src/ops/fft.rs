@@ -0,0 +1,506 @@
+use crate::model::{optional_inputs, OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_core::ops::array::{Pad, PadMode, Slice};
+use tract_core::ops::fft::{Fft, Stft};
+use tract_core::ops::math::{ComplexToInnerDim, InnerDimToComplex};
+use tract_hir::internal::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("DFT", dft);
+    reg.insert("STFT", stft);
+    reg.insert("HannWindow", |_, node| window(node, WindowKind::Hann));
+    reg.insert("HammingWindow", |_, node| window(node, WindowKind::Hamming));
+    reg.insert("BlackmanWindow", |_, node| window(node, WindowKind::Blackman));
+    reg.insert("MelWeightMatrix", mel_weight_matrix);
+}
+
+fn dft(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(1);
+    let inverse = node.get_attr_opt("inverse")?.unwrap_or(0i64) != 0;
+    let onesided = node.get_attr_opt("onesided")?.unwrap_or(0i64) != 0;
+    if inverse && onesided {
+        bail!("DFT: onesided inverse transforms are not supported")
+    }
+    let mut inputs = optional_inputs(node).skip(1);
+    let op = Dft {
+        axis,
+        inverse,
+        onesided,
+        optional_dft_length_input: inputs.next().unwrap(),
+        optional_axis_input: inputs.next().unwrap(),
+    };
+    Ok((expand(op), vec![]))
+}
+
+fn stft(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let onesided = node.get_attr_opt("onesided")?.unwrap_or(1i64) != 0;
+    let mut inputs = optional_inputs(node).skip(2);
+    let op = StftExpansion {
+        onesided,
+        optional_window_input: inputs.next().unwrap(),
+        optional_frame_length_input: inputs.next().unwrap(),
+    };
+    Ok((expand(op), vec![]))
+}
+
+fn window(node: &NodeProto, kind: WindowKind) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let periodic = node.get_attr_opt("periodic")?.unwrap_or(1i64) != 0;
+    let datum_type = node.get_attr_opt("output_datatype")?.unwrap_or(DatumType::F32);
+    Ok((expand(Window { kind, periodic, datum_type }), vec![]))
+}
+
+fn mel_weight_matrix(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let datum_type = node.get_attr_opt("output_datatype")?.unwrap_or(DatumType::F32);
+    Ok((expand(MelWeightMatrix { datum_type }), vec![]))
+}
+
+fn konst<'a>(model: &'a TypedModel, input: OutletId, what: &str) -> TractResult<&'a Arc<Tensor>> {
+    model.outlet_fact(input)?.konst.as_ref().with_context(|| format!("{} must be a constant", what))
+}
+
+/// Make sure the trailing axis holds a real and an imaginary part, padding
+/// real signals with zeros.
+fn wire_complex_pairs(
+    prefix: &str,
+    model: &mut TypedModel,
+    input: OutletId,
+) -> TractResult<OutletId> {
+    let fact = model.outlet_fact(input)?.clone();
+    let last = fact.shape.last().cloned().context("Expected a signal of rank 1 or more")?;
+    if last == 2.to_dim() {
+        Ok(input)
+    } else if last.is_one() {
+        let mut pads = vec![(0, 0); fact.rank()];
+        pads[fact.rank() - 1] = (0, 1);
+        let zero = Tensor::zero_dt(fact.datum_type, &[])?.into_arc_tensor();
+        let op = Pad { pads, mode: PadMode::Constant(zero) };
+        Ok(model.wire_node(format!("{}.imaginary", prefix), op, &[input])?[0])
+    } else {
+        bail!("Expected a trailing axis of 1 (real) or 2 (complex), got {:?}", fact)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+struct Dft {
+    axis: i64,
+    inverse: bool,
+    onesided: bool,
+    optional_dft_length_input: Option<usize>,
+    optional_axis_input: Option<usize>,
+}
+
+impl_dyn_hash!(Dft);
+
+impl Dft {
+    fn resolve_axis(&self, axis: i64, rank: usize) -> TractResult<usize> {
+        let resolved = if axis < 0 { axis + rank as i64 } else { axis };
+        if resolved < 0 || resolved >= rank as i64 - 1 {
+            bail!("DFT axis {} is invalid for rank {}", axis, rank)
+        }
+        Ok(resolved as usize)
+    }
+
+    fn output_len(&self, len: TDim) -> TDim {
+        if self.onesided {
+            len / 2 + 1
+        } else {
+            len
+        }
+    }
+
+    fn rules_with_axis<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+        rank: usize,
+        axis: usize,
+    ) -> InferenceResult {
+        for ix in (0..rank - 1).filter(|&ix| ix != axis) {
+            s.equals(&inputs[0].shape[ix], &outputs[0].shape[ix])?;
+        }
+        if let Some(ix) = self.optional_dft_length_input {
+            s.given(&inputs[ix].value, move |s, len| {
+                let len = len.cast_to_scalar::<i64>()?;
+                s.equals(&outputs[0].shape[axis], self.output_len(len.to_dim()))
+            })
+        } else {
+            s.given(&inputs[0].shape[axis], move |s, len| {
+                s.equals(&outputs[0].shape[axis], self.output_len(len))
+            })
+        }
+    }
+}
+
+impl Expansion for Dft {
+    fn name(&self) -> Cow<str> {
+        "DFT".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(
+            &inputs,
+            1 + self.optional_dft_length_input.is_some() as usize
+                + self.optional_axis_input.is_some() as usize,
+        )?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            let rank = rank as usize;
+            s.equals(&outputs[0].shape[rank - 1], 2.to_dim())?;
+            if let Some(ix) = self.optional_axis_input {
+                s.given(&inputs[ix].value, move |s, axis| {
+                    let axis = self.resolve_axis(axis.cast_to_scalar::<i64>()?, rank)?;
+                    self.rules_with_axis(s, inputs, outputs, rank, axis)
+                })
+            } else {
+                let axis = self.resolve_axis(self.axis, rank)?;
+                self.rules_with_axis(s, inputs, outputs, rank, axis)
+            }
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[0])?.clone();
+        let axis = if let Some(ix) = self.optional_axis_input {
+            konst(model, inputs[ix], "DFT axis")?.cast_to_scalar::<i64>()?
+        } else {
+            self.axis
+        };
+        let axis = self.resolve_axis(axis, fact.rank())?;
+        let input_len = fact.shape[axis].to_usize()?;
+        let len = if let Some(ix) = self.optional_dft_length_input {
+            konst(model, inputs[ix], "DFT length")?.cast_to_scalar::<i64>()? as usize
+        } else {
+            input_len
+        };
+        let mut wire = wire_complex_pairs(prefix, model, inputs[0])?;
+        if len < input_len {
+            wire = model.wire_node(
+                format!("{}.truncate", prefix),
+                Slice::new(axis, 0, len),
+                &[wire],
+            )?[0];
+        } else if len > input_len {
+            let mut pads = vec![(0, 0); fact.rank()];
+            pads[axis] = (0, len - input_len);
+            let zero = Tensor::zero_dt(fact.datum_type, &[])?.into_arc_tensor();
+            let op = Pad { pads, mode: PadMode::Constant(zero) };
+            wire = model.wire_node(format!("{}.zero_pad", prefix), op, &[wire])?[0];
+        }
+        wire = model.wire_node(format!("{}.complex", prefix), InnerDimToComplex, &[wire])?[0];
+        wire =
+            model.wire_node(format!("{}.fft", prefix), Fft::new(axis, self.inverse), &[wire])?[0];
+        wire = model.wire_node(format!("{}.real", prefix), ComplexToInnerDim, &[wire])?[0];
+        if self.inverse {
+            let norm = tensor0(1.0 / len as f64)
+                .cast_to_dt(fact.datum_type)?
+                .into_owned()
+                .broadcast_into_rank(fact.rank())?;
+            wire = model.wire_node(
+                format!("{}.norm", prefix),
+                tract_core::ops::math::mul::unary(norm.into_arc_tensor()),
+                &[wire],
+            )?[0];
+        }
+        if self.onesided {
+            wire = model.wire_node(
+                format!("{}.onesided", prefix),
+                Slice::new(axis, 0, len / 2 + 1),
+                &[wire],
+            )?[0];
+        }
+        Ok(tvec!(wire))
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+struct StftExpansion {
+    onesided: bool,
+    optional_window_input: Option<usize>,
+    optional_frame_length_input: Option<usize>,
+}
+
+impl_dyn_hash!(StftExpansion);
+
+impl StftExpansion {
+    fn rules_with_frame<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+        frame: i64,
+    ) -> InferenceResult {
+        let bins = if self.onesided { frame / 2 + 1 } else { frame };
+        s.equals(&outputs[0].shape[2], bins.to_dim())?;
+        s.given_2(&inputs[0].shape[1], &inputs[1].value, move |s, len, step| {
+            let step = step.cast_to_scalar::<i64>()?;
+            s.equals(&outputs[0].shape[1], (len - frame) / step + 1)
+        })
+    }
+}
+
+impl Expansion for StftExpansion {
+    fn name(&self) -> Cow<str> {
+        "STFT".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(
+            &inputs,
+            2 + self.optional_window_input.is_some() as usize
+                + self.optional_frame_length_input.is_some() as usize,
+        )?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, 3)?;
+        s.equals(&outputs[0].rank, 4)?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[3], 2.to_dim())?;
+        if let Some(ix) = self.optional_frame_length_input {
+            s.given(&inputs[ix].value, move |s, frame| {
+                let frame = frame.cast_to_scalar::<i64>()?;
+                self.rules_with_frame(s, inputs, outputs, frame)
+            })
+        } else if let Some(ix) = self.optional_window_input {
+            s.given(&inputs[ix].shape[0], move |s, frame| {
+                self.rules_with_frame(s, inputs, outputs, frame.to_i64()?)
+            })
+        } else {
+            bail!("STFT needs a window or a frame_length")
+        }
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let stride = konst(model, inputs[1], "STFT frame_step")?.cast_to_scalar::<i64>()? as usize;
+        let window = self
+            .optional_window_input
+            .map(|ix| konst(model, inputs[ix], "STFT window").cloned())
+            .transpose()?;
+        let frame = if let Some(ix) = self.optional_frame_length_input {
+            konst(model, inputs[ix], "STFT frame_length")?.cast_to_scalar::<i64>()? as usize
+        } else if let Some(window) = &window {
+            window.len()
+        } else {
+            bail!("STFT needs a window or a frame_length")
+        };
+        let mut wire = wire_complex_pairs(prefix, model, inputs[0])?;
+        wire = model.wire_node(format!("{}.complex", prefix), InnerDimToComplex, &[wire])?[0];
+        wire = model.wire_node(
+            format!("{}.stft", prefix),
+            Stft::new(1, frame, stride, window),
+            &[wire],
+        )?[0];
+        wire = model.wire_node(format!("{}.real", prefix), ComplexToInnerDim, &[wire])?[0];
+        if self.onesided {
+            wire = model.wire_node(
+                format!("{}.onesided", prefix),
+                Slice::new(2, 0, frame / 2 + 1),
+                &[wire],
+            )?[0];
+        }
+        Ok(tvec!(wire))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash)]
+enum WindowKind {
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+/// Generalized cosine windows. Periodic windows are computed as if they had
+/// one more sample, for use in spectral analysis.
+#[derive(Debug, Clone, Hash)]
+struct Window {
+    kind: WindowKind,
+    periodic: bool,
+    datum_type: DatumType,
+}
+
+impl_dyn_hash!(Window);
+
+impl Window {
+    fn values(&self, size: usize) -> TractResult<Tensor> {
+        let n = if self.periodic { size } else { size.saturating_sub(1) }.max(1) as f64;
+        let coefs: &[f64] = match self.kind {
+            WindowKind::Hann => &[0.5, 0.5],
+            WindowKind::Hamming => &[25. / 46., 21. / 46.],
+            WindowKind::Blackman => &[0.42, 0.5, 0.08],
+        };
+        let values = (0..size)
+            .map(|i| {
+                coefs.iter().enumerate().fold(0., |acc, (k, a)| {
+                    let sign = if k % 2 == 0 { 1. } else { -1. };
+                    acc + sign * a * (2. * std::f64::consts::PI * (k * i) as f64 / n).cos()
+                })
+            })
+            .collect::<Vec<f64>>();
+        Ok(tensor1(&values).cast_to_dt(self.datum_type)?.into_owned())
+    }
+}
+
+impl Expansion for Window {
+    fn name(&self) -> Cow<str> {
+        format!("{:?}Window", self.kind).into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, self.datum_type)?;
+        s.equals(&outputs[0].rank, 1)?;
+        s.given(&inputs[0].value, move |s, size| {
+            s.equals(&outputs[0].shape[0], size.cast_to_scalar::<i64>()?.to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let size = konst(model, inputs[0], "Window size")?.cast_to_scalar::<i64>()?;
+        let values = self.values(size.max(0) as usize)?;
+        Ok(tvec!(model.add_const(prefix, values)?))
+    }
+}
+
+/// Triangular filters mapping the bins of a onesided spectrum to the mel
+/// scale, as specified by the ONNX reference implementation.
+#[derive(Debug, Clone, Hash)]
+struct MelWeightMatrix {
+    datum_type: DatumType,
+}
+
+impl_dyn_hash!(MelWeightMatrix);
+
+impl MelWeightMatrix {
+    fn values(
+        &self,
+        num_mel_bins: usize,
+        dft_length: usize,
+        sample_rate: f64,
+        lower_edge_hertz: f64,
+        upper_edge_hertz: f64,
+    ) -> TractResult<Tensor> {
+        let num_spectrogram_bins = dft_length / 2 + 1;
+        let hz_to_mel = |hz: f64| 2595. * (1. + hz / 700.).log10();
+        let low = hz_to_mel(lower_edge_hertz);
+        let step = (hz_to_mel(upper_edge_hertz) - low) / (num_mel_bins + 1) as f64;
+        let bins = (0..num_mel_bins + 2)
+            .map(|i| {
+                let hz = 700. * (10f64.powf((low + i as f64 * step) / 2595.) - 1.);
+                ((dft_length + 1) as f64 * hz / sample_rate).floor() as usize
+            })
+            .collect::<Vec<_>>();
+        let mut output = tract_ndarray::Array2::<f64>::zeros((num_spectrogram_bins, num_mel_bins));
+        for i in 0..num_mel_bins {
+            let (left, center, right) = (bins[i], bins[i + 1], bins[i + 2]);
+            if right > num_spectrogram_bins || center >= num_spectrogram_bins {
+                bail!("MelWeightMatrix: upper edge is beyond the spectrum")
+            }
+            if center == left {
+                output[(center, i)] = 1.;
+            } else {
+                for j in left..=center {
+                    output[(j, i)] = (j - left) as f64 / (center - left) as f64;
+                }
+            }
+            for j in center..right {
+                output[(j, i)] = (right - j) as f64 / (right - center) as f64;
+            }
+        }
+        Ok(output.into_tensor().cast_to_dt(self.datum_type)?.into_owned())
+    }
+}
+
+impl Expansion for MelWeightMatrix {
+    fn name(&self) -> Cow<str> {
+        "MelWeightMatrix".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 5)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, self.datum_type)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.given(&inputs[0].value, move |s, bins| {
+            s.equals(&outputs[0].shape[1], bins.cast_to_scalar::<i64>()?.to_dim())
+        })?;
+        s.given(&inputs[1].value, move |s, len| {
+            s.equals(&outputs[0].shape[0], (len.cast_to_scalar::<i64>()? / 2 + 1).to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let num_mel_bins = konst(model, inputs[0], "num_mel_bins")?.cast_to_scalar::<i64>()?;
+        let dft_length = konst(model, inputs[1], "dft_length")?.cast_to_scalar::<i64>()?;
+        let sample_rate = konst(model, inputs[2], "sample_rate")?.cast_to_scalar::<f64>()?;
+        let lower = konst(model, inputs[3], "lower_edge_hertz")?.cast_to_scalar::<f64>()?;
+        let upper = konst(model, inputs[4], "upper_edge_hertz")?.cast_to_scalar::<f64>()?;
+        let values = self.values(
+            num_mel_bins.max(0) as usize,
+            dft_length.max(0) as usize,
+            sample_rate,
+            lower,
+            upper,
+        )?;
+        Ok(tvec!(model.add_const(prefix, values)?))
+    }
+}
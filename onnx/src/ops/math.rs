@@ -6,6 +6,7 @@ use tract_hir::ops;
 use tract_hir::ops::binary::Nary;
 
 mod clip;
+mod einsum;
 mod gemm;
 mod mat_mul_integer;
 mod pow;
@@ -29,7 +30,8 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Ceil", |_, _| Ok((Box::new(ops::math::ceil()), vec![])));
     reg.insert("Floor", |_, _| Ok((Box::new(ops::math::floor()), vec![])));
     reg.insert("Round", |_, _| Ok((Box::new(ops::math::round_half_to_even()), vec![])));
-    reg.insert("Clip", clip::clip);
+    reg.insert_since("Clip", 6, clip::clip_6);
+    reg.insert_since("Clip", 11, clip::clip_11);
 
     reg.insert("Cos", |_, _| Ok((Box::new(ops::math::cos()), vec![])));
     reg.insert("Sin", |_, _| Ok((Box::new(ops::math::sin()), vec![])));
@@ -63,6 +65,7 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("MatMulInteger", mat_mul_integer::mat_mul_integer);
     reg.insert("QLinearMatMul", mat_mul_integer::q_linear_mat_mul);
     reg.insert("Gemm", gemm::gemm);
+    reg.insert("Einsum", einsum::einsum);
 }
 
 fn isinf(
@@ -2,17 +2,6 @@ use crate::model::ParsingContext;
 use crate::pb::*;
 use tract_hir::internal::*;
 
-pub fn clip(
-    ctx: &ParsingContext,
-    node: &NodeProto,
-) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    match ctx.onnx_operator_set_version {
-        6 | 7 | 8 | 9 | 10 => clip_6(ctx, node),
-        v if v >= 10 => clip_11(ctx, node),
-        _ => bail!("Unsupported operator set for Clip operator"),
-    }
-}
-
 pub fn clip_6(
     _ctx: &ParsingContext,
     node: &NodeProto,
@@ -0,0 +1,231 @@
+use crate::model::ParsingContext;
+use crate::pb::*;
+use tract_core::ops::matmul::MatMul;
+use tract_core::ops::nn::{Reduce, Reducer};
+use tract_hir::internal::*;
+use tract_onnx_opl::einsum::{EinSum, Expr};
+
+pub fn einsum(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let equation = node.get_attr::<&str>("equation")?.to_string();
+    Ok((expand(Einsum::new(equation)), vec![]))
+}
+
+#[derive(Debug, Clone, new, Hash)]
+pub struct Einsum {
+    pub equation: String,
+}
+
+impl_dyn_hash!(Einsum);
+
+impl Expansion for Einsum {
+    fn name(&self) -> Cow<str> {
+        "Einsum".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![self.equation.clone()])
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> TractResult<()> {
+        check_output_arity(&outputs, 1)?;
+        for input in inputs {
+            s.equals(&input.datum_type, &outputs[0].datum_type)?;
+        }
+        s.given_all(inputs.iter().map(|i| &i.shape), move |s, shapes: Vec<TVec<TDim>>| {
+            let ranks: TVec<usize> = shapes.iter().map(|s| s.len()).collect();
+            let expr = Expr::parse(&self.equation, &ranks)?;
+            let shapes: TVec<&[TDim]> = shapes.iter().map(|s| &**s).collect();
+            s.equals(&outputs[0].shape, expr.output_shape(&shapes)?)
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let facts: TVec<TypedFact> =
+            inputs.iter().map(|i| model.outlet_fact(*i).cloned()).collect::<TractResult<_>>()?;
+        let ranks: TVec<usize> = facts.iter().map(|f| f.rank()).collect();
+        let expr = Expr::parse(&self.equation, &ranks)?;
+        if can_lower(&expr, &facts) {
+            Lowering { prefix, model, expr: &expr, steps: 0 }.lower(inputs)
+        } else {
+            model.wire_node(prefix, EinSum { expr }, inputs)
+        }
+    }
+}
+
+/// Lowering to MatMul and Reduce works if no operand uses a label twice (a
+/// diagonal), and if the summed labels are not broadcast.
+fn can_lower(expr: &Expr, facts: &[TypedFact]) -> bool {
+    if !facts[0].datum_type.is_float() {
+        return false;
+    }
+    if expr
+        .inputs
+        .iter()
+        .any(|labels| labels.iter().any(|c| labels.iter().filter(|d| *d == c).count() > 1))
+    {
+        return false;
+    }
+    expr.labels().iter().filter(|c| !expr.output.contains(c)).all(|c| {
+        let mut dims = expr.inputs.iter().zip(facts.iter()).filter_map(|(labels, fact)| {
+            labels.iter().position(|l| l == c).map(|ix| &fact.shape[ix])
+        });
+        let first = dims.next().unwrap();
+        dims.all(|d| d == first)
+    })
+}
+
+struct Lowering<'a> {
+    prefix: &'a str,
+    model: &'a mut TypedModel,
+    expr: &'a Expr,
+    steps: usize,
+}
+
+impl<'a> Lowering<'a> {
+    fn wire(
+        &mut self,
+        name: &str,
+        op: impl Into<Box<dyn TypedOp>>,
+        wire: &[OutletId],
+    ) -> TractResult<OutletId> {
+        self.steps += 1;
+        let name = format!("{}.{}-{}", self.prefix, name, self.steps);
+        Ok(self.model.wire_node(name, op, wire)?[0])
+    }
+
+    fn lower(mut self, inputs: &[OutletId]) -> TractResult<TVec<OutletId>> {
+        let mut operands: Vec<(OutletId, TVec<char>)> = vec![];
+        for (&input, labels) in inputs.iter().zip(self.expr.inputs.iter()) {
+            let operand = self.sum_own_labels(input, labels.clone())?;
+            operands.push(operand);
+        }
+        while operands.len() > 1 {
+            let b = operands.remove(1);
+            let a = operands.remove(0);
+            let later: TVec<char> = self
+                .expr
+                .output
+                .iter()
+                .chain(operands.iter().flat_map(|o| o.1.iter()))
+                .copied()
+                .collect();
+            let product = self.contract(a, b, &later)?;
+            operands.insert(0, product);
+        }
+        let (wire, labels) = operands.remove(0);
+        let expr = self.expr;
+        let (wire, _) = self.permute(wire, labels, &expr.output)?;
+        Ok(tvec!(wire))
+    }
+
+    /// Sum the labels only appearing in this operand and absent from the output.
+    fn sum_own_labels(
+        &mut self,
+        mut wire: OutletId,
+        mut labels: TVec<char>,
+    ) -> TractResult<(OutletId, TVec<char>)> {
+        let axes: TVec<usize> = (0..labels.len())
+            .filter(|&ix| {
+                let c = labels[ix];
+                !self.expr.output.contains(&c)
+                    && self.expr.inputs.iter().flatten().filter(|d| **d == c).count() == 1
+            })
+            .collect();
+        if axes.len() > 0 {
+            wire = self.wire("sum", Reduce::new(axes.clone(), Reducer::Sum), &[wire])?;
+            for &ax in axes.iter().rev() {
+                wire = self.wire("rm", AxisOp::Rm(ax), &[wire])?;
+                labels.remove(ax);
+            }
+        }
+        Ok((wire, labels))
+    }
+
+    fn permute(
+        &mut self,
+        mut wire: OutletId,
+        mut labels: TVec<char>,
+        order: &[char],
+    ) -> TractResult<(OutletId, TVec<char>)> {
+        for (ix, c) in order.iter().enumerate() {
+            let from = labels.iter().position(|l| l == c).unwrap();
+            if from != ix {
+                wire = self.wire("move", AxisOp::Move(from, ix), &[wire])?;
+                let c = labels.remove(from);
+                labels.insert(ix, c);
+            }
+        }
+        Ok((wire, labels))
+    }
+
+    /// Merge `len` axes starting at `at` in a single one, possibly adding it.
+    fn merge(&mut self, wire: OutletId, at: usize, len: usize) -> TractResult<OutletId> {
+        if len == 0 {
+            self.wire("add", AxisOp::Add(at), &[wire])
+        } else if len > 1 {
+            let from: TVec<TDim> = self.model.outlet_fact(wire)?.shape[at..at + len].into();
+            let to = tvec!(from.iter().product());
+            self.wire("merge", AxisOp::Reshape(at, from, to), &[wire])
+        } else {
+            Ok(wire)
+        }
+    }
+
+    /// Split the axis at `at` to `dims`, possibly removing it.
+    fn split(&mut self, wire: OutletId, at: usize, dims: TVec<TDim>) -> TractResult<OutletId> {
+        if dims.len() == 0 {
+            self.wire("rm", AxisOp::Rm(at), &[wire])
+        } else if dims.len() > 1 {
+            let from = tvec!(dims.iter().product());
+            self.wire("split", AxisOp::Reshape(at, from, dims), &[wire])
+        } else {
+            Ok(wire)
+        }
+    }
+
+    /// Contract two operands to one, as a [batch.., m, k] x [batch.., k, n]
+    /// matrix product. Labels shared by a and b are batch if needed later,
+    /// summed otherwise.
+    fn contract(
+        &mut self,
+        a: (OutletId, TVec<char>),
+        b: (OutletId, TVec<char>),
+        later: &[char],
+    ) -> TractResult<(OutletId, TVec<char>)> {
+        let batch: TVec<char> =
+            a.1.iter().filter(|c| b.1.contains(c) && later.contains(c)).copied().collect();
+        let k: TVec<char> =
+            a.1.iter().filter(|c| b.1.contains(c) && !later.contains(c)).copied().collect();
+        let m: TVec<char> = a.1.iter().filter(|c| !b.1.contains(c)).copied().collect();
+        let n: TVec<char> = b.1.iter().filter(|c| !a.1.contains(c)).copied().collect();
+        let a_order: TVec<char> = batch.iter().chain(m.iter()).chain(k.iter()).copied().collect();
+        let b_order: TVec<char> = batch.iter().chain(k.iter()).chain(n.iter()).copied().collect();
+        let (a, _) = self.permute(a.0, a.1, &a_order)?;
+        let (b, _) = self.permute(b.0, b.1, &b_order)?;
+        let m_dims: TVec<TDim> = self.model.outlet_fact(a)?.shape[batch.len()..][..m.len()].into();
+        let n_dims: TVec<TDim> = self.model.outlet_fact(b)?.shape[batch.len() + k.len()..].into();
+        let a = self.merge(a, batch.len() + m.len(), k.len())?;
+        let a = self.merge(a, batch.len(), m.len())?;
+        let b = self.merge(b, batch.len() + k.len(), n.len())?;
+        let b = self.merge(b, batch.len(), k.len())?;
+        let c = self.wire("matmul", MatMul::default(), &[a, b])?;
+        let c = self.split(c, batch.len() + 1, n_dims)?;
+        let c = self.split(c, batch.len(), m_dims)?;
+        Ok((c, batch.into_iter().chain(m.into_iter()).chain(n.into_iter()).collect()))
+    }
+}
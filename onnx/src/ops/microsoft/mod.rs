@@ -0,0 +1,87 @@
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops;
+
+mod qlinear;
+
+pub const DOMAIN: &str = "com.microsoft";
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "FusedMatMul", 1, fused_mat_mul);
+    qlinear::register_all_ops(reg);
+}
+
+fn fused_mat_mul(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let alpha = node.get_attr_opt("alpha")?.unwrap_or(1.);
+    let trans_a = node.get_attr_opt("transA")?.unwrap_or(false);
+    let trans_b = node.get_attr_opt("transB")?.unwrap_or(false);
+    for attr in &["transBatchA", "transBatchB"] {
+        if node.get_attr_opt(attr)?.unwrap_or(false) {
+            bail!("FusedMatMul {} is not supported", attr)
+        }
+    }
+    Ok((expand(FusedMatMul::new(alpha, trans_a, trans_b)), vec![]))
+}
+
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct FusedMatMul {
+    #[educe(Hash(method = "hash_f32"))]
+    alpha: f32,
+    trans_a: bool,
+    trans_b: bool,
+}
+
+impl_dyn_hash!(FusedMatMul);
+
+impl FusedMatMul {
+    fn mat_mul(&self) -> ops::matmul::MatMulInference {
+        ops::matmul::MatMulInference::default()
+            .with_a_trans(self.trans_a)
+            .with_b_trans(self.trans_b)
+    }
+}
+
+impl Expansion for FusedMatMul {
+    fn name(&self) -> Cow<str> {
+        "FusedMatMul".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 2)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &inputs[1].datum_type)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        let (trans_a, trans_b) = (self.trans_a, self.trans_b);
+        s.given_2(&inputs[0].shape, &inputs[1].shape, move |s, ashape, bshape| {
+            let (_, _, _, cshape) =
+                ops::matmul::compute_shapes(ashape, bshape, trans_a, trans_b, false)?;
+            s.equals(&outputs[0].shape, cshape)
+        })
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        if self.alpha == 1.0 {
+            return self.mat_mul().wire(name, model, inputs);
+        }
+        let wire = self.mat_mul().wire(&format!("{}.ab", name), model, inputs)?[0];
+        let alpha = tensor0(self.alpha).broadcast_into_rank(model.outlet_fact(wire)?.rank())?;
+        model.wire_node(name, ops::math::mul::unary(alpha.into_arc_tensor()), &[wire])
+    }
+}
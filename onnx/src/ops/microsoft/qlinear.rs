@@ -0,0 +1,696 @@
+use crate::model::{optional_inputs, OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::cnn::{PoolSpec, SumPool};
+use tract_hir::ops::nn::DataFormat;
+use tract_hir::tract_core::ops;
+use tract_hir::tract_core::ops::binary::wire_with_rank_broadcast;
+use tract_hir::tract_core::ops::matmul::mir_quant::clamp_and_cast_to;
+use tract_hir::tract_core::ops::quant::{quantize_linear_f32_i8, quantize_linear_f32_u8};
+
+use super::DOMAIN;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "QLinearAdd", 1, |_, node| binary(node, false));
+    reg.insert_in_domain(DOMAIN, "QLinearMul", 1, |_, node| binary(node, true));
+    reg.insert_in_domain(DOMAIN, "QLinearSigmoid", 1, sigmoid);
+    reg.insert_in_domain(DOMAIN, "QLinearLeakyRelu", 1, leaky_relu);
+    reg.insert_in_domain(DOMAIN, "QLinearAveragePool", 1, average_pool);
+    reg.insert_in_domain(DOMAIN, "QLinearGlobalAveragePool", 1, global_average_pool);
+    reg.insert_in_domain(DOMAIN, "QLinearConcat", 1, concat);
+}
+
+/// Positions of the `n` first inputs of `node`, some of them optional.
+fn slots(node: &NodeProto, n: usize) -> TVec<Option<usize>> {
+    optional_inputs(node).take(n).collect()
+}
+
+/// Scale, zero point and datum type of a quantized tensor, from its scale
+/// and optional zero point inputs. Both must be constants.
+fn qparams(
+    model: &TypedModel,
+    scale: OutletId,
+    zero_point: Option<OutletId>,
+) -> TractResult<(f32, i32, DatumType)> {
+    let scale = model
+        .outlet_fact(scale)?
+        .konst
+        .as_ref()
+        .context("scale must be a const")?
+        .cast_to_scalar::<f32>()?;
+    if let Some(zero_point) = zero_point {
+        let zero_point =
+            model.outlet_fact(zero_point)?.konst.clone().context("zero point must be a const")?;
+        Ok((scale, zero_point.cast_to_scalar::<i32>()?, zero_point.datum_type()))
+    } else {
+        Ok((scale, 0, u8::datum_type()))
+    }
+}
+
+/// Datum type of a quantized output: its zero point type, or u8.
+fn output_type<'r, 'p: 'r>(
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    output: &'p TensorProxy,
+    zero_point: Option<usize>,
+) -> InferenceResult {
+    if let Some(zero_point) = zero_point {
+        s.equals(&output.datum_type, &inputs[zero_point].datum_type)
+    } else {
+        s.equals(&output.datum_type, u8::datum_type())
+    }
+}
+
+/// Cast a quantized tensor to i32, and remove its zero point.
+fn centered(
+    model: &mut TypedModel,
+    name: &str,
+    wire: OutletId,
+    zero_point: i32,
+) -> TractResult<OutletId> {
+    let wire =
+        model.wire_node(format!("{}.cast", name), ops::cast::cast(i32::datum_type()), &[wire])?[0];
+    if zero_point == 0 {
+        return Ok(wire);
+    }
+    let rank = model.outlet_fact(wire)?.rank();
+    let zero_point = tensor0(-zero_point).broadcast_into_rank(rank)?.into_arc_tensor();
+    Ok(model.wire_node(
+        format!("{}.zero_point", name),
+        ops::math::add::unary(zero_point),
+        &[wire],
+    )?[0])
+}
+
+/// Rescale a centered i32 tensor by `scale`, broadcast to it, then add the
+/// output zero point and saturate to the output type.
+fn requantize(
+    model: &mut TypedModel,
+    name: &str,
+    wire: OutletId,
+    scale: Tensor,
+    zero_point: i32,
+    dt: DatumType,
+) -> TractResult<OutletId> {
+    let rank = model.outlet_fact(wire)?.rank();
+    let scale = scale.broadcast_into_rank(rank)?.into_arc_tensor();
+    let wire =
+        model.wire_node(format!("{}.scale", name), ops::quant::scale::unary(scale), &[wire])?[0];
+    let zero_point = tensor0(zero_point).broadcast_into_rank(rank)?.into_arc_tensor();
+    let wire = model.wire_node(
+        format!("{}.zero_point", name),
+        ops::math::add::unary(zero_point),
+        &[wire],
+    )?[0];
+    clamp_and_cast_to(model, name, dt, wire)
+}
+
+/// Subdivisions of the output step the QLinearAdd operands are rescaled to
+/// before their sum.
+const ADD_STEPS: i32 = 1 << 10;
+
+fn binary(node: &NodeProto, mul: bool) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(QLinearBinary { mul, slots: slots(node, 8) }), vec![]))
+}
+
+/// QLinearAdd and QLinearMul, computed on zero-centered values.
+#[derive(Debug, Clone, Hash)]
+pub struct QLinearBinary {
+    mul: bool,
+    slots: TVec<Option<usize>>,
+}
+
+impl_dyn_hash!(QLinearBinary);
+
+impl Expansion for QLinearBinary {
+    fn name(&self) -> Cow<str> {
+        if self.mul { "QLinearMul" } else { "QLinearAdd" }.into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, self.slots.iter().flatten().count())?;
+        check_output_arity(&outputs, 1)?;
+        let (a, b) = (&inputs[self.slots[0].unwrap()], &inputs[self.slots[3].unwrap()]);
+        output_type(s, inputs, &outputs[0], self.slots[7])?;
+        s.with(&a.shape, move |s, a_shape| {
+            s.with(&b.shape, move |s, b_shape| {
+                if let Ok(Some(c_shape)) =
+                    tract_hir::infer::helpers::infer_shape_broadcasting(&[&a_shape, &b_shape])
+                {
+                    s.equals(&outputs[0].shape, c_shape)?;
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = |ix: usize| self.slots[ix].map(|slot| inputs[slot]);
+        let (a_scale, a_zp, _) = qparams(model, input(1).unwrap(), input(2))?;
+        let (b_scale, b_zp, _) = qparams(model, input(4).unwrap(), input(5))?;
+        let (c_scale, c_zp, c_dt) = qparams(model, input(6).unwrap(), input(7))?;
+        let a = centered(model, &format!("{}.a", name), input(0).unwrap(), a_zp)?;
+        let b = centered(model, &format!("{}.b", name), input(3).unwrap(), b_zp)?;
+        if self.mul {
+            let wire = wire_with_rank_broadcast(
+                &format!("{}.mul", name),
+                model,
+                ops::math::mul::bin_typed(),
+                &[a, b],
+            )?[0];
+            let scale = tensor0(a_scale * b_scale / c_scale);
+            Ok(tvec!(requantize(model, name, wire, scale, c_zp, c_dt)?))
+        } else {
+            // operands are rescaled to a fraction of the output step, so the
+            // sum is only rounded once, to the output step
+            let mut scaled = tvec!();
+            for (wire, scale, operand) in [(a, a_scale, "a"), (b, b_scale, "b")] {
+                let rank = model.outlet_fact(wire)?.rank();
+                let scale =
+                    tensor0(scale / c_scale * ADD_STEPS as f32).broadcast_into_rank(rank)?;
+                scaled.push(
+                    model.wire_node(
+                        format!("{}.{}.scale", name, operand),
+                        ops::quant::scale::unary(scale.into_arc_tensor()),
+                        &[wire],
+                    )?[0],
+                );
+            }
+            let wire = wire_with_rank_broadcast(
+                &format!("{}.add", name),
+                model,
+                ops::math::add::bin_typed(),
+                &scaled,
+            )?[0];
+            let scale = tensor0((ADD_STEPS as f32).recip());
+            Ok(tvec!(requantize(model, name, wire, scale, c_zp, c_dt)?))
+        }
+    }
+}
+
+/// Rules for the element-wise ops taking X, X_scale, X_zero_point, Y_scale
+/// and Y_zero_point.
+fn unary_rules<'r, 'p: 'r>(
+    s: &mut Solver<'r>,
+    slots: &[Option<usize>],
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, slots.iter().flatten().count())?;
+    check_output_arity(&outputs, 1)?;
+    output_type(s, inputs, &outputs[0], slots[4])?;
+    s.equals(&inputs[0].shape, &outputs[0].shape)
+}
+
+/// Map every possible input byte through `f` applied on dequantized values,
+/// in a single lookup table.
+fn wire_lut(
+    name: &str,
+    model: &mut TypedModel,
+    inputs: &[OutletId],
+    slots: &[Option<usize>],
+    f: impl Fn(f32) -> f32,
+) -> TractResult<TVec<OutletId>> {
+    let input = |ix: usize| slots[ix].map(|slot| inputs[slot]);
+    let (x_scale, x_zp, _) = qparams(model, input(1).unwrap(), input(2))?;
+    let (y_scale, y_zp, y_dt) = qparams(model, input(3).unwrap(), input(4))?;
+    let x_dt = model.outlet_fact(inputs[0])?.datum_type;
+    if x_dt != y_dt {
+        bail!("{} input and output types must match, got {:?} and {:?}", name, x_dt, y_dt)
+    }
+    let table = (0..=255u8)
+        .map(|byte| {
+            let x = if x_dt == i8::datum_type() { byte as i8 as i32 } else { byte as i32 };
+            let y = f((x - x_zp) as f32 * x_scale);
+            if y_dt == i8::datum_type() {
+                quantize_linear_f32_i8(y, y_scale.recip(), y_zp) as u8
+            } else {
+                quantize_linear_f32_u8(y, y_scale.recip(), y_zp)
+            }
+        })
+        .collect::<Vec<u8>>();
+    let op = ops::quant::lookup_table((tract_hir::tract_core::tract_linalg::ops().lut_u8)(&table));
+    model.wire_node(name, op, &[inputs[0]])
+}
+
+fn sigmoid(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    Ok((expand(QLinearSigmoid { slots: slots(node, 5) }), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct QLinearSigmoid {
+    slots: TVec<Option<usize>>,
+}
+
+impl_dyn_hash!(QLinearSigmoid);
+
+impl Expansion for QLinearSigmoid {
+    fn name(&self) -> Cow<str> {
+        "QLinearSigmoid".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        unary_rules(s, &self.slots, inputs, outputs)
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        wire_lut(name, model, inputs, &self.slots, |x| 1.0 / (1.0 + (-x).exp()))
+    }
+}
+
+fn leaky_relu(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let alpha = node.get_attr_opt("alpha")?.unwrap_or(0.01);
+    Ok((expand(QLinearLeakyRelu { alpha, slots: slots(node, 5) }), vec![]))
+}
+
+#[derive(Debug, Clone, Educe)]
+#[educe(Hash)]
+pub struct QLinearLeakyRelu {
+    #[educe(Hash(method = "hash_f32"))]
+    alpha: f32,
+    slots: TVec<Option<usize>>,
+}
+
+impl_dyn_hash!(QLinearLeakyRelu);
+
+impl Expansion for QLinearLeakyRelu {
+    fn name(&self) -> Cow<str> {
+        "QLinearLeakyRelu".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        unary_rules(s, &self.slots, inputs, outputs)
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let alpha = self.alpha;
+        wire_lut(name, model, inputs, &self.slots, |x| if x < 0.0 { alpha * x } else { x })
+    }
+}
+
+fn data_format(node: &NodeProto) -> TractResult<DataFormat> {
+    let channels_last = node.get_attr_opt("channels_last")?.unwrap_or(false);
+    Ok(if channels_last { DataFormat::NHWC } else { DataFormat::NCHW })
+}
+
+fn average_pool(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let kernel_shape = node.get_attr_tvec("kernel_shape")?;
+    let pad = crate::ops::nn::pad(node)?;
+    let strides = crate::ops::nn::strides(node)?;
+    let count_include_pad = node.get_attr_opt("count_include_pad")?.unwrap_or(false);
+    let pool_spec = PoolSpec::new(data_format(node)?, kernel_shape, pad, None, strides, None);
+    Ok((expand(QLinearAveragePool { pool_spec, count_include_pad, slots: slots(node, 5) }), vec![]))
+}
+
+/// Average pooling of the zero-centered input, requantized once.
+#[derive(Debug, Clone, Hash)]
+pub struct QLinearAveragePool {
+    pool_spec: PoolSpec,
+    count_include_pad: bool,
+    slots: TVec<Option<usize>>,
+}
+
+impl_dyn_hash!(QLinearAveragePool);
+
+impl Expansion for QLinearAveragePool {
+    fn name(&self) -> Cow<str> {
+        "QLinearAveragePool".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, self.slots.iter().flatten().count())?;
+        check_output_arity(&outputs, 1)?;
+        output_type(s, inputs, &outputs[0], self.slots[4])?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].shape, move |s, shape| {
+            let shape = self.pool_spec.output_shape(&shape)?;
+            s.equals(&outputs[0].shape, shape.shape)
+        })
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = |ix: usize| self.slots[ix].map(|slot| inputs[slot]);
+        let (x_scale, x_zp, _) = qparams(model, input(1).unwrap(), input(2))?;
+        let (y_scale, y_zp, y_dt) = qparams(model, input(3).unwrap(), input(4))?;
+        let scale = self.window_scales(model.outlet_fact(inputs[0])?, x_scale / y_scale)?;
+        let wire = centered(model, &format!("{}.x", name), inputs[0], x_zp)?;
+        let wire = model.wire_node(
+            format!("{}.pool", name),
+            SumPool::new(self.pool_spec.clone(), self.count_include_pad, false),
+            &[wire],
+        )?[0];
+        Ok(tvec!(requantize(model, name, wire, scale, y_zp, y_dt)?))
+    }
+}
+
+impl QLinearAveragePool {
+    /// Requantization scale of the integer window sums: `scale` over the
+    /// number of values averaged at each output position.
+    fn window_scales(&self, input: &TypedFact, scale: f32) -> TractResult<Tensor> {
+        if self.count_include_pad {
+            let len = self.pool_spec.kernel_shape.iter().product::<usize>();
+            return Ok(tensor0(scale / len as f32));
+        }
+        let shape = self.pool_spec.data_format.shape(input.shape.to_tvec())?;
+        let hw = shape
+            .hw_dims()
+            .iter()
+            .map(|d| d.to_usize())
+            .collect::<TractResult<TVec<usize>>>()
+            .context("Average pooling without count_include_pad needs known spatial dims")?;
+        let ones_shape = self.pool_spec.data_format.from_n_c_hw(1, 1, hw)?.shape;
+        let ones = tract_ndarray::ArrayD::from_elem(&*ones_shape, 1f32).into_arc_tensor();
+        let counts = SumPool::new(self.pool_spec.clone(), false, false).eval(tvec!(ones))?;
+        let scales = counts[0].as_slice::<f32>()?.iter().map(|c| scale / c).collect::<Vec<_>>();
+        Tensor::from_shape(counts[0].shape(), &scales)
+    }
+}
+
+fn global_average_pool(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let data_format = data_format(node)?;
+    Ok((expand(QLinearGlobalAveragePool { data_format, slots: slots(node, 5) }), vec![]))
+}
+
+/// Global average pooling: an integer sum of the zero-centered input, the
+/// division being folded in the requantization scale.
+#[derive(Debug, Clone, Hash)]
+pub struct QLinearGlobalAveragePool {
+    data_format: DataFormat,
+    slots: TVec<Option<usize>>,
+}
+
+impl_dyn_hash!(QLinearGlobalAveragePool);
+
+impl QLinearGlobalAveragePool {
+    fn spatial_axes(&self, rank: usize) -> TVec<usize> {
+        if self.data_format == DataFormat::NHWC {
+            (1..rank - 1).collect()
+        } else {
+            (2..rank).collect()
+        }
+    }
+}
+
+impl Expansion for QLinearGlobalAveragePool {
+    fn name(&self) -> Cow<str> {
+        "QLinearGlobalAveragePool".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, self.slots.iter().flatten().count())?;
+        check_output_arity(&outputs, 1)?;
+        output_type(s, inputs, &outputs[0], self.slots[4])?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].shape, move |s, mut shape| {
+            for axis in self.spatial_axes(shape.len()) {
+                shape[axis] = 1.to_dim();
+            }
+            s.equals(&outputs[0].shape, shape)
+        })
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = |ix: usize| self.slots[ix].map(|slot| inputs[slot]);
+        let (x_scale, x_zp, _) = qparams(model, input(1).unwrap(), input(2))?;
+        let (y_scale, y_zp, y_dt) = qparams(model, input(3).unwrap(), input(4))?;
+        let shape = model.outlet_fact(inputs[0])?.shape.clone();
+        let axes = self.spatial_axes(shape.rank());
+        let count = axes
+            .iter()
+            .map(|axis| shape[*axis].to_usize())
+            .product::<TractResult<usize>>()
+            .context("QLinearGlobalAveragePool requires known spatial dimensions")?;
+        let wire = centered(model, &format!("{}.x", name), inputs[0], x_zp)?;
+        let wire = model.wire_node(
+            format!("{}.sum", name),
+            ops::nn::Reduce::new(axes, ops::nn::Reducer::Sum),
+            &[wire],
+        )?[0];
+        let scale = x_scale / (y_scale * count as f32);
+        Ok(tvec!(requantize(model, name, wire, tensor0(scale), y_zp, y_dt)?))
+    }
+}
+
+fn concat(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr("axis")?;
+    if node.input.len() < 5 || (node.input.len() - 2) % 3 != 0 {
+        bail!("QLinearConcat expects Y_scale, Y_zero_point and triples of X, X_scale, X_zero_point")
+    }
+    if node.input.iter().any(|i| i.is_empty()) {
+        bail!("QLinearConcat expects all its zero points")
+    }
+    Ok((expand(QLinearConcat { axis }), vec![]))
+}
+
+/// Concatenation, requantizing only the inputs whose quantization
+/// parameters differ from the output ones.
+#[derive(Debug, Clone, Hash)]
+pub struct QLinearConcat {
+    axis: i64,
+}
+
+impl_dyn_hash!(QLinearConcat);
+
+impl QLinearConcat {
+    fn resolve_axis(&self, rank: i64) -> TractResult<usize> {
+        let axis = if self.axis < 0 { self.axis + rank } else { self.axis };
+        if axis < 0 || axis >= rank {
+            bail!("Illegal QLinearConcat axis {} for rank {}", self.axis, rank)
+        }
+        Ok(axis as usize)
+    }
+}
+
+impl Expansion for QLinearConcat {
+    fn name(&self) -> Cow<str> {
+        "QLinearConcat".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(&outputs, 1)?;
+        let n = (inputs.len() - 2) / 3;
+        let x = move |i: usize| &inputs[2 + 3 * i];
+        s.equals(&outputs[0].datum_type, &inputs[1].datum_type)?;
+        s.equals(&outputs[0].rank, &x(0).rank)?;
+        s.equals_all((0..n).map(|i| x(i).rank.bex()).collect())?;
+        s.given(&x(0).rank, move |s, rank| {
+            let axis = self.resolve_axis(rank)?;
+            s.equals(
+                rules::expr::SumExp::new((0..n).map(|i| x(i).shape[axis].bex()).collect()),
+                &outputs[0].shape[axis],
+            )?;
+            for axis in (0..rank as usize).filter(|a| *a != axis) {
+                s.equals(&outputs[0].shape[axis], &x(0).shape[axis])?;
+                s.equals_all((0..n).map(|i| x(i).shape[axis].bex()).collect())?;
+            }
+            Ok(())
+        })
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let (y_scale, y_zp, y_dt) = qparams(model, inputs[0], Some(inputs[1]))?;
+        let mut wires = tvec!();
+        for (ix, triple) in inputs[2..].chunks(3).enumerate() {
+            let (x_scale, x_zp, x_dt) = qparams(model, triple[1], Some(triple[2]))?;
+            if (x_scale, x_zp, x_dt) == (y_scale, y_zp, y_dt) {
+                wires.push(triple[0]);
+            } else {
+                let name = format!("{}.requant-{}", name, ix);
+                let wire = centered(model, &format!("{}.x", name), triple[0], x_zp)?;
+                let scale = tensor0(x_scale / y_scale);
+                wires.push(requantize(model, &name, wire, scale, y_zp, y_dt)?);
+            }
+        }
+        let axis = self.resolve_axis(model.outlet_fact(wires[0])?.rank() as i64)?;
+        model.wire_node(name, ops::array::TypedConcat::concat_vars(axis, wires.len()), &wires)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn initializer(name: &str, t: Tensor) -> TensorProto {
+        let mut proto: TensorProto = (&t).try_into().unwrap();
+        proto.name = name.into();
+        proto
+    }
+
+    fn run(
+        op_type: &str,
+        attribute: Vec<AttributeProto>,
+        consts: Vec<TensorProto>,
+        inputs: &[&str],
+        x: Tensor,
+    ) -> Tensor {
+        let fact = TypedFact::dt_shape(x.datum_type(), x.shape());
+        let graph = GraphProto {
+            node: vec![NodeProto {
+                domain: DOMAIN.into(),
+                op_type: op_type.into(),
+                input: inputs.iter().map(|s| s.to_string()).collect(),
+                output: vec!["y".into()],
+                attribute,
+                ..NodeProto::default()
+            }],
+            initializer: consts,
+            input: vec![ValueInfoProto {
+                name: "x".into(),
+                r#type: Some((&fact).try_into().unwrap()),
+                ..ValueInfoProto::default()
+            }],
+            output: vec![ValueInfoProto { name: "y".into(), ..ValueInfoProto::default() }],
+            ..GraphProto::default()
+        };
+        let proto = ModelProto {
+            opset_import: vec![
+                OperatorSetIdProto { domain: "".into(), version: 13 },
+                OperatorSetIdProto { domain: DOMAIN.into(), version: 1 },
+            ],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let model = crate::onnx().model_for_proto_model(&proto).unwrap();
+        let model = model.into_optimized().unwrap().into_runnable().unwrap();
+        model.run(tvec!(x)).unwrap().remove(0).into_tensor()
+    }
+
+    #[test]
+    fn qlinear_add() {
+        let consts = vec![
+            initializer("a_s", tensor0(0.5f32)),
+            initializer("a_zp", tensor0(10u8)),
+            initializer("b", tensor1(&[20u8, 0])),
+            initializer("b_s", tensor0(0.25f32)),
+            initializer("b_zp", tensor0(4u8)),
+            initializer("c_s", tensor0(1f32)),
+            initializer("c_zp", tensor0(100u8)),
+        ];
+        let inputs = ["x", "a_s", "a_zp", "b", "b_s", "b_zp", "c_s", "c_zp"];
+        // (x - 10) * .5 + (b - 4) * .25, requantized around 100
+        let y = run("QLinearAdd", vec![], consts, &inputs, tensor1(&[14u8, 255]));
+        assert_eq!(y, tensor1(&[106u8, 222]));
+    }
+
+    #[test]
+    fn qlinear_sigmoid() {
+        let consts = vec![
+            initializer("x_s", tensor0(0.1f32)),
+            initializer("x_zp", tensor0(0i8)),
+            initializer("y_s", tensor0(1f32 / 256.)),
+            initializer("y_zp", tensor0(-128i8)),
+        ];
+        let inputs = ["x", "x_s", "x_zp", "y_s", "y_zp"];
+        let y = run("QLinearSigmoid", vec![], consts, &inputs, tensor1(&[-128i8, 0, 127]));
+        assert_eq!(y, tensor1(&[-128i8, 0, 127]));
+    }
+
+    #[test]
+    fn qlinear_average_pool_excluding_pad() {
+        use crate::ser::{attr_int, attr_ints};
+        let consts = vec![
+            initializer("x_s", tensor0(1f32)),
+            initializer("x_zp", tensor0(10u8)),
+            initializer("y_s", tensor0(0.5f32)),
+            initializer("y_zp", tensor0(0u8)),
+        ];
+        let inputs = ["x", "x_s", "x_zp", "y_s", "y_zp"];
+        let attrs = vec![
+            attr_ints("kernel_shape", [3]),
+            attr_ints("pads", [1, 1]),
+            attr_int("count_include_pad", 0),
+        ];
+        // windows average 2, 3 and 2 centered values, doubled by the output scale
+        let x = tensor3(&[[[11u8, 13, 16]]]);
+        let y = run("QLinearAveragePool", attrs, consts, &inputs, x);
+        assert_eq!(y, tensor3(&[[[4u8, 7, 9]]]));
+    }
+}
@@ -0,0 +1,65 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "Binarizer", 1, binarizer);
+}
+
+fn binarizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let threshold = node.get_attr_opt("threshold")?.unwrap_or(0.0);
+    Ok((expand(Binarizer { threshold }), vec![]))
+}
+
+/// 1 where the input is strictly greater than the threshold, 0 elsewhere.
+#[derive(Debug, Clone, Educe)]
+#[educe(Hash)]
+struct Binarizer {
+    #[educe(Hash(method = "hash_f32"))]
+    threshold: f32,
+}
+
+impl_dyn_hash!(Binarizer);
+
+impl Expansion for Binarizer {
+    fn name(&self) -> Cow<str> {
+        "Binarizer".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[0])?.clone();
+        let threshold = tensor0(self.threshold)
+            .cast_to_dt(fact.datum_type)?
+            .into_owned()
+            .broadcast_into_rank(fact.rank())?;
+        let above = model.wire_node(
+            format!("{}.above", prefix),
+            tract_core::ops::logic::lesser::unary(threshold.into_arc_tensor()),
+            inputs,
+        )?;
+        model.wire_node(prefix, tract_core::ops::cast::cast(fact.datum_type), &above)
+    }
+}
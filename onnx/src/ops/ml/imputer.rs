@@ -0,0 +1,120 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "Imputer", 1, imputer);
+}
+
+fn imputer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let floats = node.get_attr_opt_vec::<f32>("imputed_value_floats")?;
+    let ints = node.get_attr_opt_vec::<i64>("imputed_value_int64s")?;
+    let op = match (floats, ints) {
+        (Some(floats), None) => Imputer {
+            imputed: rctensor1(&floats),
+            replaced: rctensor0(node.get_attr_opt("replaced_value_float")?.unwrap_or(0f32)),
+        },
+        (None, Some(ints)) => Imputer {
+            imputed: rctensor1(&ints),
+            replaced: rctensor0(node.get_attr_opt("replaced_value_int64")?.unwrap_or(0i64)),
+        },
+        _ => bail!("Imputer requires exactly one of imputed_value_floats and imputed_value_int64s"),
+    };
+    Ok((expand(op), vec![]))
+}
+
+/// Substitute the imputed values, given per feature or as a single one, to
+/// the occurrences of the replaced value. A NaN replaced value matches NaNs.
+#[derive(Debug, Clone, Hash)]
+struct Imputer {
+    imputed: Arc<Tensor>,
+    replaced: Arc<Tensor>,
+}
+
+impl_dyn_hash!(Imputer);
+
+impl Expansion for Imputer {
+    fn name(&self) -> Cow<str> {
+        "Imputer".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let fact = model.outlet_fact(inputs[0])?.clone();
+        let replaces_nan = self.replaced.cast_to_scalar::<f32>()?.is_nan();
+        let matches = if replaces_nan {
+            let input = wire_f32_input(prefix, model, inputs[0])?;
+            model.wire_node(
+                format!("{}.is_nan", prefix),
+                tract_onnx_opl::is_nan::is_nan(),
+                &[input],
+            )?
+        } else {
+            let replaced = self
+                .replaced
+                .cast_to_dt(fact.datum_type)?
+                .into_owned()
+                .broadcast_into_rank(fact.rank())?;
+            model.wire_node(
+                format!("{}.matches", prefix),
+                tract_core::ops::logic::equals::unary(replaced.into_arc_tensor()),
+                inputs,
+            )?
+        };
+        let imputed = self
+            .imputed
+            .cast_to_dt(fact.datum_type)?
+            .into_owned()
+            .broadcast_into_rank(fact.rank())?;
+        let imputed = model.add_const(format!("{}.imputed", prefix), imputed)?;
+        model.wire_node(prefix, tract_core::ops::logic::Iff, &[matches[0], imputed, inputs[0]])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    #[test]
+    fn imputer() -> TractResult<()> {
+        let attributes = vec![attr_floats("imputed_value_floats", &[10., 20.])];
+        let input = tensor2(&[[0f32, 1.], [2., 0.]]);
+        let outputs = run("Imputer", attributes, tvec!(input), 1)?;
+        assert_eq!(*outputs[0], tensor2(&[[10f32, 1.], [2., 20.]]));
+        let attributes = vec![
+            attr_floats("imputed_value_floats", &[5.]),
+            attr_float("replaced_value_float", f32::NAN),
+        ];
+        let input = tensor2(&[[f32::NAN, 0.], [1., f32::NAN]]);
+        let outputs = run("Imputer", attributes, tvec!(input), 1)?;
+        assert_eq!(*outputs[0], tensor2(&[[5f32, 0.], [1., 5.]]));
+        let attributes =
+            vec![attr_ints("imputed_value_int64s", vec![7]), attr_int("replaced_value_int64", -1)];
+        let outputs = run("Imputer", attributes, tvec!(tensor1(&[-1i64, 3])), 1)?;
+        assert_eq!(*outputs[0], tensor1(&[7i64, 3]));
+        Ok(())
+    }
+}
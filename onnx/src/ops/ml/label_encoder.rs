@@ -0,0 +1,225 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_onnx_opl::ml::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "LabelEncoder", 1, label_encoder);
+}
+
+fn label_encoder(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    // ai.onnx.ml version 1 only maps between strings and their index
+    if let Some(classes) = node.get_attr_opt_vec::<String>("classes_strings")? {
+        let op = ClassesLabelEncoder {
+            classes: rctensor1(&classes),
+            default_int: node.get_attr_opt("default_int64")?.unwrap_or(-1),
+            default_string: node.get_attr_opt("default_string")?.unwrap_or("_Unused").to_string(),
+        };
+        return Ok((expand(op), vec![]));
+    }
+    let keys = if let Some(keys) = node.get_attr_opt_vec::<String>("keys_strings")? {
+        rctensor1(&keys)
+    } else if let Some(keys) = node.get_attr_opt_vec::<i64>("keys_int64s")? {
+        rctensor1(&keys)
+    } else if node.get_attr_opt_vec::<f32>("keys_floats")?.is_some() {
+        bail!("LabelEncoder with float keys is not supported")
+    } else {
+        bail!("LabelEncoder requires one of keys_strings and keys_int64s")
+    };
+    let (values, default) =
+        if let Some(values) = node.get_attr_opt_vec::<String>("values_strings")? {
+            let default = node.get_attr_opt("default_string")?.unwrap_or("_Unused").to_string();
+            (rctensor1(&values), rctensor0(default))
+        } else if let Some(values) = node.get_attr_opt_vec::<i64>("values_int64s")? {
+            (rctensor1(&values), rctensor0(node.get_attr_opt("default_int64")?.unwrap_or(-1i64)))
+        } else if let Some(values) = node.get_attr_opt_vec::<f32>("values_floats")? {
+            (rctensor1(&values), rctensor0(node.get_attr_opt("default_float")?.unwrap_or(-0f32)))
+        } else {
+            bail!("LabelEncoder requires one of values_strings, values_int64s and values_floats")
+        };
+    node.expect(keys.len() == values.len(), "as many keys as values")?;
+    Ok((expand(LabelEncoder { keys, values, default }), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+struct LabelEncoder {
+    keys: Arc<Tensor>,
+    values: Arc<Tensor>,
+    default: Arc<Tensor>,
+}
+
+impl_dyn_hash!(LabelEncoder);
+
+impl Expansion for LabelEncoder {
+    fn name(&self) -> Cow<str> {
+        "LabelEncoder".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.equals(&inputs[0].datum_type, self.keys.datum_type())?;
+        s.equals(&outputs[0].datum_type, self.values.datum_type())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let wire = model.wire_node(
+            format!("{}.reverse", prefix),
+            ReverseLookup::new(self.keys.clone(), -1)?,
+            inputs,
+        )?;
+        model.wire_node(
+            format!("{}.direct", prefix),
+            DirectLookup::new(self.values.clone(), self.default.clone())?,
+            &wire,
+        )
+    }
+}
+
+/// Version 1 of LabelEncoder: strings are mapped to their index in the
+/// classes, and integers to the class at that index.
+#[derive(Debug, Clone, Hash)]
+struct ClassesLabelEncoder {
+    classes: Arc<Tensor>,
+    default_int: i64,
+    default_string: String,
+}
+
+impl_dyn_hash!(ClassesLabelEncoder);
+
+impl Expansion for ClassesLabelEncoder {
+    fn name(&self) -> Cow<str> {
+        "LabelEncoder".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.given(&inputs[0].datum_type, move |s, dt| {
+            let output_dt =
+                if dt == String::datum_type() { i64::datum_type() } else { String::datum_type() };
+            s.equals(&outputs[0].datum_type, output_dt)
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        if model.outlet_fact(inputs[0])?.datum_type == String::datum_type() {
+            let wire = model.wire_node(
+                format!("{}.reverse", prefix),
+                ReverseLookup::new(self.classes.clone(), -1)?,
+                inputs,
+            )?;
+            let indices = model.wire_node(
+                format!("{}.cast", prefix),
+                tract_core::ops::cast::cast(i64::datum_type()),
+                &wire,
+            )?;
+            // unknown strings, looked up as -1, get the default value
+            let unknown = model.wire_node(
+                format!("{}.unknown", prefix),
+                tract_core::ops::logic::equals::unary(
+                    tensor0(-1i64)
+                        .broadcast_into_rank(model.outlet_fact(indices[0])?.rank())?
+                        .into_arc_tensor(),
+                ),
+                &indices,
+            )?;
+            let rank = model.outlet_fact(indices[0])?.rank();
+            let default = model.add_const(
+                format!("{}.default", prefix),
+                tensor0(self.default_int).broadcast_into_rank(rank)?,
+            )?;
+            model.wire_node(prefix, tract_core::ops::logic::Iff, &[unknown[0], default, indices[0]])
+        } else {
+            let indices = model.wire_node(
+                format!("{}.cast", prefix),
+                tract_core::ops::cast::cast(i32::datum_type()),
+                inputs,
+            )?;
+            model.wire_node(
+                format!("{}.direct", prefix),
+                DirectLookup::new(self.classes.clone(), rctensor0(self.default_string.clone()))?,
+                &indices,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    fn strings(s: &[&str]) -> Tensor {
+        tensor1(&s.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn string_to_int() -> TractResult<()> {
+        let attributes =
+            vec![attr_strings("keys_strings", &["a", "b"]), attr_ints("values_int64s", vec![1, 2])];
+        let outputs = run("LabelEncoder", attributes, tvec!(strings(&["b", "z", "a"])), 1)?;
+        assert_eq!(*outputs[0], tensor1(&[2i64, -1, 1]));
+        Ok(())
+    }
+
+    #[test]
+    fn int_to_string() -> TractResult<()> {
+        let attributes = vec![
+            attr_ints("keys_int64s", vec![1, 2]),
+            attr_strings("values_strings", &["one", "two"]),
+        ];
+        let outputs = run("LabelEncoder", attributes.clone(), tvec!(tensor1(&[2i64, 3])), 1)?;
+        assert_eq!(*outputs[0], strings(&["two", "_Unused"]));
+        let mut attributes = attributes;
+        attributes.push(attr_string("default_string", "none"));
+        let outputs = run("LabelEncoder", attributes, tvec!(tensor1(&[2i64, 3])), 1)?;
+        assert_eq!(*outputs[0], strings(&["two", "none"]));
+        Ok(())
+    }
+
+    #[test]
+    fn classes() -> TractResult<()> {
+        let attributes =
+            vec![attr_strings("classes_strings", &["a", "b"]), attr_int("default_int64", 7)];
+        let outputs = run("LabelEncoder", attributes.clone(), tvec!(strings(&["b", "z"])), 1)?;
+        assert_eq!(*outputs[0], tensor1(&[1i64, 7]));
+        let outputs = run("LabelEncoder", attributes, tvec!(tensor1(&[1i64, 5])), 1)?;
+        assert_eq!(*outputs[0], strings(&["b", "_Unused"]));
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_core::ops::array::TypedConcat;
+use tract_core::ops::math;
+use tract_core::ops::matmul::MatMulUnary;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "LinearClassifier", 1, linear_classifier);
+    reg.insert_in_domain(DOMAIN, "LinearRegressor", 1, linear_regressor);
+}
+
+fn linear_classifier(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let class_labels = parse_class_labels(node)?;
+    let coefficients = rctensor1(&node.get_attr_vec::<f32>("coefficients")?);
+    let intercepts = node.get_attr_opt_vec::<f32>("intercepts")?.map(|t| rctensor1(&t));
+    let post_transform = get_post_transform(node)?;
+    let op = LinearClassifier::new(class_labels, coefficients, intercepts, post_transform);
+    Ok((expand(op), vec![]))
+}
+
+fn linear_regressor(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let coefficients = node.get_attr_vec::<f32>("coefficients")?;
+    let targets = node.get_attr_opt("targets")?.unwrap_or(1);
+    let intercepts = get_vec_attr_opt::<f32>(node, "intercepts", targets)?;
+    node.expect_attr("coefficients", coefficients.len() % targets == 0, || {
+        format!("a multiple of {} targets, got {} coefficients", targets, coefficients.len())
+    })?;
+    let post_transform = get_post_transform(node)?;
+    let coefficients = rctensor1(&coefficients);
+    let intercepts = intercepts.map(|t| rctensor1(&t));
+    let op = LinearRegressor { targets, coefficients, intercepts, post_transform };
+    Ok((expand(op), vec![]))
+}
+
+/// x.Wᵀ + b, for coefficients W stored row-major with one row per output.
+fn wire_linear(
+    prefix: &str,
+    model: &mut TypedModel,
+    input: OutletId,
+    coefficients: &Tensor,
+    intercepts: Option<&Tensor>,
+) -> TractResult<OutletId> {
+    let input = wire_f32_input(prefix, model, input)?;
+    let fact = model.outlet_fact(input)?;
+    if fact.rank() != 2 {
+        bail!("Linear ML operators expect 2D input, got {:?}", fact)
+    }
+    let n_features = fact.shape[1].to_usize()?;
+    if n_features == 0 || coefficients.len() % n_features != 0 {
+        bail!("{} coefficients do not match {} features", coefficients.len(), n_features)
+    }
+    let weights =
+        coefficients.clone().into_shape(&[coefficients.len() / n_features, n_features])?;
+    let mut wire = model.wire_node(
+        format!("{}.matmul", prefix),
+        MatMulUnary::new(weights.into_arc_tensor(), false, true, true),
+        &[input],
+    )?;
+    if let Some(intercepts) = intercepts {
+        wire = model.wire_node(
+            format!("{}.intercepts", prefix),
+            math::add::unary(intercepts.clone().broadcast_into_rank(2)?.into_arc_tensor()),
+            &wire,
+        )?;
+    }
+    Ok(wire[0])
+}
+
+/// Linear classifier, as exported for logistic regressions and linear SVMs.
+///
+/// Binary classifiers may come with a single row of coefficients: the score s
+/// is then expanded to [-s, s] so the second class wins when s is positive.
+#[derive(Debug, Clone, Hash, new)]
+pub(super) struct LinearClassifier {
+    class_labels: Arc<Tensor>,
+    coefficients: Arc<Tensor>,
+    intercepts: Option<Arc<Tensor>>,
+    post_transform: Option<PostTransform>,
+}
+
+impl_dyn_hash!(LinearClassifier);
+
+impl Expansion for LinearClassifier {
+    fn name(&self) -> Cow<str> {
+        "LinearClassifier".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 2)?;
+        s.equals(&outputs[0].datum_type, self.class_labels.datum_type())?;
+        s.equals(&outputs[1].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 1)?;
+        s.equals(&outputs[1].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[1].shape[0], &inputs[0].shape[0])?;
+        s.given(&inputs[0].shape[1], move |s, n_features| {
+            if let Ok(n_features) = n_features.to_usize() {
+                let rows = self.coefficients.len() / n_features.max(1);
+                let n_scores = if rows == 1 && self.class_labels.len() == 2 { 2 } else { rows };
+                s.equals(&outputs[1].shape[1], n_scores.to_dim())?;
+            }
+            Ok(())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let score =
+            wire_linear(prefix, model, inputs[0], &self.coefficients, self.intercepts.as_deref())?;
+        let mut scores = tvec!(score);
+        if model.outlet_fact(score)?.shape[1].is_one() && self.class_labels.len() == 2 {
+            let negated = model.wire_node(
+                format!("{}.negated", prefix),
+                math::mul::unary(rctensor2(&[[-1f32]])),
+                &scores,
+            )?;
+            scores = model.wire_node(
+                format!("{}.binary", prefix),
+                TypedConcat::concat_vars(1, 2),
+                &[negated[0], score],
+            )?;
+        }
+        scores = wire_post_transform(prefix, model, scores, self.post_transform)?;
+        let labels = wire_labels(prefix, model, scores[0], &self.class_labels)?;
+        Ok(tvec!(labels, scores[0]))
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+}
+
+#[derive(Debug, Clone, Hash, new)]
+pub(super) struct LinearRegressor {
+    targets: usize,
+    coefficients: Arc<Tensor>,
+    intercepts: Option<Arc<Tensor>>,
+    post_transform: Option<PostTransform>,
+}
+
+impl_dyn_hash!(LinearRegressor);
+
+impl Expansion for LinearRegressor {
+    fn name(&self) -> Cow<str> {
+        "LinearRegressor".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], self.targets.to_dim())?;
+        s.equals(&inputs[0].shape[1], (self.coefficients.len() / self.targets).to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let wire =
+            wire_linear(prefix, model, inputs[0], &self.coefficients, self.intercepts.as_deref())?;
+        wire_post_transform(prefix, model, tvec!(wire), self.post_transform)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    #[test]
+    fn classifier() -> TractResult<()> {
+        let attributes = vec![
+            attr_floats("coefficients", &[1., 0., 0., 1., -1., -1.]),
+            attr_floats("intercepts", &[0., 0., 0.5]),
+            attr_ints("classlabels_int64s", vec![10, 20, 30]),
+        ];
+        let input = tensor2(&[[2f32, 1.], [0., 3.], [-1., -1.]]);
+        let outputs = run("LinearClassifier", attributes, tvec!(input), 2)?;
+        assert_eq!(*outputs[0], tensor1(&[10i64, 20, 30]));
+        assert_eq!(*outputs[1], tensor2(&[[2f32, 1., -2.5], [0., 3., -2.5], [-1., -1., 2.5]]));
+        Ok(())
+    }
+
+    #[test]
+    fn binary_classifier() -> TractResult<()> {
+        let attributes = vec![
+            attr_floats("coefficients", &[1., -1.]),
+            attr_floats("intercepts", &[0.5]),
+            attr_strings("classlabels_strings", &["no", "yes"]),
+        ];
+        let input = tensor2(&[[1f32, 0.], [0., 1.]]);
+        let outputs = run("LinearClassifier", attributes, tvec!(input), 2)?;
+        assert_eq!(*outputs[0], tensor1(&["yes".to_string(), "no".to_string()]));
+        assert_eq!(*outputs[1], tensor2(&[[-1.5f32, 1.5], [0.5, -0.5]]));
+        Ok(())
+    }
+
+    #[test]
+    fn regressor() -> TractResult<()> {
+        let attributes = vec![
+            attr_int("targets", 2),
+            attr_floats("coefficients", &[1., 2., 0., -1.]),
+            attr_floats("intercepts", &[1., 0.]),
+        ];
+        let input = tensor2(&[[1i64, 1], [2, 0]]);
+        let outputs = run("LinearRegressor", attributes, tvec!(input), 1)?;
+        assert_eq!(*outputs[0], tensor2(&[[4f32, -1.], [3., 0.]]));
+        Ok(())
+    }
+}
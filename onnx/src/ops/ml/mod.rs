@@ -1,9 +1,259 @@
+mod binarizer;
 mod category_mapper;
+mod imputer;
+mod label_encoder;
+mod linear;
+mod normalizer;
+mod one_hot_encoder;
+mod scaler;
+mod svm;
 mod tree_ensemble_classifier;
+mod tree_ensemble_regressor;
+mod zip_map;
 
 use crate::model::OnnxOpRegister;
+use crate::pb::NodeProto;
+use crate::pb_helpers::*;
+use tract_hir::internal::*;
+
+pub const DOMAIN: &str = "ai.onnx.ml";
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    binarizer::register_all_ops(reg);
     category_mapper::register_all_ops(reg);
+    imputer::register_all_ops(reg);
+    label_encoder::register_all_ops(reg);
+    linear::register_all_ops(reg);
+    normalizer::register_all_ops(reg);
+    one_hot_encoder::register_all_ops(reg);
+    scaler::register_all_ops(reg);
+    svm::register_all_ops(reg);
     tree_ensemble_classifier::register_all_ops(reg);
+    tree_ensemble_regressor::register_all_ops(reg);
+    zip_map::register_all_ops(reg);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PostTransform {
+    Softmax,
+    Logistic,
+    // SoftmaxZero,
+    // Probit, // probit, especially multinomial, is p.i.t.a. - so let's ignore it for now
+}
+
+pub fn parse_post_transform(s: &str) -> TractResult<Option<PostTransform>> {
+    match s {
+        "NONE" => Ok(None),
+        "SOFTMAX" => Ok(Some(PostTransform::Softmax)),
+        "LOGISTIC" => Ok(Some(PostTransform::Logistic)),
+        "PROBIT" | "SOFTMAX_ZERO" => bail!("PROBIT and SOFTMAX_ZERO unsupported"),
+        _ => bail!("Invalid post transform: {}", s),
+    }
+}
+
+fn get_post_transform(node: &NodeProto) -> TractResult<Option<PostTransform>> {
+    Ok(node.get_attr_opt("post_transform")?.map(parse_post_transform).transpose()?.flatten())
+}
+
+fn get_vec_attr<'a, T>(node: &'a NodeProto, attr: &str, n: usize) -> TractResult<Vec<T>>
+where
+    T: AttrTVecType<'a>,
+{
+    let vec = node.get_attr_vec(attr)?;
+    node.expect_attr(attr, vec.len() == n, || format!("length {}, got {}", vec.len(), n))?;
+    Ok(vec)
+}
+
+fn get_vec_attr_opt<'a, T>(node: &'a NodeProto, attr: &str, n: usize) -> TractResult<Option<Vec<T>>>
+where
+    T: AttrTVecType<'a>,
+{
+    match node.get_attr_opt_vec(attr)? {
+        Some(vec) => {
+            node.expect_attr(attr, vec.len() == n, || {
+                format!("length {} (or undefined), got {}", vec.len(), n)
+            })?;
+            Ok(Some(vec))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_class_labels(node: &NodeProto) -> TractResult<Arc<Tensor>> {
+    // parse n_classes from protobuf
+    let ints = node.get_attr_opt_slice::<i64>("classlabels_int64s")?;
+    let strs = node.get_attr_opt_tvec::<&str>("classlabels_strings")?;
+    match (ints, strs) {
+        (Some(n), None) => Ok(rctensor1(n)),
+        (None, Some(n)) => Ok(rctensor1(&n.iter().map(|d| d.to_string()).collect::<Vec<_>>())),
+        (None, None) => {
+            bail!("cannot find neither 'classlabels_int64s' not 'classlabels_strings'")
+        }
+        (Some(_), Some(_)) => {
+            bail!("only one of 'classlabels_int64s' and 'classlabels_strings' can be set")
+        }
+    }
+}
+
+/// Ensure ML operators run on f32, as ONNX-ML accepts integer and double
+/// features as well.
+fn wire_f32_input(prefix: &str, model: &mut TypedModel, input: OutletId) -> TractResult<OutletId> {
+    if model.outlet_fact(input)?.datum_type == f32::datum_type() {
+        Ok(input)
+    } else {
+        Ok(model.wire_node(
+            format!("{}.cast", prefix),
+            tract_core::ops::cast::cast(f32::datum_type()),
+            &[input],
+        )?[0])
+    }
+}
+
+fn wire_post_transform(
+    prefix: &str,
+    model: &mut TypedModel,
+    scores: TVec<OutletId>,
+    post_transform: Option<PostTransform>,
+) -> TractResult<TVec<OutletId>> {
+    match post_transform {
+        None => Ok(scores),
+        Some(PostTransform::Softmax) => tract_hir::ops::nn::LayerSoftmax::new(1, false).wire(
+            &format!("{}.softmax", prefix),
+            model,
+            &scores,
+        ),
+        Some(PostTransform::Logistic) => model.wire_node(
+            &format!("{}.logistic", prefix),
+            tract_core::ops::nn::sigmoid(),
+            &scores,
+        ),
+    }
+}
+
+/// Labels of the classes with the highest score.
+fn wire_labels(
+    prefix: &str,
+    model: &mut TypedModel,
+    scores: OutletId,
+    class_labels: &Arc<Tensor>,
+) -> TractResult<OutletId> {
+    use tract_core::ops::nn::*;
+    let winners = model.wire_node(
+        format!("{}.argmax", prefix),
+        Reduce::new(tvec!(1), Reducer::ArgMax(false)),
+        &[scores],
+    )?;
+    let reduced = model.wire_node(
+        format!("{}.rm_axis", prefix),
+        tract_core::ops::change_axes::AxisOp::Rm(1),
+        &winners,
+    )?;
+    let casted = model.wire_node(
+        format!("{}.casted", prefix),
+        tract_core::ops::cast::cast(i32::datum_type()),
+        &reduced,
+    )?;
+    wire_lookup(prefix, model, casted[0], class_labels)
+}
+
+/// Map class indices to their labels.
+fn wire_lookup(
+    prefix: &str,
+    model: &mut TypedModel,
+    indices: OutletId,
+    class_labels: &Arc<Tensor>,
+) -> TractResult<OutletId> {
+    // indices always designate a class: the fallback is never used
+    let fallback = if class_labels.datum_type() == String::datum_type() {
+        rctensor0(String::new())
+    } else {
+        Tensor::zero_dt(class_labels.datum_type(), &[])?.into_arc_tensor()
+    };
+    Ok(model.wire_node(
+        format!("{}.labels", prefix),
+        tract_onnx_opl::ml::DirectLookup::new(class_labels.clone(), fallback)?,
+        &[indices],
+    )?[0])
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+    use crate::pb::attribute_proto::AttributeType;
+    use crate::pb::*;
+    use std::convert::TryInto;
+
+    pub use crate::ser::{attr_int, attr_ints, attr_string};
+
+    pub fn attr_float(name: &str, f: f32) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string(),
+            r#type: AttributeType::Float as i32,
+            f,
+            ..AttributeProto::default()
+        }
+    }
+
+    pub fn attr_floats(name: &str, floats: &[f32]) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string(),
+            r#type: AttributeType::Floats as i32,
+            floats: floats.to_vec(),
+            ..AttributeProto::default()
+        }
+    }
+
+    pub fn attr_strings(name: &str, strings: &[&str]) -> AttributeProto {
+        AttributeProto {
+            name: name.to_string(),
+            r#type: AttributeType::Strings as i32,
+            strings: strings.iter().map(|s| s.as_bytes().to_vec()).collect(),
+            ..AttributeProto::default()
+        }
+    }
+
+    /// Run a single ai.onnx.ml node on the given inputs.
+    pub fn run(
+        op_type: &str,
+        attribute: Vec<AttributeProto>,
+        inputs: TVec<Tensor>,
+        outputs: usize,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let node = NodeProto {
+            domain: DOMAIN.into(),
+            op_type: op_type.into(),
+            input: (0..inputs.len()).map(|i| format!("input.{}", i)).collect(),
+            output: (0..outputs).map(|i| format!("output.{}", i)).collect(),
+            attribute,
+            ..NodeProto::default()
+        };
+        let input = inputs
+            .iter()
+            .zip(node.input.iter())
+            .map(|(t, name)| {
+                let fact = TypedFact::dt_shape(t.datum_type(), t.shape());
+                Ok(ValueInfoProto {
+                    name: name.clone(),
+                    r#type: Some((&fact).try_into()?),
+                    ..ValueInfoProto::default()
+                })
+            })
+            .collect::<TractResult<_>>()?;
+        let output = node
+            .output
+            .iter()
+            .map(|name| ValueInfoProto { name: name.clone(), ..ValueInfoProto::default() })
+            .collect();
+        let graph = GraphProto { node: vec![node], input, output, ..GraphProto::default() };
+        let proto = ModelProto {
+            opset_import: vec![
+                OperatorSetIdProto { domain: "".into(), version: 13 },
+                OperatorSetIdProto { domain: DOMAIN.into(), version: 2 },
+            ],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let model = crate::onnx().model_for_proto_model(&proto)?.into_optimized()?;
+        model.into_runnable()?.run(inputs)
+    }
 }
@@ -0,0 +1,114 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_core::ops::math;
+use tract_core::ops::nn::{Reduce, Reducer};
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "Normalizer", 1, normalizer);
+}
+
+fn normalizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let norm = match node.get_attr_opt("norm")?.unwrap_or("MAX") {
+        "MAX" => Norm::Max,
+        "L1" => Norm::L1,
+        "L2" => Norm::L2,
+        other => bail!("Invalid norm: {}", other),
+    };
+    Ok((expand(Normalizer { norm }), vec![]))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Norm {
+    Max,
+    L1,
+    L2,
+}
+
+/// Divide each row by its max, L1 or L2 norm.
+#[derive(Debug, Clone, Hash)]
+struct Normalizer {
+    norm: Norm,
+}
+
+impl_dyn_hash!(Normalizer);
+
+impl Expansion for Normalizer {
+    fn name(&self) -> Cow<str> {
+        "Normalizer".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = wire_f32_input(prefix, model, inputs[0])?;
+        let axis = model.outlet_fact(input)?.rank() - 1;
+        let norm = match self.norm {
+            Norm::Max => model.wire_node(
+                format!("{}.max", prefix),
+                Reduce::new(tvec!(axis), Reducer::Max),
+                &[input],
+            )?,
+            Norm::L1 => {
+                let abs = model.wire_node(format!("{}.abs", prefix), math::abs(), &[input])?;
+                model.wire_node(
+                    format!("{}.l1", prefix),
+                    Reduce::new(tvec!(axis), Reducer::Sum),
+                    &abs,
+                )?
+            }
+            Norm::L2 => {
+                let sqr = model.wire_node(format!("{}.sqr", prefix), math::square(), &[input])?;
+                let sum = model.wire_node(
+                    format!("{}.sum", prefix),
+                    Reduce::new(tvec!(axis), Reducer::Sum),
+                    &sqr,
+                )?;
+                model.wire_node(format!("{}.l2", prefix), math::sqrt(), &sum)?
+            }
+        };
+        model.wire_node(prefix, math::div::bin_typed(), &[input, norm[0]])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    fn normalize(norm: &str) -> TractResult<Arc<Tensor>> {
+        let input = tensor2(&[[1f32, -3.], [2., 2.]]);
+        Ok(run("Normalizer", vec![attr_string("norm", norm)], tvec!(input), 1)?.remove(0))
+    }
+
+    #[test]
+    fn normalizer() -> TractResult<()> {
+        assert_eq!(*normalize("MAX")?, tensor2(&[[1f32, -3.], [1., 1.]]));
+        assert_eq!(*normalize("L1")?, tensor2(&[[0.25f32, -0.75], [0.5, 0.5]]));
+        let l2 = 10f32.sqrt();
+        normalize("L2")?
+            .close_enough(&tensor2(&[[1. / l2, -3. / l2], [0.5f32.sqrt(), 0.5f32.sqrt()]]), true)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_onnx_opl::ml::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "OneHotEncoder", 1, one_hot_encoder);
+}
+
+fn one_hot_encoder(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let ints = node.get_attr_opt_vec::<i64>("cats_int64s")?;
+    let strings = node.get_attr_opt_vec::<String>("cats_strings")?;
+    let categories = match (ints, strings) {
+        (Some(ints), None) => rctensor1(&ints),
+        (None, Some(strings)) => rctensor1(&strings),
+        _ => bail!("OneHotEncoder requires exactly one of cats_int64s and cats_strings"),
+    };
+    Ok((expand(OneHotEncoder { categories }), vec![]))
+}
+
+/// One-hot encoding of categories on a new trailing axis, as f32.
+///
+/// Unknown categories are encoded as all zeros: the `zeros` attribute asking
+/// for an error instead is not honoured.
+#[derive(Debug, Clone, Hash)]
+struct OneHotEncoder {
+    categories: Arc<Tensor>,
+}
+
+impl_dyn_hash!(OneHotEncoder);
+
+impl Expansion for OneHotEncoder {
+    fn name(&self) -> Cow<str> {
+        "OneHotEncoder".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(inputs[0].rank.bex() + 1, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            let rank = rank as usize;
+            for axis in 0..rank {
+                s.equals(&inputs[0].shape[axis], &outputs[0].shape[axis])?;
+            }
+            s.equals(&outputs[0].shape[rank], self.categories.len().to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut input = inputs[0];
+        let fact = model.outlet_fact(input)?.clone();
+        let keys_dt = self.categories.datum_type();
+        if fact.datum_type != keys_dt {
+            input = model.wire_node(
+                format!("{}.cast", prefix),
+                tract_core::ops::cast::cast(keys_dt),
+                &[input],
+            )?[0];
+        }
+        // unknown categories go to an extra column, which is then dropped
+        let n = self.categories.len();
+        let indices = model.wire_node(
+            format!("{}.reverse", prefix),
+            ReverseLookup::new(self.categories.clone(), n as i32)?,
+            &[input],
+        )?;
+        let one_hot = model.wire_node(
+            format!("{}.one_hot", prefix),
+            tract_core::ops::array::OneHot {
+                axis: fact.rank(),
+                dim: n + 1,
+                off: rctensor0(0f32),
+                on: rctensor0(1f32),
+            },
+            &indices,
+        )?;
+        model.wire_node(prefix, tract_core::ops::array::Slice::new(fact.rank(), 0, n), &one_hot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    #[test]
+    fn one_hot_encoder() -> TractResult<()> {
+        let attributes = vec![attr_strings("cats_strings", &["a", "b", "c"])];
+        let input = tensor1(&["c".to_string(), "a".to_string(), "z".to_string()]);
+        let outputs = run("OneHotEncoder", attributes, tvec!(input), 1)?;
+        assert_eq!(*outputs[0], tensor2(&[[0f32, 0., 1.], [1., 0., 0.], [0., 0., 0.]]));
+        let attributes = vec![attr_ints("cats_int64s", vec![4, 2])];
+        let outputs = run("OneHotEncoder", attributes, tvec!(tensor2(&[[2i64], [4]])), 1)?;
+        assert_eq!(*outputs[0], tensor3(&[[[0f32, 1.]], [[1., 0.]]]));
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_core::ops::math;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "Scaler", 1, scaler);
+}
+
+fn scaler(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let offset = node.get_attr_opt_vec::<f32>("offset")?.unwrap_or_else(|| vec![0.0]);
+    let scale = node.get_attr_opt_vec::<f32>("scale")?.unwrap_or_else(|| vec![1.0]);
+    Ok((expand(Scaler { offset: rctensor1(&offset), scale: rctensor1(&scale) }), vec![]))
+}
+
+/// (x - offset) * scale, with one value per feature or a single one.
+#[derive(Debug, Clone, Hash)]
+struct Scaler {
+    offset: Arc<Tensor>,
+    scale: Arc<Tensor>,
+}
+
+impl_dyn_hash!(Scaler);
+
+impl Expansion for Scaler {
+    fn name(&self) -> Cow<str> {
+        "Scaler".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = wire_f32_input(prefix, model, inputs[0])?;
+        let rank = model.outlet_fact(input)?.rank();
+        let negated_offset = self.offset.to_array_view::<f32>()?.mapv(|x| -x).into_tensor();
+        let wire = model.wire_node(
+            format!("{}.offset", prefix),
+            math::add::unary(negated_offset.broadcast_into_rank(rank)?.into_arc_tensor()),
+            &[input],
+        )?;
+        model.wire_node(
+            format!("{}.scale", prefix),
+            math::mul::unary(
+                self.scale.clone().into_tensor().broadcast_into_rank(rank)?.into_arc_tensor(),
+            ),
+            &wire,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    #[test]
+    fn scaler() -> TractResult<()> {
+        let attributes = vec![attr_floats("offset", &[1., 2.]), attr_floats("scale", &[2., 0.5])];
+        let input = tensor2(&[[1f32, 2.], [3., 6.]]);
+        let outputs = run("Scaler", attributes, tvec!(input), 1)?;
+        assert_eq!(*outputs[0], tensor2(&[[0f32, 0.], [4., 2.]]));
+        let outputs = run("Scaler", vec![attr_floats("scale", &[3.])], tvec!(tensor1(&[1i64])), 1)?;
+        assert_eq!(*outputs[0], tensor1(&[3f32]));
+        Ok(())
+    }
+}
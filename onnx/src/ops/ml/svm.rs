@@ -0,0 +1,202 @@
+use super::linear::{LinearClassifier, LinearRegressor};
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_onnx_opl::ml::svm::{self, parse_kernel_type, Kernel};
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "SVMClassifier", 1, svm_classifier);
+    reg.insert_in_domain(DOMAIN, "SVMRegressor", 1, svm_regressor);
+}
+
+fn parse_kernel(node: &NodeProto) -> TractResult<Kernel> {
+    let kernel_type = parse_kernel_type(node.get_attr_opt("kernel_type")?.unwrap_or("LINEAR"))?;
+    let params = get_vec_attr_opt::<f32>(node, "kernel_params", 3)?.unwrap_or_else(|| vec![0.0; 3]);
+    Ok(Kernel { kernel_type, gamma: params[0], coef0: params[1], degree: params[2] as i32 })
+}
+
+/// Support vectors as a [S, F] matrix, and the coefficients as [_, S].
+fn parse_support_vectors(
+    node: &NodeProto,
+    n_vectors: usize,
+) -> TractResult<(Arc<Tensor>, Arc<Tensor>)> {
+    let support_vectors = node.get_attr_vec::<f32>("support_vectors")?;
+    node.expect_attr("support_vectors", support_vectors.len() % n_vectors == 0, || {
+        format!("a multiple of {} vectors, got {} values", n_vectors, support_vectors.len())
+    })?;
+    let coefficients = node.get_attr_vec::<f32>("coefficients")?;
+    node.expect_attr("coefficients", coefficients.len() % n_vectors == 0, || {
+        format!("a multiple of {} vectors, got {} values", n_vectors, coefficients.len())
+    })?;
+    let support_vectors = tensor1(&support_vectors)
+        .into_shape(&[n_vectors, support_vectors.len() / n_vectors])?
+        .into_arc_tensor();
+    let coefficients = tensor1(&coefficients)
+        .into_shape(&[coefficients.len() / n_vectors, n_vectors])?
+        .into_arc_tensor();
+    Ok((support_vectors, coefficients))
+}
+
+fn svm_classifier(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let class_labels = parse_class_labels(node)?;
+    let post_transform = get_post_transform(node)?;
+    let rho = node.get_attr_opt_vec::<f32>("rho")?.map(|rho| rctensor1(&rho));
+    let vectors_per_class: Vec<usize> =
+        node.get_attr_opt_vec("vectors_per_class")?.unwrap_or_default();
+    let n_vectors = vectors_per_class.iter().sum::<usize>();
+    if n_vectors == 0 {
+        let coefficients = rctensor1(&node.get_attr_vec::<f32>("coefficients")?);
+        let op = LinearClassifier::new(class_labels, coefficients, rho, post_transform);
+        return Ok((expand(op), vec![]));
+    }
+    let n_classes = vectors_per_class.len();
+    node.expect_attr("vectors_per_class", n_classes == class_labels.len(), || {
+        format!("{} classes, got {}", class_labels.len(), n_classes)
+    })?;
+    let n_pairs = n_classes * (n_classes - 1) / 2;
+    let rho = rho.context("SVMClassifier requires rho")?;
+    node.expect_attr("rho", rho.len() == n_pairs, || format!("{} values", n_pairs))?;
+    let (support_vectors, coefficients) = parse_support_vectors(node, n_vectors)?;
+    node.expect_attr("coefficients", coefficients.shape()[0] == n_classes - 1, || {
+        format!("{} rows of coefficients", n_classes - 1)
+    })?;
+    let prob_a = get_vec_attr_opt::<f32>(node, "prob_a", n_pairs)?.map(|t| rctensor1(&t));
+    let prob_b = get_vec_attr_opt::<f32>(node, "prob_b", n_pairs)?.map(|t| rctensor1(&t));
+    node.expect(prob_a.is_some() == prob_b.is_some(), "both or none of prob_a and prob_b")?;
+    let svm = svm::SvmClassifier {
+        kernel: parse_kernel(node)?,
+        support_vectors,
+        coefficients,
+        rho,
+        vectors_per_class: vectors_per_class.into(),
+        prob_a,
+        prob_b,
+    };
+    Ok((expand(SvmClassifier { svm, class_labels, post_transform }), vec![]))
+}
+
+fn svm_regressor(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let post_transform = get_post_transform(node)?;
+    let rho = get_vec_attr::<f32>(node, "rho", 1)?[0];
+    let one_class = node.get_attr_opt("one_class")?.unwrap_or(false);
+    let n_supports = node.get_attr_opt("n_supports")?.unwrap_or(0);
+    if n_supports == 0 {
+        node.expect(!one_class, "support vectors for a one class SVM")?;
+        let coefficients = rctensor1(&node.get_attr_vec::<f32>("coefficients")?);
+        let op = LinearRegressor::new(1, coefficients, Some(rctensor1(&[rho])), post_transform);
+        return Ok((expand(op), vec![]));
+    }
+    let (support_vectors, coefficients) = parse_support_vectors(node, n_supports)?;
+    node.expect_attr("coefficients", coefficients.shape()[0] == 1, "one per support vector")?;
+    let svm = svm::SvmRegressor {
+        kernel: parse_kernel(node)?,
+        support_vectors,
+        coefficients: coefficients.into_tensor().into_shape(&[n_supports])?.into_arc_tensor(),
+        rho,
+        one_class,
+    };
+    Ok((expand(SvmRegressor { svm, post_transform }), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+struct SvmClassifier {
+    svm: svm::SvmClassifier,
+    class_labels: Arc<Tensor>,
+    post_transform: Option<PostTransform>,
+}
+
+impl_dyn_hash!(SvmClassifier);
+
+impl Expansion for SvmClassifier {
+    fn name(&self) -> Cow<str> {
+        "SVMClassifier".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 2)?;
+        s.equals(&outputs[0].datum_type, self.class_labels.datum_type())?;
+        s.equals(&outputs[1].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], self.svm.support_vectors.shape()[1].to_dim())?;
+        s.equals(&outputs[0].rank, 1)?;
+        s.equals(&outputs[1].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[1].shape[0], &inputs[0].shape[0])?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = wire_f32_input(prefix, model, inputs[0])?;
+        let outputs = model.wire_node(format!("{}.svm", prefix), self.svm.clone(), &[input])?;
+        let scores = wire_post_transform(prefix, model, tvec!(outputs[1]), self.post_transform)?;
+        let labels = wire_lookup(prefix, model, outputs[0], &self.class_labels)?;
+        Ok(tvec!(labels, scores[0]))
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+struct SvmRegressor {
+    svm: svm::SvmRegressor,
+    post_transform: Option<PostTransform>,
+}
+
+impl_dyn_hash!(SvmRegressor);
+
+impl Expansion for SvmRegressor {
+    fn name(&self) -> Cow<str> {
+        "SVMRegressor".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], self.svm.support_vectors.shape()[1].to_dim())?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], 1.to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = wire_f32_input(prefix, model, inputs[0])?;
+        let output = model.wire_node(format!("{}.svm", prefix), self.svm.clone(), &[input])?;
+        wire_post_transform(prefix, model, output, self.post_transform)
+    }
+}
@@ -0,0 +1,118 @@
+use super::tree_ensemble_classifier::parse_nodes_data;
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+use tract_onnx_opl::ml::tree::TreeEnsemble;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "TreeEnsembleRegressor", 1, tree_regressor);
+}
+
+fn tree_regressor(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let ensemble = parse_nodes_data(node, false)?;
+    let base_values =
+        get_vec_attr_opt::<f32>(node, "base_values", ensemble.n_classes())?.map(|t| rctensor1(&t));
+    let post_transform = get_post_transform(node)?;
+    Ok((expand(TreeEnsembleRegressor { ensemble, base_values, post_transform }), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct TreeEnsembleRegressor {
+    pub ensemble: TreeEnsemble,
+    pub base_values: Option<Arc<Tensor>>,
+    pub post_transform: Option<PostTransform>,
+}
+
+impl_dyn_hash!(TreeEnsembleRegressor);
+
+impl Expansion for TreeEnsembleRegressor {
+    fn name(&self) -> Cow<str> {
+        "TreeEnsembleRegressor".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, DatumType::F32)?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+        s.equals(&outputs[0].shape[1], self.ensemble.n_classes().to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let mut targets = model.wire_node(
+            format!("{}.regressor", prefix),
+            tract_onnx_opl::ml::tree_ensemble_regressor::TreeEnsembleRegressor {
+                ensemble: self.ensemble.clone(),
+            },
+            inputs,
+        )?;
+        if let Some(base_values) = self.base_values.as_deref() {
+            targets = model.wire_node(
+                format!("{}.base_values", prefix),
+                tract_core::ops::math::add::unary(
+                    base_values.clone().broadcast_into_rank(2)?.into_arc_tensor(),
+                ),
+                &targets,
+            )?;
+        }
+        wire_post_transform(prefix, model, targets, self.post_transform)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::*;
+    use super::*;
+
+    /// Two stumps on the single feature, yielding (1, 5) under 0.5 and (3, 2)
+    /// above.
+    fn regress(aggregate: &str, base_values: Option<f32>) -> TractResult<Arc<Tensor>> {
+        let mut attributes = vec![
+            attr_int("n_targets", 1),
+            attr_string("aggregate_function", aggregate),
+            attr_ints("nodes_treeids", vec![0, 0, 0, 1, 1, 1]),
+            attr_ints("nodes_nodeids", vec![0, 1, 2, 0, 1, 2]),
+            attr_ints("nodes_featureids", vec![0; 6]),
+            attr_floats("nodes_values", &[0.5, 0., 0., 0.5, 0., 0.]),
+            attr_strings("nodes_modes", &["BRANCH_LEQ", "LEAF", "LEAF"].repeat(2)),
+            attr_ints("nodes_truenodeids", vec![1, 0, 0, 1, 0, 0]),
+            attr_ints("nodes_falsenodeids", vec![2, 0, 0, 2, 0, 0]),
+            attr_ints("target_treeids", vec![0, 0, 1, 1]),
+            attr_ints("target_nodeids", vec![1, 2, 1, 2]),
+            attr_ints("target_ids", vec![0; 4]),
+            attr_floats("target_weights", &[1., 3., 5., 2.]),
+        ];
+        if let Some(base) = base_values {
+            attributes.push(attr_floats("base_values", &[base]));
+        }
+        let input = tensor2(&[[0f32], [1.]]);
+        Ok(run("TreeEnsembleRegressor", attributes, tvec!(input), 1)?.remove(0))
+    }
+
+    #[test]
+    fn aggregate_functions() -> TractResult<()> {
+        assert_eq!(*regress("SUM", None)?, tensor2(&[[6f32], [5.]]));
+        assert_eq!(*regress("SUM", Some(10.))?, tensor2(&[[16f32], [15.]]));
+        assert_eq!(*regress("AVERAGE", None)?, tensor2(&[[3f32], [2.5]]));
+        assert_eq!(*regress("MIN", None)?, tensor2(&[[1f32], [2.]]));
+        assert_eq!(*regress("MAX", None)?, tensor2(&[[5f32], [3.]]));
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::NodeProto;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert_in_domain(DOMAIN, "ZipMap", 1, zip_map);
+}
+
+fn zip_map(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let class_labels = parse_class_labels(node)?;
+    Ok((expand(ZipMap { class_labels }), vec![]))
+}
+
+/// ZipMap turns scores into a sequence of label to score maps. tract has no
+/// map type, so the scores are passed through: column i of the output holds
+/// the score of the i-th class label.
+#[derive(Debug, Clone, Hash)]
+struct ZipMap {
+    class_labels: Arc<Tensor>,
+}
+
+impl_dyn_hash!(ZipMap);
+
+impl Expansion for ZipMap {
+    fn name(&self) -> Cow<str> {
+        "ZipMap".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["onnx-ml"]
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, DatumType::F32)?;
+        s.equals(&inputs[0].rank, 2)?;
+        s.equals(&inputs[0].shape[1], self.class_labels.len().to_dim())?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        _prefix: &str,
+        _model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        Ok(inputs.into())
+    }
+}
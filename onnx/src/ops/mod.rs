@@ -1,5 +1,6 @@
 use crate::model::{OnnxOpRegister, ParsingContext};
 use crate::pb::*;
+use crate::ser::OnnxDumperRegister;
 use tract_hir::internal::*;
 use tract_hir::ops;
 
@@ -15,36 +16,52 @@ mod array;
 mod cast;
 mod cumsum;
 mod d2s;
+mod fft;
 mod logic;
 mod math;
+mod microsoft;
 mod ml;
 mod nn;
 mod quant;
+mod random;
 pub mod rec;
 mod resize;
 mod s2d;
+mod sequence;
+mod ser;
+mod text;
 
 pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("Cast", cast::cast);
     reg.insert("Constant", konst);
     reg.insert("Identity", |_, _| Ok((Box::new(ops::identity::Identity::default()), vec![])));
     reg.insert("Resize", resize::resize);
+    reg.insert("Upsample", resize::upsample);
     array::register_all_ops(reg);
     cumsum::register_all_ops(reg);
     d2s::register_all_ops(reg);
+    fft::register_all_ops(reg);
     logic::register_all_ops(reg);
     math::register_all_ops(reg);
+    microsoft::register_all_ops(reg);
     ml::register_all_ops(reg);
     nn::register_all_ops(reg);
     quant::register_all_ops(reg);
+    random::register_all_ops(reg);
     rec::register_all_ops(reg);
     s2d::register_all_ops(reg);
+    sequence::register_all_ops(reg);
+    text::register_all_ops(reg);
+}
+
+pub fn register_all_dumpers(reg: &mut OnnxDumperRegister) {
+    ser::register_all_dumpers(reg);
 }
 
 fn konst(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let v = node.get_attr::<Tensor>("value")?;
+    let v = crate::tensor::load_tensor(node.get_attr("value")?, ctx.model_dir)?;
     Ok((Box::new(tract_hir::ops::konst::Const(v.into())), vec![]))
 }
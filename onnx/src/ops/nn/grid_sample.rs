@@ -0,0 +1,46 @@
+use tract_core::ops::nn::{GridSample, GridSampleMode, GridSamplePadding};
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn grid_sample(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let mode = match node.get_attr_opt("mode")?.unwrap_or("bilinear") {
+        "nearest" => GridSampleMode::Nearest,
+        "bilinear" | "linear" => GridSampleMode::Bilinear,
+        "bicubic" | "cubic" => GridSampleMode::Bicubic,
+        other => bail!("Unsupported GridSample mode {}", other),
+    };
+    let padding = match node.get_attr_opt("padding_mode")?.unwrap_or("zeros") {
+        "zeros" => GridSamplePadding::Zeros,
+        "border" => GridSamplePadding::Border,
+        "reflection" => GridSamplePadding::Reflection,
+        other => bail!("Unsupported GridSample padding_mode {}", other),
+    };
+    let align_corners = node.get_attr_opt("align_corners")?.unwrap_or(false);
+    Ok((inference_wrap(GridSample::new(mode, padding, align_corners), 1, rules), vec![]))
+}
+
+fn rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 2)?;
+    check_output_arity(&outputs, 1)?;
+    s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+    s.equals(&inputs[0].rank, 4)?;
+    s.equals(&inputs[1].rank, 4)?;
+    s.equals(&inputs[1].shape[3], 2.to_dim())?;
+    s.equals(&inputs[1].shape[0], &inputs[0].shape[0])?;
+    s.equals(&outputs[0].rank, 4)?;
+    s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+    s.equals(&outputs[0].shape[1], &inputs[0].shape[1])?;
+    s.equals(&outputs[0].shape[2], &inputs[1].shape[1])?;
+    s.equals(&outputs[0].shape[3], &inputs[1].shape[2])?;
+    Ok(())
+}
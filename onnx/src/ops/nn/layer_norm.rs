@@ -0,0 +1,190 @@
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+use tract_core::ops::binary::wire_with_rank_broadcast;
+use tract_core::ops::nn::LayerNorm;
+use tract_hir::internal::*;
+use tract_hir::ops::math;
+use tract_hir::ops::nn::{Reduce, Reducer};
+
+pub fn layer_normalization(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(-1);
+    let epsilon = node.get_attr_opt("epsilon")?.unwrap_or(1e-5);
+    let stash_type = node.get_attr_opt("stash_type")?.unwrap_or(1i64);
+    if stash_type != 1 {
+        bail!("Unsupported LayerNormalization stash_type {}", stash_type)
+    }
+    let optional_bias_input = crate::model::optional_inputs(node).nth(2).unwrap();
+    let mut outputs = crate::model::optional_outputs(node).skip(1);
+    let op = LayerNormalization::new(
+        axis,
+        epsilon,
+        optional_bias_input,
+        outputs.next().unwrap(),
+        outputs.next().unwrap(),
+    );
+    Ok((expand(op), vec![]))
+}
+
+#[derive(Debug, Clone, new, Educe)]
+#[educe(Hash)]
+pub struct LayerNormalization {
+    axis: i64,
+    #[educe(Hash(method = "hash_f32"))]
+    epsilon: f32,
+    optional_bias_input: Option<usize>,
+    optional_mean_output: Option<usize>,
+    optional_inv_std_dev_output: Option<usize>,
+}
+
+impl_dyn_hash!(LayerNormalization);
+
+impl LayerNormalization {
+    fn resolve_axis(&self, rank: usize) -> TractResult<usize> {
+        let axis = if self.axis < 0 { self.axis + rank as i64 } else { self.axis };
+        if axis < 0 || axis >= rank as i64 {
+            bail!("LayerNormalization axis {} is invalid for rank {}", self.axis, rank)
+        }
+        Ok(axis as usize)
+    }
+
+    /// Mean and inverse standard deviation, with the normalized axes kept as
+    /// ones, in f32 as mandated by the default stash_type.
+    fn wire_statistics(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        input: OutletId,
+        axis: usize,
+    ) -> TractResult<(OutletId, OutletId)> {
+        let fact = model.outlet_fact(input)?.clone();
+        let axes: Vec<i64> = (axis as i64..fact.rank() as i64).collect();
+        let mean = Reduce::new(Some(axes.clone()), true, Reducer::Mean).wire(
+            &format!("{}.mean", name),
+            model,
+            &[input],
+        )?;
+        let diff =
+            model.wire_node(format!("{}.diff", name), math::sub::bin_typed(), &[input, mean[0]])?;
+        let sqr = model.wire_node(format!("{}.sqr", name), math::square(), &diff)?;
+        let var = Reduce::new(Some(axes), true, Reducer::Mean).wire(
+            &format!("{}.variance", name),
+            model,
+            &sqr,
+        )?;
+        let epsilon = tensor0(self.epsilon)
+            .cast_to_dt(fact.datum_type)?
+            .into_owned()
+            .broadcast_into_rank(fact.rank())?;
+        let var = model.wire_node(
+            format!("{}.epsilon", name),
+            math::add::unary(epsilon.into_arc_tensor()),
+            &var,
+        )?;
+        let mut stats =
+            [mean[0], model.wire_node(format!("{}.rsqrt", name), math::rsqrt(), &var)?[0]];
+        if fact.datum_type != f32::datum_type() {
+            for (wire, suffix) in stats.iter_mut().zip(["mean", "inv_std_dev"]) {
+                *wire = model.wire_node(
+                    format!("{}.{}.cast", name, suffix),
+                    tract_core::ops::cast::cast(f32::datum_type()),
+                    &[*wire],
+                )?[0];
+            }
+        }
+        Ok((stats[0], stats[1]))
+    }
+}
+
+impl Expansion for LayerNormalization {
+    fn name(&self) -> Cow<str> {
+        "LayerNormalization".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axis: {} epsilon: {}", self.axis, self.epsilon)])
+    }
+
+    op_onnx!();
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(1 + self.optional_mean_output.is_some() as usize
+            + self.optional_inv_std_dev_output.is_some() as usize)
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2 + self.optional_bias_input.is_some() as usize)?;
+        check_output_arity(outputs, self.nboutputs()?)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].datum_type, &inputs[1].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        if let Some(bias) = self.optional_bias_input {
+            s.equals(&inputs[0].datum_type, &inputs[bias].datum_type)?;
+        }
+        for ix in [self.optional_mean_output, self.optional_inv_std_dev_output].iter().flatten() {
+            let stat = &outputs[*ix];
+            s.equals(&stat.datum_type, f32::datum_type())?;
+            s.equals(&stat.rank, &inputs[0].rank)?;
+            s.given(&inputs[0].rank, move |s, rank| {
+                let axis = self.resolve_axis(rank as usize)?;
+                for i in 0..rank as usize {
+                    if i < axis {
+                        s.equals(&stat.shape[i], &inputs[0].shape[i])?;
+                    } else {
+                        s.equals(&stat.shape[i], 1.to_dim())?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        name: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axis = self.resolve_axis(model.outlet_fact(inputs[0])?.rank())?;
+        let normed = model.wire_node(
+            format!("{}.normed", name),
+            LayerNorm::new(axis, self.epsilon),
+            &inputs[0..1],
+        )?;
+        let y = if let Some(bias) = self.optional_bias_input {
+            let scaled = wire_with_rank_broadcast(
+                &format!("{}.scaled", name),
+                model,
+                math::mul::bin_typed(),
+                &[normed[0], inputs[1]],
+            )?;
+            wire_with_rank_broadcast(
+                name,
+                model,
+                math::add::bin_typed(),
+                &[scaled[0], inputs[bias]],
+            )?
+        } else {
+            wire_with_rank_broadcast(name, model, math::mul::bin_typed(), &[normed[0], inputs[1]])?
+        };
+        let mut outputs = tvec!(y[0]);
+        if self.optional_mean_output.is_some() || self.optional_inv_std_dev_output.is_some() {
+            let (mean, inv_std_dev) = self.wire_statistics(name, model, inputs[0], axis)?;
+            if self.optional_mean_output.is_some() {
+                outputs.push(mean);
+            }
+            if self.optional_inv_std_dev_output.is_some() {
+                outputs.push(inv_std_dev);
+            }
+        }
+        Ok(outputs)
+    }
+}
@@ -9,9 +9,13 @@ use crate::pb_helpers::OptionExt;
 mod batch_norm;
 mod conv_transpose;
 mod dropout;
+mod grid_sample;
 mod instance_norm;
+mod layer_norm;
 mod lrn;
+mod non_max_suppression;
 mod reduce;
+mod roi_align;
 
 pub fn arg_max_min(
     _ctx: &ParsingContext,
@@ -41,38 +45,53 @@ pub fn register_all_ops(reg: &mut OnnxOpRegister) {
     reg.insert("GlobalAveragePool", |_, _| Ok((expand(ops::nn::GlobalAvgPool), vec![])));
     reg.insert("GlobalLpPool", global_lp_pool);
     reg.insert("GlobalMaxPool", |_, _| Ok((expand(ops::nn::GlobalMaxPool), vec![])));
-    reg.insert("Hardmax", layer_hard_max);
+    reg.insert_since("Hardmax", 1, layer_hard_max);
+    reg.insert_since("Hardmax", 13, layer_hard_max13);
+    reg.insert("GridSample", grid_sample::grid_sample);
     reg.insert("HardSigmoid", hard_sigmoid);
     reg.insert("InstanceNormalization", instance_norm::instance_normalization);
+    reg.insert("LayerNormalization", layer_norm::layer_normalization);
     reg.insert("LeakyRelu", leaky_relu);
-    reg.insert("LogSoftmax", layer_log_soft_max);
+    reg.insert_since("LogSoftmax", 1, layer_log_soft_max);
+    reg.insert_since("LogSoftmax", 13, layer_log_soft_max13);
     reg.insert("LRN", lrn::lrn);
     reg.insert("MaxPool", max_pool);
+    reg.insert("NonMaxSuppression", non_max_suppression::non_max_suppression);
     reg.insert("ParametricSoftplus", parametric_softplus);
     reg.insert("QLinearConv", conv_qlinear);
     reg.insert("PRelu", |_, _| Ok((expand(Prelu), vec![])));
-    reg.insert("ReduceL1", |c, node| reduce::reduce(c, node, nn::Reducer::L1));
-    reg.insert("ReduceL2", |c, node| reduce::reduce(c, node, nn::Reducer::L2));
-    reg.insert("ReduceLogSum", |c, node| reduce::reduce(c, node, nn::Reducer::LogSum));
-    reg.insert("ReduceLogSumExp", |c, node| reduce::reduce(c, node, nn::Reducer::LogSumExp));
-    reg.insert("ReduceMax", |c, node| reduce::reduce(c, node, nn::Reducer::Max));
-    reg.insert("ReduceMean", |c, node| reduce::reduce(c, node, nn::Reducer::Mean));
-    reg.insert("ReduceMin", |c, node| reduce::reduce(c, node, nn::Reducer::Min));
-    reg.insert("ReduceProd", |c, node| reduce::reduce(c, node, nn::Reducer::Prod));
-    reg.insert("ReduceSum", |c, node| reduce::reduce(c, node, nn::Reducer::Sum));
-    reg.insert("ReduceSumSquare", |c, node| reduce::reduce(c, node, nn::Reducer::SumSquare));
+    macro_rules! reduce {
+        ($name: expr, $reducer: ident, $axes_input_since: expr) => {
+            reg.insert_since($name, 1, |c, node| reduce::reduce(c, node, nn::Reducer::$reducer));
+            reg.insert_since($name, $axes_input_since, |c, node| {
+                reduce::reduce13(c, node, nn::Reducer::$reducer)
+            });
+        };
+    }
+    reduce!("ReduceL1", L1, 18);
+    reduce!("ReduceL2", L2, 18);
+    reduce!("ReduceLogSum", LogSum, 18);
+    reduce!("ReduceLogSumExp", LogSumExp, 18);
+    reduce!("ReduceMax", Max, 18);
+    reduce!("ReduceMean", Mean, 18);
+    reduce!("ReduceMin", Min, 18);
+    reduce!("ReduceProd", Prod, 18);
+    reduce!("ReduceSum", Sum, 13);
+    reduce!("ReduceSumSquare", SumSquare, 18);
+    reg.insert("RoiAlign", roi_align::roi_align);
     reg.insert("Relu", |_, _| Ok((expand(ops::activations::Clip::new(Some(0.0), None)), vec![])));
     reg.insert("ScaledTanh", scaled_tanh);
     reg.insert("Shrink", shrink);
     reg.insert("ThresholdedRelu", thresholded_relu);
     reg.insert("Selu", selu);
     reg.insert("Sigmoid", |_, _| Ok((Box::new(ops::nn::sigmoid()), vec![])));
-    reg.insert("Softmax", layer_soft_max);
+    reg.insert_since("Softmax", 1, layer_soft_max);
+    reg.insert_since("Softmax", 13, layer_soft_max13);
     reg.insert("Softplus", |_, _| Ok((expand(ops::activations::Softplus), vec![])));
     reg.insert("Softsign", |_, _| Ok((expand(ops::activations::Softsign), vec![])));
 }
 
-fn pad(node: &NodeProto) -> TractResult<cnn::PaddingSpec> {
+pub(crate) fn pad(node: &NodeProto) -> TractResult<cnn::PaddingSpec> {
     let ceil_mode = node.get_attr_opt::<isize>("ceil_mode")?.unwrap_or(0) == 1;
     let default = match node.get_attr_opt_vec::<isize>("kernel_shape")? {
         Some(shape) => {
@@ -109,7 +128,7 @@ fn dilations(node: &NodeProto) -> TractResult<Option<TVec<usize>>> {
     node.get_attr_opt_tvec("dilations")
 }
 
-fn strides(node: &NodeProto) -> TractResult<Option<TVec<usize>>> {
+pub(crate) fn strides(node: &NodeProto) -> TractResult<Option<TVec<usize>>> {
     node.get_attr_opt_tvec("strides")
 }
 
@@ -231,39 +250,51 @@ pub fn hard_sigmoid(
 }
 
 pub fn layer_hard_max(
-    ctx: &ParsingContext,
+    _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let axis = node.get_attr_opt("axis")?;
-    if ctx.onnx_operator_set_version < 13 {
-        Ok((expand(ops::nn::LayerHardmax::new(axis.unwrap_or(1), true)), vec![]))
-    } else {
-        Ok((expand(ops::nn::LayerHardmax::new(axis.unwrap_or(-1), false)), vec![]))
-    }
+    Ok((expand(ops::nn::LayerHardmax::new(axis.unwrap_or(1), true)), vec![]))
+}
+
+pub fn layer_hard_max13(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?;
+    Ok((expand(ops::nn::LayerHardmax::new(axis.unwrap_or(-1), false)), vec![]))
 }
 
 pub fn layer_log_soft_max(
-    ctx: &ParsingContext,
+    _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let axis = node.get_attr_opt("axis")?;
-    if ctx.onnx_operator_set_version < 13 {
-        Ok((expand(ops::nn::LayerLogSoftmax::new(axis.unwrap_or(1), true)), vec![]))
-    } else {
-        Ok((expand(ops::nn::LayerLogSoftmax::new(axis.unwrap_or(-1), false)), vec![]))
-    }
+    Ok((expand(ops::nn::LayerLogSoftmax::new(axis.unwrap_or(1), true)), vec![]))
+}
+
+pub fn layer_log_soft_max13(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?;
+    Ok((expand(ops::nn::LayerLogSoftmax::new(axis.unwrap_or(-1), false)), vec![]))
 }
 
 pub fn layer_soft_max(
-    ctx: &ParsingContext,
+    _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
     let axis = node.get_attr_opt("axis")?;
-    if ctx.onnx_operator_set_version < 13 {
-        Ok((expand(ops::nn::LayerSoftmax::new(axis.unwrap_or(1), true)), vec![]))
-    } else {
-        Ok((expand(ops::nn::LayerSoftmax::new(axis.unwrap_or(-1), false)), vec![]))
-    }
+    Ok((expand(ops::nn::LayerSoftmax::new(axis.unwrap_or(1), true)), vec![]))
+}
+
+pub fn layer_soft_max13(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?;
+    Ok((expand(ops::nn::LayerSoftmax::new(axis.unwrap_or(-1), false)), vec![]))
 }
 
 pub fn leaky_relu(
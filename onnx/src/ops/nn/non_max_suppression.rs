@@ -0,0 +1,128 @@
+use tract_core::ops::nn::BoxRepr;
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn non_max_suppression(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let center_point_box = node.get_attr_opt("center_point_box")?.unwrap_or(0i64);
+    let box_repr = match center_point_box {
+        0 => BoxRepr::TwoPoints,
+        1 => BoxRepr::CenterWidthHeight,
+        other => bail!("Unsupported center_point_box value {}", other),
+    };
+    let mut options = crate::model::optional_inputs(node).skip(2);
+    let op = NonMaxSuppression::new(
+        box_repr,
+        options.next().unwrap(),
+        options.next().unwrap(),
+        options.next().unwrap(),
+        Symbol::new('n'),
+    );
+    Ok((Box::new(op), vec![]))
+}
+
+#[derive(Debug, Clone, new, Hash)]
+struct NonMaxSuppression {
+    box_repr: BoxRepr,
+    optional_max_output_boxes_per_class_input: Option<usize>,
+    optional_iou_threshold_input: Option<usize>,
+    optional_score_threshold_input: Option<usize>,
+    num_selected: Symbol,
+}
+
+impl_dyn_hash!(NonMaxSuppression);
+
+impl NonMaxSuppression {
+    fn core_op(&self) -> tract_core::ops::nn::NonMaxSuppression {
+        tract_core::ops::nn::NonMaxSuppression::new(self.box_repr, self.num_selected)
+    }
+
+    /// Optional inputs, with the tensors standing in for the missing ones.
+    ///
+    /// A missing score threshold keeps all boxes. f32::MIN is used instead of
+    /// -inf as the latter has no NNEF literal.
+    fn optional_inputs(&self) -> [(Option<usize>, &'static str, Arc<Tensor>); 3] {
+        [
+            (
+                self.optional_max_output_boxes_per_class_input,
+                "max_output_boxes_per_class",
+                rctensor0(0i64),
+            ),
+            (self.optional_iou_threshold_input, "iou_threshold", rctensor0(0f32)),
+            (self.optional_score_threshold_input, "score_threshold", rctensor0(std::f32::MIN)),
+        ]
+    }
+}
+
+impl Op for NonMaxSuppression {
+    fn name(&self) -> Cow<str> {
+        "NonMaxSuppression".into()
+    }
+
+    op_onnx!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for NonMaxSuppression {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut full = tvec!(inputs[0].clone(), inputs[1].clone());
+        for (input, _, default) in self.optional_inputs().iter() {
+            full.push(input.map(|ix| inputs[ix].clone()).unwrap_or_else(|| default.clone()));
+        }
+        self.core_op().eval(full)
+    }
+}
+
+impl InferenceRulesOp for NonMaxSuppression {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(
+            &inputs,
+            2 + self.optional_max_output_boxes_per_class_input.is_some() as usize
+                + self.optional_iou_threshold_input.is_some() as usize
+                + self.optional_score_threshold_input.is_some() as usize,
+        )?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].rank, 3)?;
+        s.equals(&inputs[1].rank, 3)?;
+        s.equals(&inputs[0].shape[0], &inputs[1].shape[0])?;
+        s.equals(&inputs[0].shape[1], &inputs[1].shape[2])?;
+        s.equals(&inputs[0].shape[2], 4.to_dim())?;
+        s.equals(&outputs[0].datum_type, i64::datum_type())?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[1], 3.to_dim())?;
+        Ok(())
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let mut inputs = tvec!(mapping[&node.inputs[0]], mapping[&node.inputs[1]]);
+        for (input, name, default) in self.optional_inputs().iter() {
+            inputs.push(if let Some(ix) = input {
+                mapping[&node.inputs[*ix]]
+            } else {
+                target.add_const(format!("{}.{}", node.name, name), default.clone())?
+            });
+        }
+        target.wire_node(&*node.name, self.core_op(), &inputs)
+    }
+
+    as_op!();
+}
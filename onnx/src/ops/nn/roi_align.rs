@@ -0,0 +1,51 @@
+use tract_hir::internal::*;
+use tract_core::ops::nn::{RoiAlign, RoiAlignMode};
+
+use crate::model::ParsingContext;
+use crate::pb::NodeProto;
+
+pub fn roi_align(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let mode = match node.get_attr_opt("mode")?.unwrap_or("avg") {
+        "avg" => RoiAlignMode::Avg,
+        "max" => RoiAlignMode::Max,
+        other => bail!("Unsupported RoiAlign mode {}", other),
+    };
+    let default_transform =
+        if ctx.onnx_operator_set_version >= 16 { "half_pixel" } else { "output_half_pixel" };
+    let aligned =
+        match node.get_attr_opt("coordinate_transformation_mode")?.unwrap_or(default_transform) {
+            "half_pixel" => true,
+            "output_half_pixel" => false,
+            other => bail!("Unsupported RoiAlign coordinate_transformation_mode {}", other),
+        };
+    let output_height = node.get_attr_opt("output_height")?.unwrap_or(1);
+    let output_width = node.get_attr_opt("output_width")?.unwrap_or(1);
+    let sampling_ratio = node.get_attr_opt("sampling_ratio")?.unwrap_or(0);
+    let spatial_scale = node.get_attr_opt("spatial_scale")?.unwrap_or(1.0);
+    let op =
+        RoiAlign::new(mode, output_height, output_width, sampling_ratio, spatial_scale, aligned);
+    Ok((inference_wrap(op, 1, rules), vec![]))
+}
+
+fn rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 3)?;
+    check_output_arity(&outputs, 1)?;
+    s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+    s.equals(&inputs[0].rank, 4)?;
+    s.equals(&inputs[1].rank, 2)?;
+    s.equals(&inputs[1].shape[1], 4.to_dim())?;
+    s.equals(&inputs[2].rank, 1)?;
+    s.equals(&inputs[1].shape[0], &inputs[2].shape[0])?;
+    s.equals(&outputs[0].rank, 4)?;
+    s.equals(&outputs[0].shape[0], &inputs[1].shape[0])?;
+    s.equals(&outputs[0].shape[1], &inputs[0].shape[1])?;
+    Ok(())
+}
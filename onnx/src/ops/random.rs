@@ -0,0 +1,245 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::random::{Bernoulli, Distribution, Multinomial, Random};
+
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("Bernoulli", bernoulli);
+    reg.insert("Multinomial", multinomial);
+    reg.insert("RandomNormal", random);
+    reg.insert("RandomNormalLike", random_like);
+    reg.insert("RandomUniform", random);
+    reg.insert("RandomUniformLike", random_like);
+}
+
+/// ONNX seeds are floats, only their bits matter.
+fn seed(node: &NodeProto) -> TractResult<Option<u64>> {
+    Ok(node.get_attr_opt::<f32>("seed")?.map(|seed| seed.to_bits() as u64))
+}
+
+fn distribution(node: &NodeProto) -> TractResult<Distribution> {
+    if node.op_type.starts_with("RandomNormal") {
+        let mean = node.get_attr_opt("mean")?.unwrap_or(0.0);
+        let scale = node.get_attr_opt("scale")?.unwrap_or(1.0);
+        Ok(Distribution::Normal { mean, scale })
+    } else {
+        let low = node.get_attr_opt("low")?.unwrap_or(0.0);
+        let high = node.get_attr_opt("high")?.unwrap_or(1.0);
+        Ok(Distribution::Uniform { low, high })
+    }
+}
+
+fn random(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let dt = node.get_attr_opt("dtype")?.unwrap_or(f32::datum_type());
+    let shape: TVec<usize> = node.get_attr_tvec("shape")?;
+    let fact = TypedFact::dt_shape(dt, &*shape);
+    let op = Random::new(fact, distribution(node)?, seed(node)?);
+    Ok((inference_wrap(op, 1, random_rules), vec![]))
+}
+
+fn random_rules<'r, 'p, 's>(
+    op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    let op = op.downcast_ref::<Random>().unwrap();
+    check_input_arity(&inputs, 0)?;
+    check_output_arity(&outputs, 1)?;
+    s.equals(&outputs[0].datum_type, op.fact.datum_type)?;
+    s.equals(&outputs[0].shape, op.fact.shape.to_tvec())?;
+    Ok(())
+}
+
+fn random_like(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let dt = node.get_attr_opt("dtype")?;
+    Ok((expand(RandomLike { dt, dist: distribution(node)?, seed: seed(node)? }), vec![]))
+}
+
+/// Random tensor of the shape (and, by default, the type) of its input.
+#[derive(Debug, Clone, Hash)]
+struct RandomLike {
+    dt: Option<DatumType>,
+    dist: Distribution,
+    seed: Option<u64>,
+}
+
+impl_dyn_hash!(RandomLike);
+
+impl Expansion for RandomLike {
+    fn name(&self) -> Cow<str> {
+        match self.dist {
+            Distribution::Uniform { .. } => "RandomUniformLike".into(),
+            Distribution::Normal { .. } => "RandomNormalLike".into(),
+        }
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        if let Some(dt) = self.dt {
+            s.equals(&outputs[0].datum_type, dt)?;
+        } else {
+            s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        }
+        s.equals(&outputs[0].shape, &inputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = model.outlet_fact(inputs[0])?;
+        let fact = TypedFact::dt_shape(self.dt.unwrap_or(input.datum_type), &*input.shape);
+        model.wire_node(prefix, Random::new(fact, self.dist, self.seed), &[])
+    }
+}
+
+fn bernoulli(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let dt = node.get_attr_opt("dtype")?;
+    Ok((expand(OnnxBernoulli { dt, seed: seed(node)? }), vec![]))
+}
+
+/// Bernoulli, whose output type defaults to its input one.
+#[derive(Debug, Clone, Hash)]
+struct OnnxBernoulli {
+    dt: Option<DatumType>,
+    seed: Option<u64>,
+}
+
+impl_dyn_hash!(OnnxBernoulli);
+
+impl Expansion for OnnxBernoulli {
+    fn name(&self) -> Cow<str> {
+        "Bernoulli".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        if let Some(dt) = self.dt {
+            s.equals(&outputs[0].datum_type, dt)?;
+        } else {
+            s.equals(&outputs[0].datum_type, &inputs[0].datum_type)?;
+        }
+        s.equals(&outputs[0].shape, &inputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let dt = self.dt.unwrap_or(model.outlet_fact(inputs[0])?.datum_type);
+        model.wire_node(prefix, Bernoulli::new(dt, self.seed), inputs)
+    }
+}
+
+fn multinomial(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let dt = node.get_attr_opt("dtype")?.unwrap_or(i32::datum_type());
+    let sample_size = node.get_attr_opt("sample_size")?.unwrap_or(1);
+    let op = Multinomial::new(dt, sample_size, seed(node)?);
+    Ok((inference_wrap(op, 1, multinomial_rules), vec![]))
+}
+
+fn multinomial_rules<'r, 'p, 's>(
+    op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    let op = op.downcast_ref::<Multinomial>().unwrap();
+    check_input_arity(&inputs, 1)?;
+    check_output_arity(&outputs, 1)?;
+    s.equals(&outputs[0].datum_type, op.dt)?;
+    s.equals(&inputs[0].rank, 2)?;
+    s.equals(&outputs[0].rank, 2)?;
+    s.equals(&outputs[0].shape[0], &inputs[0].shape[0])?;
+    s.equals(&outputs[0].shape[1], op.sample_size.to_dim())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn seeded_uniform_like() -> TractResult<()> {
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[2, 3]);
+        let graph = GraphProto {
+            node: vec![NodeProto {
+                op_type: "RandomUniformLike".into(),
+                input: vec!["x".into()],
+                output: vec!["y".into()],
+                attribute: vec![
+                    AttributeProto {
+                        name: "low".into(),
+                        r#type: attribute_proto::AttributeType::Float as i32,
+                        f: -1.0,
+                        ..AttributeProto::default()
+                    },
+                    AttributeProto {
+                        name: "seed".into(),
+                        r#type: attribute_proto::AttributeType::Float as i32,
+                        f: 3.0,
+                        ..AttributeProto::default()
+                    },
+                ],
+                ..NodeProto::default()
+            }],
+            input: vec![ValueInfoProto {
+                name: "x".into(),
+                r#type: Some((&fact).try_into()?),
+                ..ValueInfoProto::default()
+            }],
+            output: vec![ValueInfoProto { name: "y".into(), ..ValueInfoProto::default() }],
+            ..GraphProto::default()
+        };
+        let proto = ModelProto {
+            opset_import: vec![OperatorSetIdProto { domain: "".into(), version: 13 }],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let model = crate::onnx().model_for_proto_model(&proto)?.into_optimized()?;
+        let model = model.into_runnable()?;
+        let run = || model.run(tvec!(Tensor::zero::<f32>(&[2, 3])?));
+        let (a, b) = (run()?, run()?);
+        assert_eq!(a, b);
+        assert_eq!(a[0].shape(), &[2, 3]);
+        assert!(a[0].as_slice::<f32>()?.iter().all(|x| (-1.0..1.0).contains(x)));
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use crate::model::{ParseResult, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+
+use tract_core::ops::scan::Loop as TypedLoop;
+
+pub fn _loop(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let graph: &GraphProto = node.get_attr("body")?;
+    let ParseResult { model: mut body, unresolved_inputs, .. } = ctx.parse_graph(graph)?;
+    // loop-carried values may change shape from one iteration to the next:
+    // only keep the datum types declared for the body outputs
+    for ix in 0..body.output_outlets()?.len() {
+        let fact = body.output_fact_mut(ix)?;
+        *fact = InferenceFact { datum_type: fact.datum_type.clone(), ..InferenceFact::default() };
+    }
+    let present = |ix: usize| node.input.get(ix).map(|s| !s.is_empty()).unwrap_or(false);
+    let carried = node.input.len().saturating_sub(2);
+    let op = Loop {
+        source_body: body.clone(),
+        body,
+        has_trip_count: present(0),
+        has_cond: present(1),
+        carried,
+        growing: tvec!(tvec!(); carried),
+    };
+    Ok((Box::new(op), unresolved_inputs))
+}
+
+/// ONNX Loop.
+///
+/// Node inputs are the optional trip count and condition (absent ones are not
+/// wired), the loop-carried dependencies and the closures of the body.
+///
+/// Shapes only flow from the outer inputs to the body inputs and from the body
+/// outputs to the outer outputs.
+///
+/// A loop-carried value may change shape from one iteration to the next. When
+/// the body computes a dimension different from the one it received, the
+/// analysis starts over from `source_body`, the body as parsed, with a fresh
+/// symbol for this dimension of the body input.
+#[derive(Debug, Clone, Hash)]
+struct Loop {
+    body: InferenceModel,
+    source_body: InferenceModel,
+    has_trip_count: bool,
+    has_cond: bool,
+    carried: usize,
+    /// For each carried value, the axes along which it changes.
+    growing: TVec<TVec<(usize, Symbol)>>,
+}
+
+impl_dyn_hash!(Loop);
+
+impl Loop {
+    fn first_carried(&self) -> usize {
+        self.has_trip_count as usize + self.has_cond as usize
+    }
+
+    fn to_typed_loop(&self) -> TractResult<TypedLoop> {
+        let body = self.body.clone().into_typed()?;
+        TypedLoop::new(body, self.has_trip_count, self.has_cond, self.carried)
+    }
+
+    /// Shapes of a carried value: the outer input and the body input agree
+    /// except on growing axes, where the body input gets its own symbol. The
+    /// outer output only gets the dimensions the body leaves unchanged.
+    fn unify_carried_shapes(
+        &mut self,
+        ix: usize,
+        input: &mut InferenceFact,
+        output: &mut InferenceFact,
+    ) -> TractResult<bool> {
+        let mut changed = false;
+        let growing = &self.growing[ix];
+        let is_growing = |axis: usize| growing.iter().any(|g| g.0 == axis);
+        let body_input = self.body.input_fact_mut(2 + ix)?;
+        let mut rank = input.shape.rank().unify(&body_input.shape.rank())?;
+        rank = rank.unify(&output.shape.rank())?;
+        let rank =
+            if let Some(rank) = rank.concretize() { rank as usize } else { return Ok(false) };
+        let outer_dims =
+            (0..rank).map(|axis| input.shape.dim(axis).unwrap_or_default()).collect::<TVec<_>>();
+        let body_dims = (0..rank)
+            .map(|axis| {
+                if let Some(g) = growing.iter().find(|g| g.0 == axis) {
+                    GenericFactoid::Only(g.1.to_dim())
+                } else {
+                    outer_dims[axis].clone()
+                }
+            })
+            .collect();
+        changed |= body_input.shape.unify_with(&ShapeFactoid::closed(body_dims))?;
+        let body_input_dims =
+            (0..rank).map(|axis| body_input.shape.dim(axis).unwrap()).collect::<TVec<_>>();
+        let outer_dims = (0..rank)
+            .map(|axis| {
+                if is_growing(axis) {
+                    outer_dims[axis].clone()
+                } else {
+                    body_input_dims[axis].clone()
+                }
+            })
+            .collect();
+        changed |= input.shape.unify_with(&ShapeFactoid::closed(outer_dims))?;
+
+        let body_output = self.body.output_fact(1 + ix)?.shape.clone();
+        let mut grown = tvec!();
+        let mut output_dims = tvec!();
+        for axis in 0..rank {
+            let (i, o) = (&body_input_dims[axis], body_output.dim(axis).unwrap_or_default());
+            match (i.concretize(), o.concretize()) {
+                (Some(i), Some(o)) if i == o => output_dims.push(GenericFactoid::Only(o)),
+                (Some(_), Some(_)) if !is_growing(axis) => {
+                    grown.push((axis, Symbol::new('g')));
+                    output_dims.push(GenericFactoid::Any)
+                }
+                _ => output_dims.push(GenericFactoid::Any),
+            }
+        }
+        if grown.len() > 0 {
+            self.growing[ix].extend(grown);
+            self.body = self.source_body.clone();
+            return Ok(true);
+        }
+        changed |= output.shape.unify_with(&ShapeFactoid::closed(output_dims))?;
+        Ok(changed)
+    }
+
+    fn unify_facts(
+        &mut self,
+        inputs: &mut [InferenceFact],
+        outputs: &mut [InferenceFact],
+    ) -> TractResult<bool> {
+        let mut changed = false;
+        let first_carried = self.first_carried();
+        if self.has_trip_count {
+            changed |= inputs[0].datum_type.unify_with(&i64::datum_type().into())?;
+        }
+        if self.has_cond {
+            changed |=
+                inputs[first_carried - 1].datum_type.unify_with(&bool::datum_type().into())?;
+        }
+        let scalar = ShapeFactoid::closed(tvec!());
+        for (ix, dt) in [(0, i64::datum_type()), (1, bool::datum_type())].iter() {
+            let fact = self.body.input_fact_mut(*ix)?;
+            changed |= fact.datum_type.unify_with(&(*dt).into())?;
+            if fact.shape.rank().concretize().is_none() {
+                changed |= fact.shape.unify_with(&scalar)?;
+            }
+        }
+        changed |=
+            self.body.output_fact_mut(0)?.datum_type.unify_with(&bool::datum_type().into())?;
+        for ix in 0..self.carried {
+            {
+                let mut facts = self.body.outlets_fact_mut(&[
+                    self.body.input_outlets()?[2 + ix],
+                    self.body.output_outlets()?[1 + ix],
+                ])?;
+                facts.push(&mut inputs[first_carried + ix]);
+                facts.push(&mut outputs[ix]);
+                changed |= Factoid::unify_all(
+                    &mut *facts.iter_mut().map(|f| &mut f.datum_type).collect::<TVec<_>>(),
+                )?;
+            }
+            changed |=
+                self.unify_carried_shapes(ix, &mut inputs[first_carried + ix], &mut outputs[ix])?;
+        }
+        for ix in first_carried + self.carried..inputs.len() {
+            let body_ix = ix - first_carried + 2;
+            changed |= inputs[ix].unify_with_mut(self.body.input_fact_mut(body_ix)?)?;
+        }
+        for ix in self.carried..outputs.len() {
+            let inner = self.body.output_fact_mut(ix + 1)?;
+            let outer = &mut outputs[ix];
+            changed |= outer.datum_type.unify_with_mut(&mut inner.datum_type)?;
+            if inner.shape.rank().concretize().is_some() {
+                let shape = std::iter::once(GenericFactoid::Any)
+                    .chain(inner.shape.dims().cloned())
+                    .collect::<TVec<_>>();
+                changed |= outer.shape.unify_with(&ShapeFactoid::closed(shape))?;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+impl Op for Loop {
+    fn name(&self) -> Cow<str> {
+        "Loop".into()
+    }
+
+    op_onnx!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for Loop {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        self.to_typed_loop()?.state(session, node_id)
+    }
+}
+
+impl InferenceOp for Loop {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let body_inputs = self.body.input_outlets()?.len();
+        let body_outputs = self.body.output_outlets()?.len();
+        if inputs.len() + 2 != body_inputs + self.first_carried() {
+            bail!("Loop receives {} inputs, body expects {}", inputs.len(), body_inputs)
+        }
+        if outputs.len() + 1 != body_outputs {
+            bail!("Loop has {} outputs, body computes {}", outputs.len(), body_outputs)
+        }
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = self.unify_facts(&mut inputs, &mut outputs)?;
+            changed |= self.body.analyse(false).context("analysing inner model")?;
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs = node.inputs.iter().map(|m| mapping[m]).collect::<TVec<_>>();
+        target.wire_node(&*node.name, self.to_typed_loop()?, &*inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len() - 1)
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn value(name: &str, fact: Option<TypedFact>) -> TractResult<ValueInfoProto> {
+        let r#type = if let Some(fact) = fact { (&fact).try_into()? } else { TypeProto::default() };
+        Ok(ValueInfoProto { name: name.into(), r#type: Some(r#type), ..ValueInfoProto::default() })
+    }
+
+    fn node(op_type: &str, input: &[&str], output: &[&str]) -> NodeProto {
+        NodeProto {
+            op_type: op_type.into(),
+            input: input.iter().map(|s| s.to_string()).collect(),
+            output: output.iter().map(|s| s.to_string()).collect(),
+            ..NodeProto::default()
+        }
+    }
+
+    #[test]
+    fn growing_carried_value() -> TractResult<()> {
+        // acc_out = concat(acc_in, x) on each iteration
+        let body = GraphProto {
+            node: vec![
+                node("Identity", &["cond_in"], &["cond_out"]),
+                NodeProto {
+                    attribute: vec![AttributeProto {
+                        name: "axis".into(),
+                        r#type: attribute_proto::AttributeType::Int as i32,
+                        i: 0,
+                        ..AttributeProto::default()
+                    }],
+                    ..node("Concat", &["acc_in", "x"], &["acc_out"])
+                },
+            ],
+            input: vec![value("i", None)?, value("cond_in", None)?, value("acc_in", None)?],
+            output: vec![value("cond_out", None)?, value("acc_out", None)?],
+            ..GraphProto::default()
+        };
+        let graph = GraphProto {
+            node: vec![NodeProto {
+                attribute: vec![AttributeProto {
+                    name: "body".into(),
+                    r#type: attribute_proto::AttributeType::Graph as i32,
+                    g: Some(body),
+                    ..AttributeProto::default()
+                }],
+                ..node("Loop", &["trip", "", "acc"], &["acc_final"])
+            }],
+            input: vec![
+                value("trip", Some(TypedFact::dt_shape(i64::datum_type(), &[0usize; 0])))?,
+                value("acc", Some(TypedFact::dt_shape(f32::datum_type(), &[2])))?,
+                value("x", Some(TypedFact::dt_shape(f32::datum_type(), &[1])))?,
+            ],
+            output: vec![value("acc_final", None)?],
+            ..GraphProto::default()
+        };
+        let proto = ModelProto {
+            opset_import: vec![OperatorSetIdProto { domain: "".into(), version: 13 }],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let inputs = || tvec!(tensor0(3i64), tensor1(&[0f32, 1.]), tensor1(&[2f32]));
+        let expected = tensor1(&[0f32, 1., 2., 2., 2.]);
+        let model = crate::onnx().model_for_proto_model(&proto)?.into_typed()?;
+        let outputs = model.clone().into_runnable()?.run(inputs())?;
+        assert_eq!(*outputs[0], expected);
+        let model = model.into_optimized()?;
+        assert!(model.output_fact(0)?.shape[0].to_usize().is_err());
+        let outputs = model.into_runnable()?.run(inputs())?;
+        assert_eq!(*outputs[0], expected);
+        Ok(())
+    }
+}
@@ -1,172 +1,235 @@
 use crate::model::ParsingContext;
 use crate::pb::*;
-use std::hash::Hash;
+use tract_core::ops::resize::{CoordTransformer, Interpolator, Nearest, ResizeAxis};
 use tract_hir::internal::*;
 
 pub fn resize(
-    _ctx: &ParsingContext,
+    ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if ctx.onnx_operator_set_version < 11 {
+        let mut op = Resize::legacy(node)?;
+        op.optional_scales_input = Some(1);
+        return Ok((expand(op), vec![]));
+    }
     let coord_transformer =
         match node.get_attr_opt("coordinate_transformation_mode")?.unwrap_or("half_pixel") {
-            "align_corners" => CoordTransformer::AlignCorners,
             "half_pixel" => CoordTransformer::HalfPixel,
-            s => todo!("coordinate_transformation_mode: {}", s),
+            "half_pixel_symmetric" => CoordTransformer::HalfPixelSymmetric,
+            "pytorch_half_pixel" => CoordTransformer::PytorchHalfPixel,
+            "align_corners" => CoordTransformer::AlignCorners,
+            "asymmetric" => CoordTransformer::Asymmetric,
+            "tf_half_pixel_for_nn" => CoordTransformer::TfHalfPixelForNn,
+            "tf_crop_and_resize" => CoordTransformer::TfCropAndResize,
+            s => bail!("Unsupported coordinate_transformation_mode: {}", s),
         };
-    let interpolator = match node.get_attr("mode")? {
-        "linear" => Interpolator::Linear,
-        s => todo!("mode: {}", s),
-    };
     let nearest = match node.get_attr_opt("nearest_mode")?.unwrap_or("round_prefer_floor") {
-        "floor" => Nearest::Floor,
         "round_prefer_floor" => Nearest::RoundPreferFloor,
-        s => todo!("nearest_mode: {}", s),
+        "round_prefer_ceil" => Nearest::RoundPreferCeil,
+        "floor" => Nearest::Floor,
+        "ceil" => Nearest::Ceil,
+        s => bail!("Unsupported nearest_mode: {}", s),
     };
-    let mut options = crate::model::optional_inputs(node).skip(2);
-    Ok((
-        Box::new(Resize {
-            optional_scales_input: options.next().unwrap(),
-            optional_sizes_input: options.next().unwrap(),
-            coord_transformer,
-            interpolator,
-            nearest,
-        }),
-        vec![],
-    ))
-}
-
-#[derive(Clone, Debug, Hash)]
-enum CoordTransformer {
-    HalfPixel,
-    AlignCorners,
+    let keep_aspect_ratio_policy =
+        match node.get_attr_opt("keep_aspect_ratio_policy")?.unwrap_or("stretch") {
+            "stretch" => KeepAspectRatioPolicy::Stretch,
+            "not_larger" => KeepAspectRatioPolicy::NotLarger,
+            "not_smaller" => KeepAspectRatioPolicy::NotSmaller,
+            s => bail!("Unsupported keep_aspect_ratio_policy: {}", s),
+        };
+    let mut options = crate::model::optional_inputs(node).skip(1);
+    let op = Resize {
+        coord_transformer,
+        interpolator: interpolator(node)?,
+        nearest,
+        cubic_coeff_a: node.get_attr_opt("cubic_coeff_a")?.unwrap_or(-0.75),
+        exclude_outside: node.get_attr_opt("exclude_outside")?.unwrap_or(0i64) != 0,
+        extrapolation_value: node.get_attr_opt("extrapolation_value")?.unwrap_or(0.0),
+        keep_aspect_ratio_policy,
+        axes: node.get_attr_opt_vec("axes")?,
+        optional_roi_input: options.next().unwrap(),
+        optional_scales_input: options.next().unwrap(),
+        optional_sizes_input: options.next().unwrap(),
+        scales: None,
+    };
+    Ok((expand(op), vec![]))
 }
 
-impl CoordTransformer {
-    fn transform(&self, x_out: usize, scale: f32, len_in: usize, len_out: usize) -> f32 {
-        match self {
-            CoordTransformer::HalfPixel => (x_out as f32 + 0.5) * scale - 0.5,
-            CoordTransformer::AlignCorners => {
-                (x_out as f32 * (len_in as f32 - 1.0)) / (len_out as f32 - 1.0)
-            }
-        }
+pub fn upsample(
+    ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let mut op = Resize::legacy(node)?;
+    if ctx.onnx_operator_set_version < 9 {
+        op.scales = Some(rctensor1(&node.get_attr_vec::<f32>("scales")?));
+    } else {
+        op.optional_scales_input = Some(1);
     }
+    Ok((expand(op), vec![]))
 }
 
-#[derive(Clone, Debug, Hash)]
-enum Interpolator {
-    Linear,
-}
-
-impl Interpolator {
-    fn interpolate(&self, y_left: f32, y_right: f32, x_ratio: f32) -> f32 {
-        match self {
-            Interpolator::Linear => y_left * (1.0 - x_ratio) + y_right * x_ratio,
-        }
-    }
+fn interpolator(node: &NodeProto) -> TractResult<Interpolator> {
+    Ok(match node.get_attr_opt("mode")?.unwrap_or("nearest") {
+        "nearest" => Interpolator::Nearest,
+        "linear" | "bilinear" => Interpolator::Linear,
+        "cubic" => Interpolator::Cubic,
+        s => bail!("Unsupported mode: {}", s),
+    })
 }
 
-#[derive(Clone, Debug, Hash)]
-enum Nearest {
-    Floor,
-    RoundPreferFloor,
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+enum KeepAspectRatioPolicy {
+    Stretch,
+    NotLarger,
+    NotSmaller,
 }
 
-#[derive(Clone, new, Debug, Hash)]
+#[derive(Clone, Debug, Educe)]
+#[educe(Hash)]
 struct Resize {
     coord_transformer: CoordTransformer,
     interpolator: Interpolator,
     nearest: Nearest,
+    #[educe(Hash(method = "hash_f32"))]
+    cubic_coeff_a: f32,
+    exclude_outside: bool,
+    #[educe(Hash(method = "hash_f32"))]
+    extrapolation_value: f32,
+    keep_aspect_ratio_policy: KeepAspectRatioPolicy,
+    axes: Option<Vec<i64>>,
+    optional_roi_input: Option<usize>,
     optional_scales_input: Option<usize>,
     optional_sizes_input: Option<usize>,
+    scales: Option<Arc<Tensor>>,
 }
 
 impl_dyn_hash!(Resize);
 
-impl Op for Resize {
-    fn name(&self) -> Cow<str> {
-        "Resize".into()
+impl Resize {
+    /// Upsample and opset 10 Resize: asymmetric coordinates, rounding down
+    /// for nearest interpolation.
+    fn legacy(node: &NodeProto) -> TractResult<Resize> {
+        Ok(Resize {
+            coord_transformer: CoordTransformer::Asymmetric,
+            interpolator: interpolator(node)?,
+            nearest: Nearest::Floor,
+            cubic_coeff_a: -0.75,
+            exclude_outside: false,
+            extrapolation_value: 0.0,
+            keep_aspect_ratio_policy: KeepAspectRatioPolicy::Stretch,
+            axes: None,
+            optional_roi_input: None,
+            optional_scales_input: None,
+            optional_sizes_input: None,
+            scales: None,
+        })
     }
 
-    op_onnx!();
-    op_as_typed_op!();
-}
+    /// Inputs whose values determine the output shape.
+    fn parameter_inputs(&self) -> TVec<usize> {
+        let roi = self
+            .optional_roi_input
+            .filter(|_| self.coord_transformer == CoordTransformer::TfCropAndResize);
+        roi.into_iter()
+            .chain(self.optional_scales_input)
+            .chain(self.optional_sizes_input)
+            .collect()
+    }
 
-impl Resize {
-    fn compute_output_shape(
+    fn resize_axes(
         &self,
-        input_shape: &[usize],
-        input_scale: Option<&Tensor>,
-        input_sizes: Option<&Tensor>,
-    ) -> TractResult<TVec<usize>> {
-        if let Some(scale) = input_scale {
-            if scale.len() == input_shape.len() {
-                let scales = scale.cast_to::<f32>()?;
-                return Ok(input_shape
-                    .iter()
-                    .zip(scales.as_slice::<f32>()?.iter())
-                    .map(|(input, scale)| ((*input as f32) * scale) as usize)
-                    .collect());
+        shape: &[TDim],
+        roi: Option<&Tensor>,
+        scales: Option<&Tensor>,
+        sizes: Option<&Tensor>,
+    ) -> TractResult<TVec<ResizeAxis>> {
+        let rank = shape.len();
+        let axes: TVec<usize> = if let Some(axes) = &self.axes {
+            axes.iter().map(|&axis| if axis < 0 { axis + rank as i64 } else { axis } as usize).collect()
+        } else {
+            (0..rank).collect()
+        };
+        // empty tensors stand for missing inputs
+        let scales = scales.or(self.scales.as_deref()).filter(|t| t.len() > 0);
+        let sizes = sizes.filter(|t| t.len() > 0);
+        let mut resize_axes = tvec!(ResizeAxis::identity(); rank);
+        if let Some(scales) = scales {
+            let scales = scales.cast_to::<f32>()?;
+            let scales = scales.as_slice::<f32>()?;
+            if scales.len() != axes.len() {
+                bail!("Resize: expected {} scales, got {:?}", axes.len(), scales)
+            }
+            for (&axis, &scale) in axes.iter().zip(scales.iter()) {
+                resize_axes[axis] = ResizeAxis::scaled(scale);
+            }
+        } else if let Some(sizes) = sizes {
+            let sizes = sizes.cast_to::<i64>()?;
+            let sizes = sizes.as_slice::<i64>()?;
+            if sizes.len() != axes.len() {
+                bail!("Resize: expected {} sizes, got {:?}", axes.len(), sizes)
+            }
+            let mut ratios = tvec!();
+            for (&axis, &size) in axes.iter().zip(sizes.iter()) {
+                ratios.push(size as f32 / shape[axis].to_usize()? as f32);
             }
+            let policy_scale = match self.keep_aspect_ratio_policy {
+                KeepAspectRatioPolicy::Stretch => None,
+                KeepAspectRatioPolicy::NotLarger => ratios.iter().cloned().reduce(f32::min),
+                KeepAspectRatioPolicy::NotSmaller => ratios.iter().cloned().reduce(f32::max),
+            };
+            for (ix, &axis) in axes.iter().enumerate() {
+                let (size, scale) = if let Some(scale) = policy_scale {
+                    let size = (scale * shape[axis].to_usize()? as f32).round() as usize;
+                    (size, scale)
+                } else {
+                    (sizes[ix] as usize, ratios[ix])
+                };
+                if shape[axis] != size.to_dim() {
+                    resize_axes[axis] = ResizeAxis::sized(size, scale);
+                }
+            }
+        } else {
+            bail!("Resize needs either scales or sizes")
         }
-        if let Some(sizes) = input_sizes {
-            if sizes.len() == input_shape.len() {
-                let size = sizes.cast_to::<i64>()?;
-                return Ok(size.as_slice::<i64>()?.iter().map(|i| *i as usize).collect());
+        if let Some(roi) = roi.filter(|roi| roi.len() > 0) {
+            let roi = roi.cast_to::<f32>()?;
+            let roi = roi.as_slice::<f32>()?;
+            if roi.len() != 2 * axes.len() {
+                bail!("Resize: expected {} roi values, got {:?}", 2 * axes.len(), roi)
+            }
+            for (ix, &axis) in axes.iter().enumerate() {
+                resize_axes[axis].roi_start = roi[ix];
+                resize_axes[axis].roi_end = roi[ix + axes.len()];
             }
         }
-        bail!(
-            "Neither shape not scale makes sense: input_shape: {:?}, scale: {:?}, sizes: {:?}",
-            input_shape, input_scale, input_sizes,
-        );
+        Ok(resize_axes)
     }
-}
 
-impl EvalOp for Resize {
-    fn is_stateless(&self) -> bool {
-        true
+    /// Resize axes from the values of the inputs listed by parameter_inputs.
+    fn resize_axes_from_values(
+        &self,
+        shape: &[TDim],
+        values: &[Arc<Tensor>],
+    ) -> TractResult<TVec<ResizeAxis>> {
+        let mut values = values.iter().map(|v| &**v);
+        let roi = if self.coord_transformer == CoordTransformer::TfCropAndResize {
+            self.optional_roi_input.and_then(|_| values.next())
+        } else {
+            None
+        };
+        let scales = self.optional_scales_input.and_then(|_| values.next());
+        let sizes = self.optional_sizes_input.and_then(|_| values.next());
+        self.resize_axes(shape, roi, scales, sizes)
     }
+}
 
-    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
-        let scales = self.optional_scales_input.and_then(|ix| inputs.get(ix));
-        let sizes = self.optional_sizes_input.and_then(|ix| inputs.get(ix));
-        let output_shape = self.compute_output_shape(
-            inputs[0].shape(),
-            scales.map(|t| &**t),
-            sizes.map(|t| &**t),
-        )?;
-        let mut data = inputs.remove(0).into_tensor().into_array::<f32>()?;
-        for axis in 0..data.ndim() {
-            if output_shape[axis] == data.shape()[axis] {
-                continue;
-            } else if output_shape[axis] > data.shape()[axis] {
-                let scale = output_shape[axis] as f32 / data.shape()[axis] as f32;
-                let mut new_shape: TVec<usize> = data.shape().into();
-                new_shape[axis] = output_shape[axis];
-                data = tract_ndarray::ArrayD::from_shape_fn(&*new_shape, |co_o| -> f32 {
-                    let x_out = co_o[axis];
-                    let x_in = self.coord_transformer.transform(
-                        x_out,
-                        scale,
-                        data.shape()[axis],
-                        new_shape[axis],
-                    );
-                    let mut co_i = co_o.clone();
-                    let x_left = (x_in as usize).min(data.shape()[axis] - 1).max(0);
-                    co_i[axis] = x_left;
-                    let y_left = data[&co_i];
-                    let x_right = (x_left + 1).min(data.shape()[axis] - 1);
-                    co_i[axis] = x_right;
-                    let y_right = data[&co_i];
-                    let x_frac = x_in - x_left as f32;
-                    self.interpolator.interpolate(y_left, y_right, x_frac)
-                })
-            }
-        }
-        Ok(tvec!(data.into_arc_tensor()))
+impl Expansion for Resize {
+    fn name(&self) -> Cow<str> {
+        "Resize".into()
     }
-}
 
-impl InferenceRulesOp for Resize {
+    op_onnx!();
+
     fn rules<'r, 'p: 'r, 's: 'r>(
         &'s self,
         s: &mut Solver<'r>,
@@ -176,97 +239,60 @@ impl InferenceRulesOp for Resize {
         check_output_arity(&outputs, 1)?;
         s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
         s.equals(&inputs[0].rank, &outputs[0].rank)?;
-        if inputs.len() == 3 && self.optional_scales_input == Some(2) {
-            rules_with_scales(self, s, inputs, outputs)
-        } else if inputs.len() == 3 && self.optional_sizes_input == Some(2) {
-            rules_with_sizes(self, s, inputs, outputs)
-        } else {
-            // bogus 4 inputs case
-            s.given_2(
-                &inputs[0].rank,
-                &inputs[self.optional_scales_input.unwrap()].shape,
-                move |s, input_rank, scale_shape| {
-                    if scale_shape.len() == 0 || scale_shape[0] != input_rank.to_dim() {
-                        rules_with_sizes(self, s, inputs, outputs)
-                    } else {
-                        rules_with_scales(self, s, inputs, outputs)
-                    }
-                },
-            )
-        }
+        s.given(&inputs[0].shape, move |s, shape| {
+            let parameters = self.parameter_inputs();
+            if parameters.is_empty() {
+                let axes = self.resize_axes_from_values(&shape, &[])?;
+                return set_output_shape(s, outputs, &shape, &axes);
+            }
+            let values = parameters.iter().map(|&ix| &inputs[ix].value).collect::<Vec<_>>();
+            s.given_all(values, move |s, values| {
+                let axes = self.resize_axes_from_values(&shape, &values)?;
+                set_output_shape(s, outputs, &shape, &axes)
+            })
+        })
     }
 
-    as_op!();
-    to_typed!();
-}
-
-fn rules_with_scales<'r, 'p: 'r, 's: 'r>(
-    op: &'s Resize,
-    s: &mut Solver<'r>,
-    inputs: &'p [TensorProxy],
-    outputs: &'p [TensorProxy],
-) -> InferenceResult {
-    let scales = &inputs[op.optional_scales_input.unwrap()];
-    s.equals(&scales.datum_type, f32::datum_type())?;
-    s.equals(&scales.rank, 1)?;
-    s.equals(&scales.shape[0], inputs[0].rank.bex().to_dim())?;
-    s.given_2(
-        &inputs[0].shape,
-        &inputs[op.optional_scales_input.unwrap()].value,
-        move |s, input_shape, scales| {
-            let input_shape =
-                input_shape.iter().map(|d| d.to_usize()).collect::<TractResult<TVec<usize>>>()?;
-            let output_size = op.compute_output_shape(&input_shape, Some(scales.as_ref()), None)?;
-            let rank = input_shape.len();
-            for i in 0..rank {
-                s.equals(&outputs[0].shape[i], output_size[i].to_dim())?;
-            }
-            Ok(())
-        },
-    )
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let values = self
+            .parameter_inputs()
+            .iter()
+            .map(|&ix| {
+                model
+                    .outlet_fact(inputs[ix])?
+                    .konst
+                    .clone()
+                    .context("Resize roi, scales and sizes must be constants")
+            })
+            .collect::<TractResult<Vec<_>>>()?;
+        let shape = model.outlet_fact(inputs[0])?.shape.to_tvec();
+        let axes = self.resize_axes_from_values(&shape, &values)?;
+        let op = tract_core::ops::resize::Resize::new(
+            axes,
+            self.coord_transformer,
+            self.interpolator,
+            self.nearest,
+            self.cubic_coeff_a,
+            self.exclude_outside,
+            self.extrapolation_value,
+        );
+        model.wire_node(prefix, op, &inputs[0..1])
+    }
 }
 
-fn rules_with_sizes<'r, 'p: 'r, 's: 'r>(
-    op: &'s Resize,
+fn set_output_shape<'r, 'p: 'r>(
     s: &mut Solver<'r>,
-    inputs: &'p [TensorProxy],
     outputs: &'p [TensorProxy],
+    shape: &[TDim],
+    axes: &[ResizeAxis],
 ) -> InferenceResult {
-    let sizes = &inputs[op.optional_sizes_input.unwrap()];
-    s.equals(&sizes.rank, 1)?;
-    s.equals(&sizes.shape[0], inputs[0].rank.bex().to_dim())?;
-    s.given(&inputs[0].rank, move |s, rank| {
-        for i in 0..(rank as usize) {
-            s.equals(&outputs[0].shape[i], sizes.value[i].bex().to_dim())?;
-        }
-        Ok(())
-    })
-}
-
-impl TypedOp for Resize {
-    as_op!();
-
-    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        let input_shape = if let Some(s) = inputs[0].shape.as_concrete() {
-            s
-        } else {
-            bail!("Only constant input shape are supported in Resize")
-        };
-        let scales = self.optional_scales_input.and_then(|ix| inputs.get(ix));
-        let sizes = self.optional_sizes_input.and_then(|ix| inputs.get(ix));
-        let output_shape = self.compute_output_shape(
-            &*input_shape,
-            scales.and_then(|f| f.konst.as_deref()),
-            sizes.and_then(|f| f.konst.as_deref()),
-        )?;
-        Ok(tvec!(TypedFact::dt_shape(inputs[0].datum_type, &output_shape)))
-    }
-
-    fn declutter(
-        &self,
-        _model: &TypedModel,
-        _node: &TypedNode,
-    ) -> TractResult<Option<TypedModelPatch>> {
-        Ok(None)
+    for (ix, (dim, axis)) in shape.iter().zip(axes.iter()).enumerate() {
+        s.equals(&outputs[0].shape[ix], axis.output_dim(dim)?)?;
     }
+    Ok(())
 }
@@ -0,0 +1,279 @@
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::type_proto::Value;
+use crate::pb::*;
+use std::convert::TryFrom;
+use tract_core::ops::sequence;
+use tract_hir::internal::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("ConcatFromSequence", concat_from_sequence);
+    reg.insert("Optional", optional);
+    reg.insert("OptionalGetElement", |_, _| {
+        Ok((inference_wrap(sequence::OptionalGetElement, 1, get_element_rules), vec![]))
+    });
+    reg.insert("OptionalHasElement", optional_has_element);
+    reg.insert("SequenceAt", |_, _| {
+        Ok((inference_wrap(sequence::SequenceAt, 1, at_rules), vec![]))
+    });
+    reg.insert("SequenceConstruct", |_, _| {
+        Ok((inference_wrap(sequence::SequenceConstruct::new(false), 1, construct_rules), vec![]))
+    });
+    reg.insert("SequenceEmpty", sequence_empty);
+    reg.insert("SequenceErase", |_, _| {
+        Ok((inference_wrap(sequence::SequenceErase, 1, erase_rules), vec![]))
+    });
+    reg.insert("SequenceInsert", |_, _| {
+        Ok((inference_wrap(sequence::SequenceInsert, 1, insert_rules), vec![]))
+    });
+    reg.insert("SequenceLength", |_, _| {
+        Ok((inference_wrap(sequence::SequenceLength, 1, length_rules), vec![]))
+    });
+    reg.insert("SplitToSequence", split_to_sequence);
+}
+
+/// Element type of an optional type attribute.
+fn element_datum_type(tp: &TypeProto) -> TractResult<DatumType> {
+    match &tp.value {
+        Some(Value::TensorType(t)) => {
+            let dt = tensor_proto::DataType::from_i32(t.elem_type).context("Unknown element type")?;
+            DatumType::try_from(dt)
+        }
+        Some(Value::SequenceType(_)) | Some(Value::OptionalType(_)) => Ok(DatumType::Sequence),
+        None => bail!("Undefined type"),
+    }
+}
+
+fn sequence_empty(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let datum_type = node.get_attr_opt("dtype")?.unwrap_or(DatumType::F32);
+    let op = sequence::SequenceEmpty::new(datum_type, false);
+    Ok((inference_wrap(op, 1, empty_rules), vec![]))
+}
+
+fn optional(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if node.input.iter().any(|i| !i.is_empty()) {
+        let op = sequence::SequenceConstruct::new(true);
+        Ok((inference_wrap(op, 1, construct_rules), vec![]))
+    } else {
+        let datum_type = element_datum_type(node.get_attr("type")?)?;
+        let op = sequence::SequenceEmpty::new(datum_type, true);
+        Ok((inference_wrap(op, 1, empty_rules), vec![]))
+    }
+}
+
+fn optional_has_element(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    if node.input.iter().any(|i| !i.is_empty()) {
+        Ok((inference_wrap(sequence::OptionalHasElement, 1, has_element_rules), vec![]))
+    } else {
+        Ok((Box::new(tract_hir::ops::konst::Const(rctensor0(false))), vec![]))
+    }
+}
+
+fn split_to_sequence(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr_opt("axis")?.unwrap_or(0);
+    let keepdims = node.get_attr_opt("keepdims")?.unwrap_or(1i64) != 0;
+    Ok((expand(SplitToSequence { axis, keepdims }), vec![]))
+}
+
+fn concat_from_sequence(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let axis = node.get_attr("axis")?;
+    let new_axis = node.get_attr_opt("new_axis")?.unwrap_or(0i64) != 0;
+    let op = sequence::ConcatFromSequence::new(axis, new_axis);
+    Ok((inference_wrap(op, 1, concat_rules), vec![]))
+}
+
+fn sequence_output<'r, 'p>(s: &mut Solver<'r>, outputs: &'p [TensorProxy]) -> InferenceResult {
+    check_output_arity(&outputs, 1)?;
+    s.equals(&outputs[0].datum_type, DatumType::Sequence)?;
+    s.equals(&outputs[0].rank, 0)?;
+    Ok(())
+}
+
+fn sequence_input<'r, 'p>(s: &mut Solver<'r>, input: &'p TensorProxy) -> InferenceResult {
+    s.equals(&input.datum_type, DatumType::Sequence)?;
+    s.equals(&input.rank, 0)?;
+    Ok(())
+}
+
+fn construct_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    if inputs.is_empty() {
+        bail!("SequenceConstruct needs at least one input")
+    }
+    s.equals_all((0..inputs.len()).map(|i| (&inputs[i].datum_type).bex()).collect())?;
+    sequence_output(s, outputs)
+}
+
+fn empty_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 0)?;
+    sequence_output(s, outputs)
+}
+
+fn at_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 2)?;
+    check_output_arity(&outputs, 1)?;
+    sequence_input(s, &inputs[0])?;
+    s.equals(&inputs[1].rank, 0)?;
+    Ok(())
+}
+
+fn insert_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    if inputs.len() != 2 && inputs.len() != 3 {
+        bail!("SequenceInsert expects 2 or 3 inputs, got {}", inputs.len())
+    }
+    sequence_input(s, &inputs[0])?;
+    if let Some(position) = inputs.get(2) {
+        s.equals(&position.rank, 0)?;
+    }
+    sequence_output(s, outputs)
+}
+
+fn erase_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    if inputs.len() != 1 && inputs.len() != 2 {
+        bail!("SequenceErase expects 1 or 2 inputs, got {}", inputs.len())
+    }
+    sequence_input(s, &inputs[0])?;
+    if let Some(position) = inputs.get(1) {
+        s.equals(&position.rank, 0)?;
+    }
+    sequence_output(s, outputs)
+}
+
+fn length_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 1)?;
+    check_output_arity(&outputs, 1)?;
+    sequence_input(s, &inputs[0])?;
+    s.equals(&outputs[0].datum_type, DatumType::I64)?;
+    s.equals(&outputs[0].rank, 0)?;
+    Ok(())
+}
+
+fn concat_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 1)?;
+    check_output_arity(&outputs, 1)?;
+    sequence_input(s, &inputs[0])?;
+    Ok(())
+}
+
+fn has_element_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 1)?;
+    check_output_arity(&outputs, 1)?;
+    s.equals(&outputs[0].datum_type, DatumType::Bool)?;
+    s.equals(&outputs[0].rank, 0)?;
+    Ok(())
+}
+
+fn get_element_rules<'r, 'p, 's>(
+    _op: &'s dyn Op,
+    _s: &mut Solver<'r>,
+    inputs: &'p [TensorProxy],
+    outputs: &'p [TensorProxy],
+) -> InferenceResult {
+    check_input_arity(&inputs, 1)?;
+    check_output_arity(&outputs, 1)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Hash)]
+struct SplitToSequence {
+    axis: i64,
+    keepdims: bool,
+}
+
+impl_dyn_hash!(SplitToSequence);
+
+impl Expansion for SplitToSequence {
+    fn name(&self) -> Cow<str> {
+        "SplitToSequence".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        if inputs.len() != 1 && inputs.len() != 2 {
+            bail!("SplitToSequence expects 1 or 2 inputs, got {}", inputs.len())
+        }
+        if let Some(split) = inputs.get(1) {
+            s.given(&split.rank, |_, rank| {
+                if rank > 1 {
+                    bail!("SplitToSequence split must be a scalar or a vector")
+                }
+                Ok(())
+            })?;
+        }
+        sequence_output(s, outputs)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as i64;
+        let axis = if self.axis < 0 { self.axis + rank } else { self.axis };
+        if axis < 0 || axis >= rank {
+            bail!("SplitToSequence axis {} is invalid for rank {}", self.axis, rank)
+        }
+        let op = sequence::SplitToSequence::new(axis as usize, self.keepdims);
+        model.wire_node(prefix, op, inputs)
+    }
+}
@@ -0,0 +1,548 @@
+use std::any::TypeId;
+use std::convert::TryFrom;
+
+use crate::pb::tensor_proto::DataType;
+use crate::pb::AttributeProto;
+use crate::ser::*;
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops;
+use tract_hir::tract_core::ops::change_axes::AxisOp;
+use tract_hir::tract_core::ops::cnn::{PaddingSpec, PoolSpec};
+use tract_hir::tract_core::ops::matmul::mir_quant::QParamKind;
+use tract_hir::tract_core::ops::nn::DataFormat;
+
+pub fn register_all_dumpers(reg: &mut OnnxDumperRegister) {
+    macro_rules! dumper {
+        ($op:ty, $path: path) => {
+            reg.insert(TypeId::of::<$op>(), |onnx, node| {
+                $path(onnx, node, node.op().downcast_ref::<$op>().unwrap())
+            })
+        };
+    }
+
+    dumper!(ops::source::TypedSource, source);
+    dumper!(ops::konst::Const, konst);
+    dumper!(ops::identity::Identity, identity);
+    dumper!(ops::cast::Cast, cast);
+    dumper!(ops::logic::Iff, iff);
+
+    dumper!(ops::change_axes::AxisOp, axis_op);
+    dumper!(ops::array::MultiBroadcastTo, broadcast);
+    dumper!(ops::array::TypedConcat, concat);
+    dumper!(ops::array::Gather, gather);
+    dumper!(ops::array::Pad, pad);
+    dumper!(ops::array::Slice, slice);
+    dumper!(ops::array::Tile, tile);
+
+    dumper!(ops::matmul::MatMul, matmul);
+    dumper!(ops::matmul::MatMulUnary, matmul_unary);
+    dumper!(ops::nn::Reduce, reduce);
+
+    dumper!(ops::cnn::ConvUnary, conv);
+    dumper!(ops::cnn::MaxPool, max_pool);
+    dumper!(ops::cnn::SumPool, sum_pool);
+
+    reg.insert_binary("Add", &ops::math::Add {});
+    reg.insert_binary("Sub", &ops::math::Sub {});
+    reg.insert_binary("Mul", &ops::math::Mul {});
+    reg.insert_binary("Div", &ops::math::Div {});
+    reg.insert_binary("Pow", &ops::math::Pow {});
+    reg.insert_binary("Min", &ops::math::Min {});
+    reg.insert_binary("Max", &ops::math::Max {});
+    reg.insert_flipped_binary("Pow", &ops::math::FlippedPow {});
+
+    reg.insert_binary("And", &ops::logic::And {});
+    reg.insert_binary("Or", &ops::logic::Or {});
+    reg.insert_binary("Xor", &ops::logic::Xor {});
+    reg.insert_binary("Equal", &ops::logic::Equals {});
+    reg.insert_binary("Less", &ops::logic::Lesser {});
+    reg.insert_binary("LessOrEqual", &ops::logic::LesserEqual {});
+    reg.insert_binary("Greater", &ops::logic::Greater {});
+    reg.insert_binary("GreaterOrEqual", &ops::logic::GreaterEqual {});
+
+    reg.insert_element_wise("Abs", &ops::math::Abs {});
+    reg.insert_element_wise("Exp", &ops::math::Exp {});
+    reg.insert_element_wise("Log", &ops::math::Ln {});
+    reg.insert_element_wise("Sqrt", &ops::math::Sqrt {});
+    reg.insert_element_wise("Reciprocal", &ops::math::Recip {});
+    reg.insert_element_wise("Neg", &ops::math::Neg {});
+    reg.insert_element_wise("Sign", &ops::math::Sign {});
+    reg.insert_element_wise("Ceil", &ops::math::Ceil {});
+    reg.insert_element_wise("Floor", &ops::math::Floor {});
+    reg.insert_element_wise("Round", &ops::math::RoundHalfToEven {});
+
+    reg.insert_element_wise("Cos", &ops::math::Cos {});
+    reg.insert_element_wise("Sin", &ops::math::Sin {});
+    reg.insert_element_wise("Tan", &ops::math::Tan {});
+    reg.insert_element_wise("Acos", &ops::math::Acos {});
+    reg.insert_element_wise("Asin", &ops::math::Asin {});
+    reg.insert_element_wise("Atan", &ops::math::Atan {});
+    reg.insert_element_wise("Cosh", &ops::math::Cosh {});
+    reg.insert_element_wise("Sinh", &ops::math::Sinh {});
+    reg.insert_element_wise("Tanh", &ops::math::Tanh {});
+    reg.insert_element_wise("Acosh", &ops::math::Acosh {});
+    reg.insert_element_wise("Asinh", &ops::math::Asinh {});
+    reg.insert_element_wise("Atanh", &ops::math::Atanh {});
+
+    reg.insert_element_wise("Sigmoid", &ops::nn::Sigmoid {});
+    reg.insert_element_wise("Not", &ops::logic::Not {});
+}
+
+fn ints(values: impl IntoIterator<Item = i64>) -> Tensor {
+    tensor1(&values.into_iter().collect::<Vec<_>>())
+}
+
+fn dims(values: &[TDim]) -> TractResult<Tensor> {
+    Ok(ints(values.iter().map(|d| d.to_i64()).collect::<TractResult<Vec<_>>>()?))
+}
+
+fn source(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    _op: &ops::source::TypedSource,
+) -> TractResult<()> {
+    onnx.add_input(node.id.into())
+}
+
+fn konst(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::konst::Const) -> TractResult<()> {
+    let name = onnx.output(node, 0);
+    onnx.add_initializer(name, &op.0)?;
+    Ok(())
+}
+
+fn identity(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    _op: &ops::identity::Identity,
+) -> TractResult<()> {
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    onnx.add_node(&node.name, "Identity", vec![input], vec![output], vec![]);
+    Ok(())
+}
+
+fn cast(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::cast::Cast) -> TractResult<()> {
+    let to = DataType::try_from(op.to)?;
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    onnx.add_node(&node.name, "Cast", vec![input], vec![output], vec![attr_int("to", to as i64)]);
+    Ok(())
+}
+
+fn iff(onnx: &mut IntoOnnx, node: &TypedNode, _op: &ops::logic::Iff) -> TractResult<()> {
+    let inputs = (0..3).map(|ix| onnx.input(node, ix)).collect();
+    let output = onnx.output(node, 0);
+    onnx.add_node(&node.name, "Where", inputs, vec![output], vec![]);
+    Ok(())
+}
+
+fn axis_op(onnx: &mut IntoOnnx, node: &TypedNode, op: &AxisOp) -> TractResult<()> {
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    match op {
+        AxisOp::Add(axis) => {
+            let axes = onnx.konst(node, "axes", &ints(Some(*axis as i64)))?;
+            onnx.add_node(&node.name, "Unsqueeze", vec![input, axes], vec![output], vec![]);
+        }
+        AxisOp::Rm(axis) => {
+            let axes = onnx.konst(node, "axes", &ints(Some(*axis as i64)))?;
+            onnx.add_node(&node.name, "Squeeze", vec![input, axes], vec![output], vec![]);
+        }
+        AxisOp::Move(from, to) => {
+            let rank = node.outputs[0].fact.rank();
+            let mut perm: TVec<i64> = (0..rank as i64).collect();
+            if from < to {
+                perm[*from..(to + 1)].rotate_left(1);
+            } else {
+                perm[*to..(from + 1)].rotate_right(1);
+            }
+            let perm = attr_ints("perm", perm);
+            onnx.add_node(&node.name, "Transpose", vec![input], vec![output], vec![perm]);
+        }
+        AxisOp::Reshape(at, _, _) => {
+            // leading axes are copied from the input, so only the reshaped
+            // and trailing ones need to be known (one of them at most may be
+            // inferred). ONNX reads any other 0 as a copy too before opset 14
+            // and allowzero.
+            let shape = node.outputs[0]
+                .fact
+                .shape
+                .iter()
+                .enumerate()
+                .map(|(ix, d)| match d.to_i64() {
+                    _ if ix < *at => Ok(0),
+                    Ok(0) => bail!("Can not express reshape to an empty tensor in ONNX"),
+                    Ok(d) => Ok(d),
+                    Err(_) => Ok(-1),
+                })
+                .collect::<TractResult<Vec<_>>>()?;
+            if shape.iter().filter(|d| **d == -1).count() > 1 {
+                bail!("Can not express reshape to {:?} in ONNX", node.outputs[0].fact.shape)
+            }
+            let shape = onnx.konst(node, "shape", &ints(shape))?;
+            onnx.add_node(&node.name, "Reshape", vec![input, shape], vec![output], vec![]);
+        }
+    }
+    Ok(())
+}
+
+fn broadcast(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    op: &ops::array::MultiBroadcastTo,
+) -> TractResult<()> {
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    let shape = onnx.konst(node, "shape", &dims(&op.shape)?)?;
+    onnx.add_node(&node.name, "Expand", vec![input, shape], vec![output], vec![]);
+    Ok(())
+}
+
+fn concat(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::array::TypedConcat) -> TractResult<()> {
+    let mut inputs = 0..node.inputs.len();
+    let wires = op
+        .slices
+        .iter()
+        .enumerate()
+        .map(|(ix, s)| match s {
+            ops::array::ConcatSlice::Var => Ok(onnx.input(node, inputs.next().unwrap())),
+            ops::array::ConcatSlice::Const(t) => onnx.konst(node, &format!("const-{}", ix), t),
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let output = onnx.output(node, 0);
+    let axis = attr_int("axis", op.axis as i64);
+    onnx.add_node(&node.name, "Concat", wires, vec![output], vec![axis]);
+    Ok(())
+}
+
+fn gather(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::array::Gather) -> TractResult<()> {
+    let inputs = vec![onnx.input(node, 0), onnx.input(node, 1)];
+    let output = onnx.output(node, 0);
+    let axis = attr_int("axis", op.axis as i64);
+    onnx.add_node(&node.name, "Gather", inputs, vec![output], vec![axis]);
+    Ok(())
+}
+
+fn pad(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::array::Pad) -> TractResult<()> {
+    use ops::array::PadMode;
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    let pads = op
+        .pads
+        .iter()
+        .map(|pair| pair.0 as i64)
+        .chain(op.pads.iter().map(|pair| pair.1 as i64))
+        .collect::<Vec<_>>();
+    let mut inputs = vec![input, onnx.konst(node, "pads", &ints(pads))?];
+    let mode = match &op.mode {
+        PadMode::Constant(c) => {
+            let dt = onnx.model.outlet_fact(node.inputs[0])?.datum_type;
+            let value = c.cast_to_dt(dt)?.into_owned();
+            inputs.push(onnx.konst(node, "value", &value)?);
+            "constant"
+        }
+        PadMode::Reflect => "reflect",
+        PadMode::Edge => "edge",
+    };
+    let mode = attr_string("mode", mode);
+    onnx.add_node(&node.name, "Pad", inputs, vec![output], vec![mode]);
+    Ok(())
+}
+
+fn slice(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::array::Slice) -> TractResult<()> {
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    let starts = onnx.konst(node, "starts", &dims(&[op.start.clone()])?)?;
+    let ends = onnx.konst(node, "ends", &dims(&[op.end.clone()])?)?;
+    let axes = onnx.konst(node, "axes", &ints(Some(op.axis as i64)))?;
+    onnx.add_node(&node.name, "Slice", vec![input, starts, ends, axes], vec![output], vec![]);
+    Ok(())
+}
+
+fn tile(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::array::Tile) -> TractResult<()> {
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    let repeats = onnx.konst(node, "repeats", &ints(op.multipliers.iter().map(|m| *m as i64)))?;
+    onnx.add_node(&node.name, "Tile", vec![input, repeats], vec![output], vec![]);
+    Ok(())
+}
+
+/// Swap the two innermost axes of `wire`.
+fn transpose_inner(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    wire: String,
+    rank: usize,
+    suffix: &str,
+    output: Option<String>,
+) -> String {
+    let mut perm: Vec<i64> = (0..rank as i64).collect();
+    perm.swap(rank - 2, rank - 1);
+    let output = output.unwrap_or_else(|| onnx.temp(node, suffix));
+    let name = format!("{}.{}", node.name, suffix);
+    onnx.add_node(
+        &name,
+        "Transpose",
+        vec![wire],
+        vec![output.clone()],
+        vec![attr_ints("perm", perm)],
+    );
+    output
+}
+
+fn wire_matmul(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    (mut a, a_rank, a_trans): (String, usize, bool),
+    (mut b, b_rank, b_trans): (String, usize, bool),
+    c_trans: bool,
+) -> TractResult<()> {
+    if a_rank < 2 || b_rank < 2 {
+        bail!("ONNX MatMul export requires operands of rank 2 or more")
+    }
+    if a_trans {
+        a = transpose_inner(onnx, node, a, a_rank, "a_trans", None);
+    }
+    if b_trans {
+        b = transpose_inner(onnx, node, b, b_rank, "b_trans", None);
+    }
+    let output = onnx.output(node, 0);
+    if c_trans {
+        let c = onnx.temp(node, "c");
+        onnx.add_node(&node.name, "MatMul", vec![a, b], vec![c.clone()], vec![]);
+        transpose_inner(onnx, node, c, a_rank.max(b_rank), "c_trans", Some(output));
+    } else {
+        onnx.add_node(&node.name, "MatMul", vec![a, b], vec![output], vec![]);
+    }
+    Ok(())
+}
+
+fn matmul(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::matmul::MatMul) -> TractResult<()> {
+    let a_rank = onnx.model.outlet_fact(node.inputs[0])?.rank();
+    let b_rank = onnx.model.outlet_fact(node.inputs[1])?.rank();
+    let a = onnx.input(node, 0);
+    let b = onnx.input(node, 1);
+    wire_matmul(onnx, node, (a, a_rank, op.a_trans), (b, b_rank, op.b_trans), op.c_trans)
+}
+
+fn matmul_unary(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    op: &ops::matmul::MatMulUnary,
+) -> TractResult<()> {
+    let b_rank = onnx.model.outlet_fact(node.inputs[0])?.rank();
+    let a = onnx.konst(node, "a", &op.a)?;
+    let b = onnx.input(node, 0);
+    wire_matmul(onnx, node, (a, op.a.rank(), op.a_trans), (b, b_rank, op.b_trans), op.c_trans)
+}
+
+fn reduce(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::nn::Reduce) -> TractResult<()> {
+    use ops::nn::Reducer;
+    let (input, output) = (onnx.input(node, 0), onnx.output(node, 0));
+    let axes = op.axes.iter().map(|a| *a as i64).collect::<Vec<_>>();
+    let keep_dims = attr_int("keepdims", 1);
+    match op.reducer {
+        Reducer::ArgMax(last) | Reducer::ArgMin(last) => {
+            if axes.len() != 1 {
+                bail!("ONNX {:?} reduces over a single axis, got {:?}", op.reducer, axes)
+            }
+            let op_type = if let Reducer::ArgMax(_) = op.reducer { "ArgMax" } else { "ArgMin" };
+            let attrs = vec![
+                attr_int("axis", axes[0]),
+                keep_dims,
+                attr_int("select_last_index", last as i64),
+            ];
+            onnx.add_node(&node.name, op_type, vec![input], vec![output], attrs);
+        }
+        Reducer::Sum => {
+            let axes = onnx.konst(node, "axes", &ints(axes))?;
+            onnx.add_node(
+                &node.name,
+                "ReduceSum",
+                vec![input, axes],
+                vec![output],
+                vec![keep_dims],
+            );
+        }
+        Reducer::Max | Reducer::Min | Reducer::Prod => {
+            let op_type = match op.reducer {
+                Reducer::Max => "ReduceMax",
+                Reducer::Min => "ReduceMin",
+                _ => "ReduceProd",
+            };
+            let attrs = vec![attr_ints("axes", axes), keep_dims];
+            onnx.add_node(&node.name, op_type, vec![input], vec![output], attrs);
+        }
+    }
+    Ok(())
+}
+
+/// Kernel, strides, dilations and padding attributes of ONNX Conv and pools.
+fn pool_attrs(spec: &PoolSpec) -> Vec<AttributeProto> {
+    let mut attrs = vec![attr_ints("kernel_shape", spec.kernel_shape.iter().map(|k| *k as i64))];
+    if let Some(strides) = &spec.strides {
+        attrs.push(attr_ints("strides", strides.iter().map(|s| *s as i64)));
+    }
+    if let Some(dilations) = &spec.dilations {
+        attrs.push(attr_ints("dilations", dilations.iter().map(|d| *d as i64)));
+    }
+    match &spec.padding {
+        PaddingSpec::Explicit(before, after, ceil_mode) => {
+            let pads = before.iter().chain(after.iter()).map(|p| *p as i64);
+            attrs.push(attr_ints("pads", pads));
+            if *ceil_mode {
+                attrs.push(attr_int("ceil_mode", 1));
+            }
+        }
+        PaddingSpec::Valid => attrs.push(attr_string("auto_pad", "VALID")),
+        PaddingSpec::SameUpper => attrs.push(attr_string("auto_pad", "SAME_UPPER")),
+        PaddingSpec::SameLower => attrs.push(attr_string("auto_pad", "SAME_LOWER")),
+    }
+    attrs
+}
+
+/// Wire an ONNX op working on NCHW data, transposing its first input and its
+/// outputs when the tract node works on NHWC.
+#[allow(clippy::too_many_arguments)]
+fn wire_nchw(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    format: DataFormat,
+    op_type: &str,
+    mut inputs: Vec<String>,
+    outputs: usize,
+    attrs: Vec<AttributeProto>,
+) -> TractResult<()> {
+    let rank = onnx.model.outlet_fact(node.inputs[0])?.rank() as i64;
+    match format {
+        DataFormat::NCHW => {
+            let outputs = (0..outputs).map(|ix| onnx.output(node, ix)).collect();
+            onnx.add_node(&node.name, op_type, inputs, outputs, attrs);
+        }
+        DataFormat::NHWC => {
+            let nchw = onnx.temp(node, "nchw");
+            let perm = vec![0, rank - 1].into_iter().chain(1..rank - 1);
+            onnx.add_node(
+                &format!("{}.nchw", node.name),
+                "Transpose",
+                vec![inputs[0].clone()],
+                vec![nchw.clone()],
+                vec![attr_ints("perm", perm)],
+            );
+            inputs[0] = nchw;
+            let temps =
+                (0..outputs).map(|ix| onnx.temp(node, &format!("nchw.{}", ix))).collect::<Vec<_>>();
+            onnx.add_node(&node.name, op_type, inputs, temps.clone(), attrs);
+            for (ix, temp) in temps.into_iter().enumerate() {
+                let perm = Some(0).into_iter().chain(2..rank).chain(Some(1));
+                let output = onnx.output(node, ix);
+                onnx.add_node(
+                    &format!("{}.nhwc", node.name),
+                    "Transpose",
+                    vec![temp],
+                    vec![output],
+                    vec![attr_ints("perm", perm)],
+                );
+            }
+        }
+        _ => bail!("ONNX {} requires a batch axis, got {:?} data", op_type, format),
+    }
+    Ok(())
+}
+
+fn qparam(
+    onnx: &mut IntoOnnx,
+    node: &TypedNode,
+    suffix: &str,
+    param: &QParamKind,
+) -> TractResult<String> {
+    match param {
+        QParamKind::Attr(t) => onnx.konst(node, suffix, t),
+        QParamKind::FromInput(ix) => Ok(onnx.input(node, *ix)),
+        QParamKind::FromQType => bail!("Can not express {} from a quantized type in ONNX", suffix),
+    }
+}
+
+fn conv(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::cnn::ConvUnary) -> TractResult<()> {
+    // ONNX kernels are OIHW, with I the input channels of each group
+    let kernel = op.kernel_as_group_o_ihw()?;
+    let output_channels = op.group * kernel.shape()[1];
+    let kernel_len = op.pool_spec.kernel_shape.iter().product::<usize>();
+    let mut kernel_shape = tvec!(output_channels, kernel.shape()[2] / kernel_len);
+    kernel_shape.extend(op.pool_spec.kernel_shape.iter().cloned());
+    let kernel = kernel.into_tensor().into_shape(&kernel_shape)?;
+    let bias = if let Some(bias) = &op.bias {
+        if bias.len() == 1 {
+            Some(bias.broadcast_scalar_to_shape(&[output_channels])?)
+        } else if bias.len() == output_channels {
+            Some(bias.clone().into_tensor().into_shape(&[output_channels])?)
+        } else {
+            bail!("Can not express bias of shape {:?} in ONNX", bias.shape())
+        }
+    } else {
+        None
+    };
+    let mut attrs = pool_attrs(&op.pool_spec);
+    attrs.push(attr_int("group", op.group as i64));
+    let input = onnx.input(node, 0);
+    let (op_type, inputs) = if let Some((dt, qp)) = &op.q_params {
+        let x_zp = qparam(onnx, node, "x_zero_point", &qp.b0)?;
+        let w_zp = qparam(onnx, node, "w_zero_point", &qp.a0)?;
+        let kernel = onnx.konst(node, "kernel", &kernel)?;
+        if *dt == i32::datum_type() {
+            if bias.is_some() {
+                bail!("ONNX ConvInteger has no bias")
+            }
+            ("ConvInteger", vec![input, kernel, x_zp, w_zp])
+        } else {
+            let x_scale = qparam(onnx, node, "x_scale", &qp.b_scale)?;
+            let w_scale = qparam(onnx, node, "w_scale", &qp.a_scale)?;
+            let y_scale = qparam(onnx, node, "y_scale", &qp.c_scale)?;
+            let y_zp = qparam(onnx, node, "y_zero_point", &qp.c0)?;
+            let mut inputs = vec![input, x_scale, x_zp, kernel, w_scale, w_zp, y_scale, y_zp];
+            if let Some(bias) = &bias {
+                inputs.push(onnx.konst(node, "bias", bias)?);
+            }
+            ("QLinearConv", inputs)
+        }
+    } else {
+        let mut inputs = vec![input, onnx.konst(node, "kernel", &kernel)?];
+        if let Some(bias) = &bias {
+            inputs.push(onnx.konst(node, "bias", bias)?);
+        }
+        ("Conv", inputs)
+    };
+    wire_nchw(onnx, node, op.pool_spec.data_format, op_type, inputs, 1, attrs)
+}
+
+fn max_pool(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::cnn::MaxPool) -> TractResult<()> {
+    let outputs = match op.with_index_outputs {
+        None => 1,
+        Some(DatumType::I64) if op.pool_spec.data_format == DataFormat::NCHW => 2,
+        Some(dt) => bail!("Can not express {:?} MaxPool indices in ONNX", dt),
+    };
+    let input = onnx.input(node, 0);
+    let attrs = pool_attrs(&op.pool_spec);
+    wire_nchw(onnx, node, op.pool_spec.data_format, "MaxPool", vec![input], outputs, attrs)
+}
+
+fn sum_pool(onnx: &mut IntoOnnx, node: &TypedNode, op: &ops::cnn::SumPool) -> TractResult<()> {
+    if op.pool_spec.dilations().iter().any(|d| *d != 1) {
+        bail!("ONNX AveragePool has no dilations")
+    }
+    let mut input = onnx.input(node, 0);
+    if !op.normalize {
+        // averaging values scaled by the window size gives their sums,
+        // padding included or not
+        let dt = onnx.model.outlet_fact(node.inputs[0])?.datum_type;
+        let len = op.pool_spec.kernel_shape.iter().product::<usize>();
+        let len = tensor0(len as f32).cast_to_dt(dt)?.into_owned();
+        let len = onnx.konst(node, "kernel_len", &len)?;
+        let scaled = onnx.temp(node, "scaled");
+        onnx.add_node(
+            &format!("{}.scaled", node.name),
+            "Mul",
+            vec![input, len],
+            vec![scaled.clone()],
+            vec![],
+        );
+        input = scaled;
+    }
+    let mut attrs = pool_attrs(&op.pool_spec);
+    let count_include_pad = op.count_include_pad || !op.normalize;
+    attrs.push(attr_int("count_include_pad", count_include_pad as i64));
+    wire_nchw(onnx, node, op.pool_spec.data_format, "AveragePool", vec![input], 1, attrs)
+}
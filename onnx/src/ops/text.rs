@@ -0,0 +1,244 @@
+use crate::model::{OnnxOpRegister, ParsingContext};
+use crate::pb::*;
+use tract_hir::internal::*;
+use tract_hir::ops::binary::IntoHir;
+use tract_onnx_opl::text::*;
+
+pub fn register_all_ops(reg: &mut OnnxOpRegister) {
+    reg.insert("RegexFullMatch", regex_full_match);
+    reg.insert("StringConcat", |_, _| Ok((StringConcat.into_hir(), vec![])));
+    reg.insert("StringNormalizer", string_normalizer);
+    reg.insert("StringSplit", string_split);
+    reg.insert("TfIdfVectorizer", tfidf_vectorizer);
+}
+
+fn regex_full_match(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let pattern: String = node.get_attr("pattern")?;
+    Ok((expand(RegexFullMatchExpansion(RegexFullMatch::new(pattern)?)), vec![]))
+}
+
+fn string_normalizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let case_change = node.get_attr_opt("case_change_action")?.unwrap_or("NONE");
+    let case_change = tract_onnx_opl::text::string_normalizer::parse_case_change(case_change)?;
+    let is_case_sensitive = node.get_attr_opt("is_case_sensitive")?.unwrap_or(0i64) != 0;
+    let stopwords = node.get_attr_opt_vec("stopwords")?.unwrap_or_default();
+    let op = StringNormalizer { case_change, is_case_sensitive, stopwords, len: Symbol::new('w') };
+    Ok((expand(StringNormalizerExpansion(op)), vec![]))
+}
+
+fn string_split(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let delimiter = node.get_attr_opt("delimiter")?.unwrap_or_default();
+    let maxsplit: Option<i64> = node.get_attr_opt("maxsplit")?;
+    node.expect_attr("maxsplit", maxsplit.map(|m| m >= 0).unwrap_or(true), "positive")?;
+    let op = StringSplit { delimiter, maxsplit: maxsplit.map(|m| m as usize), len: Symbol::new('t') };
+    Ok((expand(StringSplitExpansion(op)), vec![]))
+}
+
+fn tfidf_vectorizer(
+    _ctx: &ParsingContext,
+    node: &NodeProto,
+) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
+    let mode = tract_onnx_opl::text::tfidf_vectorizer::parse_mode(node.get_attr("mode")?)?;
+    let min_gram_length = node.get_attr("min_gram_length")?;
+    let max_gram_length = node.get_attr("max_gram_length")?;
+    let max_skip_count = node.get_attr("max_skip_count")?;
+    let ints = node.get_attr_opt_slice::<i64>("pool_int64s")?;
+    let strings: Option<Vec<String>> = node.get_attr_opt_vec("pool_strings")?;
+    let pool = match (ints, strings) {
+        (Some(ints), None) => rctensor1(ints),
+        (None, Some(strings)) => rctensor1(&strings),
+        _ => bail!("TfIdfVectorizer requires exactly one of pool_int64s and pool_strings"),
+    };
+    let ngram_counts = node.get_attr_tvec("ngram_counts")?;
+    let ngram_indexes = node.get_attr_tvec("ngram_indexes")?;
+    let weights = node.get_attr_opt_slice::<f32>("weights")?.map(rctensor1);
+    let op = TfIdfVectorizer::new(
+        mode,
+        min_gram_length,
+        max_gram_length,
+        max_skip_count,
+        pool,
+        ngram_counts,
+        ngram_indexes,
+        weights,
+    )?;
+    Ok((expand(TfIdfVectorizerExpansion(op)), vec![]))
+}
+
+#[derive(Debug, Clone, Hash)]
+struct RegexFullMatchExpansion(RegexFullMatch);
+
+impl_dyn_hash!(RegexFullMatchExpansion);
+
+impl Expansion for RegexFullMatchExpansion {
+    fn name(&self) -> Cow<str> {
+        "RegexFullMatch".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, bool::datum_type())?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+struct StringNormalizerExpansion(StringNormalizer);
+
+impl_dyn_hash!(StringNormalizerExpansion);
+
+impl Expansion for StringNormalizerExpansion {
+    fn name(&self) -> Cow<str> {
+        "StringNormalizer".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, String::datum_type())?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            if rank == 2 {
+                s.equals(&outputs[0].shape[0], 1.to_dim())?;
+            }
+            if self.0.stopwords.is_empty() {
+                s.equals(&inputs[0].shape, &outputs[0].shape)?;
+            } else {
+                s.equals(&outputs[0].shape[rank as usize - 1], self.0.len.to_dim())?;
+            }
+            Ok(())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+struct StringSplitExpansion(StringSplit);
+
+impl_dyn_hash!(StringSplitExpansion);
+
+impl Expansion for StringSplitExpansion {
+    fn name(&self) -> Cow<str> {
+        "StringSplit".into()
+    }
+
+    op_onnx!();
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 2)?;
+        s.equals(&inputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[0].datum_type, String::datum_type())?;
+        s.equals(&outputs[1].datum_type, i64::datum_type())?;
+        s.equals(inputs[0].rank.bex() + 1, &outputs[0].rank)?;
+        s.equals(&inputs[0].shape, &outputs[1].shape)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            for axis in 0..rank as usize {
+                s.equals(&inputs[0].shape[axis], &outputs[0].shape[axis])?;
+            }
+            s.equals(&outputs[0].shape[rank as usize], self.0.len.to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+struct TfIdfVectorizerExpansion(TfIdfVectorizer);
+
+impl_dyn_hash!(TfIdfVectorizerExpansion);
+
+impl Expansion for TfIdfVectorizerExpansion {
+    fn name(&self) -> Cow<str> {
+        "TfIdfVectorizer".into()
+    }
+
+    op_onnx!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, 1)?;
+        check_output_arity(&outputs, 1)?;
+        s.equals(&outputs[0].datum_type, f32::datum_type())?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            if rank == 2 {
+                s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+            }
+            s.equals(&outputs[0].shape[rank as usize - 1], self.0.output_len().to_dim())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        model.wire_node(prefix, self.0.clone(), inputs)
+    }
+}
@@ -88,6 +88,7 @@ impl Display for attribute_proto::AttributeType {
             AttributeType::Strings => "list of strings",
             AttributeType::Graph => "graph",
             AttributeType::Graphs => "graphs",
+            AttributeType::TypeProto => "type",
             _ => "<undefined>",
         })
     }
@@ -114,6 +115,12 @@ impl<'a> AttrScalarType<'a> for Tensor {
     }
 }
 
+impl<'a> AttrScalarType<'a> for &'a TensorProto {
+    fn get_attr_opt_scalar(node: &'a NodeProto, name: &str) -> TractResult<Option<Self>> {
+        node.get_attr_opt_with_type(name, AttributeType::Tensor)?.and_ok(|a| a.t.as_ref().unwrap())
+    }
+}
+
 impl<'a> AttrScalarType<'a> for &'a [u8] {
     fn get_attr_opt_scalar(node: &'a NodeProto, name: &str) -> TractResult<Option<Self>> {
         Ok(node.get_attr_opt_with_type(name, AttributeType::String)?.map(|attr| &*attr.s))
@@ -160,6 +167,13 @@ impl<'a> AttrScalarType<'a> for usize {
     }
 }
 
+impl<'a> AttrScalarType<'a> for &'a TypeProto {
+    fn get_attr_opt_scalar(node: &'a NodeProto, name: &str) -> TractResult<Option<Self>> {
+        node.get_attr_opt_with_type(name, AttributeType::TypeProto)?
+            .and_ok(|a| a.tp.as_ref().unwrap())
+    }
+}
+
 impl<'a> AttrScalarType<'a> for &'a GraphProto {
     fn get_attr_opt_scalar(node: &'a NodeProto, name: &str) -> TractResult<Option<Self>> {
         node.get_attr_opt_with_type(name, AttributeType::Graph)?.and_ok(|a| a.g.as_ref().unwrap())
@@ -0,0 +1,443 @@
+use std::any::TypeId;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::binary::{BinMiniOp, TypedBinOp, UnaryOp};
+use tract_hir::tract_core::ops::element_wise::{ElementWiseMiniOp, ElementWiseOp};
+
+use crate::model::Onnx;
+use crate::pb::*;
+use attribute_proto::AttributeType;
+
+/// ONNX operator set the serializer targets.
+pub const OPSET_VERSION: i64 = 13;
+
+pub type FromTract = fn(&mut IntoOnnx, node: &TypedNode) -> TractResult<()>;
+
+#[derive(Clone, Default)]
+pub struct OnnxDumperRegister {
+    pub dumpers: HashMap<TypeId, FromTract>,
+    pub element_wise_ops: HashMap<TypeId, &'static str>,
+    pub binary_ops: HashMap<TypeId, &'static str>,
+    /// Binary ops computing `op(b, a)`, dumped with swapped operands.
+    pub flipped_binary_ops: HashMap<TypeId, &'static str>,
+}
+
+impl OnnxDumperRegister {
+    pub fn insert(&mut self, id: TypeId, func: FromTract) {
+        self.dumpers.insert(id, func);
+    }
+
+    pub fn insert_element_wise(&mut self, op_type: &'static str, ew: &dyn ElementWiseMiniOp) {
+        self.element_wise_ops.insert(ew.type_id(), op_type);
+    }
+
+    pub fn insert_binary(&mut self, op_type: &'static str, op: &dyn BinMiniOp) {
+        self.binary_ops.insert(op.type_id(), op_type);
+    }
+
+    pub fn insert_flipped_binary(&mut self, op_type: &'static str, op: &dyn BinMiniOp) {
+        self.flipped_binary_ops.insert(op.type_id(), op_type);
+    }
+
+    fn dump(&self, onnx: &mut IntoOnnx, node: &TypedNode) -> TractResult<()> {
+        if let Some(dumper) = self.dumpers.get(&node.op().type_id()) {
+            return dumper(onnx, node);
+        } else if let Some(op) = node.op_as::<ElementWiseOp>() {
+            if let Some(op_type) = self.element_wise_ops.get(&op.0.as_ref().type_id()) {
+                let input = onnx.input(node, 0);
+                let output = onnx.output(node, 0);
+                onnx.add_node(&node.name, op_type, vec![input], vec![output], vec![]);
+                return Ok(());
+            }
+        } else if let Some(op) = node.op_as::<TypedBinOp>() {
+            if let Some(op_type) = self.binary_ops.get(&op.0.as_ref().type_id()) {
+                let inputs = vec![onnx.input(node, 0), onnx.input(node, 1)];
+                let output = onnx.output(node, 0);
+                onnx.add_node(&node.name, op_type, inputs, vec![output], vec![]);
+                return Ok(());
+            }
+        } else if let Some(op) = node.op_as::<UnaryOp>() {
+            let mini_op = op.mini_op.as_ref().type_id();
+            let (op_type, flipped) = if let Some(op_type) = self.binary_ops.get(&mini_op) {
+                (op_type, false)
+            } else if let Some(op_type) = self.flipped_binary_ops.get(&mini_op) {
+                (op_type, true)
+            } else {
+                bail!("No ONNX equivalent for {} ({})", node, node.op().name())
+            };
+            let a = onnx.konst(node, "a", &op.a)?;
+            let input = onnx.input(node, 0);
+            let inputs = if flipped { vec![input, a] } else { vec![a, input] };
+            let output = onnx.output(node, 0);
+            onnx.add_node(&node.name, op_type, inputs, vec![output], vec![]);
+            return Ok(());
+        }
+        bail!("No ONNX equivalent for {} ({})", node, node.op().name())
+    }
+}
+
+pub fn to_proto_model(framework: &Onnx, model: &TypedModel) -> TractResult<ModelProto> {
+    let mut into_onnx = IntoOnnx::new(framework, model);
+    into_onnx.translate()?;
+    into_onnx.into_proto_model()
+}
+
+pub struct IntoOnnx<'a> {
+    pub framework: &'a Onnx,
+    pub model: &'a TypedModel,
+    pub names: HashMap<OutletId, String>,
+    pub inputs: Vec<ValueInfoProto>,
+    pub initializers: Vec<TensorProto>,
+    pub nodes: Vec<NodeProto>,
+    tensor_names: HashSet<String>,
+    node_names: HashSet<String>,
+}
+
+impl<'a> IntoOnnx<'a> {
+    pub fn new(framework: &'a Onnx, model: &'a TypedModel) -> IntoOnnx<'a> {
+        IntoOnnx {
+            framework,
+            model,
+            names: Default::default(),
+            inputs: Default::default(),
+            initializers: Default::default(),
+            nodes: Default::default(),
+            tensor_names: Default::default(),
+            node_names: Default::default(),
+        }
+    }
+
+    fn translate(&mut self) -> TractResult<()> {
+        for node in self.model.nodes() {
+            for ix in 0..node.outputs.len() {
+                let outlet = OutletId::new(node.id, ix);
+                let hint = if let Some(label) = self.model.outlet_label(outlet) {
+                    label.to_string()
+                } else if node.outputs.len() == 1 {
+                    node.name.clone()
+                } else {
+                    format!("{}.{}", node.name, ix)
+                };
+                let name = self.unique_name(&hint);
+                self.names.insert(outlet, name);
+            }
+        }
+        for node in self.model.eval_order()? {
+            let node = self.model.node(node);
+            self.framework
+                .dumper_register
+                .dump(self, node)
+                .with_context(|| format!("Dumping node {}", node))?;
+        }
+        Ok(())
+    }
+
+    fn into_proto_model(self) -> TractResult<ModelProto> {
+        let outputs = self
+            .model
+            .output_outlets()?
+            .iter()
+            .map(|o| self.value_info(*o))
+            .collect::<TractResult<Vec<_>>>()?;
+        let IntoOnnx { inputs, initializers, nodes, .. } = self;
+        let graph = GraphProto {
+            node: nodes,
+            name: "tract".to_string(),
+            initializer: initializers,
+            input: inputs,
+            output: outputs,
+            ..GraphProto::default()
+        };
+        Ok(ModelProto {
+            ir_version: 7,
+            opset_import: vec![OperatorSetIdProto {
+                domain: "".to_string(),
+                version: OPSET_VERSION,
+            }],
+            producer_name: "tract".to_string(),
+            producer_version: env!("CARGO_PKG_VERSION").to_string(),
+            graph: Some(graph),
+            ..ModelProto::default()
+        })
+    }
+
+    fn unique_name(&mut self, hint: &str) -> String {
+        unique_name(&mut self.tensor_names, hint)
+    }
+
+    /// Name of the tensor feeding input `ix` of `node`.
+    pub fn input(&self, node: &TypedNode, ix: usize) -> String {
+        self.names[&node.inputs[ix]].clone()
+    }
+
+    /// Name of the tensor produced by output `ix` of `node`.
+    pub fn output(&self, node: &TypedNode, ix: usize) -> String {
+        self.names[&OutletId::new(node.id, ix)].clone()
+    }
+
+    /// Fresh name for an intermediate tensor.
+    pub fn temp(&mut self, node: &TypedNode, suffix: &str) -> String {
+        self.unique_name(&format!("{}.{}", node.name, suffix))
+    }
+
+    pub fn value_info(&self, outlet: OutletId) -> TractResult<ValueInfoProto> {
+        let fact = self.model.outlet_fact(outlet)?;
+        Ok(ValueInfoProto {
+            name: self.names[&outlet].clone(),
+            r#type: Some(TypeProto::try_from(fact)?),
+            ..ValueInfoProto::default()
+        })
+    }
+
+    pub fn add_input(&mut self, outlet: OutletId) -> TractResult<()> {
+        let info = self.value_info(outlet)?;
+        self.inputs.push(info);
+        Ok(())
+    }
+
+    pub fn add_initializer(&mut self, name: String, tensor: &Tensor) -> TractResult<String> {
+        let mut proto = TensorProto::try_from(tensor)?;
+        proto.name = name.clone();
+        self.initializers.push(proto);
+        Ok(name)
+    }
+
+    /// Store `tensor` as an initializer, returning its fresh name.
+    pub fn konst(
+        &mut self,
+        node: &TypedNode,
+        suffix: &str,
+        tensor: &Tensor,
+    ) -> TractResult<String> {
+        let name = self.temp(node, suffix);
+        self.add_initializer(name, tensor)
+    }
+
+    pub fn add_node(
+        &mut self,
+        name: &str,
+        op_type: &str,
+        input: Vec<String>,
+        output: Vec<String>,
+        attribute: Vec<AttributeProto>,
+    ) {
+        let name = unique_name(&mut self.node_names, name);
+        self.nodes.push(NodeProto {
+            name,
+            op_type: op_type.to_string(),
+            input,
+            output,
+            attribute,
+            ..NodeProto::default()
+        })
+    }
+}
+
+fn unique_name(used: &mut HashSet<String>, hint: &str) -> String {
+    let mut name = hint.to_string();
+    let mut ix = 0;
+    while used.contains(&name) {
+        ix += 1;
+        name = format!("{}.{}", hint, ix);
+    }
+    used.insert(name.clone());
+    name
+}
+
+pub fn attr_int(name: &str, i: i64) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Int as i32,
+        i,
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_ints(name: &str, ints: impl IntoIterator<Item = i64>) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Ints as i32,
+        ints: ints.into_iter().collect(),
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_string(name: &str, s: &str) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::String as i32,
+        s: s.as_bytes().to_vec(),
+        ..AttributeProto::default()
+    }
+}
+
+pub fn attr_tensor(name: &str, t: &Tensor) -> TractResult<AttributeProto> {
+    Ok(AttributeProto {
+        name: name.to_string(),
+        r#type: AttributeType::Tensor as i32,
+        t: Some(TensorProto::try_from(t)?),
+        ..AttributeProto::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tract_hir::tract_core::ops;
+    use tract_hir::tract_core::ops::change_axes::AxisOp;
+
+    fn round_trip(model: &TypedModel, input: Tensor) -> TractResult<()> {
+        let onnx = crate::onnx();
+        let mut buffer = vec![];
+        onnx.write(model, &mut buffer)?;
+        let reloaded = onnx.model_for_read(&mut &*buffer)?.into_optimized()?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+        let found = reloaded.into_runnable()?.run(tvec!(input))?;
+        assert_eq!(expected, found);
+        Ok(())
+    }
+
+    #[test]
+    fn matmul_reduce_round_trip() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", TypedFact::dt_shape(f32::datum_type(), &[2, 3]))?;
+        let a = rctensor2(&[[1f32, 2.], [3., 4.], [5., 6.]]);
+        let wire = model.wire_node(
+            "mm",
+            ops::matmul::MatMulUnary { a, a_trans: true, b_trans: true, c_trans: true },
+            &[source],
+        )?;
+        let wire = model.wire_node("exp", ops::math::exp(), &wire)?;
+        let wire =
+            model.wire_node("sum", ops::nn::Reduce::new(tvec!(1), ops::nn::Reducer::Sum), &wire)?;
+        let wire = model.wire_node("rm", AxisOp::Rm(1), &wire)?;
+        model.set_output_outlets(&wire)?;
+        round_trip(&model, tensor2(&[[0.1f32, 0.2, 0.3], [0.4, 0.5, 0.6]]))
+    }
+
+    fn range(shape: &[usize], offset: i32, modulo: i32) -> TractResult<Tensor> {
+        let len = shape.iter().product::<usize>() as i32;
+        let values = (0..len).map(|i| (i % modulo + offset) as f32).collect::<Vec<_>>();
+        Tensor::from_shape(shape, &values)
+    }
+
+    #[test]
+    fn conv_pools_round_trip() -> TractResult<()> {
+        use ops::cnn::*;
+        use ops::nn::DataFormat;
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[1, 2, 5, 5]);
+        let source = model.add_source("input", fact)?;
+        let padding = PaddingSpec::Explicit(tvec!(1, 1), tvec!(1, 1), false);
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(3, 3), padding, None, Some(tvec!(1, 2)), Some(3));
+        let kernel = range(&[3, 2, 3, 3], -2, 5)?.into_arc_tensor();
+        let bias = Some(rctensor1(&[1f32, 0., -1.]));
+        let conv = ConvUnary::new(pool_spec, KernelFormat::OIHW, kernel, 1, bias, None);
+        let wire = model.wire_node("conv", conv, &[source])?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(2, 2), PaddingSpec::SameUpper, None, None, None);
+        let wire = model.wire_node("avg", SumPool::new(pool_spec, false, true), &wire)?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(2, 1), Default::default(), None, None, None);
+        let wire = model.wire_node("max", MaxPool::new(pool_spec, None), &wire)?;
+        model.set_output_outlets(&wire)?;
+        round_trip(&model, range(&[1, 2, 5, 5], -10, 23)?)
+    }
+
+    #[test]
+    fn nhwc_grouped_conv_sum_pool_round_trip() -> TractResult<()> {
+        use ops::cnn::*;
+        use ops::nn::DataFormat;
+        let mut model = TypedModel::default();
+        let fact = TypedFact::dt_shape(f32::datum_type(), &[1, 4, 4, 2]);
+        let source = model.add_source("input", fact)?;
+        let padding = PaddingSpec::Explicit(tvec!(1, 0), tvec!(1, 0), false);
+        let pool_spec = PoolSpec::new(DataFormat::NHWC, tvec!(3, 2), padding, None, None, None);
+        let wire = model.wire_node("sum", SumPool::new(pool_spec, false, false), &[source])?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NHWC, tvec!(2, 2), Default::default(), None, None, Some(4));
+        // HWIO kernel of all input channels and the output channels of a group
+        let kernel = range(&[2, 2, 2, 2], -3, 7)?.into_arc_tensor();
+        let conv = ConvUnary::new(pool_spec, KernelFormat::HWIO, kernel, 2, None, None);
+        let wire = model.wire_node("conv", conv, &wire)?;
+        model.set_output_outlets(&wire)?;
+        round_trip(&model, range(&[1, 4, 4, 2], -5, 11)?)
+    }
+
+    #[test]
+    fn qlinear_conv_round_trip() -> TractResult<()> {
+        use ops::cnn::*;
+        use ops::matmul::mir_quant::QParamKind;
+        use ops::matmul::MatMulQParams;
+        use ops::nn::DataFormat;
+        let mut model = TypedModel::default();
+        let source =
+            model.add_source("input", TypedFact::dt_shape(u8::datum_type(), &[1, 1, 3, 4]))?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(2, 2), Default::default(), None, None, Some(2));
+        let kernel = rctensor4(&[[[[1u8, 3], [5, 7]]], [[[9u8, 2], [4, 6]]]]);
+        let q_params = MatMulQParams {
+            a0: QParamKind::Attr(rctensor0(4u8)),
+            a_scale: QParamKind::Attr(rctensor0(0.5f32)),
+            b0: QParamKind::Attr(rctensor0(10u8)),
+            b_scale: QParamKind::Attr(rctensor0(0.25f32)),
+            c0: QParamKind::Attr(rctensor0(128u8)),
+            c_scale: QParamKind::Attr(rctensor0(0.1f32)),
+        };
+        let bias = Some(rctensor1(&[20i32, -20]));
+        let conv = ConvUnary::new(
+            pool_spec,
+            KernelFormat::OIHW,
+            kernel,
+            1,
+            bias,
+            Some((u8::datum_type(), q_params)),
+        );
+        let wire = model.wire_node("conv", conv, &[source])?;
+        model.set_output_outlets(&wire)?;
+        round_trip(&model, tensor4(&[[[[0u8, 10, 20, 30], [7, 14, 21, 28], [40, 30, 20, 10]]]]))
+    }
+
+    #[test]
+    fn decluttered_constant_operands_round_trip() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", TypedFact::dt_shape(f32::datum_type(), &[2, 3]))?;
+        let bias = model.add_const("bias", rctensor2(&[[1f32, 2., 3.]]))?;
+        let shift = model.add_const("shift", rctensor2(&[[10f32]]))?;
+        let exponents = model.add_const("exponents", rctensor2(&[[1f32, 2., 3.]]))?;
+        let wire = model.wire_node("add", ops::math::add::bin_typed(), &[source, bias])?;
+        let wire = model.wire_node("sub", ops::math::sub::bin_typed(), &[shift, wire[0]])?;
+        let wire = model.wire_node("sub_b", ops::math::sub::bin_typed(), &[wire[0], bias])?;
+        let wire = model.wire_node("pow", ops::math::pow::bin_typed(), &[wire[0], exponents])?;
+        model.set_output_outlets(&wire)?;
+        let model = model.into_decluttered()?;
+        assert!(model.nodes().iter().all(|n| !n.op_is::<ops::binary::TypedBinOp>()));
+        round_trip(&model, tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]))
+    }
+
+    #[test]
+    fn empty_reshape() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", TypedFact::dt_shape(f32::datum_type(), &[2, 0]))?;
+        let reshape = AxisOp::Reshape(0, tvec!(2.into(), 0.into()), tvec!(0.into(), 3.into()));
+        let wire = model.wire_node("reshape", reshape, &[source])?;
+        model.set_output_outlets(&wire)?;
+        let err = crate::onnx().write(&model, vec![]).unwrap_err();
+        assert!(format!("{:?}", err).contains("empty tensor"));
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_op() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", TypedFact::dt_shape(f32::datum_type(), &[2]))?;
+        let wire = model.wire_node("square", ops::math::square(), &[source])?;
+        model.set_output_outlets(&wire)?;
+        let err = crate::onnx().write(&model, vec![]).unwrap_err();
+        assert!(format!("{:?}", err).contains("No ONNX equivalent"));
+        Ok(())
+    }
+}
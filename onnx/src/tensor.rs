@@ -2,6 +2,7 @@ use crate::pb::tensor_proto::DataType;
 use crate::pb::*;
 use prost::Message;
 use std::convert::{TryFrom, TryInto};
+use std::{fs, path};
 use tract_hir::internal::*;
 
 impl TryFrom<DataType> for DatumType {
@@ -26,6 +27,28 @@ impl TryFrom<DataType> for DatumType {
     }
 }
 
+impl TryFrom<DatumType> for DataType {
+    type Error = TractError;
+    fn try_from(t: DatumType) -> TractResult<DataType> {
+        match t {
+            DatumType::Bool => Ok(DataType::Bool),
+            DatumType::U8 => Ok(DataType::Uint8),
+            DatumType::U16 => Ok(DataType::Uint16),
+            DatumType::U32 => Ok(DataType::Uint32),
+            DatumType::U64 => Ok(DataType::Uint64),
+            DatumType::I8 => Ok(DataType::Int8),
+            DatumType::I16 => Ok(DataType::Int16),
+            DatumType::I32 => Ok(DataType::Int32),
+            DatumType::I64 | DatumType::TDim => Ok(DataType::Int64),
+            DatumType::F16 => Ok(DataType::Float16),
+            DatumType::F32 => Ok(DataType::Float),
+            DatumType::F64 => Ok(DataType::Double),
+            DatumType::String => Ok(DataType::String),
+            _ => bail!("No ONNX type for {:?}", t),
+        }
+    }
+}
+
 impl<'a> TryFrom<&'a type_proto::Tensor> for InferenceFact {
     type Error = TractError;
     fn try_from(t: &'a type_proto::Tensor) -> TractResult<InferenceFact> {
@@ -58,84 +81,244 @@ impl TryFrom<type_proto::Tensor> for InferenceFact {
     }
 }
 
+/// Sequences and optionals are scalars of DatumType::Sequence, carrying the
+/// type and shape of their elements.
+impl<'a> TryFrom<&'a TypeProto> for InferenceFact {
+    type Error = TractError;
+    fn try_from(t: &'a TypeProto) -> TractResult<InferenceFact> {
+        match &t.value {
+            Some(type_proto::Value::TensorType(t)) => t.try_into(),
+            Some(type_proto::Value::SequenceType(s)) => {
+                Ok(InferenceFact::sequence(sequence_fact(s.elem_type.as_deref(), false)?))
+            }
+            Some(type_proto::Value::OptionalType(o)) => {
+                Ok(InferenceFact::sequence(sequence_fact(o.elem_type.as_deref(), true)?))
+            }
+            None => Ok(InferenceFact::default()),
+        }
+    }
+}
+
+/// Element information of a sequence or optional type. Dimensions without
+/// a value get a fresh symbol, as they may vary from one element to another.
+fn sequence_fact(elem_type: Option<&TypeProto>, optional: bool) -> TractResult<SequenceFact> {
+    let (datum_type, shape) = match elem_type.and_then(|t| t.value.as_ref()) {
+        Some(type_proto::Value::TensorType(t)) => {
+            let dt = DataType::from_i32(t.elem_type).context("Unknown element type")?;
+            let shape = t.shape.as_ref().map(|shape| {
+                ShapeFact::from_dims(shape.dim.iter().map(|d| match d.value {
+                    Some(tensor_shape_proto::dimension::Value::DimValue(v)) if v > 0 => v.to_dim(),
+                    _ => Symbol::new('s').into(),
+                }))
+            });
+            (dt.try_into()?, shape)
+        }
+        Some(_) => (DatumType::Sequence, Some(ShapeFact::from_dims(TVec::<TDim>::new()))),
+        None => bail!("Undefined sequence element type"),
+    };
+    Ok(SequenceFact { optional, ..SequenceFact::new(datum_type, shape, None) })
+}
+
+/// Tensor type of a typed fact, symbolic dimensions becoming named
+/// parameters.
+impl<'a> TryFrom<&'a TypedFact> for TypeProto {
+    type Error = TractError;
+    fn try_from(fact: &'a TypedFact) -> TractResult<TypeProto> {
+        let dim = fact
+            .shape
+            .iter()
+            .map(|d| {
+                let value = if let Ok(d) = d.to_i64() {
+                    tensor_shape_proto::dimension::Value::DimValue(d)
+                } else {
+                    tensor_shape_proto::dimension::Value::DimParam(d.to_string())
+                };
+                tensor_shape_proto::Dimension { value: Some(value), ..Default::default() }
+            })
+            .collect();
+        let tensor = type_proto::Tensor {
+            elem_type: DataType::try_from(fact.datum_type)? as i32,
+            shape: Some(TensorShapeProto { dim }),
+        };
+        Ok(TypeProto { value: Some(type_proto::Value::TensorType(tensor)), ..Default::default() })
+    }
+}
+
+impl<'a> TryFrom<&'a Tensor> for TensorProto {
+    type Error = TractError;
+    fn try_from(t: &'a Tensor) -> TractResult<TensorProto> {
+        let mut proto = TensorProto {
+            dims: t.shape().iter().map(|&d| d as i64).collect(),
+            data_type: DataType::try_from(t.datum_type())? as i32,
+            ..TensorProto::default()
+        };
+        match t.datum_type() {
+            DatumType::String => {
+                proto.string_data =
+                    t.as_slice::<String>()?.iter().map(|s| s.as_bytes().to_vec()).collect()
+            }
+            DatumType::TDim => {
+                let t = t.cast_to::<i64>()?;
+                proto.raw_data = unsafe { t.as_bytes().to_vec() }
+            }
+            DatumType::Bool => {
+                proto.raw_data = t.as_slice::<bool>()?.iter().map(|&b| b as u8).collect()
+            }
+            _ => proto.raw_data = unsafe { t.as_bytes().to_vec() },
+        }
+        Ok(proto)
+    }
+}
+
 impl<'a> TryFrom<&'a TensorProto> for Tensor {
     type Error = TractError;
     fn try_from(t: &TensorProto) -> TractResult<Tensor> {
-        let dt = DataType::from_i32(t.data_type).unwrap().try_into()?;
-        let shape: Vec<usize> = t.dims.iter().map(|&i| i as usize).collect();
-        if t.raw_data.len() > 0 {
-            unsafe {
-                match dt {
-                    DatumType::U8 => Tensor::from_raw::<u8>(&*shape, &*t.raw_data),
-                    DatumType::U16 => Tensor::from_raw::<u16>(&*shape, &*t.raw_data),
-                    DatumType::U32 => Tensor::from_raw::<u32>(&*shape, &*t.raw_data),
-                    DatumType::U64 => Tensor::from_raw::<u64>(&*shape, &*t.raw_data),
-                    DatumType::I8 => Tensor::from_raw::<i8>(&*shape, &*t.raw_data),
-                    DatumType::I16 => Tensor::from_raw::<i16>(&*shape, &*t.raw_data),
-                    DatumType::I32 => Tensor::from_raw::<i32>(&*shape, &*t.raw_data),
-                    DatumType::I64 => Tensor::from_raw::<i64>(&*shape, &*t.raw_data),
-                    DatumType::F16 => Tensor::from_raw::<f16>(&*shape, &*t.raw_data),
-                    DatumType::F32 => Tensor::from_raw::<f32>(&*shape, &*t.raw_data),
-                    DatumType::F64 => Tensor::from_raw::<f64>(&*shape, &*t.raw_data),
-                    DatumType::Bool => Ok(Tensor::from_raw::<u8>(&*shape, &*t.raw_data)?
-                        .into_array::<u8>()?
-                        .mapv(|x| x != 0)
-                        .into()),
-                    _ => unimplemented!("FIXME, raw tensor loading"),
-                }
+        load_tensor(t, None)
+    }
+}
+
+/// Build a tensor from its proto.
+///
+/// Tensors stored as external data are looked up relative to `model_dir`,
+/// the directory of the model file.
+pub fn load_tensor(t: &TensorProto, model_dir: Option<&path::Path>) -> TractResult<Tensor> {
+    let dt = DataType::from_i32(t.data_type).unwrap().try_into()?;
+    let shape: Vec<usize> = t.dims.iter().map(|&i| i as usize).collect();
+    if t.data_location() == tensor_proto::DataLocation::External {
+        let model_dir = model_dir.ok_or_else(|| {
+            format_err!("Tensor {} uses external data, but the model location is unknown", t.name)
+        })?;
+        load_external_data(t, dt, &shape, model_dir)
+            .with_context(|| format!("Loading external data for tensor {}", t.name))
+    } else if t.raw_data.len() > 0 {
+        tensor_from_raw(dt, &shape, &t.raw_data)
+    } else {
+        use tract_ndarray::Array;
+        let it = match dt {
+            DatumType::Bool => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x != 0).collect())?
+                    .into()
+            }
+            DatumType::U8 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as u8).collect())?
+                    .into()
+            }
+            DatumType::U16 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as u16).collect())?
+                    .into()
+            }
+            DatumType::U32 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x).collect())?.into()
+            }
+            DatumType::U64 => {
+                Array::from_shape_vec(&*shape, t.int64_data.iter().map(|&x| x).collect())?.into()
+            }
+            DatumType::I8 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as i8).collect())?
+                    .into()
+            }
+            DatumType::I16 => {
+                Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as i16).collect())?
+                    .into()
+            }
+            DatumType::I32 => Array::from_shape_vec(&*shape, t.int32_data.to_vec())?.into(),
+            DatumType::I64 => Array::from_shape_vec(&*shape, t.int64_data.to_vec())?.into(),
+            DatumType::F32 => Array::from_shape_vec(&*shape, t.float_data.to_vec())?.into(),
+            DatumType::F64 => Array::from_shape_vec(&*shape, t.double_data.to_vec())?.into(),
+            DatumType::String => {
+                let strings = t
+                    .string_data
+                    .iter()
+                    .cloned()
+                    .map(String::from_utf8)
+                    .collect::<Result<Vec<String>, _>>()
+                    .context("Invalid UTF8 buffer")?;
+                Array::from_shape_vec(&*shape, strings)?.into()
             }
-        } else {
-            use tract_ndarray::Array;
-            let it = match dt {
-                DatumType::Bool => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x != 0).collect())?
-                        .into()
-                }
-                DatumType::U8 => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as u8).collect())?
-                        .into()
-                }
-                DatumType::U16 => Array::from_shape_vec(
-                    &*shape,
-                    t.int32_data.iter().map(|&x| x as u16).collect(),
-                )?
-                .into(),
-                DatumType::U32 => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x).collect())?
-                        .into()
-                }
-                DatumType::U64 => {
-                    Array::from_shape_vec(&*shape, t.int64_data.iter().map(|&x| x).collect())?
-                        .into()
-                }
-                DatumType::I8 => {
-                    Array::from_shape_vec(&*shape, t.int32_data.iter().map(|&x| x as i8).collect())?
-                        .into()
-                }
-                DatumType::I16 => Array::from_shape_vec(
-                    &*shape,
-                    t.int32_data.iter().map(|&x| x as i16).collect(),
-                )?
-                .into(),
-                DatumType::I32 => Array::from_shape_vec(&*shape, t.int32_data.to_vec())?.into(),
-                DatumType::I64 => Array::from_shape_vec(&*shape, t.int64_data.to_vec())?.into(),
-                DatumType::F32 => Array::from_shape_vec(&*shape, t.float_data.to_vec())?.into(),
-                DatumType::F64 => Array::from_shape_vec(&*shape, t.double_data.to_vec())?.into(),
-                DatumType::String => {
-                    let strings = t
-                        .string_data
-                        .iter()
-                        .cloned()
-                        .map(String::from_utf8)
-                        .collect::<Result<Vec<String>, _>>()
-                        .context("Invalid UTF8 buffer")?;
-                    Array::from_shape_vec(&*shape, strings)?.into()
-                }
-                _ => unimplemented!("FIXME, struct tensor loading"),
-            };
-            Ok(it)
+            _ => unimplemented!("FIXME, struct tensor loading"),
+        };
+        Ok(it)
+    }
+}
+
+fn tensor_from_raw(dt: DatumType, shape: &[usize], raw: &[u8]) -> TractResult<Tensor> {
+    unsafe {
+        match dt {
+            DatumType::U8 => Tensor::from_raw::<u8>(&*shape, raw),
+            DatumType::U16 => Tensor::from_raw::<u16>(&*shape, raw),
+            DatumType::U32 => Tensor::from_raw::<u32>(&*shape, raw),
+            DatumType::U64 => Tensor::from_raw::<u64>(&*shape, raw),
+            DatumType::I8 => Tensor::from_raw::<i8>(&*shape, raw),
+            DatumType::I16 => Tensor::from_raw::<i16>(&*shape, raw),
+            DatumType::I32 => Tensor::from_raw::<i32>(&*shape, raw),
+            DatumType::I64 => Tensor::from_raw::<i64>(&*shape, raw),
+            DatumType::F16 => Tensor::from_raw::<f16>(&*shape, raw),
+            DatumType::F32 => Tensor::from_raw::<f32>(&*shape, raw),
+            DatumType::F64 => Tensor::from_raw::<f64>(&*shape, raw),
+            DatumType::Bool => Ok(Tensor::from_raw::<u8>(&*shape, raw)?
+                .into_array::<u8>()?
+                .mapv(|x| x != 0)
+                .into()),
+            _ => bail!("Can not load raw {:?} tensor", dt),
+        }
+    }
+}
+
+/// Read the bytes of a tensor stored in a file next to the model.
+///
+/// The file is memory-mapped for the time of the read only: the tensor gets
+/// its own copy of the bytes.
+fn load_external_data(
+    t: &TensorProto,
+    dt: DatumType,
+    shape: &[usize],
+    model_dir: &path::Path,
+) -> TractResult<Tensor> {
+    let mut location = None;
+    let mut offset = 0usize;
+    let mut length = None;
+    for entry in &t.external_data {
+        match &*entry.key {
+            "location" => location = Some(&*entry.value),
+            "offset" => offset = entry.value.parse()?,
+            "length" => length = Some(entry.value.parse()?),
+            _ => (),
         }
     }
+    let location = location.ok_or_else(|| format_err!("No location in external data"))?;
+    let location = path::Path::new(location);
+    if location
+        .components()
+        .any(|c| !matches!(c, path::Component::Normal(_) | path::Component::CurDir))
+    {
+        bail!("External data location {:?} must be relative to the model directory", location)
+    }
+    let path = model_dir.join(location);
+    let file = fs::File::open(&path).with_context(|| format!("Opening {:?}", path))?;
+    #[cfg(not(target_arch = "wasm32"))]
+    let data = unsafe { mapr::Mmap::map(&file)? };
+    #[cfg(target_arch = "wasm32")]
+    let data = {
+        use std::io::Read;
+        let mut data = vec![];
+        (&file).read_to_end(&mut data)?;
+        data
+    };
+    let expected = shape.iter().product::<usize>() * dt.size_of();
+    let length = length.unwrap_or(expected);
+    if length != expected {
+        bail!("Expected {} bytes for {:?} {:?}, external data has {}", expected, shape, dt, length)
+    }
+    if offset.checked_add(length).map(|end| end > data.len()).unwrap_or(true) {
+        bail!(
+            "{:?} is too short ({} bytes) to read {} bytes at {}",
+            path,
+            data.len(),
+            length,
+            offset
+        )
+    }
+    tensor_from_raw(dt, shape, &data[offset..][..length])
 }
 
 impl TryFrom<TensorProto> for Tensor {
@@ -155,3 +338,128 @@ pub fn proto_from_reader<R: ::std::io::Read>(mut r: R) -> TractResult<TensorProt
 pub fn from_reader<R: ::std::io::Read>(r: R) -> TractResult<Tensor> {
     proto_from_reader(r)?.try_into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn external(location: &str, offset: usize, length: Option<usize>) -> TensorProto {
+        let mut entries = vec![("location", location.to_string()), ("offset", offset.to_string())];
+        if let Some(length) = length {
+            entries.push(("length", length.to_string()));
+        }
+        TensorProto {
+            name: "w".into(),
+            dims: vec![2, 3],
+            data_type: DataType::Float as i32,
+            external_data: entries
+                .into_iter()
+                .map(|(key, value)| StringStringEntryProto { key: key.into(), value })
+                .collect(),
+            data_location: tensor_proto::DataLocation::External as i32,
+            ..TensorProto::default()
+        }
+    }
+
+    #[test]
+    fn external_data() -> TractResult<()> {
+        let dir = std::env::temp_dir().join(format!("tract-onnx-external-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let mut bytes = vec![0u8; 8];
+        for i in 0..6 {
+            bytes.extend((i as f32).to_le_bytes().iter());
+        }
+        fs::write(dir.join("weights.bin"), &bytes)?;
+        let expected = tensor2(&[[0f32, 1., 2.], [3., 4., 5.]]);
+        assert_eq!(load_tensor(&external("weights.bin", 8, None), Some(&dir))?, expected);
+        assert_eq!(load_tensor(&external("weights.bin", 8, Some(24)), Some(&dir))?, expected);
+        assert!(load_tensor(&external("weights.bin", 8, Some(20)), Some(&dir)).is_err());
+        assert!(load_tensor(&external("weights.bin", 16, None), Some(&dir)).is_err());
+        assert!(load_tensor(&external("weights.bin", usize::MAX, None), Some(&dir)).is_err());
+        assert!(load_tensor(&external("weights.bin", 8, None), None).is_err());
+        let absolute = dir.join("weights.bin");
+        assert!(load_tensor(&external(absolute.to_str().unwrap(), 8, None), Some(&dir)).is_err());
+        let parent = format!("../{}/weights.bin", dir.file_name().unwrap().to_str().unwrap());
+        assert!(load_tensor(&external(&parent, 8, None), Some(&dir)).is_err());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn sequence_input() -> TractResult<()> {
+        let element = TypeProto {
+            value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                elem_type: DataType::Float as i32,
+                shape: Some(TensorShapeProto {
+                    dim: vec![
+                        tensor_shape_proto::Dimension {
+                            value: Some(tensor_shape_proto::dimension::Value::DimValue(2)),
+                            ..Default::default()
+                        },
+                        tensor_shape_proto::Dimension {
+                            value: Some(tensor_shape_proto::dimension::Value::DimParam("n".into())),
+                            ..Default::default()
+                        },
+                    ],
+                }),
+            })),
+            ..Default::default()
+        };
+        let sequence = TypeProto {
+            value: Some(type_proto::Value::SequenceType(Box::new(type_proto::Sequence {
+                elem_type: Some(Box::new(element)),
+            }))),
+            ..Default::default()
+        };
+        let mut index: TensorProto = (&tensor0(1i64)).try_into()?;
+        index.name = "i".into();
+        let node = |op_type: &str, input: &[&str], output: &str, axis: Option<i64>| NodeProto {
+            op_type: op_type.into(),
+            input: input.iter().map(|s| s.to_string()).collect(),
+            output: vec![output.into()],
+            attribute: axis
+                .into_iter()
+                .map(|i| AttributeProto {
+                    name: "axis".into(),
+                    r#type: attribute_proto::AttributeType::Int as i32,
+                    i,
+                    ..AttributeProto::default()
+                })
+                .collect(),
+            ..NodeProto::default()
+        };
+        let output = |name: &str| ValueInfoProto { name: name.into(), ..Default::default() };
+        let graph = GraphProto {
+            node: vec![
+                node("SequenceAt", &["s", "i"], "at", None),
+                node("ConcatFromSequence", &["s"], "concat", Some(1)),
+            ],
+            initializer: vec![index],
+            input: vec![ValueInfoProto {
+                name: "s".into(),
+                r#type: Some(sequence),
+                ..Default::default()
+            }],
+            output: vec![output("at"), output("concat")],
+            ..GraphProto::default()
+        };
+        let proto = ModelProto {
+            opset_import: vec![OperatorSetIdProto { domain: "".into(), version: 13 }],
+            graph: Some(graph),
+            ..ModelProto::default()
+        };
+        let model = crate::onnx().model_for_proto_model(&proto)?.into_optimized()?;
+        let fact = model.input_fact(0)?.sequence_fact()?;
+        assert_eq!(fact.datum_type, f32::datum_type());
+        assert_eq!(fact.shape.as_ref().unwrap().rank(), 2);
+        assert_eq!(model.output_fact(0)?.datum_type, f32::datum_type());
+        assert_eq!(model.output_fact(0)?.shape[0], 2.to_dim());
+        let a = rctensor2(&[[1f32], [2.]]);
+        let b = rctensor2(&[[3f32, 4.], [5., 6.]]);
+        let input = tensor0(Sequence::new(f32::datum_type(), vec![a, b.clone()]));
+        let result = model.into_runnable()?.run(tvec!(input))?;
+        assert_eq!(result[0], b);
+        assert_eq!(*result[1], tensor2(&[[1f32, 3., 4.], [2., 5., 6.]]));
+        Ok(())
+    }
+}
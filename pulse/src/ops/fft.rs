@@ -0,0 +1,48 @@
+use crate::internal::*;
+use tract_core::ops::fft::Stft;
+
+register_all!(Stft: pulsify);
+
+fn pulsify(
+    op: &Stft,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _pulse: usize,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let mut wire = mapping[&node.inputs[0]];
+    let fact = target.outlet_fact(wire)?.clone();
+    if fact.axis != op.axis {
+        return Ok(None);
+    }
+    let pulse = fact.pulse();
+    if pulse % op.stride != 0 {
+        bail!("Pulsificaton requires pulse to be a stride multiple")
+    }
+    let overlap = op.frame.saturating_sub(op.stride);
+    let misalignment = fact.delay % pulse;
+    if overlap > 0 || misalignment > 0 {
+        let align_to = (overlap + fact.delay).divceil(op.stride) * op.stride;
+        let delay = align_to - overlap - fact.delay;
+        wire = target.wire_node(
+            format!("{}.delay", node.name),
+            tract_pulse_opl::ops::Delay::new_typed(&(&fact).into(), fact.axis, delay, overlap),
+            &[wire],
+        )?[0];
+    }
+    Ok(Some(target.wire_node(&*node.name, op.clone(), &[wire])?))
+}
+
+impl PulsedOp for Stft {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape = self.output_facts(&[&inputs[0].to_pulse_fact()])?.remove(0).shape;
+        fact.delay /= self.stride;
+        fact.dim = (fact.dim.clone() - (self.frame - 1).to_dim()).div_ceil(self.stride as _);
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
@@ -6,11 +6,13 @@ pub mod cnn;
 pub mod delay;
 pub mod downsample;
 pub mod dummy;
+pub mod fft;
 pub mod matmul;
 pub mod qmatmul;
 pub mod scan;
 pub mod slice;
 pub mod source;
+pub mod upsample;
 
 pub(crate) fn sync_inputs(
     node: &TypedNode,
@@ -46,10 +48,12 @@ register_all_mod!(
     array,
     cnn,
     downsample,
+    fft,
     matmul,
     qmatmul,
     scan,
-    source
+    source,
+    upsample
 );
 
 pub struct OpPulsifier {
@@ -0,0 +1,34 @@
+use crate::internal::*;
+use tract_core::ops::Upsample;
+
+register_all!(Upsample: pulsify);
+
+fn pulsify(
+    op: &Upsample,
+    _source: &TypedModel,
+    node: &TypedNode,
+    target: &mut PulsedModel,
+    mapping: &HashMap<OutletId, OutletId>,
+    _pulse: usize,
+) -> TractResult<Option<TVec<OutletId>>> {
+    let input = mapping[&node.inputs[0]];
+    if target.outlet_fact(input)?.axis != op.axis {
+        return Ok(None);
+    }
+    Ok(Some(target.wire_node(&*node.name, op.clone(), &[input])?))
+}
+
+impl PulsedOp for Upsample {
+    fn pulsed_output_facts(&self, inputs: &[&PulsedFact]) -> TractResult<TVec<PulsedFact>> {
+        let mut fact = inputs[0].clone();
+        fact.shape.set(self.axis, fact.shape[self.axis].clone() * self.factor);
+        if fact.axis == self.axis {
+            fact.dim = fact.dim.clone() * self.factor;
+            fact.delay *= self.factor;
+        }
+        Ok(tvec!(fact))
+    }
+
+    as_op!();
+    pulsed_op_to_typed_op!();
+}
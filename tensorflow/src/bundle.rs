@@ -0,0 +1,265 @@
+//! Reader for TensorFlow tensor bundles, the checkpoint format found in the
+//! `variables/` directory of a SavedModel.
+//!
+//! A bundle is made of a `<prefix>.index` file, a LevelDB-style sorted table
+//! mapping tensor names to `BundleEntryProto`s, and of
+//! `<prefix>.data-<shard>-of-<shards>` files holding the tensor bytes.
+//! TensorFlow does not compress index blocks, and this reader does not support
+//! compressed ones. Partitioned tensors are not supported either, and
+//! checksums are not verified.
+use std::convert::{TryFrom, TryInto};
+use std::path::Path;
+
+use prost::Message;
+use tract_hir::internal::*;
+
+use crate::tfpb::tensorflow::bundle_header_proto::Endianness;
+use crate::tfpb::tensorflow::{BundleEntryProto, BundleHeaderProto, DataType};
+
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const FOOTER_LEN: usize = 48;
+const BLOCK_TRAILER_LEN: usize = 5;
+
+/// Read all the tensors of the bundle at `prefix` (for instance
+/// `saved_model/variables/variables`).
+pub fn read_bundle(prefix: impl AsRef<Path>) -> TractResult<HashMap<String, Tensor>> {
+    let prefix = prefix.as_ref().to_string_lossy().into_owned();
+    let index = std::fs::read(format!("{}.index", prefix))
+        .with_context(|| format!("Reading bundle index {}.index", prefix))?;
+    let mut entries = vec![];
+    table_entries(&index, |key, value| {
+        entries.push((key.to_vec(), value.to_vec()));
+        Ok(())
+    })?;
+    let header =
+        entries.iter().find(|(key, _)| key.is_empty()).context("Bundle index has no header")?;
+    let header = BundleHeaderProto::decode(&*header.1)?;
+    if header.endianness != Endianness::Little as i32 {
+        bail!("Only little endian bundles are supported")
+    }
+    let shards = (0..header.num_shards)
+        .map(|shard| {
+            let path = format!("{}.data-{:05}-of-{:05}", prefix, shard, header.num_shards);
+            std::fs::read(&path).with_context(|| format!("Reading bundle data {}", path))
+        })
+        .collect::<TractResult<Vec<Vec<u8>>>>()?;
+    let mut tensors = HashMap::new();
+    for (key, value) in entries.iter().filter(|(key, _)| !key.is_empty()) {
+        let name = String::from_utf8(key.clone())?;
+        let entry = BundleEntryProto::decode(&**value)?;
+        let tensor = entry_tensor(&entry, &shards)
+            .with_context(|| format!("Reading tensor {} from bundle", name))?;
+        tensors.insert(name, tensor);
+    }
+    Ok(tensors)
+}
+
+fn entry_tensor(entry: &BundleEntryProto, shards: &[Vec<u8>]) -> TractResult<Tensor> {
+    if entry.slices.len() > 0 {
+        bail!("Partitioned tensors are not supported")
+    }
+    let dt = DataType::from_i32(entry.dtype).context("Invalid datatype")?;
+    let dt = DatumType::try_from(dt)?;
+    let shape: TVec<usize> = TVec::try_from(entry.shape.as_ref().context("Missing shape")?)?;
+    let shard = shards.get(entry.shard_id as usize).context("Invalid shard id")?;
+    let bytes = usize::try_from(entry.offset)
+        .ok()
+        .zip(usize::try_from(entry.size).ok())
+        .and_then(|(offset, size)| shard.get(offset..offset.checked_add(size)?))
+        .context("Tensor data out of shard bounds")?;
+    if dt == DatumType::Blob {
+        // element lengths as varints, a checksum of the lengths, then the bytes
+        let mut cursor = bytes;
+        let len = shape.iter().product();
+        let lens = (0..len).map(|_| varint(&mut cursor)).collect::<TractResult<Vec<u64>>>()?;
+        cursor = cursor.get(4..).context("Truncated string tensor")?;
+        let blobs = lens
+            .into_iter()
+            .map(|len| {
+                let (blob, rest) = split(cursor, len as usize)?;
+                cursor = rest;
+                Ok(Blob(blob.to_vec()))
+            })
+            .collect::<TractResult<Vec<Blob>>>()?;
+        Ok(tract_ndarray::ArrayD::from_shape_vec(&*shape, blobs)?.into_tensor())
+    } else {
+        if bytes.len() != shape.iter().product::<usize>() * dt.size_of() {
+            bail!("Expected {:?} {:?}, found {} bytes", dt, shape, bytes.len())
+        }
+        unsafe { Tensor::from_raw_dt(dt, &shape, bytes) }
+    }
+}
+
+/// Call `f` on every key and value of a sorted table, in order.
+fn table_entries(
+    table: &[u8],
+    mut f: impl FnMut(&[u8], &[u8]) -> TractResult<()>,
+) -> TractResult<()> {
+    if table.len() < FOOTER_LEN
+        || u64::from_le_bytes(table[table.len() - 8..].try_into()?) != TABLE_MAGIC
+    {
+        bail!("Not a sorted table (bad magic number)")
+    }
+    let mut footer = &table[table.len() - FOOTER_LEN..];
+    let _metaindex = block_handle(&mut footer)?;
+    let index = block(table, block_handle(&mut footer)?)?;
+    block_entries(index, |_, mut handle| {
+        let data = block(table, block_handle(&mut handle)?)?;
+        block_entries(data, &mut f)
+    })
+}
+
+fn block_handle(cursor: &mut &[u8]) -> TractResult<(usize, usize)> {
+    Ok((varint(cursor)? as usize, varint(cursor)? as usize))
+}
+
+fn block(table: &[u8], (offset, size): (usize, usize)) -> TractResult<&[u8]> {
+    let block = offset
+        .checked_add(size)
+        .and_then(|end| end.checked_add(BLOCK_TRAILER_LEN))
+        .and_then(|end| table.get(offset..end))
+        .context("Truncated table")?;
+    if block[size] != 0 {
+        bail!("Compressed table blocks are not supported")
+    }
+    Ok(&block[..size])
+}
+
+fn block_entries(
+    block: &[u8],
+    mut f: impl FnMut(&[u8], &[u8]) -> TractResult<()>,
+) -> TractResult<()> {
+    let (entries, restarts) = split(block, block.len().saturating_sub(4))?;
+    let restarts = u32::from_le_bytes(restarts.try_into()?) as usize;
+    let mut cursor = entries.get(..entries.len().saturating_sub(4 * restarts)).unwrap_or(&[]);
+    let mut key: Vec<u8> = vec![];
+    while cursor.len() > 0 {
+        let shared = varint(&mut cursor)? as usize;
+        let unshared = varint(&mut cursor)? as usize;
+        let value_len = varint(&mut cursor)? as usize;
+        let (suffix, rest) = split(cursor, unshared)?;
+        let (value, rest) = split(rest, value_len)?;
+        key.truncate(shared);
+        key.extend_from_slice(suffix);
+        f(&key, value)?;
+        cursor = rest;
+    }
+    Ok(())
+}
+
+fn split(bytes: &[u8], at: usize) -> TractResult<(&[u8], &[u8])> {
+    if at > bytes.len() {
+        bail!("Truncated table")
+    }
+    Ok(bytes.split_at(at))
+}
+
+fn varint(cursor: &mut &[u8]) -> TractResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = cursor.split_first().context("Truncated varint")?;
+        *cursor = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Invalid varint")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn put_varint(buf: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            buf.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        buf.push(v as u8);
+    }
+
+    fn put_block(table: &mut Vec<u8>, entries: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+        let offset = table.len();
+        for (key, value) in entries {
+            put_varint(table, 0);
+            put_varint(table, key.len() as u64);
+            put_varint(table, value.len() as u64);
+            table.extend_from_slice(key);
+            table.extend_from_slice(value);
+        }
+        table.extend_from_slice(&0u32.to_le_bytes());
+        table.extend_from_slice(&1u32.to_le_bytes());
+        let mut handle = vec![];
+        put_varint(&mut handle, offset as u64);
+        put_varint(&mut handle, (table.len() - offset) as u64);
+        table.extend_from_slice(&[0; BLOCK_TRAILER_LEN]);
+        handle
+    }
+
+    #[test]
+    fn bundle() -> TractResult<()> {
+        let dir = std::env::temp_dir().join(format!("tract-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let prefix = dir.join("variables");
+
+        let data: Vec<u8> = [1f32, 2., 3., 4.].iter().flat_map(|x| x.to_le_bytes()).collect();
+        std::fs::write(format!("{}.data-00000-of-00001", prefix.display()), &data)?;
+
+        let header = BundleHeaderProto { num_shards: 1, ..BundleHeaderProto::default() };
+        let shape = crate::tfpb::tensorflow::TensorShapeProto {
+            dim: vec![2, 2]
+                .into_iter()
+                .map(|size| crate::tfpb::tensorflow::tensor_shape_proto::Dim {
+                    size,
+                    name: String::new(),
+                })
+                .collect(),
+            unknown_rank: false,
+        };
+        let entry = BundleEntryProto {
+            dtype: DataType::DtFloat as i32,
+            shape: Some(shape),
+            size: data.len() as i64,
+            ..BundleEntryProto::default()
+        };
+        let mut table = vec![];
+        let data_handle = put_block(
+            &mut table,
+            &[(b"", header.encode_to_vec()), (b"dense/kernel", entry.encode_to_vec())],
+        );
+        let index_handle = put_block(&mut table, &[(b"dense/kernel", data_handle)]);
+        let mut footer = vec![0, 0];
+        footer.extend(index_handle);
+        footer.resize(FOOTER_LEN - 8, 0);
+        footer.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+        table.extend(footer);
+        std::fs::write(format!("{}.index", prefix.display()), &table)?;
+
+        let tensors = read_bundle(&prefix)?;
+        std::fs::remove_dir_all(&dir)?;
+        assert_eq!(tensors.len(), 1);
+        assert_eq!(tensors["dense/kernel"], tensor2(&[[1f32, 2.], [3., 4.]]));
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let entry = |offset: i64, size: i64| BundleEntryProto {
+            dtype: DataType::DtFloat as i32,
+            shape: Some(crate::tfpb::tensorflow::TensorShapeProto::default()),
+            offset,
+            size,
+            ..BundleEntryProto::default()
+        };
+        let shards = vec![vec![0u8; 8]];
+        assert!(entry_tensor(&entry(4, 4), &shards).is_ok());
+        assert!(entry_tensor(&entry(8, 4), &shards).is_err());
+        assert!(entry_tensor(&entry(-4, 4), &shards).is_err());
+        assert!(entry_tensor(&entry(i64::MAX, i64::MAX), &shards).is_err());
+        let table = [0u8; 16];
+        assert!(block(&table, (0, 8)).is_ok());
+        assert!(block(&table, (usize::MAX, 1)).is_err());
+        assert!(block(&table, (1, usize::MAX)).is_err());
+    }
+}
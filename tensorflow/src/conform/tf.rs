@@ -82,6 +82,7 @@ impl From<Tensor> for TensorHolder {
             DatumType::ComplexF16 => unimplemented!(),
             DatumType::ComplexF32 => unimplemented!(),
             DatumType::ComplexF64 => unimplemented!(),
+            DatumType::Sequence => panic!("Sequence tensors have no TensorFlow equivalent"),
             DatumType::TDim => {
                 let dims = m.to_array_view::<TDim>().unwrap();
                 if let Ok(dims) = dims.iter().map(|d| d.to_i32()).collect::<TractResult<Vec<_>>>() {
@@ -0,0 +1,265 @@
+//! Functions of the graph library.
+//!
+//! TensorFlow 2 graphs call library functions through PartitionedCall-like
+//! ops, which are inlined in the calling graph before parsing, and use them as
+//! bodies of functional control flow ops (While, If), which are parsed as
+//! separate graphs.
+//!
+//! Inside a function, tensors are referred to as `node:output_arg:index`,
+//! or by their name for the function arguments. This module rewrites them to
+//! the `node:index` form of graphs.
+use std::borrow::Cow;
+
+use tract_hir::internal::*;
+
+use crate::tfpb::tensorflow::{DataType, FunctionDef, GraphDef, NodeDef, TensorShapeProto};
+
+/// Ops calling the function named by their `f` attribute.
+const CALL_OPS: &[&str] = &["PartitionedCall", "StatefulPartitionedCall"];
+
+/// Output arguments of the ops producing more than one, in order. Other ops
+/// are assumed to have a single (possibly list) output argument, unless they
+/// are library functions called directly.
+const OUTPUT_ARGS: &[(&str, &[&str])] = &[
+    ("BlockLSTM", &["i", "cs", "f", "o", "ci", "co", "h"]),
+    (
+        "FusedBatchNorm",
+        &["y", "batch_mean", "batch_variance", "reserve_space_1", "reserve_space_2"],
+    ),
+    (
+        "FusedBatchNormV2",
+        &["y", "batch_mean", "batch_variance", "reserve_space_1", "reserve_space_2"],
+    ),
+    (
+        "FusedBatchNormV3",
+        &[
+            "y",
+            "batch_mean",
+            "batch_variance",
+            "reserve_space_1",
+            "reserve_space_2",
+            "reserve_space_3",
+        ],
+    ),
+    ("Merge", &["output", "value_index"]),
+    ("Switch", &["output_false", "output_true"]),
+    ("TopKV2", &["values", "indices"]),
+    ("Unique", &["y", "idx"]),
+];
+
+pub fn find<'l>(library: &'l [FunctionDef], name: &str) -> TractResult<&'l FunctionDef> {
+    library
+        .iter()
+        .find(|f| f.signature.as_ref().map(|s| &*s.name) == Some(name))
+        .with_context(|| format!("Function {} not found in graph library", name))
+}
+
+/// Body of a function, with node names prefixed by `prefix`.
+///
+/// Function arguments are expected to be provided by nodes named after
+/// them (with the prefix). Returns the body nodes and the names of the
+/// function results.
+fn expand(
+    func: &FunctionDef,
+    prefix: &str,
+    library: &[FunctionDef],
+) -> TractResult<(Vec<NodeDef>, Vec<String>)> {
+    let ops: HashMap<&str, &str> =
+        func.node_def.iter().map(|n| (&*n.name, &*n.op)).collect::<HashMap<_, _>>();
+    let rename = |input: &str| -> TractResult<String> {
+        if input.starts_with('^') {
+            return Ok(format!("^{}{}", prefix, &input[1..]));
+        }
+        let parts: Vec<&str> = input.split(':').collect();
+        match parts.len() {
+            1 | 2 => Ok(format!("{}{}", prefix, input)),
+            3 => {
+                let op = ops.get(parts[0]).with_context(|| format!("No node for {}", input))?;
+                let offset = if let Ok(callee) = find(library, op) {
+                    let signature =
+                        callee.signature.as_ref().context("Function without signature")?;
+                    signature.output_arg.iter().position(|a| a.name == parts[1]).with_context(
+                        || format!("No output {} for function {}", parts[1], signature.name),
+                    )?
+                } else {
+                    OUTPUT_ARGS
+                        .iter()
+                        .find(|(o, _)| o == op)
+                        .and_then(|(_, args)| args.iter().position(|a| *a == parts[1]))
+                        .unwrap_or(0)
+                };
+                Ok(format!("{}{}:{}", prefix, parts[0], offset + parts[2].parse::<usize>()?))
+            }
+            _ => bail!("Invalid tensor reference {}", input),
+        }
+    };
+    let nodes = func
+        .node_def
+        .iter()
+        .map(|node| {
+            let mut node = node.clone();
+            node.name = format!("{}{}", prefix, node.name);
+            node.input = node.input.iter().map(|i| rename(i)).collect::<TractResult<_>>()?;
+            Ok(node)
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let signature = func.signature.as_ref().context("Function without signature")?;
+    let results = signature
+        .output_arg
+        .iter()
+        .map(|arg| {
+            let result = func
+                .ret
+                .get(&arg.name)
+                .with_context(|| format!("No value for {} result {}", signature.name, arg.name))?;
+            rename(result)
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    Ok((nodes, results))
+}
+
+/// Type of each argument of the function. `types` gives the types of the
+/// arguments at call site, for functions with generic arguments.
+fn argument_types(func: &FunctionDef, types: &[DataType]) -> TractResult<Vec<(String, DataType)>> {
+    let signature = func.signature.as_ref().context("Function without signature")?;
+    signature
+        .input_arg
+        .iter()
+        .enumerate()
+        .map(|(ix, arg)| {
+            let dt = if let Some(dt) =
+                DataType::from_i32(arg.r#type).filter(|dt| *dt != DataType::DtInvalid)
+            {
+                dt
+            } else if let Some(dt) = types.get(ix) {
+                *dt
+            } else {
+                bail!("Can not type argument {} of function {}", arg.name, signature.name)
+            };
+            Ok((arg.name.clone(), dt))
+        })
+        .collect()
+}
+
+fn placeholder(name: &str, dt: DataType, shape: Option<TensorShapeProto>) -> NodeDef {
+    let mut node = crate::tfpb::node().name(name).op("Placeholder").attr("dtype", dt);
+    if let Some(shape) = shape {
+        node = node.attr("shape", shape);
+    }
+    node
+}
+
+fn scalar() -> TensorShapeProto {
+    TensorShapeProto { dim: vec![], unknown_rank: false }
+}
+
+/// A function as a graph, its arguments being placeholders. Returns the
+/// graph and the names of the function results.
+pub fn function_graph(
+    func: &FunctionDef,
+    types: &[DataType],
+    library: &[FunctionDef],
+) -> TractResult<(GraphDef, Vec<String>)> {
+    let mut graph = crate::tfpb::graph();
+    for (name, dt) in argument_types(func, types)? {
+        graph.node.push(placeholder(&name, dt, None));
+    }
+    let (nodes, results) = expand(func, "", library)?;
+    graph.node.extend(nodes);
+    Ok((graph, results))
+}
+
+/// The body of a `While` loop, in the form expected by the core Loop op:
+/// it receives the iteration number, the current condition and the loop
+/// variables, runs the body function, then the condition function on the
+/// updated variables. Returns the graph and the names of the next condition
+/// and of the updated loop variables.
+pub fn loop_body_graph(
+    cond: &FunctionDef,
+    body: &FunctionDef,
+    types: &[DataType],
+    library: &[FunctionDef],
+) -> TractResult<(GraphDef, Vec<String>)> {
+    let mut graph = crate::tfpb::graph();
+    graph.node.push(placeholder("iteration", DataType::DtInt64, Some(scalar())));
+    graph.node.push(placeholder("condition", DataType::DtBool, Some(scalar())));
+    for (name, dt) in argument_types(body, types)? {
+        graph.node.push(placeholder(&format!("body/{}", name), dt, None));
+    }
+    let (nodes, vars) = expand(body, "body/", library)?;
+    graph.node.extend(nodes);
+    let args = argument_types(cond, types)?;
+    if args.len() != vars.len() {
+        bail!("While condition takes {} arguments, body produces {}", args.len(), vars.len())
+    }
+    for ((name, _), var) in args.iter().zip(vars.iter()) {
+        graph
+            .node
+            .push(crate::tfpb::node().name(format!("cond/{}", name)).op("Identity").input(var));
+    }
+    let (nodes, cond_results) = expand(cond, "cond/", library)?;
+    graph.node.extend(nodes);
+    let mut results = vec![cond_results.get(0).context("While condition without result")?.clone()];
+    results.extend(vars);
+    Ok((graph, results))
+}
+
+/// Inline the function calls of a graph, until none is left.
+///
+/// A call node is replaced by an Identity node per argument, the function body
+/// (all named after the call node) and an IdentityN node holding the call
+/// name and exposing the function results.
+pub fn inline_calls<'g>(
+    graph: &'g GraphDef,
+    library: &[FunctionDef],
+) -> TractResult<Cow<'g, GraphDef>> {
+    let mut graph = Cow::Borrowed(graph);
+    for _ in 0..64 {
+        let callee = |node: &NodeDef| -> TractResult<Option<&FunctionDef>> {
+            if CALL_OPS.contains(&&*node.op) {
+                Ok(Some(find(library, node.get_attr_func("f")?)?))
+            } else {
+                Ok(find(library, &node.op).ok())
+            }
+        };
+        let mut calls = false;
+        for node in &graph.node {
+            calls = calls || callee(node)?.is_some();
+        }
+        if !calls {
+            return Ok(graph);
+        }
+        let mut nodes = vec![];
+        for node in &graph.node {
+            let func = if let Some(func) = callee(node)? {
+                func
+            } else {
+                nodes.push(node.clone());
+                continue;
+            };
+            let prefix = format!("{}/", node.name);
+            let (data, control): (Vec<&String>, Vec<&String>) =
+                node.input.iter().partition(|i| !i.starts_with('^'));
+            let args =
+                argument_types(func, &node.get_attr_opt_list_type("Tin")?.unwrap_or_default())?;
+            if args.len() != data.len() {
+                bail!("Call {} has {} inputs for {} arguments", node.name, data.len(), args.len())
+            }
+            for ((name, _), input) in args.iter().zip(data) {
+                let mut arg = crate::tfpb::node()
+                    .name(format!("{}{}", prefix, name))
+                    .op("Identity")
+                    .input(input);
+                arg.input.extend(control.iter().map(|c| c.to_string()));
+                nodes.push(arg);
+            }
+            let (body, results) = expand(func, &prefix, library)?;
+            nodes.extend(body);
+            let mut outputs = crate::tfpb::node().name(&node.name).op("IdentityN");
+            outputs.input = results;
+            nodes.push(outputs);
+        }
+        graph.to_mut().node = nodes;
+    }
+    bail!("Function calls still present after 64 inlining passes (recursive function?)")
+}
@@ -1,11 +1,47 @@
-use crate::tfpb::tensorflow::{GraphDef, NodeDef, SavedModel};
+use crate::tfpb::tensorflow::{
+    DataType, FunctionDef, GraphDef, MetaGraphDef, NodeDef, SavedModel, TrackableObjectGraph,
+};
 use prost::Message;
 use std::{fs, path};
 use tract_hir::internal::*;
 
-#[derive(Default)]
-pub struct ParsingContext {
+pub struct ParsingContext<'a> {
     pub node_output_arities: HashMap<String, usize>,
+    pub framework: &'a Tensorflow,
+    pub library: &'a [FunctionDef],
+}
+
+impl<'a> ParsingContext<'a> {
+    pub fn function(&self, name: &str) -> TractResult<&'a FunctionDef> {
+        crate::function::find(self.library, name)
+    }
+
+    /// Parse a function of the library as a model whose inputs are the
+    /// function arguments and outputs the function results.
+    pub fn parse_function(&self, name: &str, types: &[DataType]) -> TractResult<InferenceModel> {
+        let (graph, results) =
+            crate::function::function_graph(self.function(name)?, types, self.library)?;
+        self.parse_subgraph(&graph, &results).with_context(|| format!("Parsing function {}", name))
+    }
+
+    /// Parse a graph calling functions of the library, with the given
+    /// tensors as outputs.
+    pub fn parse_subgraph(
+        &self,
+        graph: &GraphDef,
+        outputs: &[String],
+    ) -> TractResult<InferenceModel> {
+        let mut model = self.framework.parse_graph_with_library(graph, self.library)?.0;
+        let outputs = outputs
+            .iter()
+            .map(|output| {
+                let (node, slot) = Tensorflow::parse_input(output)?;
+                Ok(OutletId::new(model.node_id_by_name(node)?, slot))
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        model.set_output_outlets(&outputs)?;
+        Ok(model)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -62,7 +98,7 @@ impl Tensorflow {
     // "src_output" indicating which output tensor to use from "node". If
     // "src_output" is 0 the ":0" suffix can be omitted. Regular inputs may
     // optionally be followed by control inputs that have the format "^node".
-    fn parse_input(i: &str) -> TractResult<(&str, usize)> {
+    pub(crate) fn parse_input(i: &str) -> TractResult<(&str, usize)> {
         let pair = if i.starts_with("^") {
             (&i[1..], 0)
         } else {
@@ -115,12 +151,212 @@ impl Tensorflow {
         Ok(saved.meta_graphs.remove(0).graph_def.unwrap())
     }
 
+    /// Read `saved_model.pb` from a SavedModel directory.
+    pub fn open_saved_model_dir(&self, dir: impl AsRef<path::Path>) -> TractResult<SavedModel> {
+        self.open_saved_model(&mut fs::File::open(dir.as_ref().join("saved_model.pb"))?)
+    }
+
+    /// Read the variables checkpoint of a SavedModel directory, by
+    /// checkpoint key. A SavedModel without variables gives an empty map.
+    pub fn read_saved_model_variables(
+        &self,
+        dir: impl AsRef<path::Path>,
+    ) -> TractResult<HashMap<String, Tensor>> {
+        let prefix = dir.as_ref().join("variables").join("variables");
+        if prefix.with_extension("index").exists() {
+            crate::bundle::read_bundle(prefix)
+        } else {
+            Ok(HashMap::new())
+        }
+    }
+
+    /// Find the meta graph providing the `signature` signature_def.
+    pub fn saved_model_meta_graph<'s>(
+        saved: &'s SavedModel,
+        signature: &str,
+    ) -> TractResult<&'s MetaGraphDef> {
+        saved.meta_graphs.iter().find(|mg| mg.signature_def.contains_key(signature)).with_context(
+            || {
+                let mut available: Vec<&String> =
+                    saved.meta_graphs.iter().flat_map(|mg| mg.signature_def.keys()).collect();
+                available.sort();
+                format!("No signature {:?} in saved model (found {:?})", signature, available)
+            },
+        )
+    }
+
+    /// Load a SavedModel directory, with its variables as constants, and the
+    /// inputs and outputs of `signature` (usually "serving_default").
+    pub fn model_for_saved_model_dir(
+        &self,
+        dir: impl AsRef<path::Path>,
+        signature: &str,
+    ) -> TractResult<InferenceModel> {
+        let saved = self.open_saved_model_dir(&dir)?;
+        let variables = self.read_saved_model_variables(&dir)?;
+        let meta_graph = Self::saved_model_meta_graph(&saved, signature)?;
+        Ok(self.parse_meta_graph(meta_graph, signature, &variables)?.0)
+    }
+
+    /// Parse a meta graph, replacing its variables by their values from
+    /// the checkpoint, and mapping the inputs and outputs of `signature` to
+    /// the model inputs and outputs, in the order of the signature keys.
+    /// Model inputs and outputs are labelled by their signature key.
+    ///
+    /// Both reference (VariableV2) and resource (VarHandleOp) variables are
+    /// supported. Calls to functions of the graph library are inlined first,
+    /// and functional While and If become core loops and conditionals.
+    pub fn parse_meta_graph(
+        &self,
+        meta_graph: &MetaGraphDef,
+        signature: &str,
+        variables: &HashMap<String, Tensor>,
+    ) -> TractResult<TfModelAndExtensions> {
+        let graph = meta_graph.graph_def.as_ref().context("Meta graph without graph")?;
+        let library = graph.library.as_ref().map(|l| &*l.function).unwrap_or(&[]);
+        let inlined = crate::function::inline_calls(graph, library)?;
+        let graph = &*inlined;
+        let signature = meta_graph
+            .signature_def
+            .get(signature)
+            .with_context(|| format!("No signature {:?} in meta graph", signature))?;
+        let keys = Self::checkpoint_keys(graph)?;
+        let object_keys = Self::object_graph_keys(meta_graph, variables)?;
+        let TfModelAndExtensions(mut model, extensions) = self.parse_graph(graph)?;
+        for pbnode in &graph.node {
+            let op: Box<dyn InferenceOp> = match &*pbnode.op {
+                "VariableV2" | "VarHandleOp" => {
+                    let shared_name =
+                        pbnode.get_attr_opt_str("shared_name")?.filter(|s| !s.is_empty());
+                    let name = shared_name.as_ref().unwrap_or(&pbnode.name);
+                    let key =
+                        keys.get(&pbnode.name).or_else(|| object_keys.get(name)).unwrap_or(name);
+                    let value = variables.get(key).with_context(|| {
+                        format!(
+                            "No value in checkpoint for variable {} (key {:?})",
+                            pbnode.name, key
+                        )
+                    })?;
+                    Box::new(tract_hir::ops::konst::Const(value.clone().into_arc_tensor()))
+                }
+                "ReadVariableOp" => Box::new(tract_hir::ops::identity::Identity),
+                _ => continue,
+            };
+            let id = model.node_id_by_name(&pbnode.name)?;
+            model.node_mut(id).op = op;
+        }
+        let mut outlets = |infos: &HashMap<String, crate::tfpb::tensorflow::TensorInfo>| {
+            let mut infos: Vec<_> = infos.iter().collect();
+            infos.sort_by_key(|(key, _)| *key);
+            infos
+                .into_iter()
+                .map(|(key, info)| {
+                    use crate::tfpb::tensorflow::tensor_info::Encoding;
+                    let name = match &info.encoding {
+                        Some(Encoding::Name(name)) => name,
+                        _ => bail!("Signature {:?} is not a dense tensor", key),
+                    };
+                    let (node, slot) = Self::parse_input(name)?;
+                    let outlet = OutletId::new(model.node_id_by_name(node)?, slot);
+                    model.set_outlet_label(outlet, key.to_string())?;
+                    Ok(outlet)
+                })
+                .collect::<TractResult<TVec<OutletId>>>()
+        };
+        let inputs = outlets(&signature.inputs)?;
+        let outputs = outlets(&signature.outputs)?;
+        model.set_input_outlets(&inputs)?;
+        model.set_output_outlets(&outputs)?;
+        // drop initializers, savers and everything outside the signature
+        let model = model.into_compact()?;
+        let extensions = TfModelExtensions { control_inputs: vec![], ..extensions };
+        Ok(TfModelAndExtensions(model, extensions))
+    }
+
+    // Checkpoint keys of the variables, by variable node name, from the
+    // restore ops assigning to them. Variables no restore op feeds are
+    // assumed to use their name as key.
+    fn checkpoint_keys(graph: &GraphDef) -> TractResult<HashMap<String, String>> {
+        let nodes: HashMap<&str, &NodeDef> = graph.node.iter().map(|n| (&*n.name, n)).collect();
+        let resolve = |input: &str| -> TractResult<(&NodeDef, usize)> {
+            let (mut name, mut slot) = Self::parse_input(input)?;
+            loop {
+                let node = nodes.get(name).with_context(|| format!("No node {}", name))?;
+                if node.op == "Identity" && node.input.len() > 0 {
+                    let (n, s) = Self::parse_input(&node.input[0])?;
+                    name = n;
+                    slot = s;
+                } else {
+                    return Ok((node, slot));
+                }
+            }
+        };
+        let mut keys = HashMap::new();
+        for pbnode in &graph.node {
+            if (pbnode.op != "Assign" && pbnode.op != "AssignVariableOp") || pbnode.input.len() < 2
+            {
+                continue;
+            }
+            let (var, _) = resolve(&pbnode.input[0])?;
+            let (restore, slot) = resolve(&pbnode.input[1])?;
+            if restore.op != "RestoreV2" || restore.input.len() < 2 {
+                continue;
+            }
+            let names = resolve(&restore.input[1])?.0.get_attr_tensor("value")?;
+            let key = names.as_slice::<Blob>()?.get(slot).context("Invalid RestoreV2 slot")?;
+            keys.insert(var.name.clone(), String::from_utf8(key.to_vec())?);
+        }
+        Ok(keys)
+    }
+
+    // Checkpoint keys of the variables of a TF2 SavedModel, by variable name.
+    // The saved object graph of the meta graph lists the variables with the
+    // same node numbering as the trackable object graph stored in the
+    // checkpoint, whose VARIABLE_VALUE attributes give the keys
+    // (".../.ATTRIBUTES/VARIABLE_VALUE").
+    fn object_graph_keys(
+        meta_graph: &MetaGraphDef,
+        variables: &HashMap<String, Tensor>,
+    ) -> TractResult<HashMap<String, String>> {
+        use crate::tfpb::tensorflow::saved_object::Kind;
+        let (saved, trackable) =
+            match (&meta_graph.object_graph_def, variables.get("_CHECKPOINTABLE_OBJECT_GRAPH")) {
+                (Some(saved), Some(trackable)) => (saved, trackable),
+                _ => return Ok(HashMap::new()),
+            };
+        let trackable = TrackableObjectGraph::decode(&**trackable.to_scalar::<Blob>()?)
+            .context("Decoding checkpoint object graph")?;
+        let mut keys = HashMap::new();
+        for (saved, trackable) in saved.nodes.iter().zip(trackable.nodes.iter()) {
+            if let Some(Kind::Variable(variable)) = &saved.kind {
+                if let Some(value) =
+                    trackable.attributes.iter().find(|attr| attr.name == "VARIABLE_VALUE")
+                {
+                    keys.insert(variable.name.clone(), value.checkpoint_key.clone());
+                }
+            }
+        }
+        Ok(keys)
+    }
+
     pub fn parse_graph(&self, graph: &GraphDef) -> TractResult<TfModelAndExtensions> {
+        let library = graph.library.as_ref().map(|l| &*l.function).unwrap_or(&[]);
+        self.parse_graph_with_library(graph, library)
+    }
+
+    fn parse_graph_with_library(
+        &self,
+        graph: &GraphDef,
+        library: &[FunctionDef],
+    ) -> TractResult<TfModelAndExtensions> {
         use crate::ops::control_flow as cf;
 
+        let inlined = crate::function::inline_calls(graph, library)?;
+        let graph = &*inlined;
         let mut model = InferenceModel::default();
         let mut inputs = tvec!();
-        let mut context = ParsingContext::default();
+        let mut context =
+            ParsingContext { node_output_arities: HashMap::default(), framework: self, library };
         let mut control_inputs = vec![];
 
         // compute min output arity for all nodes
@@ -250,3 +486,185 @@ impl Framework<GraphDef, InferenceModel> for Tensorflow {
         Ok(self.parse_graph(graph)?.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tfpb::tensorflow::tensor_info::Encoding;
+    use crate::tfpb::tensorflow::tensor_shape_proto::Dim;
+    use crate::tfpb::tensorflow::*;
+    use crate::tfpb::{graph, node};
+
+    fn shape(dims: &[i64]) -> TensorShapeProto {
+        TensorShapeProto {
+            dim: dims.iter().map(|&size| Dim { size, name: String::new() }).collect(),
+            unknown_rank: false,
+        }
+    }
+
+    fn strings(name: &str, values: &[&str]) -> NodeDef {
+        let value = TensorProto {
+            dtype: DataType::DtString as i32,
+            tensor_shape: Some(shape(&[values.len() as i64])),
+            string_val: values.iter().map(|s| s.as_bytes().to_vec()).collect(),
+            ..TensorProto::default()
+        };
+        node().name(name).op("Const").attr("dtype", DataType::DtString).attr("value", value)
+    }
+
+    fn tensor_info(name: &str) -> TensorInfo {
+        TensorInfo { encoding: Some(Encoding::Name(name.into())), ..TensorInfo::default() }
+    }
+
+    #[test]
+    fn meta_graph_with_restored_variable() -> TractResult<()> {
+        let graph = graph()
+            .node(
+                node()
+                    .name("x")
+                    .op("Placeholder")
+                    .attr("dtype", DataType::DtFloat)
+                    .attr("shape", shape(&[2])),
+            )
+            .node(
+                node()
+                    .name("w")
+                    .op("VariableV2")
+                    .attr("dtype", DataType::DtFloat)
+                    .attr("shape", shape(&[2]))
+                    .attr("container", "")
+                    .attr("shared_name", ""),
+            )
+            .node(node().name("y").op("AddV2").input("x").input("w"))
+            .node(strings("save/filename", &["model"]))
+            .node(strings("save/tensor_names", &["weights"]))
+            .node(strings("save/shape_and_slices", &[""]))
+            .node(
+                node()
+                    .name("save/RestoreV2")
+                    .op("RestoreV2")
+                    .input("save/filename")
+                    .input("save/tensor_names")
+                    .input("save/shape_and_slices"),
+            )
+            .node(node().name("save/Assign").op("Assign").input("w").input("save/RestoreV2"));
+        let signature = SignatureDef {
+            inputs: vec![("input".to_string(), tensor_info("x:0"))].into_iter().collect(),
+            outputs: vec![("output".to_string(), tensor_info("y:0"))].into_iter().collect(),
+            ..SignatureDef::default()
+        };
+        let meta_graph = MetaGraphDef {
+            graph_def: Some(graph),
+            signature_def: vec![("serving_default".to_string(), signature)].into_iter().collect(),
+            ..MetaGraphDef::default()
+        };
+        let variables = vec![("weights".to_string(), tensor1(&[1f32, 2.]))].into_iter().collect();
+        let model =
+            crate::tensorflow().parse_meta_graph(&meta_graph, "serving_default", &variables)?.0;
+        let input = model.input_outlets()?[0];
+        let output = model.output_outlets()?[0];
+        assert_eq!(model.outlet_label(input), Some("input"));
+        assert_eq!(model.outlet_label(output), Some("output"));
+        let model = model.into_optimized()?.into_runnable()?;
+        let result = model.run(tvec!(tensor1(&[10f32, 20.])))?;
+        assert_eq!(*result[0], tensor1(&[11f32, 22.]));
+        Ok(())
+    }
+
+    fn resource_variable_graph() -> GraphDef {
+        graph()
+            .node(
+                node()
+                    .name("x")
+                    .op("Placeholder")
+                    .attr("dtype", DataType::DtFloat)
+                    .attr("shape", shape(&[2])),
+            )
+            .node(
+                node()
+                    .name("w")
+                    .op("VarHandleOp")
+                    .attr("dtype", DataType::DtFloat)
+                    .attr("shape", shape(&[2]))
+                    .attr("container", "")
+                    .attr("shared_name", "dense/w"),
+            )
+            .node(node().name("w/read").op("ReadVariableOp").input("w"))
+            .node(node().name("y").op("AddV2").input("x").input("w/read"))
+    }
+
+    fn resource_variable_meta_graph(object_graph_def: Option<SavedObjectGraph>) -> MetaGraphDef {
+        let signature = SignatureDef {
+            inputs: vec![("input".to_string(), tensor_info("x:0"))].into_iter().collect(),
+            outputs: vec![("output".to_string(), tensor_info("y:0"))].into_iter().collect(),
+            ..SignatureDef::default()
+        };
+        MetaGraphDef {
+            graph_def: Some(resource_variable_graph()),
+            signature_def: vec![("serving_default".to_string(), signature)].into_iter().collect(),
+            object_graph_def,
+            ..MetaGraphDef::default()
+        }
+    }
+
+    #[test]
+    fn meta_graph_with_object_graph_variable() -> TractResult<()> {
+        use trackable_object_graph::trackable_object::{ObjectReference, SerializedTensor};
+        use trackable_object_graph::TrackableObject;
+        let child = ObjectReference { node_id: 1, local_name: "w".into() };
+        let saved = SavedObjectGraph {
+            nodes: vec![
+                SavedObject { children: vec![child.clone()], ..SavedObject::default() },
+                SavedObject {
+                    kind: Some(saved_object::Kind::Variable(SavedVariable {
+                        name: "dense/w".into(),
+                        ..SavedVariable::default()
+                    })),
+                    ..SavedObject::default()
+                },
+            ],
+            ..SavedObjectGraph::default()
+        };
+        let key = "w/.ATTRIBUTES/VARIABLE_VALUE";
+        let trackable = TrackableObjectGraph {
+            nodes: vec![
+                TrackableObject { children: vec![child], ..TrackableObject::default() },
+                TrackableObject {
+                    attributes: vec![SerializedTensor {
+                        name: "VARIABLE_VALUE".into(),
+                        full_name: "dense/w".into(),
+                        checkpoint_key: key.into(),
+                        optional_restore: false,
+                    }],
+                    ..TrackableObject::default()
+                },
+            ],
+        };
+        let mut encoded = vec![];
+        trackable.encode(&mut encoded)?;
+        let variables = vec![
+            ("_CHECKPOINTABLE_OBJECT_GRAPH".to_string(), tensor0(Blob(encoded))),
+            (key.to_string(), tensor1(&[1f32, 2.])),
+        ]
+        .into_iter()
+        .collect();
+        let meta_graph = resource_variable_meta_graph(Some(saved));
+        let model =
+            crate::tensorflow().parse_meta_graph(&meta_graph, "serving_default", &variables)?.0;
+        let model = model.into_optimized()?.into_runnable()?;
+        let result = model.run(tvec!(tensor1(&[10f32, 20.])))?;
+        assert_eq!(*result[0], tensor1(&[11f32, 22.]));
+        Ok(())
+    }
+
+    #[test]
+    fn meta_graph_with_missing_variable() {
+        let meta_graph = resource_variable_meta_graph(None);
+        let variables = HashMap::new();
+        let err = crate::tensorflow()
+            .parse_meta_graph(&meta_graph, "serving_default", &variables)
+            .err()
+            .unwrap();
+        assert!(format!("{:?}", err).contains("No value in checkpoint for variable w"));
+    }
+}
@@ -0,0 +1,706 @@
+//! Functional control flow of TensorFlow 2: While and If, whose bodies are
+//! functions of the graph library, translated to core Loop and If.
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::{logic, scan};
+
+use crate::model::{ParsingContext, TfOpRegister};
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn register_all_ops(reg: &mut TfOpRegister) {
+    reg.insert("IdentityN", |_, node| {
+        Ok(Box::new(IdentityN(node.input.iter().filter(|i| !i.starts_with('^')).count())))
+    });
+    reg.insert("If", _if);
+    reg.insert("StatelessIf", _if);
+    reg.insert("StatelessWhile", _while);
+    reg.insert("While", _while);
+}
+
+fn _if(ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let types = node.get_attr_opt_list_type("Tin")?.unwrap_or_default();
+    let then_body = ctx.parse_function(node.get_attr_func("then_branch")?, &types)?;
+    let else_body = ctx.parse_function(node.get_attr_func("else_branch")?, &types)?;
+    Ok(Box::new(If::new(then_body, else_body)))
+}
+
+fn _while(ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let types = node.get_attr_list_type("T")?;
+    let cond = node.get_attr_func("cond")?;
+    let body = node.get_attr_func("body")?;
+    let (graph, results) = crate::function::loop_body_graph(
+        ctx.function(cond)?,
+        ctx.function(body)?,
+        &types,
+        ctx.library,
+    )?;
+    let body = ctx
+        .parse_subgraph(&graph, &results)
+        .with_context(|| format!("Parsing loop body {}", body))?;
+    let cond = ctx.parse_function(cond, &types)?;
+    Ok(Box::new(While::new(cond, body, types.len())))
+}
+
+/// Unify the types and shapes of a set of facts.
+fn unify_types_and_shapes(facts: &mut [&mut InferenceFact]) -> TractResult<bool> {
+    let mut changed =
+        Factoid::unify_all(&mut *facts.iter_mut().map(|f| &mut f.datum_type).collect::<TVec<_>>())?;
+    changed |=
+        Factoid::unify_all(&mut *facts.iter_mut().map(|f| &mut f.shape).collect::<TVec<_>>())?;
+    Ok(changed)
+}
+
+/// Wire the nodes of a model in another one, on the given inputs.
+fn wire_inline(
+    body: &TypedModel,
+    prefix: &str,
+    target: &mut TypedModel,
+    inputs: &[OutletId],
+) -> TractResult<TVec<OutletId>> {
+    let mut mapping: HashMap<OutletId, OutletId> =
+        body.input_outlets()?.iter().cloned().zip(inputs.iter().cloned()).collect();
+    for id in body.eval_order()? {
+        if body.input_outlets()?.iter().any(|o| o.node == id) {
+            continue;
+        }
+        let node = body.node(id);
+        let node_inputs = node.inputs.iter().map(|o| mapping[o]).collect::<TVec<_>>();
+        let name = format!("{}.{}", prefix, node.name);
+        for (slot, outlet) in
+            target.wire_node(name, node.op.clone(), &node_inputs)?.iter().enumerate()
+        {
+            mapping.insert(OutletId::new(id, slot), *outlet);
+        }
+    }
+    Ok(body.output_outlets()?.iter().map(|o| mapping[o]).collect())
+}
+
+/// Forwards its inputs.
+#[derive(Debug, Clone, new, Hash)]
+pub struct IdentityN(usize);
+
+impl_dyn_hash!(IdentityN);
+
+impl Op for IdentityN {
+    fn name(&self) -> Cow<str> {
+        "IdentityN".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for IdentityN {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        Ok(inputs)
+    }
+}
+
+impl InferenceRulesOp for IdentityN {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(&inputs, self.0)?;
+        check_output_arity(&outputs, self.0)?;
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            s.equals(&input.datum_type, &output.datum_type)?;
+            s.equals(&input.shape, &output.shape)?;
+        }
+        Ok(())
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        _target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        Ok(node.inputs.iter().map(|i| mapping[i]).collect())
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.0)
+    }
+
+    as_op!();
+}
+
+/// If or StatelessIf: the condition, then the inputs of both branches.
+#[derive(Debug, Clone, new, Hash)]
+struct If {
+    then_body: InferenceModel,
+    else_body: InferenceModel,
+}
+
+impl_dyn_hash!(If);
+
+impl If {
+    fn unify_facts(
+        &mut self,
+        inputs: &mut [InferenceFact],
+        outputs: &mut [InferenceFact],
+    ) -> TractResult<bool> {
+        let mut changed = inputs[0].datum_type.unify_with(&bool::datum_type().into())?;
+        for (ix, input) in inputs[1..].iter_mut().enumerate() {
+            let then_input = self.then_body.input_fact_mut(ix)?;
+            let else_input = self.else_body.input_fact_mut(ix)?;
+            changed |= unify_types_and_shapes(&mut [input, then_input, else_input])?;
+        }
+        for (ix, output) in outputs.iter_mut().enumerate() {
+            let then_output = self.then_body.output_fact_mut(ix)?;
+            let else_output = self.else_body.output_fact_mut(ix)?;
+            changed |= unify_types_and_shapes(&mut [output, then_output, else_output])?;
+        }
+        Ok(changed)
+    }
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for If {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let body =
+            if inputs[0].cast_to_scalar::<bool>()? { &self.then_body } else { &self.else_body };
+        let inputs = inputs[1..].iter().map(|t| t.clone().into_tensor()).collect();
+        body.clone().into_runnable()?.run(inputs)
+    }
+}
+
+impl InferenceOp for If {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        for body in &[&self.then_body, &self.else_body] {
+            if inputs.len() != body.input_outlets()?.len() + 1 {
+                bail!(
+                    "If receives {} inputs, branch expects {}",
+                    inputs.len() - 1,
+                    body.inputs.len()
+                )
+            }
+            if outputs.len() != body.output_outlets()?.len() {
+                bail!("If has {} outputs, branch computes {}", outputs.len(), body.outputs.len())
+            }
+        }
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = self.unify_facts(&mut inputs, &mut outputs)?;
+            changed |= self.then_body.analyse(false).context("analysing then branch")?;
+            changed |= self.else_body.analyse(false).context("analysing else branch")?;
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs = node.inputs.iter().map(|o| mapping[o]).collect::<TVec<_>>();
+        let input_mapping: Vec<usize> = (1..inputs.len()).collect();
+        let op = logic::If::new(
+            self.then_body.clone().into_typed()?,
+            input_mapping.clone(),
+            self.else_body.clone().into_typed()?,
+            input_mapping,
+        );
+        target.wire_node(&*node.name, op, &inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.then_body.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+/// While or StatelessWhile: runs the body function on the loop variables as
+/// long as the condition function holds on them.
+///
+/// `cond` is the condition function, computing the initial condition, and
+/// `body` the core Loop body, computing the next condition and variables.
+///
+/// Loop variables may change shape from one iteration to the next (TF
+/// shape_invariants). When the body computes a dimension different from the
+/// one it received, the analysis starts over from `source_body`, the body as
+/// parsed, with a fresh symbol for this dimension of the body input.
+#[derive(Debug, Clone, Hash)]
+struct While {
+    cond: InferenceModel,
+    body: InferenceModel,
+    source_body: InferenceModel,
+    /// For each loop variable, the axes along which it changes.
+    growing: TVec<TVec<(usize, Symbol)>>,
+}
+
+impl_dyn_hash!(While);
+
+impl While {
+    fn new(cond: InferenceModel, body: InferenceModel, vars: usize) -> While {
+        While { cond, source_body: body.clone(), body, growing: tvec!(tvec!(); vars) }
+    }
+
+    /// Shapes of a loop variable: the outer input and the body input agree
+    /// except on growing axes, where the body input gets its own symbol. The
+    /// outer output only gets the dimensions the body leaves unchanged.
+    fn unify_var_shapes(
+        &mut self,
+        ix: usize,
+        input: &mut InferenceFact,
+        output: &mut InferenceFact,
+    ) -> TractResult<bool> {
+        let mut changed = self.cond.input_fact_mut(ix)?.shape.unify_with_mut(&mut input.shape)?;
+        let growing = &self.growing[ix];
+        let is_growing = |axis: usize| growing.iter().any(|g| g.0 == axis);
+        let body_input = self.body.input_fact_mut(2 + ix)?;
+        let mut rank = input.shape.rank().unify(&body_input.shape.rank())?;
+        rank = rank.unify(&output.shape.rank())?;
+        let rank =
+            if let Some(rank) = rank.concretize() { rank as usize } else { return Ok(changed) };
+        let outer_dims =
+            (0..rank).map(|axis| input.shape.dim(axis).unwrap_or_default()).collect::<TVec<_>>();
+        let body_dims = (0..rank)
+            .map(|axis| {
+                if let Some(g) = growing.iter().find(|g| g.0 == axis) {
+                    GenericFactoid::Only(g.1.to_dim())
+                } else {
+                    outer_dims[axis].clone()
+                }
+            })
+            .collect();
+        changed |= body_input.shape.unify_with(&ShapeFactoid::closed(body_dims))?;
+        let body_input_dims =
+            (0..rank).map(|axis| body_input.shape.dim(axis).unwrap()).collect::<TVec<_>>();
+        let outer_dims = (0..rank)
+            .map(|axis| {
+                if is_growing(axis) {
+                    outer_dims[axis].clone()
+                } else {
+                    body_input_dims[axis].clone()
+                }
+            })
+            .collect();
+        changed |= input.shape.unify_with(&ShapeFactoid::closed(outer_dims))?;
+
+        let body_output = self.body.output_fact(1 + ix)?.shape.clone();
+        let mut grown = tvec!();
+        let mut output_dims = tvec!();
+        for axis in 0..rank {
+            let (i, o) = (&body_input_dims[axis], body_output.dim(axis).unwrap_or_default());
+            match (i.concretize(), o.concretize()) {
+                (Some(i), Some(o)) if i == o => output_dims.push(GenericFactoid::Only(o)),
+                (Some(_), Some(_)) if !is_growing(axis) => {
+                    grown.push((axis, Symbol::new('g')));
+                    output_dims.push(GenericFactoid::Any)
+                }
+                _ => output_dims.push(GenericFactoid::Any),
+            }
+        }
+        if grown.len() > 0 {
+            self.growing[ix].extend(grown);
+            self.body = self.source_body.clone();
+            return Ok(true);
+        }
+        changed |= output.shape.unify_with(&ShapeFactoid::closed(output_dims))?;
+        Ok(changed)
+    }
+
+    fn unify_facts(
+        &mut self,
+        inputs: &mut [InferenceFact],
+        outputs: &mut [InferenceFact],
+    ) -> TractResult<bool> {
+        let boolean = bool::datum_type().into();
+        let mut changed = self.cond.output_fact_mut(0)?.datum_type.unify_with(&boolean)?;
+        changed |= self.body.output_fact_mut(0)?.datum_type.unify_with(&boolean)?;
+        for (ix, (input, output)) in inputs.iter_mut().zip(outputs.iter_mut()).enumerate() {
+            {
+                let mut outlets = vec![self.body.input_outlets()?[2 + ix]];
+                let body_output = self.body.output_outlets()?[1 + ix];
+                if !outlets.contains(&body_output) {
+                    outlets.push(body_output);
+                }
+                let mut facts = self.body.outlets_fact_mut(&outlets)?;
+                facts.push(self.cond.input_fact_mut(ix)?);
+                facts.push(input);
+                facts.push(output);
+                changed |= Factoid::unify_all(
+                    &mut *facts.iter_mut().map(|f| &mut f.datum_type).collect::<TVec<_>>(),
+                )?;
+            }
+            changed |= self.unify_var_shapes(ix, input, output)?;
+        }
+        Ok(changed)
+    }
+}
+
+impl Op for While {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for While {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let cond = self.cond.clone().into_runnable()?;
+        let body = self.body.clone().into_runnable()?;
+        let mut vars: TVec<Tensor> = inputs.into_iter().map(|t| t.into_tensor()).collect();
+        let mut running = cond.run(vars.clone())?[0].cast_to_scalar::<bool>()?;
+        let mut iteration = 0i64;
+        while running {
+            let mut body_inputs = tvec!(tensor0(iteration), tensor0(running));
+            body_inputs.extend(vars.drain(..));
+            let mut outputs = body.run(body_inputs)?.into_iter();
+            running = outputs.next().context("Loop body without condition")?.cast_to_scalar()?;
+            vars.extend(outputs.map(|t| t.into_tensor()));
+            iteration += 1;
+        }
+        Ok(vars.into_iter().map(|t| t.into_arc_tensor()).collect())
+    }
+}
+
+impl InferenceOp for While {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        if inputs.len() != outputs.len() || inputs.len() != self.cond.input_outlets()?.len() {
+            bail!("While expects as many inputs and outputs as the condition has arguments")
+        }
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = self.unify_facts(&mut inputs, &mut outputs)?;
+            changed |= self.cond.analyse(false).context("analysing loop condition")?;
+            changed |= self.body.analyse(false).context("analysing loop body")?;
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let vars = node.inputs.iter().map(|o| mapping[o]).collect::<TVec<_>>();
+        let cond = self.cond.clone().into_typed()?;
+        let mut inputs = wire_inline(&cond, &format!("{}.cond", node.name), target, &vars)?;
+        inputs.extend(vars.iter().cloned());
+        let op = scan::Loop::new(self.body.clone().into_typed()?, false, true, vars.len())?;
+        target.wire_node(&*node.name, op, &inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.cond.input_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::tfpb::tensorflow::attr_value::{ListValue, Value};
+    use crate::tfpb::tensorflow::op_def::ArgDef;
+    use crate::tfpb::tensorflow::*;
+    use crate::tfpb::{graph, node};
+
+    fn func(name: &str) -> AttrValue {
+        AttrValue {
+            value: Some(Value::Func(NameAttrList { name: name.into(), attr: HashMap::new() })),
+        }
+    }
+
+    fn types(types: &[DataType]) -> AttrValue {
+        let list = types.iter().map(|&dt| dt as i32).collect();
+        AttrValue { value: Some(Value::List(ListValue { r#type: list, ..ListValue::default() })) }
+    }
+
+    fn int_args(names: &[&str]) -> Vec<ArgDef> {
+        names
+            .iter()
+            .map(|name| ArgDef {
+                name: name.to_string(),
+                r#type: DataType::DtInt32 as i32,
+                ..ArgDef::default()
+            })
+            .collect()
+    }
+
+    fn function(
+        name: &str,
+        inputs: &[&str],
+        outputs: &[(&str, &str)],
+        nodes: Vec<NodeDef>,
+    ) -> FunctionDef {
+        let output_names = outputs.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        FunctionDef {
+            signature: Some(OpDef {
+                name: name.into(),
+                input_arg: int_args(inputs),
+                output_arg: int_args(&output_names),
+                ..OpDef::default()
+            }),
+            node_def: nodes,
+            ret: outputs.iter().map(|(name, ret)| (name.to_string(), ret.to_string())).collect(),
+            ..FunctionDef::default()
+        }
+    }
+
+    fn placeholder(name: &str, dt: DataType) -> NodeDef {
+        let scalar = TensorShapeProto { dim: vec![], unknown_rank: false };
+        node().name(name).op("Placeholder").attr("dtype", dt).attr("shape", scalar)
+    }
+
+    fn library() -> TractResult<FunctionDefLibrary> {
+        let one = TensorProto::try_from(&tensor0(1i32))?;
+        let mut less = function(
+            "less",
+            &["i", "limit"],
+            &[("z", "lt:z:0")],
+            vec![node().name("lt").op("Less").input("i").input("limit")],
+        );
+        less.signature.as_mut().unwrap().output_arg[0].r#type = DataType::DtBool as i32;
+        let mut less_doubling = less.clone();
+        less_doubling.signature.as_mut().unwrap().name = "less_doubling".into();
+        less_doubling.signature.as_mut().unwrap().input_arg = int_args(&["i", "limit", "acc"]);
+        Ok(FunctionDefLibrary {
+            function: vec![
+                less,
+                function(
+                    "step",
+                    &["i", "limit"],
+                    &[("i", "add:z:0"), ("limit", "id:output:0")],
+                    vec![
+                        node()
+                            .name("one")
+                            .op("Const")
+                            .attr("dtype", DataType::DtInt32)
+                            .attr("value", one.clone()),
+                        node().name("add").op("AddV2").input("i").input("one:output:0"),
+                        node().name("id").op("Identity").input("limit"),
+                    ],
+                ),
+                less_doubling,
+                function(
+                    "doubling",
+                    &["i", "limit", "acc"],
+                    &[("i", "add:z:0"), ("limit", "id:output:0"), ("acc", "concat:output:0")],
+                    vec![
+                        node()
+                            .name("one")
+                            .op("Const")
+                            .attr("dtype", DataType::DtInt32)
+                            .attr("value", one.clone()),
+                        node()
+                            .name("zero")
+                            .op("Const")
+                            .attr("dtype", DataType::DtInt32)
+                            .attr("value", TensorProto::try_from(&tensor0(0i32))?),
+                        node().name("add").op("AddV2").input("i").input("one:output:0"),
+                        node().name("id").op("Identity").input("limit"),
+                        node()
+                            .name("seven")
+                            .op("Const")
+                            .attr("dtype", DataType::DtInt32)
+                            .attr("value", TensorProto::try_from(&tensor1(&[7i32]))?),
+                        node()
+                            .name("concat")
+                            .op("ConcatV2")
+                            .input("acc")
+                            .input("seven:output:0")
+                            .input("zero:output:0"),
+                    ],
+                ),
+                function(
+                    "swap",
+                    &["x", "y"],
+                    &[("a", "y_id:output:0"), ("b", "x_id:output:0")],
+                    vec![
+                        node().name("x_id").op("Identity").input("x"),
+                        node().name("y_id").op("Identity").input("y"),
+                    ],
+                ),
+                function(
+                    "second_of_swap",
+                    &["x", "y"],
+                    &[("z", "s:b:0")],
+                    vec![node().name("s").op("swap").input("x").input("y")],
+                ),
+                function(
+                    "twice",
+                    &["a"],
+                    &[("b", "add:z:0")],
+                    vec![node().name("add").op("AddV2").input("a").input("a")],
+                ),
+                function(
+                    "same",
+                    &["a"],
+                    &[("b", "id:output:0")],
+                    vec![node().name("id").op("Identity").input("a")],
+                ),
+            ],
+            ..FunctionDefLibrary::default()
+        })
+    }
+
+    fn run(graph: GraphDef, output: &str, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut model = crate::tensorflow().model_for_proto_model(&graph)?;
+        model.set_output_names(&[output])?;
+        model.analyse(false)?;
+        let expected = model.clone().into_runnable()?.run(inputs.clone())?;
+        let outputs = model.into_optimized()?.into_runnable()?.run(inputs)?;
+        assert_eq!(expected, outputs);
+        Ok(outputs)
+    }
+
+    #[test]
+    fn while_and_call() -> TractResult<()> {
+        let mut graph = graph()
+            .node(placeholder("x", DataType::DtInt32))
+            .node(placeholder("limit", DataType::DtInt32))
+            .node(
+                node()
+                    .name("loop")
+                    .op("StatelessWhile")
+                    .input("x")
+                    .input("limit")
+                    .attr("T", types(&[DataType::DtInt32, DataType::DtInt32]))
+                    .attr("cond", func("less"))
+                    .attr("body", func("step")),
+            )
+            .node(
+                node()
+                    .name("double")
+                    .op("PartitionedCall")
+                    .input("loop")
+                    .attr("Tin", types(&[DataType::DtInt32]))
+                    .attr("f", func("twice")),
+            );
+        graph.library = Some(library()?);
+        let outputs = run(graph.clone(), "double", tvec!(tensor0(3i32), tensor0(10i32)))?;
+        assert_eq!(*outputs[0], tensor0(20i32));
+        let outputs = run(graph, "double", tvec!(tensor0(12i32), tensor0(10i32)))?;
+        assert_eq!(*outputs[0], tensor0(24i32));
+        Ok(())
+    }
+
+    #[test]
+    fn while_growing_variable() -> TractResult<()> {
+        let mut graph = graph()
+            .node(placeholder("x", DataType::DtInt32))
+            .node(placeholder("limit", DataType::DtInt32))
+            .node(
+                node()
+                    .name("acc")
+                    .op("Placeholder")
+                    .attr("dtype", DataType::DtInt32)
+                    .attr("shape", TensorShapeProto { dim: vec![], unknown_rank: true }),
+            )
+            .node(
+                node()
+                    .name("loop")
+                    .op("StatelessWhile")
+                    .input("x")
+                    .input("limit")
+                    .input("acc")
+                    .attr("T", types(&[DataType::DtInt32; 3]))
+                    .attr("cond", func("less_doubling"))
+                    .attr("body", func("doubling")),
+            );
+        graph.library = Some(library()?);
+        let mut model = crate::tensorflow().model_for_proto_model(&graph)?;
+        model.set_output_names(&["loop:2"])?;
+        model.set_input_fact(2, InferenceFact::dt_shape(i32::datum_type(), &[2]))?;
+        let model = model.into_optimized()?.into_runnable()?;
+        let outputs = model.run(tvec!(tensor0(0i32), tensor0(2i32), tensor1(&[1i32, 2])))?;
+        assert_eq!(*outputs[0], tensor1(&[1i32, 2, 7, 7]));
+        Ok(())
+    }
+
+    #[test]
+    fn direct_function_call() -> TractResult<()> {
+        let mut graph = graph()
+            .node(placeholder("x", DataType::DtInt32))
+            .node(placeholder("y", DataType::DtInt32))
+            .node(
+                node()
+                    .name("call")
+                    .op("PartitionedCall")
+                    .input("x")
+                    .input("y")
+                    .attr("Tin", types(&[DataType::DtInt32; 2]))
+                    .attr("f", func("second_of_swap")),
+            );
+        graph.library = Some(library()?);
+        let outputs = run(graph, "call", tvec!(tensor0(3i32), tensor0(5i32)))?;
+        assert_eq!(*outputs[0], tensor0(3i32));
+        Ok(())
+    }
+
+    #[test]
+    fn if_branches() -> TractResult<()> {
+        let mut graph = graph()
+            .node(placeholder("c", DataType::DtBool))
+            .node(placeholder("x", DataType::DtInt32))
+            .node(
+                node()
+                    .name("if")
+                    .op("StatelessIf")
+                    .input("c")
+                    .input("x")
+                    .attr("Tin", types(&[DataType::DtInt32]))
+                    .attr("then_branch", func("twice"))
+                    .attr("else_branch", func("same")),
+            );
+        graph.library = Some(library()?);
+        let outputs = run(graph.clone(), "if", tvec!(tensor0(true), tensor0(3i32)))?;
+        assert_eq!(*outputs[0], tensor0(6i32));
+        let outputs = run(graph, "if", tvec!(tensor0(false), tensor0(3i32)))?;
+        assert_eq!(*outputs[0], tensor0(3i32));
+        Ok(())
+    }
+}
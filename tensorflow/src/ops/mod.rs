@@ -14,6 +14,7 @@ macro_rules! op_tf {
 
 pub mod array;
 pub mod control_flow;
+pub mod functional;
 pub mod logic;
 pub mod math;
 pub mod nn;
@@ -25,6 +26,7 @@ pub mod vars;
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     array::register_all_ops(reg);
     control_flow::register_all_ops(reg);
+    functional::register_all_ops(reg);
     logic::register_all_ops(reg);
     math::register_all_ops(reg);
     nn::register_all_ops(reg);
@@ -1,4 +1,3 @@
-mod philox;
 mod random_uniform;
 
 use crate::model::TfOpRegister;
@@ -2,7 +2,7 @@ use crate::model::ParsingContext;
 use crate::tfpb::tensorflow::NodeDef;
 use tract_hir::internal::*;
 
-use super::philox::Philox4x32x10;
+use tract_hir::tract_core::ops::random::Philox4x32x10;
 
 pub fn random_uniform(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
     let dtype = node.get_attr_datum_type("dtype")?;
@@ -221,3 +221,76 @@ impl TypedOp for Assign {
         Ok(tvec!(inputs[0].clone()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tfpb::tensorflow::tensor_shape_proto::Dim;
+    use crate::tfpb::tensorflow::*;
+    use crate::tfpb::{graph, node};
+    use tract_hir::tract_core::tract_linalg::multithread::Executor;
+
+    fn shape(dims: &[i64]) -> TensorShapeProto {
+        TensorShapeProto {
+            dim: dims.iter().map(|&size| Dim { size, name: String::new() }).collect(),
+            unknown_rank: false,
+        }
+    }
+
+    // two accumulators, each adding the input to its variable at every run
+    fn accumulators() -> TractResult<TypedModel> {
+        let mut graph = graph().node(
+            node()
+                .name("x")
+                .op("Placeholder")
+                .attr("dtype", DataType::DtFloat)
+                .attr("shape", shape(&[2])),
+        );
+        for var in &["v", "w"] {
+            graph = graph
+                .node(
+                    node()
+                        .name(var)
+                        .op("VariableV2")
+                        .attr("dtype", DataType::DtFloat)
+                        .attr("shape", shape(&[2]))
+                        .attr("container", "")
+                        .attr("shared_name", ""),
+                )
+                .node(node().name(format!("{}/add", var)).op("AddV2").input(var).input("x"))
+                .node(
+                    node()
+                        .name(format!("{}/assign", var))
+                        .op("Assign")
+                        .input(var)
+                        .input(format!("{}/add", var)),
+                );
+        }
+        let mut model = crate::tensorflow().model_for_proto_model(&graph)?.into_typed()?;
+        for (ix, id) in
+            model.node_id_by_name("v").into_iter().chain(model.node_id_by_name("w")).enumerate()
+        {
+            let var = model.node_mut(id).op_as_mut::<VariableV2>().unwrap();
+            var.initializer = Some(rctensor1(&[ix as f32; 2]));
+        }
+        Ok(model)
+    }
+
+    #[test]
+    fn parallel_plan_keeps_variables_across_runs() -> TractResult<()> {
+        let model = accumulators()?;
+        let input = tensor1(&[1f32, 2.]);
+        let plan = SimplePlan::new(&model)?;
+        let mut state = SimpleState::new(&plan)?;
+        let expected =
+            (0..2).map(|_| state.run(tvec!(input.clone()))).collect::<TractResult<Vec<_>>>()?;
+        let plan = ParallelPlan::new(&model)?;
+        let mut state = ParallelState::new(&plan)?;
+        state.set_executor(Executor::multithread(4)?);
+        let found =
+            (0..2).map(|_| state.run(tvec!(input.clone()))).collect::<TractResult<Vec<_>>>()?;
+        assert_eq!(found, expected);
+        assert_eq!(*found[1][0], tensor1(&[2f32, 4.]));
+        Ok(())
+    }
+}
@@ -73,6 +73,7 @@ impl TryFrom<DatumType> for DataType {
                 | DatumType::ComplexF64
             => bail!("Dimension is not translatable in protobuf"),
             DatumType::TDim => bail!("Dimension is not translatable in protobuf"),
+            DatumType::Sequence => bail!("Sequence is not translatable in protobuf"),
         }
     }
 }
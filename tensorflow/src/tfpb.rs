@@ -136,6 +136,41 @@ impl NodeDef {
         Ok(None)
     }
 
+    pub fn get_attr_list_type(&self, name: &str) -> TractResult<Vec<DataType>> {
+        Ok(self.get_attr_opt_list_type(name)?.with_context(|| {
+            format!("Node {} ({}) expected list<type> attribute '{}'", self.name, self.op, name)
+        })?)
+    }
+
+    pub fn get_attr_opt_list_type(&self, name: &str) -> TractResult<Option<Vec<DataType>>> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::List(list) = a.value.as_ref().unwrap() {
+                return Ok(Some(
+                    list.r#type
+                        .iter()
+                        .map(|&t| DataType::from_i32(t).context("Invalid datatype"))
+                        .collect::<TractResult<_>>()?,
+                ));
+            }
+        };
+        Ok(None)
+    }
+
+    pub fn get_attr_func(&self, name: &str) -> TractResult<&str> {
+        Ok(self.get_attr_opt_func(name)?.with_context(|| {
+            format!("Node {} ({}) expected function attribute '{}'", self.name, self.op, name)
+        })?)
+    }
+
+    pub fn get_attr_opt_func(&self, name: &str) -> TractResult<Option<&str>> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::Func(func) = a.value.as_ref().unwrap() {
+                return Ok(Some(&*func.name));
+            }
+        };
+        Ok(None)
+    }
+
     pub fn get_attr_shape(&self, name: &str) -> TractResult<TVec<isize>> {
         Ok(self.get_attr_opt_shape(name)?.with_context(|| {
             format!("Node {} ({}) expected shape attribute '{}'", self.name, self.op, name)